@@ -0,0 +1,167 @@
+//! Rules that unlock achievements.
+//!
+//! Every achievement watches one trigger, a counter kept on the user or the
+//! user's maps, and unlocks once the counter reaches its threshold. Unlocking
+//! pays a reward into the user's wallet. Handlers record events here and
+//! announce whatever got unlocked.
+
+use entity::achievement::{self, Entity as Achievement};
+use entity::map::{self, Entity as Map};
+use entity::map_play;
+use entity::user::{self, Entity as User};
+use entity::user_achievement::{self, Entity as UserAchievement};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+    sea_query::Expr, sea_query::OnConflict,
+};
+
+use crate::db::UserId;
+use crate::wallet;
+
+/// Counters achievements can be unlocked by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    RaceWins,
+    CheckpointsPassed,
+    MapPlays, // Plays of a map the user created
+}
+
+impl Trigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::RaceWins => "race_wins",
+            Trigger::CheckpointsPassed => "checkpoints_passed",
+            Trigger::MapPlays => "map_plays",
+        }
+    }
+}
+
+/// Count a race win. Returns the achievements it unlocked.
+pub async fn record_win(
+    db: &DatabaseConnection,
+    user_id: UserId,
+) -> Result<Vec<achievement::Model>, DbErr> {
+    let Some(user) = increment_user(db, user_id, user::Column::RaceWins, 1).await? else {
+        return Ok(Vec::new());
+    };
+
+    unlock(db, user_id, Trigger::RaceWins, user.race_wins).await
+}
+
+/// Count checkpoints a racer passed. Returns the achievements they unlocked.
+pub async fn record_checkpoints(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    passed: i64,
+) -> Result<Vec<achievement::Model>, DbErr> {
+    let Some(user) = increment_user(db, user_id, user::Column::CheckpointsPassed, passed).await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    unlock(
+        db,
+        user_id,
+        Trigger::CheckpointsPassed,
+        user.checkpoints_passed,
+    )
+    .await
+}
+
+/// Count a race on a map, all-time and among its recent plays. Returns the
+/// author of the map and the achievements they unlocked.
+pub async fn record_map_play(
+    db: &DatabaseConnection,
+    map_id: i32,
+) -> Result<Option<(UserId, Vec<achievement::Model>)>, DbErr> {
+    let Some(map) = Map::update_many()
+        .col_expr(
+            map::Column::PlayCount,
+            Expr::col(map::Column::PlayCount).add(1),
+        )
+        .filter(map::Column::Id.eq(map_id))
+        .exec_with_returning(db)
+        .await?
+        .pop()
+    else {
+        return Ok(None);
+    };
+
+    map_play::ActiveModel {
+        map_id: Set(map_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let unlocked = unlock(db, map.author_id, Trigger::MapPlays, map.play_count).await?;
+
+    Ok(Some((map.author_id, unlocked)))
+}
+
+// Helper function to add to a counter of a user, returning the updated user
+async fn increment_user(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    column: user::Column,
+    amount: i64,
+) -> Result<Option<user::Model>, DbErr> {
+    Ok(User::update_many()
+        .col_expr(column, Expr::col(column).add(amount))
+        .filter(user::Column::Id.eq(user_id))
+        .exec_with_returning(db)
+        .await?
+        .pop())
+}
+
+// Unlock the achievements of a trigger whose threshold the counter reached
+// and that the user doesn't have yet
+async fn unlock(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    trigger: Trigger,
+    value: i64,
+) -> Result<Vec<achievement::Model>, DbErr> {
+    let reached = Achievement::find()
+        .filter(achievement::Column::Trigger.eq(trigger.as_str()))
+        .filter(achievement::Column::Threshold.lte(value))
+        .all(db)
+        .await?;
+
+    let mut unlocked = Vec::new();
+    for achievement in reached {
+        // Conflicts mean the user unlocked it before, possibly concurrently
+        let inserted = UserAchievement::insert(user_achievement::ActiveModel {
+            user_id: Set(user_id),
+            achievement_id: Set(achievement.id),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                user_achievement::Column::UserId,
+                user_achievement::Column::AchievementId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        if inserted > 0 {
+            if let Err(e) = wallet::grant(
+                db,
+                user_id,
+                wallet::ACHIEVEMENT_COINS,
+                wallet::ACHIEVEMENT_REASON,
+                &format!("achievement:{}", achievement.id),
+            )
+            .await
+            {
+                tracing::error!("Error paying achievement reward: {}", e);
+            }
+            unlocked.push(achievement);
+        }
+    }
+
+    Ok(unlocked)
+}
@@ -0,0 +1,49 @@
+//! Activity of users.
+//!
+//! Notable things users do are stored as events that others can browse,
+//! subject to the visibility the user chose in their settings.
+
+use entity::activity;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, Set};
+
+use crate::db::UserId;
+
+/// Things users do that show up in their activity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    MapCreated,
+    RaceWon,
+    PartyJoined,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::MapCreated => "map_created",
+            Kind::RaceWon => "race_won",
+            Kind::PartyJoined => "party_joined",
+        }
+    }
+}
+
+/// Record an event in the activity of a user, with the map and party it
+/// happened on
+pub async fn record(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    kind: Kind,
+    map_id: Option<i32>,
+    party_id: Option<i32>,
+) -> Result<(), DbErr> {
+    activity::ActiveModel {
+        user_id: Set(user_id),
+        kind: Set(kind.as_str().to_string()),
+        map_id: Set(map_id),
+        party_id: Set(party_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::achievement::{self, Entity as Achievement};
+use entity::user::Entity as User;
+use entity::user_achievement::{self, Entity as UserAchievement};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct AchievementResponse {
+    id: i32,
+    key: String,
+    name: String,
+    description: String,
+    /// Counter the achievement watches: race_wins, checkpoints_passed or map_plays
+    trigger: String,
+    /// Value of the counter that unlocks the achievement
+    threshold: i64,
+}
+
+impl From<achievement::Model> for AchievementResponse {
+    fn from(achievement: achievement::Model) -> Self {
+        Self {
+            id: achievement.id,
+            key: achievement.key,
+            name: achievement.name,
+            description: achievement.description,
+            trigger: achievement.trigger,
+            threshold: achievement.threshold,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnlockedAchievementResponse {
+    #[serde(flatten)]
+    achievement: AchievementResponse,
+    unlocked_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/achievements", get(list_achievements))
+        .route("/users/{id}/achievements", get(list_user_achievements))
+}
+
+/// List every achievement that can be unlocked
+#[utoipa::path(
+    get,
+    path = "/api/achievements",
+    tag = "achievements",
+    responses(
+        (status = 200, description = "Achievements retrieved successfully", body = Vec<AchievementResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_achievements(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AchievementResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let achievements = Achievement::find()
+        .order_by_asc(achievement::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        achievements
+            .into_iter()
+            .map(AchievementResponse::from)
+            .collect(),
+    ))
+}
+
+/// List the achievements a user unlocked
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/achievements",
+    tag = "achievements",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Unlocked achievements retrieved successfully", body = Vec<UnlockedAchievementResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_user_achievements(
+    State(state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<Vec<UnlockedAchievementResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // First verify user exists
+    let _ = User::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", user_id),
+        ))?;
+
+    let unlocked = UserAchievement::find()
+        .filter(user_achievement::Column::UserId.eq(user_id))
+        .order_by_asc(user_achievement::Column::UnlockedAt)
+        .find_also_related(Achievement)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .filter_map(|(unlock, achievement)| {
+            Some(UnlockedAchievementResponse {
+                achievement: achievement?.into(),
+                unlocked_at: unlock.unlocked_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(unlocked))
+}
@@ -0,0 +1,183 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::activity::{self, Entity as Activity};
+use entity::follow::{self, Entity as Follow};
+use entity::map::{self, Entity as Map};
+use entity::party::{self, Entity as Party};
+use entity::user::{self, Entity as User};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::pagination::{Paginated, PaginationParams};
+use crate::blocking;
+use crate::db::AppState;
+use crate::settings::{self, ActivityVisibility};
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivityResponse {
+    id: i32,
+    /// What happened: map_created, race_won or party_joined
+    kind: String,
+    /// Map the activity happened on, unless it was deleted since
+    map: Option<ActivityMapResponse>,
+    /// Party the activity happened in, unless it was disbanded since
+    party: Option<ActivityPartyResponse>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivityMapResponse {
+    id: i32,
+    title: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivityPartyResponse {
+    id: i32,
+    name: String,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/users/{id}/activity", get(list_activity))
+}
+
+/// List what a user did recently, newest first
+///
+/// Users choose in their settings whether everyone, only their followers or
+/// nobody else can see their activity.
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/activity",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "User ID"),
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Activity retrieved successfully", body = Paginated<ActivityResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "The activity of the user is hidden", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_activity(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<ActivityResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let viewer_id = auth_user.0.sub;
+
+    let user = User::find_by_id(id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", id),
+        ))?;
+
+    // Users always see their own activity
+    if user.id != viewer_id {
+        let blocked = blocking::has_blocked(db, user.id, viewer_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        let visible = match settings::activity_visibility(db, user.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        {
+            ActivityVisibility::Public => true,
+            ActivityVisibility::Followers => Follow::find()
+                .filter(follow::Column::FollowerId.eq(viewer_id))
+                .filter(follow::Column::FollowedId.eq(user.id))
+                .one(db)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .is_some(),
+            ActivityVisibility::Private => false,
+        };
+
+        if blocked || !visible {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "The activity of this user is hidden".to_string(),
+            ));
+        }
+    }
+
+    let paginator = Activity::find()
+        .filter(activity::Column::UserId.eq(user.id))
+        .order_by_desc(activity::Column::CreatedAt)
+        .order_by_desc(activity::Column::Id)
+        .paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let activities = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let maps: HashMap<i32, map::Model> = Map::find()
+        .filter(map::Column::Id.is_in(activities.iter().filter_map(|a| a.map_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect();
+
+    let parties: HashMap<i32, party::Model> = Party::find()
+        .filter(party::Column::Id.is_in(activities.iter().filter_map(|a| a.party_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|party| (party.id, party))
+        .collect();
+
+    let activities = activities
+        .into_iter()
+        .map(|activity| ActivityResponse {
+            id: activity.id,
+            kind: activity.kind,
+            map: activity
+                .map_id
+                .and_then(|id| maps.get(&id))
+                .map(|map| ActivityMapResponse {
+                    id: map.id,
+                    title: map.title.clone(),
+                }),
+            party: activity
+                .party_id
+                .and_then(|id| parties.get(&id))
+                .map(|party| ActivityPartyResponse {
+                    id: party.id,
+                    name: party.name.clone(),
+                }),
+            created_at: activity.created_at,
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        activities,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
@@ -0,0 +1,830 @@
+use auth::middleware::AdminUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use entity::map::{self, Entity as Map};
+use entity::map_report::{self, Entity as MapReport};
+use entity::party::{self, Entity as Party};
+use entity::user::Entity as User;
+use entity::user_party::{self, Entity as UserParty};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use utoipa::{IntoParams, ToSchema};
+
+use super::map_reports::MapReportResponse;
+use super::maps::MapResponse;
+use super::pagination::{Paginated, PaginationParams};
+use super::wallet::TransactionResponse;
+use super::ws::WsMessage;
+use crate::db::{AppState, SocketCommand};
+use crate::metrics::METRICS_WINDOW_MINUTES;
+use crate::wallet;
+
+// Longest reason and idempotency key of a grant
+const MAX_GRANT_REASON_LENGTH: usize = 64;
+const MAX_GRANT_KEY_LENGTH: usize = 128;
+
+// Permanent bans are stored as bans until the last second of year 9999
+fn permanent_ban() -> chrono::DateTime<chrono::Utc> {
+    chrono::NaiveDate::from_ymd_opt(9999, 12, 31)
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .expect("valid date")
+        .and_utc()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LiveStateResponse {
+    instance_id: String,
+    ws_connections: usize,
+    active_parties: Vec<ActivePartyResponse>,
+    active_races: usize,
+    errors: ErrorRateResponse,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivePartyResponse {
+    party_id: i32,
+    name: String,
+    owner_id: i32,
+    member_count: i64,
+    connected_count: usize,
+    racing: bool,
+    race_started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ErrorRateResponse {
+    window_minutes: i64,
+    total_requests: u64,
+    client_errors: u64,
+    server_errors: u64,
+    server_error_rate: f64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AnnouncementRequest {
+    message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AnnouncementResponse {
+    parties_reached: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BanRequest {
+    /// Shown to the user when they are rejected
+    reason: Option<String>,
+    /// End of the ban; the ban is permanent if omitted
+    until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BanResponse {
+    user_id: i32,
+    banned_until: Option<chrono::DateTime<chrono::FixedOffset>>,
+    ban_reason: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GrantRequest {
+    /// Coins to add, or to take off if negative
+    amount: i64,
+    /// Recorded on the transaction, e.g. "compensation"
+    reason: String,
+    /// Unique per user; repeating a grant with the same key applies it once
+    idempotency_key: String,
+}
+
+/// Maps sharing the fingerprint of their route
+#[derive(Serialize, ToSchema)]
+pub struct DuplicateMapsResponse {
+    fingerprint: String,
+    /// Oldest first, so the original likely comes first
+    maps: Vec<MapResponse>,
+}
+
+// Which reports the moderation queue lists
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportQueueParams {
+    /// List the reports dealt with instead of the open ones
+    #[serde(default)]
+    resolved: bool,
+}
+
+impl From<entity::user::Model> for BanResponse {
+    fn from(user: entity::user::Model) -> Self {
+        Self {
+            user_id: user.id,
+            banned_until: user.banned_until,
+            ban_reason: user.ban_reason,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/live", get(live_state))
+        .route("/admin/announcements", post(broadcast_announcement))
+        .route("/admin/users/{id}/sign-out", post(sign_out_user))
+        .route("/admin/users/{id}/ban", post(ban_user).delete(unban_user))
+        .route("/admin/users/{id}/wallet/grants", post(grant_currency))
+        .route("/admin/reports", get(list_reports))
+        .route("/admin/reports/{id}/resolve", post(resolve_report))
+        .route("/admin/maps/duplicates", get(list_duplicate_maps))
+        .route("/admin/maps/{id}/hide", post(hide_map).delete(unhide_map))
+}
+
+/// Get live server state for this instance
+#[utoipa::path(
+    get,
+    path = "/api/admin/live",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Live server state retrieved successfully", body = LiveStateResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn live_state(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<LiveStateResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Parties with an open websocket channel on this instance
+    let party_ids: Vec<i32> = state
+        .party_channels
+        .lock()
+        .unwrap()
+        .keys()
+        .copied()
+        .collect();
+
+    // Count connected users per party
+    let mut connected_counts: HashMap<i32, usize> = HashMap::new();
+    for party_id in state.user_parties.lock().unwrap().values() {
+        *connected_counts.entry(*party_id).or_default() += 1;
+    }
+
+    let races: HashMap<i32, chrono::DateTime<chrono::Utc>> = state
+        .active_races
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(party_id, race)| (*party_id, race.started_at))
+        .collect();
+
+    let parties = Party::find()
+        .filter(party::Column::Id.is_in(party_ids.clone()))
+        .order_by_asc(party::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let member_counts: HashMap<i32, i64> = UserParty::find()
+        .select_only()
+        .column(user_party::Column::PartyId)
+        .column_as(user_party::Column::Id.count(), "member_count")
+        .filter(user_party::Column::PartyId.is_in(party_ids))
+        .group_by(user_party::Column::PartyId)
+        .into_tuple::<(i32, i64)>()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .collect();
+
+    let active_parties = parties
+        .into_iter()
+        .map(|party| ActivePartyResponse {
+            party_id: party.id,
+            member_count: member_counts.get(&party.id).copied().unwrap_or(0),
+            connected_count: connected_counts.get(&party.id).copied().unwrap_or(0),
+            racing: races.contains_key(&party.id),
+            race_started_at: races.get(&party.id).copied(),
+            name: party.name,
+            owner_id: party.owner_id,
+        })
+        .collect();
+
+    let counts = state.request_metrics.recent();
+    let server_error_rate = if counts.total == 0 {
+        0.0
+    } else {
+        counts.server_errors as f64 / counts.total as f64
+    };
+
+    Ok(Json(LiveStateResponse {
+        instance_id: state.config.instance_id.clone(),
+        ws_connections: state.ws_connections.load(Ordering::Relaxed),
+        active_parties,
+        active_races: races.len(),
+        errors: ErrorRateResponse {
+            window_minutes: METRICS_WINDOW_MINUTES,
+            total_requests: counts.total,
+            client_errors: counts.client_errors,
+            server_errors: counts.server_errors,
+            server_error_rate,
+        },
+    }))
+}
+
+/// Broadcast an announcement into every party channel
+#[utoipa::path(
+    post,
+    path = "/api/admin/announcements",
+    tag = "admin",
+    request_body = AnnouncementRequest,
+    responses(
+        (status = 200, description = "Announcement broadcast", body = AnnouncementResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn broadcast_announcement(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(payload): Json<AnnouncementRequest>,
+) -> Result<Json<AnnouncementResponse>, (StatusCode, String)> {
+    if payload.message.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Announcement message cannot be empty".to_string(),
+        ));
+    }
+
+    let announcement = serde_json::to_string(&WsMessage::Announcement {
+        message: payload.message,
+    })
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let party_channels = state.party_channels.lock().unwrap();
+    let parties_reached = party_channels
+        .values()
+        .filter(|channel| channel.send(announcement.clone()).is_ok())
+        .count();
+
+    tracing::info!("Announcement broadcast to {} parties", parties_reached);
+
+    Ok(Json(AnnouncementResponse { parties_reached }))
+}
+
+/// Sign a user out of every session
+///
+/// Revokes the user's refresh tokens and, when Redis is configured, every
+/// access token issued to them so far.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/sign-out",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "User signed out"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn sign_out_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    _admin: AdminUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the user exists
+    let _ = User::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", id),
+        ))?;
+
+    auth::user::revoke_sessions(db, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .blocklist
+        .revoke_user(id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!("User {} signed out by an admin", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Ban a user
+///
+/// Banned users can't log in, refresh their tokens or use any token issued
+/// before the ban, and their websocket connection on this instance is closed.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/ban",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    request_body = BanRequest,
+    responses(
+        (status = 200, description = "User banned", body = BanResponse),
+        (status = 400, description = "Ban ends in the past", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn ban_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+    Json(payload): Json<BanRequest>,
+) -> Result<Json<BanResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let until = payload.until.unwrap_or_else(permanent_ban);
+    if until <= chrono::Utc::now() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Ban must end in the future".to_string(),
+        ));
+    }
+
+    let reason = payload
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+
+    let user = auth::user::ban(db, id, until, reason)
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::InvalidCredentials => (
+                StatusCode::NOT_FOUND,
+                format!("User with id {} not found", id),
+            ),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    state
+        .blocklist
+        .revoke_user(id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Kick the user out of the game
+    if let Some(socket) = state.user_sockets.lock().unwrap().get(&id) {
+        let _ = socket.send(SocketCommand::Close);
+    }
+
+    tracing::info!("User {} banned by admin {}", id, admin.0.sub);
+
+    Ok(Json(user.into()))
+}
+
+/// Lift the ban of a user
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}/ban",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Ban lifted", body = BanResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unban_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+) -> Result<Json<BanResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let user = auth::user::unban(db, id).await.map_err(|e| match e {
+        auth::AuthError::InvalidCredentials => (
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", id),
+        ),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    tracing::info!("User {} unbanned by admin {}", id, admin.0.sub);
+
+    Ok(Json(user.into()))
+}
+
+/// Grant coins to a user, or take them off
+///
+/// Grants are idempotent: repeating a request with the same key returns the
+/// original transaction without changing the balance again.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/wallet/grants",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    request_body = GrantRequest,
+    responses(
+        (status = 200, description = "Grant applied", body = TransactionResponse),
+        (status = 400, description = "Invalid grant", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 409, description = "Balance too low", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn grant_currency(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+    Json(payload): Json<GrantRequest>,
+) -> Result<Json<TransactionResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let reason = payload.reason.trim();
+    let idempotency_key = payload.idempotency_key.trim();
+    if payload.amount == 0
+        || reason.is_empty()
+        || reason.chars().count() > MAX_GRANT_REASON_LENGTH
+        || idempotency_key.is_empty()
+        || idempotency_key.chars().count() > MAX_GRANT_KEY_LENGTH
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Grants need a non-zero amount, a reason of at most {} and a key of at most {} characters",
+                MAX_GRANT_REASON_LENGTH, MAX_GRANT_KEY_LENGTH
+            ),
+        ));
+    }
+
+    User::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", id),
+        ))?;
+
+    // Keys of admin grants can't collide with those of rewards
+    let transaction = wallet::grant(
+        db,
+        id,
+        payload.amount,
+        reason,
+        &format!("admin:{}", idempotency_key),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((
+        StatusCode::CONFLICT,
+        "The balance is too low for this amount".to_string(),
+    ))?;
+
+    tracing::info!(
+        "Granted {} coins to user {} by admin {}",
+        transaction.amount,
+        id,
+        admin.0.sub
+    );
+
+    Ok(Json(transaction.into()))
+}
+
+/// List the reports of maps, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/admin/reports",
+    tag = "admin",
+    params(PaginationParams, ReportQueueParams),
+    responses(
+        (status = 200, description = "Reports retrieved successfully", body = Paginated<MapReportResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_reports(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(params): Query<ReportQueueParams>,
+) -> Result<Json<Paginated<MapReportResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let query = if params.resolved {
+        MapReport::find().filter(map_report::Column::ResolvedAt.is_not_null())
+    } else {
+        MapReport::find().filter(map_report::Column::ResolvedAt.is_null())
+    };
+
+    let paginator = query
+        .order_by_asc(map_report::Column::CreatedAt)
+        .order_by_asc(map_report::Column::Id)
+        .paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let reports = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(MapReportResponse::from)
+        .collect();
+
+    Ok(Json(Paginated::new(
+        reports,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Mark a report as dealt with
+///
+/// Resolving a report again keeps it as it is.
+#[utoipa::path(
+    post,
+    path = "/api/admin/reports/{id}/resolve",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "Report ID")
+    ),
+    responses(
+        (status = 200, description = "Report resolved", body = MapReportResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "Report not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn resolve_report(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+) -> Result<Json<MapReportResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let report = MapReport::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Report with id {} not found", id),
+        ))?;
+
+    if report.resolved_at.is_some() {
+        return Ok(Json(report.into()));
+    }
+
+    let mut report: map_report::ActiveModel = report.into();
+    report.resolved_at = Set(Some(chrono::Utc::now().fixed_offset()));
+    let report = report
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!("Map report {} resolved by admin {}", id, admin.0.sub);
+
+    Ok(Json(report.into()))
+}
+
+/// List likely duplicate maps
+///
+/// Groups maps whose routes are nearly identical, with the groups that
+/// grew most recently first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/maps/duplicates",
+    tag = "admin",
+    params(PaginationParams),
+    responses(
+        (status = 200, description = "Duplicate maps retrieved successfully", body = Paginated<DuplicateMapsResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_duplicate_maps(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<DuplicateMapsResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let paginator = Map::find()
+        .select_only()
+        .column(map::Column::Fingerprint)
+        .filter(map::Column::Fingerprint.is_not_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .group_by(map::Column::Fingerprint)
+        .having(Expr::expr(Expr::col(map::Column::Id).count()).gt(1))
+        .order_by_desc(Expr::col(map::Column::Id).max())
+        .order_by_asc(map::Column::Fingerprint)
+        .into_tuple::<String>()
+        .paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let fingerprints = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let maps = Map::find()
+        .filter(map::Column::Fingerprint.is_in(fingerprints.clone()))
+        .filter(map::Column::DeletedAt.is_null())
+        .order_by_asc(map::Column::CreatedAt)
+        .order_by_asc(map::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let groups = fingerprints
+        .into_iter()
+        .map(|fingerprint| DuplicateMapsResponse {
+            maps: maps
+                .iter()
+                .filter(|map| map.fingerprint.as_deref() == Some(fingerprint.as_str()))
+                .cloned()
+                .map(MapResponse::from)
+                .collect(),
+            fingerprint,
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        groups,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Take a map down pending review
+///
+/// Only its author and admins see the map until it is restored; it is left
+/// out of listings and can't be picked for races. Reports of the map stay
+/// open. To remove the map for good, delete it.
+#[utoipa::path(
+    post,
+    path = "/api/admin/maps/{id}/hide",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map hidden", body = MapResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn hide_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let map = find_map(&state, id).await?;
+
+    // Hiding a map again keeps when it was first hidden
+    if map.hidden_at.is_some() {
+        return Ok(Json(map.into()));
+    }
+
+    let mut map: map::ActiveModel = map.into();
+    map.hidden_at = Set(Some(chrono::Utc::now().fixed_offset()));
+    let map = map
+        .update(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!("Map {} hidden by admin {}", id, admin.0.sub);
+
+    Ok(Json(map.into()))
+}
+
+/// Restore a map taken down
+///
+/// The open reports of the map are resolved, as it was found fine.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/maps/{id}/hide",
+    tag = "admin",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map restored", body = MapResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Admin access required", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unhide_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    admin: AdminUser,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let map = find_map(&state, id).await?;
+
+    MapReport::update_many()
+        .col_expr(
+            map_report::Column::ResolvedAt,
+            Expr::value(chrono::Utc::now().fixed_offset()),
+        )
+        .filter(map_report::Column::MapId.eq(map.id))
+        .filter(map_report::Column::ResolvedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut map: map::ActiveModel = map.into();
+    map.hidden_at = Set(None);
+    let map = map
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!("Map {} restored by admin {}", id, admin.0.sub);
+
+    Ok(Json(map.into()))
+}
+
+// Helper function to find a map by ID
+async fn find_map(state: &AppState, id: i32) -> Result<map::Model, (StatusCode, String)> {
+    Map::find_by_id(id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
+        ))
+}
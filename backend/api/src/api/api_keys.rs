@@ -0,0 +1,166 @@
+use auth::api_key;
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use chrono::DateTime;
+use entity::api_key::Model as ApiKeyModel;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// What the key is used for, e.g. the name of a game server or bot
+    name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    id: i32,
+    name: String,
+    /// Start of the key, to tell keys apart
+    prefix: String,
+    created_at: DateTime<chrono::FixedOffset>,
+    last_used_at: Option<DateTime<chrono::FixedOffset>>,
+}
+
+impl From<ApiKeyModel> for ApiKeyResponse {
+    fn from(key: ApiKeyModel) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            created_at: key.created_at,
+            last_used_at: key.last_used_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    api_key: ApiKeyResponse,
+    /// The key to send in the X-API-Key header. It is only shown once.
+    key: String,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/api-keys", get(list_api_keys))
+        .route("/api-keys", post(create_api_key))
+        .route("/api-keys/{id}", delete(revoke_api_key))
+}
+
+/// List the API keys of the current user
+#[utoipa::path(
+    get,
+    path = "/api/api-keys",
+    tag = "api-keys",
+    responses(
+        (status = 200, description = "List of API keys retrieved successfully", body = Vec<ApiKeyResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<ApiKeyResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let keys = api_key::list(db, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+/// Create an API key for server-to-server calls
+///
+/// Requests made with the key in the `X-API-Key` header act on behalf of the
+/// current user, without administrator rights.
+#[utoipa::path(
+    post,
+    path = "/api/api-keys",
+    tag = "api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created successfully", body = CreatedApiKeyResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreatedApiKeyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "API key name must not be empty".to_string(),
+        ));
+    }
+
+    let (stored, key) = api_key::create(db, auth_user.0.sub, name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(CreatedApiKeyResponse {
+        api_key: stored.into(),
+        key,
+    }))
+}
+
+/// Revoke an API key of the current user
+#[utoipa::path(
+    delete,
+    path = "/api/api-keys/{id}",
+    tag = "api-keys",
+    params(
+        ("id" = i32, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "API key not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let revoked = api_key::revoke(db, auth_user.0.sub, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if !revoked {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("API key with id {} not found", id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,19 +1,62 @@
-use auth::{Auth, user};
+use auth::middleware::AuthUser;
+use auth::oauth::{OAuthClient, OAuthProvider};
+use auth::{Scope, email, password, user};
 use axum::{
     Router,
-    extract::{Json, State},
-    http::StatusCode,
-    routing::post,
+    extract::{ConnectInfo, Json, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post},
 };
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, Cookie, authorization::Bearer},
+};
+use entity::user::Entity as User;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use utoipa::ToSchema;
 
+use super::linked_accounts::{platform_error, platform_verifier};
+use crate::client_ip;
+use crate::config::Config;
 use crate::db::AppState;
+use crate::progression;
+use crate::rate_limit;
+use crate::validation::{self, ValidationErrorResponse};
+
+const MAX_DEVICE_NAME_LENGTH: usize = 200;
+
+// Cookie tying an OAuth flow to the browser that started it
+const OAUTH_NONCE_COOKIE: &str = "oauth_nonce";
+const OAUTH_NONCE_PATH: &str = "/api/auth/oauth";
+const OAUTH_NONCE_MAX_AGE: u64 = 600; // in seconds, as long as the state lasts
 
 // Local types for OpenAPI
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub name: String,
+    pub password: String,
+    /// Token of the solved captcha, required if captchas are enabled
+    pub captcha_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub name: String,
+    pub password: String,
+}
+
+// Returned when logins are locked after too many failures
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginLockedResponse {
+    /// "account_locked" or "too_many_failed_logins"
+    pub error: String,
+    pub message: String,
+    /// Seconds until logins are allowed again
+    pub retry_after: u64,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -21,6 +64,14 @@ pub struct RefreshRequest {
     pub refresh_token: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+    /// Also sign out every other session of the user
+    #[serde(default)]
+    pub all_sessions: bool,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
@@ -29,6 +80,88 @@ pub struct AuthResponse {
     pub token_type: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    pub id: i32,
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: chrono::DateTime<chrono::FixedOffset>,
+    pub last_seen_at: chrono::DateTime<chrono::FixedOffset>,
+    /// Whether this is the session of the access token used for the request
+    pub current: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScopedTokenRequest {
+    /// What the token may be used for
+    pub scope: Vec<Scope>,
+    /// Lifetime in seconds, at most that of a regular access token
+    pub expires_in: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScopedTokenResponse {
+    pub access_token: String,
+    pub expires_in: i64,
+    pub token_type: String,
+    pub scope: Vec<Scope>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginCodeRequest {
+    /// Email the code to this verified address instead of returning it
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginCodeResponse {
+    /// Code to enter on the other device
+    pub code: String,
+    pub expires_in: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RedeemLoginCodeRequest {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct EmailVerifiedResponse {
+    pub email: String,
+    pub verified_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    /// Token from the password reset link
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PlatformLoginRequest {
+    /// Ticket from the platform's SDK: the hex encoded Steam web API ticket,
+    /// or the Epic access token
+    pub ticket: String,
+}
+
+#[derive(Deserialize)]
+pub struct OAuthCallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
 impl From<auth::AuthResponse> for AuthResponse {
     fn from(response: auth::AuthResponse) -> Self {
         Self {
@@ -40,10 +173,32 @@ impl From<auth::AuthResponse> for AuthResponse {
     }
 }
 
-pub fn router() -> Router<AppState> {
-    Router::new()
+pub fn router(state: &AppState) -> Router<AppState> {
+    // Limit the endpoints that create users or hand out tokens
+    let rate_limited = Router::new()
         .route("/auth/register", post(register))
+        .route("/auth/login", post(login))
         .route("/auth/refresh", post(refresh))
+        .route("/auth/verify/request", post(request_email_verification))
+        .route("/auth/link/request", post(request_login_code))
+        .route("/auth/link/redeem", post(redeem_login_code))
+        .route("/auth/password/forgot", post(forgot_password))
+        .route("/auth/password/reset", post(reset_password))
+        .route("/auth/platform/{platform}/login", post(platform_login))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_auth_requests,
+        ));
+
+    Router::new()
+        .merge(rate_limited)
+        .route("/auth/logout", post(logout))
+        .route("/auth/token", post(scoped_token))
+        .route("/auth/sessions", get(list_sessions))
+        .route("/auth/sessions/{id}", delete(revoke_session))
+        .route("/auth/verify/{token}", get(verify_email))
+        .route("/auth/oauth/{provider}/start", get(oauth_start))
+        .route("/auth/oauth/{provider}/callback", get(oauth_callback))
 }
 
 /// Register a new user
@@ -54,30 +209,122 @@ pub fn router() -> Router<AppState> {
     request_body = RegisterRequest,
     responses(
         (status = 200, description = "User registered successfully", body = AuthResponse),
-        (status = 400, description = "Bad request", body = String),
+        (status = 400, description = "Password too short or captcha failed", body = String),
+        (status = 409, description = "Name is already taken", body = String),
+        (status = 422, description = "Invalid name", body = ValidationErrorResponse),
+        (status = 429, description = "Too many requests", body = String),
         (status = 500, description = "Internal server error", body = String)
     )
 )]
 async fn register(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+) -> Result<Json<AuthResponse>, Response> {
     let db = &state.conn;
 
-    // Create Auth instance
-    let auth = Auth::new(
-        state.config.jwt_secret.clone(),
-        state.config.jwt_expiry,
-        state.config.refresh_expiry,
-    );
+    // Stop automated sign-ups on instances that require a captcha
+    if let Some(verifier) = state.config.captcha_verifier() {
+        verifier
+            .verify(
+                payload.captcha_token.as_deref(),
+                &client_ip::from_request(&state.config.trusted_proxies, &headers, peer),
+            )
+            .await
+            .map_err(|e| match e {
+                auth::AuthError::CaptchaFailed => {
+                    (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+                }
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            })?;
+    }
+
+    let auth = &state.auth;
 
     // Convert to internal type
-    let req = user::RegisterRequest { name: payload.name };
+    let req = user::RegisterRequest {
+        name: payload.name,
+        password: payload.password,
+    };
 
     // Register user
-    let result = user::register(db, &auth, req)
+    let result = user::register(
+        db,
+        auth,
+        &state.validator,
+        req,
+        session_info(&state.config, &headers, peer),
+    )
+    .await
+    .map_err(|e| match e {
+        auth::AuthError::Validation(errors) => validation::invalid(errors),
+        auth::AuthError::PasswordTooShort(_) => {
+            (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+        }
+        auth::AuthError::NameTaken => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    })?;
+
+    record_daily_login(&state, &result).await;
+
+    Ok(Json(result.into()))
+}
+
+/// Log in with name and password
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in successfully", body = AuthResponse),
+        (status = 401, description = "Invalid credentials", body = String),
+        (status = 403, description = "Account is banned", body = String),
+        (status = 423, description = "Account is locked after too many failed logins", body = LoginLockedResponse),
+        (status = 429, description = "Too many requests or failed logins from this client", body = LoginLockedResponse),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn login(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<AuthResponse>, Response> {
+    let db = &state.conn;
+    let auth = &state.auth;
+    let lockout = &state.login_lockout;
+    let ip = client_ip::from_request(&state.config.trusted_proxies, &headers, peer);
+
+    // Stop guessing once an account or client has failed too often. The
+    // attempt counts until the password turns out right.
+    lockout
+        .attempt(&payload.name, &ip)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(login_locked)?;
+
+    // Convert to internal type
+    let req = user::LoginRequest {
+        name: payload.name.clone(),
+        password: payload.password,
+    };
+
+    // Verify credentials
+    let result = match user::login(db, auth, req, session_info(&state.config, &headers, peer)).await
+    {
+        Ok(result) => result,
+        Err(e @ auth::AuthError::InvalidCredentials) => {
+            return Err((StatusCode::UNAUTHORIZED, e.to_string()).into_response());
+        }
+        Err(e @ auth::AuthError::Banned { .. }) => {
+            return Err((StatusCode::FORBIDDEN, e.to_string()).into_response());
+        }
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    };
+
+    lockout.clear(&payload.name, &ip).await;
+    record_daily_login(&state, &result).await;
 
     Ok(Json(result.into()))
 }
@@ -91,21 +338,20 @@ async fn register(
     responses(
         (status = 200, description = "Token refreshed successfully", body = AuthResponse),
         (status = 401, description = "Invalid or expired refresh token", body = String),
+        (status = 403, description = "Account is banned", body = String),
+        (status = 429, description = "Too many requests", body = String),
         (status = 500, description = "Internal server error", body = String)
     )
 )]
 async fn refresh(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<RefreshRequest>,
 ) -> Result<Json<AuthResponse>, (StatusCode, String)> {
     let db = &state.conn;
 
-    // Create Auth instance
-    let auth = Auth::new(
-        state.config.jwt_secret.clone(),
-        state.config.jwt_expiry,
-        state.config.refresh_expiry,
-    );
+    let auth = &state.auth;
 
     // Convert to internal type
     let req = user::RefreshRequest {
@@ -113,14 +359,866 @@ async fn refresh(
     };
 
     // Refresh token
-    let result = user::refresh_token(db, &auth, req)
+    let result = user::refresh_token(db, auth, req, session_info(&state.config, &headers, peer))
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::InvalidToken
+            | auth::AuthError::RefreshTokenExpired
+            | auth::AuthError::JwtError(_) => (StatusCode::UNAUTHORIZED, e.to_string()),
+            auth::AuthError::Banned { .. } => (StatusCode::FORBIDDEN, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    record_daily_login(&state, &result).await;
+
+    Ok(Json(result.into()))
+}
+
+/// Request a one-time code to log in on another device
+///
+/// A logged-in device gets the code in the response to display it, e.g. a
+/// phone showing the code for a console client. Without a token, the code is
+/// emailed to the user with the given verified address instead; the response
+/// doesn't tell whether such a user exists.
+#[utoipa::path(
+    post,
+    path = "/api/auth/link/request",
+    tag = "auth",
+    request_body = LoginCodeRequest,
+    responses(
+        (status = 200, description = "Login code created", body = LoginCodeResponse),
+        (status = 202, description = "Login code emailed, if the address belongs to a user"),
+        (status = 400, description = "Invalid email address", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        (),
+        ("jwt" = [])
+    )
+)]
+async fn request_login_code(
+    State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(payload): Json<LoginCodeRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Email the code to a verified address
+    if let Some(address) = payload.email {
+        let address = email::normalize_email(&address)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        let user = User::find()
+            .filter(entity::user::Column::Email.eq(address.clone()))
+            .filter(entity::user::Column::EmailVerifiedAt.is_not_null())
+            .one(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        if let Some(user) = user {
+            let code = user::create_login_code(db, user.id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            state
+                .mailer
+                .send(
+                    &address,
+                    "Your World Racers login code",
+                    format!(
+                        "Hi {},\n\nEnter this code to log in:\n{}\n\nThe code expires in {} minutes. If you didn't ask for it, you can ignore this email.\n",
+                        user.name,
+                        code,
+                        user::LOGIN_CODE_EXPIRY / 60
+                    ),
+                )
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+
+        return Ok(StatusCode::ACCEPTED.into_response());
+    }
+
+    // Otherwise the code is for the logged-in user
+    let TypedHeader(Authorization(bearer)) = bearer.ok_or((
+        StatusCode::UNAUTHORIZED,
+        "No authorization token provided".to_string(),
+    ))?;
+
+    let auth = &state.auth;
+
+    let claims = auth
+        .verify_token(bearer.token())
+        .ok()
+        .filter(|claims| !claims.is_scoped())
+        .ok_or((
+            StatusCode::UNAUTHORIZED,
+            "Invalid authorization token".to_string(),
+        ))?;
+
+    let claims = state.blocklist.check(claims).await.map_err(|_| {
+        (
+            StatusCode::UNAUTHORIZED,
+            "Invalid authorization token".to_string(),
+        )
+    })?;
+
+    let code = user::create_login_code(db, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(LoginCodeResponse {
+        code,
+        expires_in: user::LOGIN_CODE_EXPIRY,
+    })
+    .into_response())
+}
+
+/// Log in with a code requested on another device
+#[utoipa::path(
+    post,
+    path = "/api/auth/link/redeem",
+    tag = "auth",
+    request_body = RedeemLoginCodeRequest,
+    responses(
+        (status = 200, description = "Logged in successfully", body = AuthResponse),
+        (status = 401, description = "Invalid, used or expired code", body = String),
+        (status = 403, description = "Account is banned", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn redeem_login_code(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<RedeemLoginCodeRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let auth = &state.auth;
+
+    let result = user::login_with_code(
+        db,
+        auth,
+        &payload.code,
+        session_info(&state.config, &headers, peer),
+    )
+    .await
+    .map_err(|e| match e {
+        auth::AuthError::InvalidCredentials => {
+            (StatusCode::UNAUTHORIZED, "Invalid login code".to_string())
+        }
+        auth::AuthError::Banned { .. } => (StatusCode::FORBIDDEN, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    record_daily_login(&state, &result).await;
+
+    Ok(Json(result.into()))
+}
+
+/// Logout by revoking a refresh token
+///
+/// The access token sent as bearer token, if any, is revoked as well. With
+/// `all_sessions`, every access token issued to the user so far is revoked.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body = LogoutRequest,
+    responses(
+        (status = 204, description = "Logged out successfully"),
+        (status = 401, description = "Invalid or expired refresh token", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn logout(
+    State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(payload): Json<LogoutRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let auth = &state.auth;
+
+    // Convert to internal type
+    let req = user::LogoutRequest {
+        refresh_token: payload.refresh_token,
+        all_sessions: payload.all_sessions,
+    };
+
+    let all_sessions = req.all_sessions;
+
+    // Revoke the session(s)
+    let user_id = user::logout(db, auth, req).await.map_err(|e| match e {
+        auth::AuthError::InvalidToken | auth::AuthError::JwtError(_) => {
+            (StatusCode::UNAUTHORIZED, e.to_string())
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    // Revoke access tokens right away instead of waiting for them to expire
+    if all_sessions {
+        state
+            .blocklist
+            .revoke_user(user_id, state.config.jwt_expiry)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    } else if let Some(TypedHeader(Authorization(bearer))) = bearer
+        && let Ok(claims) = auth.verify_token(bearer.token())
+        && claims.sub == user_id
+    {
+        state
+            .blocklist
+            .revoke_token(&claims)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create an access token restricted to some scopes
+///
+/// Scoped tokens can be handed to less trusted code, e.g. a websocket-only
+/// token for a spectator overlay or a map-upload-only token for a map editor.
+#[utoipa::path(
+    post,
+    path = "/api/auth/token",
+    tag = "auth",
+    request_body = ScopedTokenRequest,
+    responses(
+        (status = 200, description = "Scoped token created", body = ScopedTokenResponse),
+        (status = 400, description = "No scope requested", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Scoped tokens can't create other tokens", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn scoped_token(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<ScopedTokenRequest>,
+) -> Result<Json<ScopedTokenResponse>, (StatusCode, String)> {
+    let claims = auth_user.0;
+
+    let auth = &state.auth;
+
+    let expires_in = payload
+        .expires_in
+        .unwrap_or(auth.jwt_expiry())
+        .clamp(1, auth.jwt_expiry());
+
+    let access_token = auth
+        .generate_scoped_token(claims.sub, claims.name, payload.scope.clone(), expires_in)
+        .map_err(|e| match e {
+            auth::AuthError::InsufficientScope => (
+                StatusCode::BAD_REQUEST,
+                "At least one scope is required".to_string(),
+            ),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    Ok(Json(ScopedTokenResponse {
+        access_token,
+        expires_in,
+        token_type: "Bearer".to_string(),
+        scope: payload.scope,
+    }))
+}
+
+/// List the active sessions of the current user
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Active sessions, most recently used first", body = Vec<SessionResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn list_sessions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<SessionResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let claims = auth_user.0;
+
+    let sessions = user::list_sessions(db, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let response = sessions
+        .into_iter()
+        .map(|session| SessionResponse {
+            id: session.id,
+            device_name: session.device_name,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            current: claims.sid == Some(session.id),
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// Revoke a session of the current user, e.g. a login on another device
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{id}",
+    tag = "auth",
+    params(
+        ("id" = i32, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Session not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn revoke_session(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Revoke the refresh token of the session
+    let revoked = user::revoke_session(db, auth_user.0.sub, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if !revoked {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Session with id {} not found", id),
+        ));
+    }
+
+    // Revoke the access tokens of the session as well
+    state
+        .blocklist
+        .revoke_session(id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Set the email address of the current user and send a verification link to it
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify/request",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 202, description = "Verification link sent"),
+        (status = 400, description = "Invalid email address", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 409, description = "Email address is already in use", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn request_email_verification(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let address = email::normalize_email(&payload.email)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let token = email::request_verification(db, auth_user.0.sub, &address)
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::InvalidEmail => (StatusCode::BAD_REQUEST, e.to_string()),
+            auth::AuthError::EmailTaken => (StatusCode::CONFLICT, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    let link = format!(
+        "{}/api/auth/verify/{}",
+        state.config.public_base_url.trim_end_matches('/'),
+        token
+    );
+
+    state
+        .mailer
+        .send(
+            &address,
+            "Verify your World Racers email address",
+            format!(
+                "Hi {},\n\nOpen this link to verify your email address:\n{}\n\nThe link expires in 24 hours. If you didn't ask for it, you can ignore this email.\n",
+                auth_user.0.name, link
+            ),
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Verify an email address with the token from the verification link
+#[utoipa::path(
+    get,
+    path = "/api/auth/verify/{token}",
+    tag = "auth",
+    params(
+        ("token" = String, Path, description = "Token from the verification link")
+    ),
+    responses(
+        (status = 200, description = "Email address verified", body = EmailVerifiedResponse),
+        (status = 400, description = "Invalid or already used token", body = String),
+        (status = 410, description = "Token expired", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn verify_email(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<EmailVerifiedResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let user = email::verify(db, &token).await.map_err(|e| match e {
+        auth::AuthError::InvalidToken => (StatusCode::BAD_REQUEST, e.to_string()),
+        auth::AuthError::TokenExpired => (StatusCode::GONE, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    let (Some(email), Some(verified_at)) = (user.email, user.email_verified_at) else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Email address was not verified".to_string(),
+        ));
+    };
+
+    Ok(Json(EmailVerifiedResponse { email, verified_at }))
+}
+
+/// Send a password reset link to a verified email address
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/forgot",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "Reset link sent if the address belongs to a user"),
+        (status = 400, description = "Invalid email address", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn forgot_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let result = password::request_reset(db, &payload.email)
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::InvalidEmail => (StatusCode::BAD_REQUEST, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    // Answer the same for unknown addresses, so they can't be probed
+    let Some((user, token)) = result else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let Some(address) = user.email else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let link = format!(
+        "{}/reset-password?token={}",
+        state.config.public_base_url.trim_end_matches('/'),
+        token
+    );
+
+    state
+        .mailer
+        .send(
+            &address,
+            "Reset your World Racers password",
+            format!(
+                "Hi {},\n\nOpen this link to choose a new password:\n{}\n\nThe link expires in 1 hour. If you didn't ask for it, you can ignore this email.\n",
+                user.name, link
+            ),
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Set a new password with the token from a reset link, signing out every session
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/reset",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "Invalid or already used token, or password too short", body = String),
+        (status = 410, description = "Token expired", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let user = password::reset(db, &payload.token, &payload.password)
         .await
         .map_err(|e| match e {
-            auth::AuthError::InvalidToken | auth::AuthError::RefreshTokenExpired => {
-                (StatusCode::UNAUTHORIZED, e.to_string())
+            auth::AuthError::InvalidToken | auth::AuthError::PasswordTooShort(_) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
             }
+            auth::AuthError::TokenExpired => (StatusCode::GONE, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    // Sign out the access tokens of every session as well
+    state
+        .blocklist
+        .revoke_user(user.id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Start a social login by redirecting to the provider
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/{provider}/start",
+    tag = "auth",
+    params(
+        ("provider" = String, Path, description = "OAuth provider (google or discord)")
+    ),
+    responses(
+        (status = 303, description = "Redirect to the provider's consent page, setting a cookie the callback needs"),
+        (status = 404, description = "Unknown or unconfigured provider", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn oauth_start(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Response, (StatusCode, String)> {
+    let (provider, client) = oauth_client(&state.config, &provider)?;
+
+    let auth = &state.auth;
+
+    let (oauth_state, nonce) = auth
+        .generate_oauth_state(provider)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // The provider sends the browser back to the callback with a top-level
+    // navigation, which Lax cookies survive
+    Ok((
+        [(
+            header::SET_COOKIE,
+            oauth_nonce_cookie(&state.config, &nonce, OAUTH_NONCE_MAX_AGE),
+        )],
+        Redirect::to(&client.authorize_url(&oauth_state)),
+    )
+        .into_response())
+}
+
+/// Complete a social login and issue tokens
+///
+/// Creates a new user the first time a provider account signs in.
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/{provider}/callback",
+    tag = "auth",
+    params(
+        ("provider" = String, Path, description = "OAuth provider (google or discord)"),
+        ("code" = Option<String>, Query, description = "Authorization code from the provider"),
+        ("state" = Option<String>, Query, description = "State returned by the provider"),
+        ("error" = Option<String>, Query, description = "Error returned by the provider")
+    ),
+    responses(
+        (status = 200, description = "Logged in successfully", body = AuthResponse),
+        (status = 400, description = "Missing code or state", body = String),
+        (status = 401, description = "Login was denied, or the state is invalid, used or from another browser", body = String),
+        (status = 403, description = "Account is banned", body = String),
+        (status = 404, description = "Unknown or unconfigured provider", body = String),
+        (status = 502, description = "The provider could not be reached", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn oauth_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(params): Query<OAuthCallbackParams>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    cookies: Option<TypedHeader<Cookie>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let db = &state.conn;
+    let (provider, client) = oauth_client(&state.config, &provider)?;
+
+    if let Some(error) = params.error {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            format!("Login with {} failed: {}", provider, error),
+        ));
+    }
+
+    let (Some(code), Some(oauth_state)) = (params.code, params.state) else {
+        return Err((StatusCode::BAD_REQUEST, "Missing code or state".to_string()));
+    };
+
+    let auth = &state.auth;
+
+    // Make sure the flow was started by us, in this browser, and only
+    // completed once
+    let nonce = cookies
+        .as_ref()
+        .and_then(|cookies| cookies.get(OAUTH_NONCE_COOKIE));
+    let claims = auth
+        .verify_oauth_state(&oauth_state, provider, nonce)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid OAuth state".to_string()))?;
+    state
+        .ws_tickets
+        .redeem_oauth_state(claims)
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::TokenRevoked => (
+                StatusCode::UNAUTHORIZED,
+                "OAuth state already used".to_string(),
+            ),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         })?;
 
+    let profile = client
+        .exchange_code(&code)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+
+    let result = user::login_with_oauth(
+        db,
+        auth,
+        profile,
+        session_info(&state.config, &headers, peer),
+    )
+    .await
+    .map_err(|e| match e {
+        auth::AuthError::Banned { .. } => (StatusCode::FORBIDDEN, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    record_daily_login(&state, &result).await;
+
+    // The nonce is of no use anymore
+    Ok((
+        [(header::SET_COOKIE, oauth_nonce_cookie(&state.config, "", 0))],
+        Json(AuthResponse::from(result)),
+    ))
+}
+
+/// Log in from a game client with a linked platform account
+///
+/// The account has to be linked to a user with `POST /api/linked-accounts`
+/// first.
+#[utoipa::path(
+    post,
+    path = "/api/auth/platform/{platform}/login",
+    tag = "auth",
+    params(
+        ("platform" = String, Path, description = "Platform (steam or epic)")
+    ),
+    request_body = PlatformLoginRequest,
+    responses(
+        (status = 200, description = "Logged in successfully", body = AuthResponse),
+        (status = 401, description = "Invalid ticket or the account is not linked", body = String),
+        (status = 403, description = "Account is banned", body = String),
+        (status = 404, description = "Unknown or unconfigured platform", body = String),
+        (status = 429, description = "Too many requests", body = String),
+        (status = 502, description = "The platform could not be reached", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+async fn platform_login(
+    State(state): State<AppState>,
+    Path(platform): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<PlatformLoginRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let (platform, verifier) = platform_verifier(&state, &platform)?;
+
+    let platform_user_id = verifier
+        .verify(&payload.ticket)
+        .await
+        .map_err(platform_error)?;
+
+    let auth = &state.auth;
+
+    let result = user::login_with_platform(
+        db,
+        auth,
+        platform,
+        &platform_user_id,
+        session_info(&state.config, &headers, peer),
+    )
+    .await
+    .map_err(|e| match e {
+        auth::AuthError::InvalidCredentials => (
+            StatusCode::UNAUTHORIZED,
+            format!("No user is linked to this {} account", platform),
+        ),
+        auth::AuthError::Banned { .. } => (StatusCode::FORBIDDEN, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    record_daily_login(&state, &result).await;
+
     Ok(Json(result.into()))
 }
+
+// Helper function to award the daily login XP to the user tokens were issued
+// to. Logins never fail because of it.
+async fn record_daily_login(state: &AppState, tokens: &auth::AuthResponse) {
+    let Ok(claims) = state.auth.verify_token(&tokens.access_token) else {
+        return;
+    };
+
+    if let Err(e) = progression::record_daily_login(&state.conn, claims.sub).await {
+        tracing::error!("Error awarding daily login XP: {}", e);
+    }
+}
+
+// Helper function to answer logins rejected by the lockout
+fn login_locked(e: auth::AuthError) -> Response {
+    let (status, error, retry_after) = match e {
+        auth::AuthError::AccountLocked(retry_after) => {
+            (StatusCode::LOCKED, "account_locked", retry_after)
+        }
+        auth::AuthError::TooManyFailedLogins(retry_after) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            "too_many_failed_logins",
+            retry_after,
+        ),
+        _ => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    (
+        status,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        Json(LoginLockedResponse {
+            error: error.to_string(),
+            message: e.to_string(),
+            retry_after,
+        }),
+    )
+        .into_response()
+}
+
+// Helper function to describe where a login comes from. Clients may name the
+// device with an X-Device-Name header; the user agent is used otherwise.
+fn session_info(config: &Config, headers: &HeaderMap, peer: SocketAddr) -> user::SessionInfo {
+    let device_name = headers
+        .get("x-device-name")
+        .or_else(|| headers.get(header::USER_AGENT))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().chars().take(MAX_DEVICE_NAME_LENGTH).collect())
+        .filter(|value: &String| !value.is_empty());
+
+    user::SessionInfo {
+        device_name,
+        ip_address: Some(client_ip::from_request(
+            &config.trusted_proxies,
+            headers,
+            peer,
+        )),
+    }
+}
+
+// Helper function to build the cookie keeping the nonce of an OAuth flow.
+// Scripts can't read it, and it is only sent over HTTPS if the API is served
+// over it.
+fn oauth_nonce_cookie(config: &Config, nonce: &str, max_age: u64) -> String {
+    let secure = config
+        .oauth_redirect_base_url
+        .as_deref()
+        .is_some_and(|url| url.starts_with("https://"));
+
+    format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        OAUTH_NONCE_COOKIE,
+        nonce,
+        OAUTH_NONCE_PATH,
+        max_age,
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+// Helper function to build the client of a configured provider
+fn oauth_client(
+    config: &Config,
+    provider: &str,
+) -> Result<(OAuthProvider, OAuthClient), (StatusCode, String)> {
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("OAuth provider {} is not available", provider),
+        )
+    };
+
+    let provider: OAuthProvider = provider.parse().map_err(|_| not_found())?;
+
+    let (client_id, client_secret) = match provider {
+        OAuthProvider::Google => (&config.google_client_id, &config.google_client_secret),
+        OAuthProvider::Discord => (&config.discord_client_id, &config.discord_client_secret),
+    };
+
+    let (Some(client_id), Some(client_secret), Some(base_url)) =
+        (client_id, client_secret, &config.oauth_redirect_base_url)
+    else {
+        return Err(not_found());
+    };
+
+    let redirect_uri = format!(
+        "{}/api/auth/oauth/{}/callback",
+        base_url.trim_end_matches('/'),
+        provider
+    );
+
+    Ok((
+        provider,
+        OAuthClient::new(
+            provider,
+            client_id.clone(),
+            client_secret.clone(),
+            redirect_uri,
+        ),
+    ))
+}
@@ -0,0 +1,217 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use entity::block::{self, Entity as Block};
+use entity::follow::{self, Entity as Follow};
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::users::UserResponse;
+use crate::db::AppState;
+
+#[derive(Deserialize, ToSchema)]
+pub struct BlockUserRequest {
+    user_id: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockResponse {
+    user: UserResponse,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/blocks", get(list_blocks))
+        .route("/blocks", post(block_user))
+        .route("/blocks/{user_id}", delete(unblock_user))
+}
+
+/// List the users the current user has blocked
+#[utoipa::path(
+    get,
+    path = "/api/blocks",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "Blocked users retrieved successfully", body = Vec<BlockResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_blocks(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<BlockResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let blocks = Block::find()
+        .filter(block::Column::BlockerId.eq(auth_user.0.sub))
+        .order_by_asc(block::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(blocks.iter().map(|block| block.blocked_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let blocks = blocks
+        .into_iter()
+        .filter_map(|block| {
+            Some(BlockResponse {
+                user: users.remove(&block.blocked_id)?.into(),
+                created_at: block.created_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(blocks))
+}
+
+/// Block a user
+///
+/// Blocked users can't join parties owned by the current user, and are left
+/// out of the party member lists the current user sees. Follows between the
+/// two users end.
+#[utoipa::path(
+    post,
+    path = "/api/blocks",
+    tag = "blocks",
+    request_body = BlockUserRequest,
+    responses(
+        (status = 200, description = "User blocked successfully", body = BlockResponse),
+        (status = 400, description = "Users can't block themselves", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn block_user(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<BlockUserRequest>,
+) -> Result<Json<BlockResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    if payload.user_id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "You can't block yourself".to_string(),
+        ));
+    }
+
+    let blocked_user = User::find_by_id(payload.user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", payload.user_id),
+        ))?;
+
+    // Blocking a user again keeps the original block
+    let existing = Block::find()
+        .filter(block::Column::BlockerId.eq(user_id))
+        .filter(block::Column::BlockedId.eq(blocked_user.id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let block = match existing {
+        Some(block) => block,
+        None => block::ActiveModel {
+            blocker_id: Set(user_id),
+            blocked_id: Set(blocked_user.id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+    };
+
+    Follow::delete_many()
+        .filter(
+            Condition::any()
+                .add(
+                    Condition::all()
+                        .add(follow::Column::FollowerId.eq(user_id))
+                        .add(follow::Column::FollowedId.eq(blocked_user.id)),
+                )
+                .add(
+                    Condition::all()
+                        .add(follow::Column::FollowerId.eq(blocked_user.id))
+                        .add(follow::Column::FollowedId.eq(user_id)),
+                ),
+        )
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(BlockResponse {
+        user: blocked_user.into(),
+        created_at: block.created_at,
+    }))
+}
+
+/// Unblock a user
+#[utoipa::path(
+    delete,
+    path = "/api/blocks/{user_id}",
+    tag = "blocks",
+    params(
+        ("user_id" = i32, Path, description = "ID of the blocked user")
+    ),
+    responses(
+        (status = 204, description = "User unblocked"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User is not blocked", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unblock_user(
+    State(state): State<AppState>,
+    Path(blocked_id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let result = Block::delete_many()
+        .filter(block::Column::BlockerId.eq(auth_user.0.sub))
+        .filter(block::Column::BlockedId.eq(blocked_id))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not blocked", blocked_id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
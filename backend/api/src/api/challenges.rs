@@ -0,0 +1,303 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use entity::challenge_result::{self, Entity as ChallengeResult};
+use entity::daily_challenge;
+use entity::map::Entity as Map;
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait,
+    sea_query::{Expr, Func, SimpleExpr},
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::leaderboards::{LeaderboardParams, LeaderboardWindow};
+use super::maps::{self, MapResponse};
+use super::pagination::{Paginated, PaginationParams};
+use super::users::UserResponse;
+use crate::challenges;
+use crate::db::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct DailyChallengeResponse {
+    /// Day of the challenge in UTC
+    day: NaiveDate,
+    map: MapResponse,
+    /// When the next challenge starts
+    ends_at: DateTime<Utc>,
+    /// Best time of the current user on the challenge, if they finished it
+    best_time_ms: Option<i64>,
+}
+
+/// Place of a user on the leaderboard of daily challenges
+#[derive(Serialize, ToSchema)]
+pub struct ChallengeStandingResponse {
+    rank: u64,
+    user: UserResponse,
+    /// Best time of the user on the challenge, left out for windows longer
+    /// than a day as their challenges are on different maps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finish_time_ms: Option<i64>,
+    /// When the user set their best time, or finished the last of their
+    /// challenges within the window
+    finished_at: DateTime<chrono::FixedOffset>,
+    /// Challenges the user finished within the window
+    challenges_finished: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/challenges/today", get(get_today))
+        .route("/challenges/{day}/leaderboard", get(get_leaderboard))
+}
+
+/// Get today's challenge
+///
+/// Every day in UTC, one published map is the challenge of the day. Races
+/// finished on it during the day count towards its leaderboard.
+#[utoipa::path(
+    get,
+    path = "/api/challenges/today",
+    tag = "challenges",
+    responses(
+        (status = 200, description = "Challenge retrieved successfully", body = DailyChallengeResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "No map to challenge players on", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_today(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<DailyChallengeResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let today = Utc::now().date_naive();
+
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            "There is no challenge today".to_string(),
+        )
+    };
+
+    let challenge = challenges::challenge_of(db, today)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(not_found)?;
+
+    let map = Map::find_by_id(challenge.map_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(not_found)?;
+
+    let mut map = MapResponse::from(map);
+    maps::mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    maps::attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    maps::attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let best_time_ms = ChallengeResult::find()
+        .filter(challenge_result::Column::ChallengeId.eq(challenge.id))
+        .filter(challenge_result::Column::UserId.eq(auth_user.0.sub))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(|result| result.finish_time_ms);
+
+    let ends_at = (today + Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or_else(Utc::now);
+
+    Ok(Json(DailyChallengeResponse {
+        day: today,
+        map,
+        ends_at,
+        best_time_ms,
+    }))
+}
+
+/// Rank the players of a daily challenge by their best time
+///
+/// The window defaults to the challenge of the day. Longer windows cover the
+/// challenges from the start of the window up to the day, and rank players by
+/// how many of them they finished. Equal counts rank whoever got there first.
+#[utoipa::path(
+    get,
+    path = "/api/challenges/{day}/leaderboard",
+    tag = "challenges",
+    params(
+        ("day" = NaiveDate, Path, description = "Day of the challenge, e.g. 2025-05-30"),
+        LeaderboardParams,
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Leaderboard retrieved successfully", body = Paginated<ChallengeStandingResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "No challenge on that day", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_leaderboard(
+    State(state): State<AppState>,
+    Path(day): Path<NaiveDate>,
+    Query(params): Query<LeaderboardParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<ChallengeStandingResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let window = params.window.unwrap_or(LeaderboardWindow::Today);
+    if window != LeaderboardWindow::Today {
+        return window_leaderboard(db, window.first_day(day), day, &pagination)
+            .await
+            .map(Json)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+    }
+
+    let challenge = challenges::find_challenge(db, day)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("There was no challenge on {}", day),
+        ))?;
+
+    // Earlier times rank first among equal ones
+    let paginator = ChallengeResult::find()
+        .filter(challenge_result::Column::ChallengeId.eq(challenge.id))
+        .find_also_related(User)
+        .filter(user::Column::DeletedAt.is_null())
+        .order_by_asc(challenge_result::Column::FinishTimeMs)
+        .order_by_asc(challenge_result::Column::FinishedAt)
+        .order_by_asc(challenge_result::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let results = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let offset = (pagination.page() - 1) * pagination.per_page();
+    let standings = results
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (result, user))| {
+            Some(ChallengeStandingResponse {
+                rank: offset + index as u64 + 1,
+                user: user?.into(),
+                finish_time_ms: Some(result.finish_time_ms),
+                finished_at: result.finished_at,
+                challenges_finished: 1,
+            })
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        standings,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+// Helper function to rank players by the challenges they finished from the
+// first day, if any, up to the last day
+async fn window_leaderboard(
+    db: &DatabaseConnection,
+    first_day: Option<NaiveDate>,
+    last_day: NaiveDate,
+    pagination: &PaginationParams,
+) -> Result<Paginated<ChallengeStandingResponse>, DbErr> {
+    let mut query = ChallengeResult::find()
+        .select_only()
+        .column(challenge_result::Column::UserId)
+        .column_as(
+            SimpleExpr::from(Func::count(Expr::col((
+                ChallengeResult,
+                challenge_result::Column::Id,
+            )))),
+            "challenges_finished",
+        )
+        .column_as(
+            SimpleExpr::from(Func::max(Expr::col((
+                ChallengeResult,
+                challenge_result::Column::FinishedAt,
+            )))),
+            "finished_at",
+        )
+        .join(
+            JoinType::InnerJoin,
+            challenge_result::Relation::DailyChallenge.def(),
+        )
+        .join(JoinType::InnerJoin, challenge_result::Relation::User.def())
+        .filter(user::Column::DeletedAt.is_null())
+        .filter(daily_challenge::Column::Day.lte(last_day));
+    if let Some(first_day) = first_day {
+        query = query.filter(daily_challenge::Column::Day.gte(first_day));
+    }
+
+    let paginator = query
+        .group_by(challenge_result::Column::UserId)
+        .order_by_desc(Expr::cust("challenges_finished"))
+        .order_by_asc(Expr::cust("finished_at"))
+        .order_by_asc(challenge_result::Column::UserId)
+        .into_tuple::<(i32, i64, DateTime<chrono::FixedOffset>)>()
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator.num_items_and_pages().await?;
+    let results = paginator.fetch_page(pagination.page() - 1).await?;
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(results.iter().map(|(user_id, _, _)| *user_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let offset = (pagination.page() - 1) * pagination.per_page();
+    let standings = results
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (user_id, challenges_finished, finished_at))| {
+            Some(ChallengeStandingResponse {
+                rank: offset + index as u64 + 1,
+                user: users.remove(&user_id)?.into(),
+                finish_time_ms: None,
+                finished_at,
+                challenges_finished,
+            })
+        })
+        .collect();
+
+    Ok(Paginated::new(
+        standings,
+        pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    ))
+}
@@ -0,0 +1,1012 @@
+use auth::middleware::AuthUser;
+use auth::validation::{FieldError, ValidationCode};
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use entity::crew::{self, Entity as Crew};
+use entity::crew_member::{self, Entity as CrewMember};
+use entity::race::{self, Entity as Race};
+use entity::race_participant::{self, Entity as RaceParticipant};
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, JoinType, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, SqlErr,
+    TransactionTrait,
+    sea_query::{Alias, Expr, Func, LikeExpr, Query as SubQuery, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+
+use super::leaderboards::{LeaderboardParams, LeaderboardWindow};
+use super::pagination::{Paginated, PaginationParams};
+use super::users::UserResponse;
+use crate::blocking;
+use crate::crews::{self, CrewRole};
+use crate::db::AppState;
+use crate::validation::{self, ValidationErrorResponse};
+
+// Limits of the crew fields
+const MAX_CREW_NAME_LENGTH: usize = 32;
+const MIN_CREW_TAG_LENGTH: usize = 2;
+const MAX_CREW_TAG_LENGTH: usize = 5;
+const MAX_CREW_DESCRIPTION_LENGTH: usize = 280;
+
+// Most members a crew may have
+const MAX_CREW_MEMBERS: u64 = 50;
+
+#[derive(Serialize, ToSchema)]
+pub struct CrewResponse {
+    id: i32,
+    name: String,
+    /// Short uppercase tag shown next to member names
+    tag: String,
+    description: String,
+    member_count: i64,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrewMemberResponse {
+    user: UserResponse,
+    role: CrewRole,
+    joined_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrewWithMembersResponse {
+    #[serde(flatten)]
+    crew: CrewResponse,
+    members: Vec<CrewMemberResponse>,
+}
+
+/// Place of a crew on the leaderboard
+#[derive(Serialize, ToSchema)]
+pub struct CrewStandingResponse {
+    rank: u64,
+    crew: CrewResponse,
+    /// Races won by the current members within the window
+    race_wins: i64,
+    /// XP of the current members, of all time
+    xp: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateCrewRequest {
+    name: String,
+    /// 2 to 5 letters or digits
+    tag: String,
+    description: Option<String>,
+}
+
+// Fields left out are kept; a description set to an empty string is cleared
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCrewRequest {
+    name: Option<String>,
+    tag: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CrewRoleRequest {
+    /// Making a member the owner hands the crew over, and makes the current
+    /// owner an officer
+    role: CrewRole,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CrewSearchParams {
+    /// Only crews whose name or tag contains this, ignoring case
+    q: Option<String>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/crews", get(list_crews).post(create_crew))
+        .route("/crews/leaderboard", get(crew_leaderboard))
+        .route(
+            "/crews/{id}",
+            get(get_crew).patch(update_crew).delete(delete_crew),
+        )
+        .route("/crews/{id}/join", post(join_crew))
+        .route("/crews/{id}/leave", post(leave_crew))
+        .route("/crews/{id}/members/{user_id}", delete(remove_member))
+        .route("/crews/{id}/members/{user_id}/role", put(set_member_role))
+}
+
+/// List crews, optionally searching by name or tag
+#[utoipa::path(
+    get,
+    path = "/api/crews",
+    tag = "crews",
+    params(CrewSearchParams, PaginationParams),
+    responses(
+        (status = 200, description = "Crews retrieved successfully", body = Paginated<CrewResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_crews(
+    State(state): State<AppState>,
+    Query(search): Query<CrewSearchParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<CrewResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let mut query = Crew::find().order_by_asc(crew::Column::Id);
+
+    if let Some(q) = search.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        // Match the search literally, not as a pattern
+        let escaped = q
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("%{}%", escaped);
+        query = query.filter(
+            Expr::expr(Func::lower(Expr::col(crew::Column::Name)))
+                .like(LikeExpr::new(pattern.clone()).escape('\\'))
+                .or(Expr::expr(Func::lower(Expr::col(crew::Column::Tag)))
+                    .like(LikeExpr::new(pattern).escape('\\'))),
+        );
+    }
+
+    let paginator = query.paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let crews = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let crews = with_member_counts(db, crews)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(Paginated::new(
+        crews,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Rank crews by the races their members won, then by their XP
+///
+/// The window limits which race wins count, while XP always counts all
+/// time.
+#[utoipa::path(
+    get,
+    path = "/api/crews/leaderboard",
+    tag = "crews",
+    params(LeaderboardParams, PaginationParams),
+    responses(
+        (status = 200, description = "Leaderboard retrieved successfully", body = Paginated<CrewStandingResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn crew_leaderboard(
+    State(state): State<AppState>,
+    Query(params): Query<LeaderboardParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<CrewStandingResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Wins of all time are counted on the users, those of a window are
+    // counted from the stored race results
+    let window = params.window.unwrap_or(LeaderboardWindow::AllTime);
+    let race_wins = match window
+        .first_day(chrono::Utc::now().date_naive())
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+    {
+        Some(since) => SimpleExpr::SubQuery(
+            None,
+            Box::new(
+                SubQuery::select()
+                    .expr(Func::count(Expr::col((
+                        RaceParticipant,
+                        race_participant::Column::Id,
+                    ))))
+                    .from(RaceParticipant)
+                    .inner_join(
+                        Race,
+                        Expr::col((Race, race::Column::Id))
+                            .equals((RaceParticipant, race_participant::Column::RaceId)),
+                    )
+                    .and_where(
+                        Expr::col((RaceParticipant, race_participant::Column::UserId))
+                            .equals((user::Entity, user::Column::Id)),
+                    )
+                    .and_where(
+                        Expr::col((RaceParticipant, race_participant::Column::Position)).eq(1),
+                    )
+                    .and_where(Expr::col((Race, race::Column::StartedAt)).gte(since.and_utc()))
+                    .to_owned()
+                    .into_sub_query_statement(),
+            ),
+        ),
+        None => Expr::col((user::Entity, user::Column::RaceWins)).into(),
+    };
+
+    // Sums of bigints are numeric in Postgres, so they are cast back
+    let paginator = CrewMember::find()
+        .select_only()
+        .column(crew_member::Column::CrewId)
+        .column_as(
+            SimpleExpr::from(Func::cast_as(Func::sum(race_wins), Alias::new("bigint"))),
+            "race_wins",
+        )
+        .column_as(
+            SimpleExpr::from(Func::cast_as(
+                Func::sum(Expr::col((user::Entity, user::Column::Xp))),
+                Alias::new("bigint"),
+            )),
+            "xp",
+        )
+        .join(JoinType::InnerJoin, crew_member::Relation::User.def())
+        .filter(user::Column::DeletedAt.is_null())
+        .group_by(crew_member::Column::CrewId)
+        .order_by_desc(Expr::cust("race_wins"))
+        .order_by_desc(Expr::cust("xp"))
+        .order_by_asc(crew_member::Column::CrewId)
+        .into_tuple::<(i32, i64, i64)>()
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let standings = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let crews = Crew::find()
+        .filter(crew::Column::Id.is_in(standings.iter().map(|(crew_id, _, _)| *crew_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut crews: HashMap<i32, CrewResponse> = with_member_counts(db, crews)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|crew| (crew.id, crew))
+        .collect();
+
+    let offset = (pagination.page() - 1) * pagination.per_page();
+    let standings = standings
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (crew_id, race_wins, xp))| {
+            Some(CrewStandingResponse {
+                rank: offset + index as u64 + 1,
+                crew: crews.remove(&crew_id)?,
+                race_wins,
+                xp,
+            })
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        standings,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Get a crew with its members
+#[utoipa::path(
+    get,
+    path = "/api/crews/{id}",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 200, description = "Crew found", body = CrewWithMembersResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Crew not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_crew(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<CrewWithMembersResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let crew = find_crew(db, id).await?;
+
+    crew_with_members(db, crew).await.map(Json)
+}
+
+/// Create a crew, led by the current user
+#[utoipa::path(
+    post,
+    path = "/api/crews",
+    tag = "crews",
+    request_body = CreateCrewRequest,
+    responses(
+        (status = 200, description = "Crew created successfully", body = CrewWithMembersResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 409, description = "Already in a crew, or name or tag taken", body = String),
+        (status = 422, description = "Invalid name, tag or description", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_crew(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<CreateCrewRequest>,
+) -> Result<Json<CrewWithMembersResponse>, Response> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let name = state
+        .validator
+        .text("name", &payload.name, MAX_CREW_NAME_LENGTH)
+        .map_err(|e| validation::invalid(vec![e]))?;
+    let tag = check_tag(&state, &payload.tag).map_err(|e| validation::invalid(vec![e]))?;
+    let description = payload
+        .description
+        .map(|description| check_description(&state, &description))
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?
+        .unwrap_or_default();
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let crew = crew::ActiveModel {
+        name: Set(name),
+        tag: Set(tag),
+        description: Set(description),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| conflict_or_internal(e, "A crew with this name or tag already exists"))?;
+
+    crew_member::ActiveModel {
+        crew_id: Set(crew.id),
+        user_id: Set(user_id),
+        role: Set(CrewRole::Owner.as_str().to_string()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| conflict_or_internal(e, "You are already in a crew"))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    crew_with_members(db, crew)
+        .await
+        .map(Json)
+        .map_err(IntoResponse::into_response)
+}
+
+/// Update a crew (only by owner)
+#[utoipa::path(
+    patch,
+    path = "/api/crews/{id}",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID")
+    ),
+    request_body = UpdateCrewRequest,
+    responses(
+        (status = 200, description = "Crew updated successfully", body = CrewResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the crew owner can update the crew", body = String),
+        (status = 404, description = "Crew not found", body = String),
+        (status = 409, description = "Name or tag taken", body = String),
+        (status = 422, description = "Invalid name, tag or description", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_crew(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdateCrewRequest>,
+) -> Result<Json<CrewResponse>, Response> {
+    let db = &state.conn;
+
+    let crew = find_crew(db, id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    require_role(db, crew.id, auth_user.0.sub, &[CrewRole::Owner])
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let mut errors = Vec::new();
+    let name = payload
+        .name
+        .map(|name| state.validator.text("name", &name, MAX_CREW_NAME_LENGTH))
+        .transpose()
+        .unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        });
+    let tag = payload
+        .tag
+        .map(|tag| check_tag(&state, &tag))
+        .transpose()
+        .unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        });
+    let description = payload
+        .description
+        .map(|description| check_description(&state, &description))
+        .transpose()
+        .unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        });
+    if !errors.is_empty() {
+        return Err(validation::invalid(errors));
+    }
+
+    let mut crew_model: crew::ActiveModel = crew.into();
+
+    if let Some(name) = name {
+        crew_model.name = Set(name);
+    }
+
+    if let Some(tag) = tag {
+        crew_model.tag = Set(tag);
+    }
+
+    if let Some(description) = description {
+        crew_model.description = Set(description);
+    }
+
+    let crew = crew_model
+        .update(db)
+        .await
+        .map_err(|e| conflict_or_internal(e, "A crew with this name or tag already exists"))?;
+
+    let crew = with_member_counts(db, vec![crew])
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .pop()
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Crew with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    Ok(Json(crew))
+}
+
+/// Delete a crew (only by owner)
+#[utoipa::path(
+    delete,
+    path = "/api/crews/{id}",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 204, description = "Crew deleted successfully"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the crew owner can delete the crew", body = String),
+        (status = 404, description = "Crew not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_crew(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let crew = find_crew(db, id).await?;
+    require_role(db, crew.id, auth_user.0.sub, &[CrewRole::Owner]).await?;
+
+    // Members go away with the crew
+    Crew::delete_by_id(crew.id)
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Join a crew
+#[utoipa::path(
+    post,
+    path = "/api/crews/{id}/join",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 200, description = "Joined the crew", body = CrewWithMembersResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Blocked by the crew owner", body = String),
+        (status = 404, description = "Crew not found", body = String),
+        (status = 409, description = "Already in a crew, or crew is full", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn join_crew(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<CrewWithMembersResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let crew = find_crew(db, id).await?;
+
+    // Users blocked by the owner can't join
+    let owner_id = CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew.id))
+        .filter(crew_member::Column::Role.eq(CrewRole::Owner.as_str()))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(|owner| owner.user_id);
+    if let Some(owner_id) = owner_id
+        && blocking::has_blocked(db, owner_id, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You can't join this crew".to_string(),
+        ));
+    }
+
+    // Lock the crew so concurrent joins can't overshoot the member limit
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let _ = Crew::find_by_id(crew.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let members = CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew.id))
+        .count(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if members >= MAX_CREW_MEMBERS {
+        return Err((StatusCode::CONFLICT, "Crew is full".to_string()));
+    }
+
+    crew_member::ActiveModel {
+        crew_id: Set(crew.id),
+        user_id: Set(user_id),
+        role: Set(CrewRole::Member.as_str().to_string()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => (
+            StatusCode::CONFLICT,
+            "You are already in a crew".to_string(),
+        ),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    crew_with_members(db, crew).await.map(Json)
+}
+
+/// Leave a crew
+///
+/// If the owner leaves, the longest-standing officer takes over, or else the
+/// longest-standing member. The crew is deleted when its last member leaves.
+#[utoipa::path(
+    post,
+    path = "/api/crews/{id}/leave",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 204, description = "Left the crew"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Not a member of the crew", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn leave_crew(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    remove_from_crew(&state.conn, id, auth_user.0.sub).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a member from a crew
+///
+/// The owner can remove anyone else; officers can remove members.
+#[utoipa::path(
+    delete,
+    path = "/api/crews/{id}/members/{user_id}",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID"),
+        ("user_id" = i32, Path, description = "User ID of the member")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 400, description = "Use leave to remove yourself", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not allowed to remove this member", body = String),
+        (status = 404, description = "Crew or member not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    if user_id == auth_user.0.sub {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Leave the crew to remove yourself".to_string(),
+        ));
+    }
+
+    let crew = find_crew(db, id).await?;
+    let caller = require_role(
+        db,
+        crew.id,
+        auth_user.0.sub,
+        &[CrewRole::Owner, CrewRole::Officer],
+    )
+    .await?;
+    let member = find_member(db, crew.id, user_id).await?;
+
+    // Officers can only remove members below them
+    if caller != CrewRole::Owner && CrewRole::from_column(&member.role) != CrewRole::Member {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the crew owner can remove officers".to_string(),
+        ));
+    }
+
+    member
+        .delete(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Change the role of a member of a crew (only by owner)
+#[utoipa::path(
+    put,
+    path = "/api/crews/{id}/members/{user_id}/role",
+    tag = "crews",
+    params(
+        ("id" = i32, Path, description = "Crew ID"),
+        ("user_id" = i32, Path, description = "User ID of the member")
+    ),
+    request_body = CrewRoleRequest,
+    responses(
+        (status = 200, description = "Role changed successfully", body = CrewWithMembersResponse),
+        (status = 400, description = "The owner's own role can't be changed", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the crew owner can change roles", body = String),
+        (status = 404, description = "Crew or member not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_member_role(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth_user: AuthUser,
+    Json(payload): Json<CrewRoleRequest>,
+) -> Result<Json<CrewWithMembersResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let crew = find_crew(db, id).await?;
+    require_role(db, crew.id, auth_user.0.sub, &[CrewRole::Owner]).await?;
+
+    if user_id == auth_user.0.sub {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hand the crew over to change your own role".to_string(),
+        ));
+    }
+
+    let member = find_member(db, crew.id, user_id).await?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // There is one owner, who steps down to officer when handing over
+    if payload.role == CrewRole::Owner {
+        CrewMember::update_many()
+            .col_expr(
+                crew_member::Column::Role,
+                Expr::value(CrewRole::Officer.as_str()),
+            )
+            .filter(crew_member::Column::CrewId.eq(crew.id))
+            .filter(crew_member::Column::UserId.eq(auth_user.0.sub))
+            .exec(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    let mut member: crew_member::ActiveModel = member.into();
+    member.role = Set(payload.role.as_str().to_string());
+    member
+        .update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    crew_with_members(db, crew).await.map(Json)
+}
+
+// Helper function to take a user out of a crew, handing it over if they
+// owned it
+async fn remove_from_crew(
+    db: &DatabaseConnection,
+    crew_id: i32,
+    user_id: i32,
+) -> Result<(), (StatusCode, String)> {
+    let member = find_member(db, crew_id, user_id).await?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    member
+        .delete(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    crews::hand_over(&txn, crew_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+// Helper function to check a crew tag and return it uppercased
+fn check_tag(state: &AppState, tag: &str) -> Result<String, FieldError> {
+    let tag = state
+        .validator
+        .text("tag", tag, MAX_CREW_TAG_LENGTH)?
+        .to_uppercase();
+
+    if tag.chars().count() < MIN_CREW_TAG_LENGTH {
+        return Err(FieldError {
+            field: "tag".to_string(),
+            code: ValidationCode::Length,
+            message: format!(
+                "Must be between {} and {} characters long",
+                MIN_CREW_TAG_LENGTH, MAX_CREW_TAG_LENGTH
+            ),
+        });
+    }
+
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(FieldError {
+            field: "tag".to_string(),
+            code: ValidationCode::Charset,
+            message: "May only contain letters and digits".to_string(),
+        });
+    }
+
+    Ok(tag)
+}
+
+// Helper function to check a crew description; an empty one is allowed
+fn check_description(state: &AppState, description: &str) -> Result<String, FieldError> {
+    if description.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    state
+        .validator
+        .text("description", description, MAX_CREW_DESCRIPTION_LENGTH)
+}
+
+// Helper function to answer unique constraint violations with 409
+fn conflict_or_internal(e: DbErr, conflict: &str) -> Response {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            (StatusCode::CONFLICT, conflict.to_string()).into_response()
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Helper function to find a crew by ID
+async fn find_crew(db: &DatabaseConnection, id: i32) -> Result<crew::Model, (StatusCode, String)> {
+    Crew::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Crew with id {} not found", id),
+        ))
+}
+
+// Helper function to find the membership of a user in a crew
+async fn find_member(
+    db: &DatabaseConnection,
+    crew_id: i32,
+    user_id: i32,
+) -> Result<crew_member::Model, (StatusCode, String)> {
+    CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew_id))
+        .filter(crew_member::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not a member of this crew", user_id),
+        ))
+}
+
+// Helper function to check that a user has one of the given roles in a crew
+async fn require_role(
+    db: &DatabaseConnection,
+    crew_id: i32,
+    user_id: i32,
+    roles: &[CrewRole],
+) -> Result<CrewRole, (StatusCode, String)> {
+    let role = CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew_id))
+        .filter(crew_member::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(|member| CrewRole::from_column(&member.role));
+
+    match role {
+        Some(role) if roles.contains(&role) => Ok(role),
+        _ => Err((
+            StatusCode::FORBIDDEN,
+            "You are not allowed to manage this crew".to_string(),
+        )),
+    }
+}
+
+// Helper function to add the member counts to crews
+async fn with_member_counts(
+    db: &DatabaseConnection,
+    crews: Vec<crew::Model>,
+) -> Result<Vec<CrewResponse>, DbErr> {
+    let counts: HashMap<i32, i64> = CrewMember::find()
+        .select_only()
+        .column(crew_member::Column::CrewId)
+        .column_as(crew_member::Column::Id.count(), "member_count")
+        .filter(crew_member::Column::CrewId.is_in(crews.iter().map(|crew| crew.id)))
+        .group_by(crew_member::Column::CrewId)
+        .into_tuple::<(i32, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(crews
+        .into_iter()
+        .map(|crew| CrewResponse {
+            member_count: counts.get(&crew.id).copied().unwrap_or(0),
+            id: crew.id,
+            name: crew.name,
+            tag: crew.tag,
+            description: crew.description,
+            created_at: crew.created_at,
+        })
+        .collect())
+}
+
+// Helper function to load the members of a crew, the owner first
+async fn crew_with_members(
+    db: &DatabaseConnection,
+    crew: crew::Model,
+) -> Result<CrewWithMembersResponse, (StatusCode, String)> {
+    let members = CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew.id))
+        .find_also_related(User)
+        .order_by_asc(crew_member::Column::JoinedAt)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut members: Vec<CrewMemberResponse> = members
+        .into_iter()
+        .filter_map(|(member, user)| {
+            let user = user.filter(|user| user.deleted_at.is_none())?;
+
+            Some(CrewMemberResponse {
+                user: user.into(),
+                role: CrewRole::from_column(&member.role),
+                joined_at: member.joined_at,
+            })
+        })
+        .collect();
+    members.sort_by_key(|member| match member.role {
+        CrewRole::Owner => 0,
+        CrewRole::Officer => 1,
+        CrewRole::Member => 2,
+    });
+
+    let crew = with_member_counts(db, vec![crew])
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .pop()
+        .ok_or((StatusCode::NOT_FOUND, "Crew not found".to_string()))?;
+
+    Ok(CrewWithMembersResponse { crew, members })
+}
@@ -0,0 +1,412 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use entity::{
+    activity, api_key, block, checkpoint, follow, linked_account, map, map_favorite, party,
+    playlist, playlist_map, race_award, race_participant, race_replay, recent_player,
+    refresh_token, user, user_achievement, user_identity, user_name_history, user_party,
+    user_settings, vehicle_loadout, wallet, wallet_transaction,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::races::ReplayFrameResponse;
+use crate::db::AppState;
+use crate::{replays, splits};
+
+/// Everything stored about a user. Secrets such as password and token hashes
+/// are left out.
+#[derive(Serialize, ToSchema)]
+pub struct UserExport {
+    exported_at: chrono::DateTime<chrono::Utc>,
+    profile: ExportedProfile,
+    #[schema(value_type = Option<Object>)]
+    settings: Option<user_settings::Model>,
+    #[schema(value_type = Vec<Object>)]
+    name_history: Vec<user_name_history::Model>,
+    maps: Vec<ExportedMap>,
+    playlists: Vec<ExportedPlaylist>,
+    /// Parties the user owns
+    #[schema(value_type = Vec<Object>)]
+    parties: Vec<party::Model>,
+    #[schema(value_type = Vec<Object>)]
+    party_memberships: Vec<user_party::Model>,
+    /// How the user did in each race they took part in
+    race_results: Vec<ExportedRaceResult>,
+    /// Positions of the user recorded in race replays
+    replays: Vec<ExportedReplay>,
+    #[schema(value_type = Vec<Object>)]
+    race_awards: Vec<race_award::Model>,
+    #[schema(value_type = Vec<Object>)]
+    achievements: Vec<user_achievement::Model>,
+    #[schema(value_type = Vec<Object>)]
+    activity: Vec<activity::Model>,
+    #[schema(value_type = Vec<Object>)]
+    loadouts: Vec<vehicle_loadout::Model>,
+    #[schema(value_type = Option<Object>)]
+    wallet: Option<wallet::Model>,
+    #[schema(value_type = Vec<Object>)]
+    wallet_transactions: Vec<wallet_transaction::Model>,
+    #[schema(value_type = Vec<Object>)]
+    favorites: Vec<map_favorite::Model>,
+    #[schema(value_type = Vec<Object>)]
+    blocks: Vec<block::Model>,
+    /// Users the user follows
+    #[schema(value_type = Vec<Object>)]
+    follows: Vec<follow::Model>,
+    /// Players the user raced with
+    #[schema(value_type = Vec<Object>)]
+    recent_players: Vec<recent_player::Model>,
+    #[schema(value_type = Vec<Object>)]
+    linked_accounts: Vec<linked_account::Model>,
+    /// Accounts of OAuth providers the user signs in with
+    #[schema(value_type = Vec<Object>)]
+    identities: Vec<user_identity::Model>,
+    sessions: Vec<ExportedSession>,
+    api_keys: Vec<ExportedApiKey>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedProfile {
+    id: i32,
+    name: String,
+    email: Option<String>,
+    email_verified_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    avatar_url: Option<String>,
+    country: Option<String>,
+    bio: Option<String>,
+    favorite_vehicle: Option<String>,
+    xp: i64,
+    level: i32,
+    race_wins: i64,
+    checkpoints_passed: i64,
+    is_admin: bool,
+    banned_until: Option<chrono::DateTime<chrono::FixedOffset>>,
+    ban_reason: Option<String>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedMap {
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    map: map::Model,
+    #[schema(value_type = Vec<Object>)]
+    checkpoints: Vec<checkpoint::Model>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedPlaylist {
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    playlist: playlist::Model,
+    #[schema(value_type = Vec<Object>)]
+    maps: Vec<playlist_map::Model>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedRaceResult {
+    race_id: i32,
+    position: Option<i32>,
+    finish_time_ms: Option<i64>,
+    dnf: bool,
+    /// When the user passed each checkpoint since the start
+    splits_ms: Vec<Option<u64>>,
+    submitted_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedReplay {
+    race_id: i32,
+    sample_interval_ms: i32,
+    frames: Vec<ReplayFrameResponse>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedSession {
+    device_name: Option<String>,
+    ip_address: Option<String>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    last_seen_at: chrono::DateTime<chrono::FixedOffset>,
+    expires_at: chrono::DateTime<chrono::FixedOffset>,
+    revoked_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportedApiKey {
+    name: String,
+    prefix: String,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    last_used_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    revoked_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl From<user::Model> for ExportedProfile {
+    fn from(user: user::Model) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified_at: user.email_verified_at,
+            avatar_url: user.avatar_url,
+            country: user.country,
+            bio: user.bio,
+            favorite_vehicle: user.favorite_vehicle,
+            xp: user.xp,
+            level: user.level,
+            race_wins: user.race_wins,
+            checkpoints_passed: user.checkpoints_passed,
+            is_admin: user.is_admin,
+            banned_until: user.banned_until,
+            ban_reason: user.ban_reason,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl From<race_participant::Model> for ExportedRaceResult {
+    fn from(result: race_participant::Model) -> Self {
+        Self {
+            race_id: result.race_id,
+            position: result.position,
+            finish_time_ms: result.finish_time_ms,
+            dnf: result.dnf,
+            splits_ms: result
+                .splits
+                .as_deref()
+                .map(splits::decode)
+                .unwrap_or_default(),
+            submitted_at: result.submitted_at,
+        }
+    }
+}
+
+impl From<race_replay::Model> for ExportedReplay {
+    fn from(replay: race_replay::Model) -> Self {
+        Self {
+            race_id: replay.race_id,
+            sample_interval_ms: replay.sample_interval_ms,
+            frames: replays::decode(&replay.frames)
+                .into_iter()
+                .map(ReplayFrameResponse::from)
+                .collect(),
+            created_at: replay.created_at,
+        }
+    }
+}
+
+impl From<refresh_token::Model> for ExportedSession {
+    fn from(session: refresh_token::Model) -> Self {
+        Self {
+            device_name: session.device_name,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+            revoked_at: session.revoked_at,
+        }
+    }
+}
+
+impl From<api_key::Model> for ExportedApiKey {
+    fn from(key: api_key::Model) -> Self {
+        Self {
+            name: key.name,
+            prefix: key.prefix,
+            created_at: key.created_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/users/me/export", get(export_me))
+}
+
+/// Download everything stored about the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/export",
+    tag = "users",
+    responses(
+        (status = 200, description = "Export of the user's data, as a JSON attachment", body = UserExport),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn export_me(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = auth_user.0.sub;
+
+    let export = collect_export(&state.conn, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", user_id),
+        ))?;
+
+    let disposition = format!(
+        "attachment; filename=\"world-racers-export-{}.json\"",
+        user_id
+    );
+
+    Ok(([(header::CONTENT_DISPOSITION, disposition)], Json(export)))
+}
+
+// Helper function to gather the data of a user
+async fn collect_export(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Option<UserExport>, DbErr> {
+    let Some(user) = user::Entity::find_by_id(user_id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let maps = map::Entity::find()
+        .filter(map::Column::AuthorId.eq(user_id))
+        .order_by_asc(map::Column::Id)
+        .find_with_related(checkpoint::Entity)
+        .order_by_asc(checkpoint::Column::Position)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(map, checkpoints)| ExportedMap { map, checkpoints })
+        .collect();
+
+    let playlists = playlist::Entity::find()
+        .filter(playlist::Column::AuthorId.eq(user_id))
+        .order_by_asc(playlist::Column::Id)
+        .find_with_related(playlist_map::Entity)
+        .order_by_asc(playlist_map::Column::Position)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(playlist, maps)| ExportedPlaylist { playlist, maps })
+        .collect();
+
+    Ok(Some(UserExport {
+        exported_at: chrono::Utc::now(),
+        profile: user.into(),
+        settings: user_settings::Entity::find_by_id(user_id).one(db).await?,
+        name_history: user_name_history::Entity::find()
+            .filter(user_name_history::Column::UserId.eq(user_id))
+            .order_by_asc(user_name_history::Column::Id)
+            .all(db)
+            .await?,
+        maps,
+        playlists,
+        parties: party::Entity::find()
+            .filter(party::Column::OwnerId.eq(user_id))
+            .order_by_asc(party::Column::Id)
+            .all(db)
+            .await?,
+        party_memberships: user_party::Entity::find()
+            .filter(user_party::Column::UserId.eq(user_id))
+            .order_by_asc(user_party::Column::Id)
+            .all(db)
+            .await?,
+        race_results: race_participant::Entity::find()
+            .filter(race_participant::Column::UserId.eq(user_id))
+            .order_by_asc(race_participant::Column::RaceId)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ExportedRaceResult::from)
+            .collect(),
+        replays: race_replay::Entity::find()
+            .filter(race_replay::Column::UserId.eq(user_id))
+            .order_by_asc(race_replay::Column::RaceId)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ExportedReplay::from)
+            .collect(),
+        race_awards: race_award::Entity::find()
+            .filter(race_award::Column::UserId.eq(user_id))
+            .order_by_asc(race_award::Column::Id)
+            .all(db)
+            .await?,
+        achievements: user_achievement::Entity::find()
+            .filter(user_achievement::Column::UserId.eq(user_id))
+            .order_by_asc(user_achievement::Column::Id)
+            .all(db)
+            .await?,
+        activity: activity::Entity::find()
+            .filter(activity::Column::UserId.eq(user_id))
+            .order_by_asc(activity::Column::Id)
+            .all(db)
+            .await?,
+        loadouts: vehicle_loadout::Entity::find()
+            .filter(vehicle_loadout::Column::UserId.eq(user_id))
+            .order_by_asc(vehicle_loadout::Column::Id)
+            .all(db)
+            .await?,
+        favorites: map_favorite::Entity::find()
+            .filter(map_favorite::Column::UserId.eq(user_id))
+            .order_by_asc(map_favorite::Column::Id)
+            .all(db)
+            .await?,
+        wallet: wallet::Entity::find_by_id(user_id).one(db).await?,
+        wallet_transactions: wallet_transaction::Entity::find()
+            .filter(wallet_transaction::Column::UserId.eq(user_id))
+            .order_by_asc(wallet_transaction::Column::Id)
+            .all(db)
+            .await?,
+        blocks: block::Entity::find()
+            .filter(block::Column::BlockerId.eq(user_id))
+            .order_by_asc(block::Column::Id)
+            .all(db)
+            .await?,
+        follows: follow::Entity::find()
+            .filter(follow::Column::FollowerId.eq(user_id))
+            .order_by_asc(follow::Column::Id)
+            .all(db)
+            .await?,
+        recent_players: recent_player::Entity::find()
+            .filter(recent_player::Column::UserId.eq(user_id))
+            .order_by_asc(recent_player::Column::Id)
+            .all(db)
+            .await?,
+        linked_accounts: linked_account::Entity::find()
+            .filter(linked_account::Column::UserId.eq(user_id))
+            .order_by_asc(linked_account::Column::Id)
+            .all(db)
+            .await?,
+        identities: user_identity::Entity::find()
+            .filter(user_identity::Column::UserId.eq(user_id))
+            .order_by_asc(user_identity::Column::Id)
+            .all(db)
+            .await?,
+        sessions: refresh_token::Entity::find()
+            .filter(refresh_token::Column::UserId.eq(user_id))
+            .order_by_asc(refresh_token::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ExportedSession::from)
+            .collect(),
+        api_keys: api_key::Entity::find()
+            .filter(api_key::Column::UserId.eq(user_id))
+            .order_by_asc(api_key::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ExportedApiKey::from)
+            .collect(),
+    }))
+}
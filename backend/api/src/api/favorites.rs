@@ -0,0 +1,251 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use entity::map::{self, Entity as Map};
+use entity::map_favorite::{self, Entity as MapFavorite};
+use sea_orm::{
+    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+    sea_query::{Expr, OnConflict},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::maps::{MapResponse, attach_authors, attach_tags, mark_favorites};
+use super::pagination::{Paginated, PaginationParams};
+use crate::db::AppState;
+use crate::policy;
+
+#[derive(Serialize, ToSchema)]
+pub struct FavoriteResponse {
+    map: MapResponse,
+    favorited_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/maps/{id}/favorite",
+            post(favorite_map).delete(unfavorite_map),
+        )
+        .route("/users/me/favorites", get(list_favorites))
+}
+
+/// List the maps the current user favorited, most recent first
+#[utoipa::path(
+    get,
+    path = "/api/users/me/favorites",
+    tag = "maps",
+    params(PaginationParams),
+    responses(
+        (status = 200, description = "Favorite maps retrieved successfully", body = Paginated<FavoriteResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_favorites(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<FavoriteResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let paginator = MapFavorite::find()
+        .find_also_related(Map)
+        .filter(map_favorite::Column::UserId.eq(auth_user.0.sub))
+        .filter(map::Column::DeletedAt.is_null())
+        .order_by_desc(map_favorite::Column::CreatedAt)
+        .order_by_desc(map_favorite::Column::Id)
+        .paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let favorites = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let favorites = favorites
+        .into_iter()
+        .filter_map(|(favorite, map)| {
+            Some(FavoriteResponse {
+                map: map?.into(),
+                favorited_at: favorite.created_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        favorites,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Add a map to the favorites of the current user
+///
+/// Favoriting a map again keeps it as it is.
+#[utoipa::path(
+    post,
+    path = "/api/maps/{id}/favorite",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map favorited", body = MapResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn favorite_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Drafts of others can't be favorited, as they can't be seen
+    Map::find_by_id(id)
+        .one(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .filter(|map| policy::can_view_map(&auth_user.0, map))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
+        ))?;
+
+    let inserted = MapFavorite::insert(map_favorite::ActiveModel {
+        user_id: Set(user_id),
+        map_id: Set(id),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([map_favorite::Column::UserId, map_favorite::Column::MapId])
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(&txn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Only count the favorite if it is new
+    if inserted > 0 {
+        Map::update_many()
+            .col_expr(
+                map::Column::FavoriteCount,
+                Expr::col(map::Column::FavoriteCount).add(1),
+            )
+            .filter(map::Column::Id.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    let map = Map::find_by_id(id)
+        .one(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
+        ))?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(&txn, user_id, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(&txn, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(&txn, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(map))
+}
+
+/// Remove a map from the favorites of the current user
+#[utoipa::path(
+    delete,
+    path = "/api/maps/{id}/favorite",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 204, description = "Map removed from favorites"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map is not a favorite", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unfavorite_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let result = MapFavorite::delete_many()
+        .filter(map_favorite::Column::UserId.eq(auth_user.0.sub))
+        .filter(map_favorite::Column::MapId.eq(id))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} is not a favorite", id),
+        ));
+    }
+
+    Map::update_many()
+        .col_expr(
+            map::Column::FavoriteCount,
+            Expr::col(map::Column::FavoriteCount).sub(1),
+        )
+        .filter(map::Column::Id.eq(id))
+        .filter(map::Column::FavoriteCount.gt(0))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,300 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use entity::follow::{self, Entity as Follow};
+use entity::map::{self, Entity as Map};
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    sea_query::Query as SqlQuery,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::maps::{MapResponse, MapStatus, attach_authors, attach_tags, mark_favorites};
+use super::pagination::{Paginated, PaginationParams};
+use super::users::UserResponse;
+use crate::blocking;
+use crate::db::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct FollowResponse {
+    user: UserResponse,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FeedItemResponse {
+    map: MapResponse,
+    author: UserResponse,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/users/me/following", get(list_following))
+        .route("/users/me/feed", get(get_feed))
+        .route(
+            "/users/{id}/follow",
+            post(follow_user).delete(unfollow_user),
+        )
+}
+
+/// List the users the current user follows
+#[utoipa::path(
+    get,
+    path = "/api/users/me/following",
+    tag = "follows",
+    responses(
+        (status = 200, description = "Followed users retrieved successfully", body = Vec<FollowResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_following(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<FollowResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let follows = Follow::find()
+        .filter(follow::Column::FollowerId.eq(auth_user.0.sub))
+        .order_by_asc(follow::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(follows.iter().map(|follow| follow.followed_id)))
+        .filter(user::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let follows = follows
+        .into_iter()
+        .filter_map(|follow| {
+            Some(FollowResponse {
+                user: users.remove(&follow.followed_id)?.into(),
+                created_at: follow.created_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(follows))
+}
+
+/// Maps published by the users the current user follows, newest first
+#[utoipa::path(
+    get,
+    path = "/api/users/me/feed",
+    tag = "follows",
+    params(PaginationParams),
+    responses(
+        (status = 200, description = "Feed retrieved successfully", body = Paginated<FeedItemResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_feed(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<FeedItemResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let followed_ids = SqlQuery::select()
+        .column(follow::Column::FollowedId)
+        .from(Follow)
+        .and_where(follow::Column::FollowerId.eq(user_id))
+        .to_owned();
+
+    let paginator = Map::find()
+        .find_also_related(User)
+        .filter(map::Column::AuthorId.in_subquery(followed_ids))
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .filter(user::Column::DeletedAt.is_null())
+        .order_by_desc(map::Column::CreatedAt)
+        .order_by_desc(map::Column::Id)
+        .paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let maps = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let (mut maps, authors): (Vec<MapResponse>, Vec<Option<user::Model>>) = maps
+        .into_iter()
+        .map(|(map, author)| (MapResponse::from(map), author))
+        .unzip();
+
+    mark_favorites(db, user_id, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let items = maps
+        .into_iter()
+        .zip(authors)
+        .filter_map(|(map, author)| {
+            Some(FeedItemResponse {
+                map,
+                author: author?.into(),
+            })
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(
+        items,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Follow a user to see the maps they publish in the feed
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/follow",
+    tag = "follows",
+    params(
+        ("id" = i32, Path, description = "ID of the user to follow")
+    ),
+    responses(
+        (status = 200, description = "User followed successfully", body = FollowResponse),
+        (status = 400, description = "Users can't follow themselves", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "The user has blocked the current user", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn follow_user(
+    State(state): State<AppState>,
+    Path(followed_id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<FollowResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    if followed_id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "You can't follow yourself".to_string(),
+        ));
+    }
+
+    let followed_user = User::find_by_id(followed_id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", followed_id),
+        ))?;
+
+    if blocking::has_blocked(db, followed_user.id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You can't follow this user".to_string(),
+        ));
+    }
+
+    // Following a user again keeps the original follow
+    let existing = Follow::find()
+        .filter(follow::Column::FollowerId.eq(user_id))
+        .filter(follow::Column::FollowedId.eq(followed_user.id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let follow = match existing {
+        Some(follow) => follow,
+        None => follow::ActiveModel {
+            follower_id: Set(user_id),
+            followed_id: Set(followed_user.id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+    };
+
+    Ok(Json(FollowResponse {
+        user: followed_user.into(),
+        created_at: follow.created_at,
+    }))
+}
+
+/// Unfollow a user
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}/follow",
+    tag = "follows",
+    params(
+        ("id" = i32, Path, description = "ID of the followed user")
+    ),
+    responses(
+        (status = 204, description = "User unfollowed"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User is not followed", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unfollow_user(
+    State(state): State<AppState>,
+    Path(followed_id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let result = Follow::delete_many()
+        .filter(follow::Column::FollowerId.eq(auth_user.0.sub))
+        .filter(follow::Column::FollowedId.eq(followed_id))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not followed", followed_id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
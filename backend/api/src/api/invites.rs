@@ -0,0 +1,513 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::{Duration, Utc};
+use entity::invite::{self, Entity as Invite};
+use entity::party::{self, Entity as Party};
+use entity::user::{self, Entity as User};
+use entity::user_party::{self, Entity as UserParty};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::parties::{self, JoinPartyResponse, PartyResponse};
+use super::users::UserResponse;
+use super::ws::{self, WsMessage};
+use crate::blocking;
+use crate::db::AppState;
+use crate::membership;
+use crate::party_bans;
+
+// How long an invite can be accepted
+const INVITE_TTL: i64 = 3600; // in seconds
+
+#[derive(Deserialize, ToSchema)]
+pub struct InviteRequest {
+    /// User to invite
+    user_id: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InviteLinkResponse {
+    /// Redeem with POST /api/parties/join-link
+    token: String,
+    /// Link to the client page that redeems the token, if configured
+    url: Option<String>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct JoinLinkRequest {
+    token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InviteResponse {
+    id: i32,
+    party: PartyResponse,
+    inviter: UserResponse,
+    invitee_id: i32,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    expires_at: chrono::DateTime<chrono::FixedOffset>,
+    /// Whether the invitee was online to be notified right away
+    delivered: bool,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/parties/{id}/invite", post(invite_user))
+        .route("/parties/{id}/invite-link", get(create_invite_link))
+        .route("/parties/join-link", post(join_by_link))
+        .route("/invites", get(list_invites))
+        .route("/invites/{id}/accept", post(accept_invite))
+        .route("/invites/{id}/decline", post(decline_invite))
+}
+
+/// Invite a user to a party
+///
+/// Any member of the party can invite. The invitee is notified over their
+/// websocket connection if they are online, and can find the invite in
+/// their pending invites otherwise. Inviting a user again renews the invite.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/invite",
+    tag = "invites",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = InviteRequest,
+    responses(
+        (status = 200, description = "User invited successfully", body = InviteResponse),
+        (status = 400, description = "User is already a member", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not a member of the party, or blocked", body = String),
+        (status = 404, description = "Party or user not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn invite_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<InviteRequest>,
+) -> Result<Json<InviteResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    if !membership::is_member(&state, user_id, party.id).await {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are not a member of this party".to_string(),
+        ));
+    }
+
+    let inviter = User::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", user_id),
+        ))?;
+
+    let invitee = User::find_by_id(payload.user_id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", payload.user_id),
+        ))?;
+
+    if membership::is_member(&state, invitee.id, party.id).await {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "User is already a member of this party".to_string(),
+        ));
+    }
+
+    // Neither users who blocked the inviter nor users the owner blocked or
+    // banned can be invited
+    let blocked = blocking::has_blocked(db, invitee.id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        || blocking::has_blocked(db, party.owner_id, invitee.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        || party_bans::is_banned(db, party.id, invitee.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if blocked {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You can't invite this user".to_string(),
+        ));
+    }
+
+    // Inviting again replaces the previous invite
+    Invite::delete_many()
+        .filter(invite::Column::PartyId.eq(party.id))
+        .filter(invite::Column::InviteeId.eq(invitee.id))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let invite = invite::ActiveModel {
+        party_id: Set(party.id),
+        inviter_id: Set(user_id),
+        invitee_id: Set(invitee.id),
+        expires_at: Set((Utc::now() + Duration::seconds(INVITE_TTL)).fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let delivered = ws::notify_user(
+        &state,
+        invitee.id,
+        &WsMessage::PartyInvite {
+            invite_id: invite.id,
+            party_id: party.id,
+            party_name: party.name.clone(),
+            inviter_id: inviter.id,
+            inviter_name: inviter.name.clone(),
+            expires_at: invite.expires_at,
+        },
+    );
+
+    Ok(Json(InviteResponse {
+        id: invite.id,
+        party: PartyResponse::from(party).with_live_state(&state),
+        inviter: inviter.into(),
+        invitee_id: invite.invitee_id,
+        created_at: invite.created_at,
+        expires_at: invite.expires_at,
+        delivered,
+    }))
+}
+
+/// Get a link that lets anyone join a party for a day
+///
+/// Any member of the party can share a link. Unlike the party code, the link
+/// expires, and it stops working once the member who shared it left the
+/// party.
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/invite-link",
+    tag = "invites",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Invite link created successfully", body = InviteLinkResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not a member of the party", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_invite_link(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<InviteLinkResponse>, (StatusCode, String)> {
+    let party = Party::find_by_id(id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    if !membership::is_member(&state, auth_user.0.sub, party.id).await {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are not a member of this party".to_string(),
+        ));
+    }
+
+    let token = state
+        .auth
+        .generate_party_invite(party.id, auth_user.0.sub)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let url = state
+        .config
+        .invite_link_base_url
+        .as_ref()
+        .map(|base_url| format!("{}?invite={}", base_url, token));
+
+    Ok(Json(InviteLinkResponse {
+        token,
+        url,
+        expires_at: Utc::now() + Duration::seconds(auth::PARTY_INVITE_EXPIRY),
+    }))
+}
+
+/// Join a party through an invite link
+#[utoipa::path(
+    post,
+    path = "/api/parties/join-link",
+    tag = "invites",
+    request_body = JoinLinkRequest,
+    responses(
+        (status = 200, description = "Joined the party", body = JoinPartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Blocked by the party owner, or banned from the party", body = String),
+        (status = 404, description = "Invite link invalid or expired", body = String),
+        (status = 409, description = "Party is full", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn join_by_link(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<JoinLinkRequest>,
+) -> Result<Json<JoinPartyResponse>, (StatusCode, String)> {
+    let invalid = || {
+        (
+            StatusCode::NOT_FOUND,
+            "Invite link is invalid or expired".to_string(),
+        )
+    };
+
+    let claims = state
+        .auth
+        .verify_party_invite(&payload.token)
+        .map_err(|_| invalid())?;
+
+    let party = Party::find_by_id(claims.party_id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(invalid)?;
+
+    // Links of members who left don't work anymore
+    if !membership::is_member(&state, claims.sub, party.id).await {
+        return Err(invalid());
+    }
+
+    parties::join(&state, auth_user.0.sub, party)
+        .await
+        .map(Json)
+}
+
+/// List the pending invites of the current user
+#[utoipa::path(
+    get,
+    path = "/api/invites",
+    tag = "invites",
+    responses(
+        (status = 200, description = "Pending invites retrieved successfully", body = Vec<InviteResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_invites(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<InviteResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let invites = Invite::find()
+        .filter(invite::Column::InviteeId.eq(auth_user.0.sub))
+        .filter(invite::Column::ExpiresAt.gt(Utc::now()))
+        .find_also_related(Party)
+        .order_by_desc(invite::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Invites of users the current user blocked since are left out
+    let blocked_ids = blocking::blocked_ids(db, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let inviters: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(invites.iter().map(|(invite, _)| invite.inviter_id)))
+        .filter(user::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let invites = invites
+        .into_iter()
+        .filter(|(invite, _)| !blocked_ids.contains(&invite.inviter_id))
+        .filter_map(|(invite, party)| {
+            let inviter = inviters.get(&invite.inviter_id)?;
+
+            Some(InviteResponse {
+                id: invite.id,
+                party: PartyResponse::from(party?).with_live_state(&state),
+                inviter: inviter.clone().into(),
+                invitee_id: invite.invitee_id,
+                created_at: invite.created_at,
+                expires_at: invite.expires_at,
+                delivered: true,
+            })
+        })
+        .collect();
+
+    Ok(Json(invites))
+}
+
+/// Accept an invite and join its party
+#[utoipa::path(
+    post,
+    path = "/api/invites/{id}/accept",
+    tag = "invites",
+    params(
+        ("id" = i32, Path, description = "Invite ID")
+    ),
+    responses(
+        (status = 200, description = "Joined the party", body = PartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Blocked by the party owner, or banned from the party", body = String),
+        (status = 404, description = "Invite not found or expired", body = String),
+        (status = 409, description = "Party is full", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn accept_invite(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let (invite, party) = find_invite(&state, id, user_id).await?;
+
+    if blocking::has_blocked(db, party.owner_id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        || party_bans::is_banned(db, party.id, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You can't join this party".to_string(),
+        ));
+    }
+
+    // Joining by other means meanwhile uses up the invite as well
+    let already_member = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .filter(user_party::Column::PartyId.eq(party.id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .is_some();
+
+    if !already_member {
+        parties::add_member(db, user_id, party.id).await?;
+        membership::remember(&state, user_id, party.id);
+        parties::record_join(db, user_id, &party).await;
+    }
+
+    invite
+        .delete(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PartyResponse::from(party).with_live_state(&state)))
+}
+
+/// Decline an invite
+#[utoipa::path(
+    post,
+    path = "/api/invites/{id}/decline",
+    tag = "invites",
+    params(
+        ("id" = i32, Path, description = "Invite ID")
+    ),
+    responses(
+        (status = 204, description = "Invite declined"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Invite not found or expired", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn decline_invite(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (invite, _) = find_invite(&state, id, auth_user.0.sub).await?;
+
+    invite
+        .delete(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helper function to find a pending invite of a user along with its party
+async fn find_invite(
+    state: &AppState,
+    id: i32,
+    user_id: i32,
+) -> Result<(invite::Model, party::Model), (StatusCode, String)> {
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Invite with id {} not found", id),
+        )
+    };
+
+    let (invite, party) = Invite::find_by_id(id)
+        .filter(invite::Column::InviteeId.eq(user_id))
+        .find_also_related(Party)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(not_found)?;
+
+    if invite.expires_at < Utc::now() {
+        return Err(not_found());
+    }
+
+    Ok((invite, party.ok_or_else(not_found)?))
+}
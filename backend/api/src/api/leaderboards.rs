@@ -0,0 +1,41 @@
+use chrono::{Datelike, Days, NaiveDate};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+/// Period of time a leaderboard covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardWindow {
+    Today,
+    /// Since Monday
+    Week,
+    /// Since the season started. Seasons last three months, starting in
+    /// January, April, July and October.
+    Season,
+    AllTime,
+}
+
+impl LeaderboardWindow {
+    /// First day (in UTC) of the window that contains a day, or `None` for
+    /// all time
+    pub fn first_day(&self, day: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            LeaderboardWindow::Today => Some(day),
+            LeaderboardWindow::Week => {
+                day.checked_sub_days(Days::new(day.weekday().num_days_from_monday() as u64))
+            }
+            LeaderboardWindow::Season => {
+                NaiveDate::from_ymd_opt(day.year(), (day.month0() / 3) * 3 + 1, 1)
+            }
+            LeaderboardWindow::AllTime => None,
+        }
+    }
+}
+
+// Query parameters of leaderboards
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardParams {
+    /// Period of time to rank by, all time unless stated otherwise
+    pub window: Option<LeaderboardWindow>,
+}
@@ -0,0 +1,196 @@
+use auth::middleware::AuthUser;
+use auth::platform::{self, Platform};
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use chrono::DateTime;
+use entity::linked_account::Model as LinkedAccountModel;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+
+#[derive(Deserialize, ToSchema)]
+pub struct LinkAccountRequest {
+    /// Platform of the account (steam or epic)
+    platform: String,
+    /// Ticket from the platform's SDK: the hex encoded Steam web API ticket,
+    /// or the Epic access token
+    ticket: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LinkedAccountResponse {
+    id: i32,
+    platform: String,
+    /// Id of the account at the platform, e.g. the SteamID64
+    platform_user_id: String,
+    created_at: DateTime<chrono::FixedOffset>,
+}
+
+impl From<LinkedAccountModel> for LinkedAccountResponse {
+    fn from(account: LinkedAccountModel) -> Self {
+        Self {
+            id: account.id,
+            platform: account.platform,
+            platform_user_id: account.platform_user_id,
+            created_at: account.created_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/linked-accounts", get(list_linked_accounts))
+        .route("/linked-accounts", post(link_account))
+        .route("/linked-accounts/{id}", delete(unlink_account))
+}
+
+/// List the platform accounts linked to the current user
+#[utoipa::path(
+    get,
+    path = "/api/linked-accounts",
+    tag = "linked-accounts",
+    responses(
+        (status = 200, description = "Linked accounts retrieved successfully", body = Vec<LinkedAccountResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_linked_accounts(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<LinkedAccountResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let accounts = platform::list(db, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        accounts
+            .into_iter()
+            .map(LinkedAccountResponse::from)
+            .collect(),
+    ))
+}
+
+/// Link a Steam or Epic account to the current user
+///
+/// The ticket is verified with the platform, after which the account can be
+/// used to log in from game clients on that platform.
+#[utoipa::path(
+    post,
+    path = "/api/linked-accounts",
+    tag = "linked-accounts",
+    request_body = LinkAccountRequest,
+    responses(
+        (status = 200, description = "Account linked successfully", body = LinkedAccountResponse),
+        (status = 401, description = "Unauthorized or invalid ticket", body = String),
+        (status = 404, description = "Unknown or unconfigured platform", body = String),
+        (status = 409, description = "The account or platform is already linked", body = String),
+        (status = 502, description = "The platform could not be reached", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn link_account(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<LinkAccountRequest>,
+) -> Result<Json<LinkedAccountResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let (platform, verifier) = platform_verifier(&state, &payload.platform)?;
+
+    let platform_user_id = verifier
+        .verify(&payload.ticket)
+        .await
+        .map_err(platform_error)?;
+
+    let account = platform::link(db, auth_user.0.sub, platform, platform_user_id)
+        .await
+        .map_err(platform_error)?;
+
+    Ok(Json(account.into()))
+}
+
+/// Unlink a platform account from the current user
+#[utoipa::path(
+    delete,
+    path = "/api/linked-accounts/{id}",
+    tag = "linked-accounts",
+    params(
+        ("id" = i32, Path, description = "Linked account ID")
+    ),
+    responses(
+        (status = 204, description = "Account unlinked"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Linked account not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unlink_account(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let unlinked = platform::unlink(db, auth_user.0.sub, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if !unlinked {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Linked account with id {} not found", id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helper function to look up the verifier of a configured platform
+pub(crate) fn platform_verifier(
+    state: &AppState,
+    platform: &str,
+) -> Result<(Platform, platform::PlatformVerifier), (StatusCode, String)> {
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Platform {} is not available", platform),
+        )
+    };
+
+    let platform: Platform = platform.parse().map_err(|_| not_found())?;
+    let verifier = state
+        .config
+        .platform_verifier(platform)
+        .ok_or_else(not_found)?;
+
+    Ok((platform, verifier))
+}
+
+// Helper function to map errors of verifying and linking platform accounts
+pub(crate) fn platform_error(e: auth::AuthError) -> (StatusCode, String) {
+    match e {
+        auth::AuthError::InvalidPlatformTicket => (StatusCode::UNAUTHORIZED, e.to_string()),
+        auth::AuthError::PlatformError(_) => (StatusCode::BAD_GATEWAY, e.to_string()),
+        auth::AuthError::PlatformAccountTaken(_) | auth::AuthError::PlatformAlreadyLinked(_) => {
+            (StatusCode::CONFLICT, e.to_string())
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
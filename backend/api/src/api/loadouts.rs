@@ -0,0 +1,432 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use entity::vehicle_loadout::{self, Entity as VehicleLoadout};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+
+// Limits of a loadout
+const MAX_LOADOUTS: u64 = 10;
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_MODEL_LENGTH: usize = 32;
+const MAX_DECALS: usize = 16;
+const MAX_DECALS_SIZE: usize = 4096; // in bytes of JSON
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateLoadoutRequest {
+    name: String,
+    /// Vehicle model the client renders
+    model: String,
+    /// Paint color as #RRGGBB
+    color: String,
+    /// Decals as a JSON array, interpreted by the client
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    decals: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateLoadoutRequest {
+    name: Option<String>,
+    model: Option<String>,
+    color: Option<String>,
+    #[schema(value_type = Option<Vec<Object>>)]
+    decals: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoadoutResponse {
+    id: i32,
+    name: String,
+    model: String,
+    color: String,
+    #[schema(value_type = Vec<Object>)]
+    decals: serde_json::Value,
+    /// Whether other racers see this loadout
+    selected: bool,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+impl From<vehicle_loadout::Model> for LoadoutResponse {
+    fn from(loadout: vehicle_loadout::Model) -> Self {
+        Self {
+            id: loadout.id,
+            name: loadout.name,
+            model: loadout.model,
+            color: loadout.color,
+            decals: loadout.decals,
+            selected: loadout.selected,
+            created_at: loadout.created_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/users/me/loadouts",
+            get(list_loadouts).post(create_loadout),
+        )
+        .route(
+            "/users/me/loadouts/{id}",
+            get(get_loadout)
+                .patch(update_loadout)
+                .delete(delete_loadout),
+        )
+        .route("/users/me/loadouts/{id}/select", post(select_loadout))
+}
+
+/// List the vehicle loadouts of the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/loadouts",
+    tag = "loadouts",
+    responses(
+        (status = 200, description = "Loadouts retrieved successfully", body = Vec<LoadoutResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_loadouts(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<LoadoutResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let loadouts = VehicleLoadout::find()
+        .filter(vehicle_loadout::Column::UserId.eq(auth_user.0.sub))
+        .order_by_asc(vehicle_loadout::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        loadouts.into_iter().map(LoadoutResponse::from).collect(),
+    ))
+}
+
+/// Get a vehicle loadout of the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/loadouts/{id}",
+    tag = "loadouts",
+    params(
+        ("id" = i32, Path, description = "Loadout ID")
+    ),
+    responses(
+        (status = 200, description = "Loadout found", body = LoadoutResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Loadout not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_loadout(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<LoadoutResponse>, (StatusCode, String)> {
+    let loadout = find_loadout(&state.conn, auth_user.0.sub, id).await?;
+
+    Ok(Json(loadout.into()))
+}
+
+/// Create a vehicle loadout
+///
+/// The first loadout of a user is selected right away.
+#[utoipa::path(
+    post,
+    path = "/api/users/me/loadouts",
+    tag = "loadouts",
+    request_body = CreateLoadoutRequest,
+    responses(
+        (status = 200, description = "Loadout created successfully", body = LoadoutResponse),
+        (status = 400, description = "Invalid loadout or too many loadouts", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_loadout(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<CreateLoadoutRequest>,
+) -> Result<Json<LoadoutResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let name = validate_text("Name", payload.name, MAX_NAME_LENGTH)?;
+    let model = validate_text("Model", payload.model, MAX_MODEL_LENGTH)?;
+    let color = validate_color(payload.color)?;
+    let decals = validate_decals(payload.decals.unwrap_or_else(|| serde_json::json!([])))?;
+
+    let count = VehicleLoadout::find()
+        .filter(vehicle_loadout::Column::UserId.eq(user_id))
+        .count(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if count >= MAX_LOADOUTS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("A user can have at most {} loadouts", MAX_LOADOUTS),
+        ));
+    }
+
+    let loadout = vehicle_loadout::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(name),
+        model: Set(model),
+        color: Set(color),
+        decals: Set(decals),
+        selected: Set(count == 0),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(loadout.into()))
+}
+
+/// Update a vehicle loadout of the current user
+#[utoipa::path(
+    patch,
+    path = "/api/users/me/loadouts/{id}",
+    tag = "loadouts",
+    params(
+        ("id" = i32, Path, description = "Loadout ID")
+    ),
+    request_body = UpdateLoadoutRequest,
+    responses(
+        (status = 200, description = "Loadout updated successfully", body = LoadoutResponse),
+        (status = 400, description = "Invalid loadout", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Loadout not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_loadout(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdateLoadoutRequest>,
+) -> Result<Json<LoadoutResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let loadout = find_loadout(db, auth_user.0.sub, id).await?;
+    let mut loadout_model: vehicle_loadout::ActiveModel = loadout.into();
+
+    if let Some(name) = payload.name {
+        loadout_model.name = Set(validate_text("Name", name, MAX_NAME_LENGTH)?);
+    }
+    if let Some(model) = payload.model {
+        loadout_model.model = Set(validate_text("Model", model, MAX_MODEL_LENGTH)?);
+    }
+    if let Some(color) = payload.color {
+        loadout_model.color = Set(validate_color(color)?);
+    }
+    if let Some(decals) = payload.decals {
+        loadout_model.decals = Set(validate_decals(decals)?);
+    }
+
+    let loadout = loadout_model
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(loadout.into()))
+}
+
+/// Delete a vehicle loadout of the current user
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/loadouts/{id}",
+    tag = "loadouts",
+    params(
+        ("id" = i32, Path, description = "Loadout ID")
+    ),
+    responses(
+        (status = 204, description = "Loadout deleted"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Loadout not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_loadout(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let result = VehicleLoadout::delete_many()
+        .filter(vehicle_loadout::Column::Id.eq(id))
+        .filter(vehicle_loadout::Column::UserId.eq(auth_user.0.sub))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Loadout with id {} not found", id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Select the loadout other racers see
+#[utoipa::path(
+    post,
+    path = "/api/users/me/loadouts/{id}/select",
+    tag = "loadouts",
+    params(
+        ("id" = i32, Path, description = "Loadout ID")
+    ),
+    responses(
+        (status = 200, description = "Loadout selected", body = LoadoutResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Loadout not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn select_loadout(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<LoadoutResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let loadout = find_loadout(db, user_id, id).await?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Deselect the current loadout first, only one may be selected
+    VehicleLoadout::update_many()
+        .col_expr(vehicle_loadout::Column::Selected, Expr::value(false))
+        .filter(vehicle_loadout::Column::UserId.eq(user_id))
+        .filter(vehicle_loadout::Column::Selected.eq(true))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut loadout_model: vehicle_loadout::ActiveModel = loadout.into();
+    loadout_model.selected = Set(true);
+    let loadout = loadout_model
+        .update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(loadout.into()))
+}
+
+// Helper function to find a loadout of a user
+async fn find_loadout(
+    db: &DatabaseConnection,
+    user_id: i32,
+    id: i32,
+) -> Result<vehicle_loadout::Model, (StatusCode, String)> {
+    VehicleLoadout::find_by_id(id)
+        .filter(vehicle_loadout::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Loadout with id {} not found", id),
+        ))
+}
+
+// Helper function to validate a required text field
+fn validate_text(
+    field: &str,
+    value: String,
+    max_length: usize,
+) -> Result<String, (StatusCode, String)> {
+    let value = value.trim();
+    if value.is_empty() || value.chars().count() > max_length {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "{} must be between 1 and {} characters long",
+                field, max_length
+            ),
+        ));
+    }
+
+    Ok(value.to_string())
+}
+
+// Helper function to validate a #RRGGBB color
+fn validate_color(color: String) -> Result<String, (StatusCode, String)> {
+    let color = color.trim().to_ascii_lowercase();
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if !valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Color must be given as #RRGGBB".to_string(),
+        ));
+    }
+
+    Ok(color)
+}
+
+// Helper function to validate decals; their contents are up to the client,
+// but their number and size are limited
+fn validate_decals(decals: serde_json::Value) -> Result<serde_json::Value, (StatusCode, String)> {
+    let count = decals.as_array().map(Vec::len).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Decals must be an array".to_string(),
+    ))?;
+
+    if count > MAX_DECALS || decals.to_string().len() > MAX_DECALS_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "At most {} decals of {} bytes in total are allowed",
+                MAX_DECALS, MAX_DECALS_SIZE
+            ),
+        ));
+    }
+
+    Ok(decals)
+}
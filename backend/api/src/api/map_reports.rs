@@ -0,0 +1,189 @@
+use auth::middleware::AuthUser;
+use auth::validation::{FieldError, ValidationCode};
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use entity::map::Entity as Map;
+use entity::map_report::{self, Entity as MapReport};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+use crate::policy;
+use crate::validation::{self, ValidationErrorResponse};
+
+// Longest details of a report
+const MAX_REPORT_DETAILS_LENGTH: usize = 500;
+
+/// Why a map was reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+    /// Offensive title, description or shape
+    Offensive,
+    /// Can't be finished, e.g. checkpoints out of reach
+    Broken,
+    Spam,
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Offensive => "offensive",
+            ReportReason::Broken => "broken",
+            ReportReason::Spam => "spam",
+            ReportReason::Other => "other",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "offensive" => ReportReason::Offensive,
+            "broken" => ReportReason::Broken,
+            "spam" => ReportReason::Spam,
+            _ => ReportReason::Other,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReportMapRequest {
+    reason: ReportReason,
+    /// What is wrong with the map, at most 500 characters
+    details: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MapReportResponse {
+    id: i32,
+    map_id: i32,
+    reporter_id: i32,
+    reason: ReportReason,
+    details: Option<String>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    /// When a moderator dealt with the report, if they did
+    resolved_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl From<map_report::Model> for MapReportResponse {
+    fn from(report: map_report::Model) -> Self {
+        Self {
+            id: report.id,
+            map_id: report.map_id,
+            reporter_id: report.reporter_id,
+            reason: ReportReason::from_column(&report.reason),
+            details: report.details,
+            created_at: report.created_at,
+            resolved_at: report.resolved_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/maps/{id}/report", post(report_map))
+}
+
+/// Report a map to the moderators
+///
+/// The report waits in the moderation queue until a moderator deals with
+/// it. A user can have one open report per map.
+#[utoipa::path(
+    post,
+    path = "/api/maps/{id}/report",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    request_body = ReportMapRequest,
+    responses(
+        (status = 200, description = "Map reported successfully", body = MapReportResponse),
+        (status = 400, description = "The map is the user's own", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 409, description = "The user already reported the map", body = String),
+        (status = 422, description = "Details too long", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn report_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<ReportMapRequest>,
+) -> Result<Json<MapReportResponse>, Response> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let details = payload
+        .details
+        .map(|details| details.trim().to_string())
+        .filter(|details| !details.is_empty());
+    if details
+        .as_ref()
+        .is_some_and(|details| details.chars().count() > MAX_REPORT_DETAILS_LENGTH)
+    {
+        return Err(validation::invalid(vec![FieldError {
+            field: "details".to_string(),
+            code: ValidationCode::Length,
+            message: format!("Must be at most {} characters", MAX_REPORT_DETAILS_LENGTH),
+        }]));
+    }
+
+    let map = Map::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .filter(|map| policy::can_view_map(&auth_user.0, map))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Map with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    if map.author_id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "You can't report your own map".to_string(),
+        )
+            .into_response());
+    }
+
+    let open_reports = MapReport::find()
+        .filter(map_report::Column::MapId.eq(map.id))
+        .filter(map_report::Column::ReporterId.eq(user_id))
+        .filter(map_report::Column::ResolvedAt.is_null())
+        .count(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    if open_reports > 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            "You already reported this map".to_string(),
+        )
+            .into_response());
+    }
+
+    let report = map_report::ActiveModel {
+        map_id: Set(map.id),
+        reporter_id: Set(user_id),
+        reason: Set(payload.reason.as_str().to_string()),
+        details: Set(details),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    Ok(Json(report.into()))
+}
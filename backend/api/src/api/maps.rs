@@ -1,39 +1,368 @@
+use auth::middleware::{AuthUser, MapUploadUser};
+use auth::validation::{FieldError, ValidationCode};
 use axum::{
     Router,
-    extract::{Json, Path, State},
-    http::StatusCode,
-    routing::{delete, get, post},
+    extract::{Json, Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
 };
-use chrono::DateTime;
+use chrono::{DateTime, Duration, Utc};
 use entity::checkpoint::{self, Entity as Checkpoint};
 use entity::map::{self, Entity as Map};
-use entity::user::Entity as User;
+use entity::map_favorite::{self, Entity as MapFavorite};
+use entity::map_play::{self, Entity as MapPlay};
+use entity::map_segment::{self, Entity as MapSegment};
+use entity::map_tag::{self, Entity as MapTag};
+use entity::tag::{self, Entity as Tag};
+use entity::user::{self, Entity as User};
+use rand::Rng;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
-    TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+    sea_query::{Expr, Func, LikeExpr, NullOrdering, OnConflict, Order, Query as SqlQuery},
 };
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use std::collections::{HashMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
 
+use super::pagination::{Paginated, PaginationParams};
+use crate::activity;
 use crate::db::AppState;
+use crate::difficulty;
+use crate::fingerprint;
+use crate::map_stats::MapStats;
+use crate::policy;
+use crate::progression;
+use crate::race::{CHECKPOINT_RADIUS_METERS, Point, distance_meters};
+use crate::road_snapping::RoadSnapError;
+use crate::road_surfaces::Surface;
+use crate::validation::{self, ValidationErrorResponse};
 
-#[derive(Deserialize, ToSchema)]
+// Longest map title
+const MAX_TITLE_LENGTH: usize = 64;
+
+// Window the plays of trending maps are counted in, and how many are listed
+const TRENDING_DAYS: i64 = 7;
+const TRENDING_LIMIT: u64 = 20;
+
+// How far around a location maps are looked for unless asked, and at most
+const DEFAULT_NEARBY_RADIUS_KM: f64 = 10.0;
+const MAX_NEARBY_RADIUS_KM: f64 = 100.0;
+
+// Image types thumbnails can be uploaded as, with their file extension
+const THUMBNAIL_TYPES: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/webp", "webp"),
+];
+
+// How long a thumbnail upload URL can be used
+const THUMBNAIL_UPLOAD_EXPIRY: u64 = 600; // in seconds
+
+// Range of how close a car must pass to a checkpoint, in meters
+const MIN_CHECKPOINT_RADIUS: f32 = 5.0;
+const MAX_CHECKPOINT_RADIUS: f32 = 200.0;
+
+// Closest two points in a row of a map can be, in meters
+const MIN_POINT_SPACING_METERS: f64 = 10.0;
+
+// Most tags a map can have, and the longest tag
+const MAX_TAGS: usize = 8;
+const MAX_TAG_LENGTH: usize = 24;
+
+// Tells map archives apart from other JSON, and the newest version of the
+// archive format this server reads and writes
+const ARCHIVE_FORMAT: &str = "world-racers-map";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// What a checkpoint is for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointKind {
+    /// Must be passed
+    #[default]
+    Mandatory,
+    /// May be left out, e.g. a gate on a shortcut
+    Optional,
+    /// Must be passed, and racers are timed at it
+    Split,
+    /// The last gate before the finish line
+    Finish,
+}
+
+impl CheckpointKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointKind::Mandatory => "mandatory",
+            CheckpointKind::Optional => "optional",
+            CheckpointKind::Split => "split",
+            CheckpointKind::Finish => "finish",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "optional" => CheckpointKind::Optional,
+            "split" => CheckpointKind::Split,
+            "finish" => CheckpointKind::Finish,
+            _ => CheckpointKind::Mandatory,
+        }
+    }
+}
+
+/// Weather a map is raced in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    #[default]
+    Clear,
+    Cloudy,
+    Rain,
+    Fog,
+    Snow,
+}
+
+impl Weather {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Cloudy => "cloudy",
+            Weather::Rain => "rain",
+            Weather::Fog => "fog",
+            Weather::Snow => "snow",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "cloudy" => Weather::Cloudy,
+            "rain" => Weather::Rain,
+            "fog" => Weather::Fog,
+            "snow" => Weather::Snow,
+            _ => Weather::Clear,
+        }
+    }
+}
+
+/// Time of day a map is raced at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeOfDay {
+    Dawn,
+    #[default]
+    Day,
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeOfDay::Dawn => "dawn",
+            TimeOfDay::Day => "day",
+            TimeOfDay::Dusk => "dusk",
+            TimeOfDay::Night => "night",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "dawn" => TimeOfDay::Dawn,
+            "dusk" => TimeOfDay::Dusk,
+            "night" => TimeOfDay::Night,
+            _ => TimeOfDay::Day,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CheckpointData {
-    latitude: f32,
-    longitude: f32,
+    latitude: f64,
+    longitude: f64,
     position: i32,
+    /// How close a car must pass for the checkpoint to count, between 5 and
+    /// 200 meters; 25 unless given
+    radius_meters: Option<f32>,
+    /// Mandatory unless given; a finish checkpoint must come last
+    #[serde(default)]
+    kind: CheckpointKind,
 }
 
-#[derive(Deserialize, ToSchema)]
+impl From<checkpoint::Model> for CheckpointData {
+    fn from(checkpoint: checkpoint::Model) -> Self {
+        Self {
+            latitude: checkpoint.latitude,
+            longitude: checkpoint.longitude,
+            position: checkpoint.position,
+            radius_meters: Some(checkpoint.radius_meters),
+            kind: CheckpointKind::from_column(&checkpoint.kind),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CreateMapRequest {
     title: String,
     description: String,
-    author_id: i32,
-    start_latitude: f32,
-    start_longitude: f32,
-    end_latitude: f32,
-    end_longitude: f32,
+    start_latitude: f64,
+    start_longitude: f64,
+    end_latitude: f64,
+    end_longitude: f64,
     checkpoints: Vec<CheckpointData>,
+    /// At most 8, e.g. "city", "offroad" or "sprint"
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Clear unless given
+    #[serde(default)]
+    weather: Weather,
+    /// Day unless given
+    #[serde(default)]
+    time_of_day: TimeOfDay,
+}
+
+/// Who can find a map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MapStatus {
+    /// Only visible to its author, until published
+    Draft,
+    Published,
+    /// Reachable by ID, but not listed
+    Unlisted,
+}
+
+impl MapStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MapStatus::Draft => "draft",
+            MapStatus::Published => "published",
+            MapStatus::Unlisted => "unlisted",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "draft" => MapStatus::Draft,
+            "unlisted" => MapStatus::Unlisted,
+            _ => MapStatus::Published,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PublishMapRequest {
+    /// Keep the map out of listings, so only those given its ID find it
+    #[serde(default)]
+    unlisted: bool,
+}
+
+/// Order of the map listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MapSort {
+    #[default]
+    Newest,
+    /// Most favorited first
+    Popular,
+    MostPlayed,
+    Easiest,
+    Hardest,
+}
+
+// Order and filters of the map listing
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListMapsParams {
+    /// Order of the maps, newest first by default
+    #[serde(default)]
+    sort: MapSort,
+    /// Only maps made by this user; listing the current user's own maps
+    /// includes their drafts and unlisted maps
+    author_id: Option<i32>,
+    /// Only maps with this tag
+    tag: Option<String>,
+    /// Only maps at least this difficult, from 1 to 10
+    min_difficulty: Option<f32>,
+    /// Only maps at most this difficult, from 1 to 10
+    max_difficulty: Option<f32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateMapParams {
+    /// Move the start, finish and checkpoints onto the nearest drivable
+    /// roads and keep the route between them, if road snapping is enabled
+    #[serde(default)]
+    snap: bool,
+    /// Create the map even if a nearly identical one already exists
+    #[serde(default)]
+    allow_duplicate: bool,
+}
+
+// Criteria of the map search; all given ones must match
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MapSearchParams {
+    /// Only maps whose title contains this, ignoring case
+    q: Option<String>,
+    /// Only maps starting at or north of this latitude
+    min_lat: Option<f64>,
+    /// Only maps starting at or south of this latitude
+    max_lat: Option<f64>,
+    /// Only maps starting at or east of this longitude
+    min_lon: Option<f64>,
+    /// Only maps starting at or west of this longitude
+    max_lon: Option<f64>,
+}
+
+// Criteria of a random map; all given ones must match
+#[derive(Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RandomMapParams {
+    /// Only maps with this tag
+    tag: Option<String>,
+    /// Only maps at least this difficult, from 1 to 10
+    min_difficulty: Option<f32>,
+    /// Only maps at most this difficult, from 1 to 10
+    max_difficulty: Option<f32>,
+    /// Only maps at least this long, in kilometers
+    min_length_km: Option<f64>,
+    /// Only maps at most this long, in kilometers
+    max_length_km: Option<f64>,
+}
+
+// Fields left out are kept
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateMapRequest {
+    title: Option<String>,
+    description: Option<String>,
+    start_latitude: Option<f64>,
+    start_longitude: Option<f64>,
+    end_latitude: Option<f64>,
+    end_longitude: Option<f64>,
+    /// Replaces all checkpoints of the map
+    checkpoints: Option<Vec<CheckpointData>>,
+    /// Replaces all tags of the map
+    tags: Option<Vec<String>>,
+    weather: Option<Weather>,
+    time_of_day: Option<TimeOfDay>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ThumbnailUploadRequest {
+    /// image/jpeg, image/png or image/webp
+    content_type: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ThumbnailUploadResponse {
+    /// URL to PUT the image to, with the requested content type
+    upload_url: String,
+    /// URL the image is served from once uploaded
+    thumbnail_url: String,
+    /// Seconds the upload URL can be used for
+    expires_in: u64,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -43,11 +372,44 @@ pub struct MapResponse {
     description: String,
     created_at: DateTime<chrono::FixedOffset>,
     author_id: i32,
-    start_latitude: f32,
-    start_longitude: f32,
-    end_latitude: f32,
-    end_longitude: f32,
+    status: MapStatus,
+    start_latitude: f64,
+    start_longitude: f64,
+    end_latitude: f64,
+    end_longitude: f64,
     checkpoint_count: i32,
+    favorite_count: i32,
+    /// Races started on the map of all time
+    play_count: i64,
+    /// Preview image of the map, if one was uploaded
+    thumbnail_url: Option<String>,
+    /// From 1 (easy) to 10 (hard), by the length, turns and checkpoint
+    /// density of the route
+    difficulty: Option<f32>,
+    /// Length of the route through the checkpoints in meters, in straight
+    /// lines between them
+    length_meters: Option<f64>,
+    /// Conditions every racer on the map sees
+    weather: Weather,
+    time_of_day: TimeOfDay,
+    /// Whether moderators took the map down pending review; only its author
+    /// and admins see it then
+    hidden: bool,
+    /// Tags of the map, in alphabetical order
+    tags: Vec<String>,
+    /// Who made the map, absent if they deleted their account
+    author: Option<MapAuthorResponse>,
+    /// Whether the current user favorited the map
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_favorited: Option<bool>,
+}
+
+/// The author of a map, as shown on map cards
+#[derive(Serialize, ToSchema)]
+pub struct MapAuthorResponse {
+    id: i32,
+    name: String,
+    avatar_url: Option<String>,
 }
 
 impl From<map::Model> for MapResponse {
@@ -58,22 +420,64 @@ impl From<map::Model> for MapResponse {
             description: map.description,
             created_at: map.created_at,
             author_id: map.author_id,
+            status: MapStatus::from_column(&map.status),
             start_latitude: map.start_latitude,
             start_longitude: map.start_longitude,
             end_latitude: map.end_latitude,
             end_longitude: map.end_longitude,
             checkpoint_count: map.checkpoint_count,
+            favorite_count: map.favorite_count,
+            play_count: map.play_count,
+            thumbnail_url: map.thumbnail_url,
+            difficulty: map.difficulty,
+            length_meters: map.length_meters,
+            weather: Weather::from_column(&map.weather),
+            time_of_day: TimeOfDay::from_column(&map.time_of_day),
+            hidden: map.hidden_at.is_some(),
+            tags: Vec::new(),
+            author: None,
+            is_favorited: None,
         }
     }
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct TrendingMapResponse {
+    #[serde(flatten)]
+    map: MapResponse,
+    /// Races started on the map in the last 7 days
+    recent_plays: i64,
+}
+
+// Location to find maps around
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NearbyMapsParams {
+    lat: f64,
+    lon: f64,
+    /// How far from the location maps may start, at most 100 km; 10 unless
+    /// given
+    radius_km: Option<f64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NearbyMapResponse {
+    #[serde(flatten)]
+    map: MapResponse,
+    /// How far from the location the map starts
+    distance_km: f64,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct CheckpointResponse {
     id: i32,
     map_id: i32,
-    latitude: f32,
-    longitude: f32,
+    latitude: f64,
+    longitude: f64,
     position: i32,
+    /// How close a car must pass for the checkpoint to count
+    radius_meters: f32,
+    kind: CheckpointKind,
 }
 
 impl From<checkpoint::Model> for CheckpointResponse {
@@ -84,6 +488,8 @@ impl From<checkpoint::Model> for CheckpointResponse {
             latitude: checkpoint.latitude,
             longitude: checkpoint.longitude,
             position: checkpoint.position,
+            radius_meters: checkpoint.radius_meters,
+            kind: CheckpointKind::from_column(&checkpoint.kind),
         }
     }
 }
@@ -92,172 +498,980 @@ impl From<checkpoint::Model> for CheckpointResponse {
 pub struct MapWithCheckpointsResponse {
     map: MapResponse,
     checkpoints: Vec<CheckpointResponse>,
+    /// Route through the points along roads, as an encoded polyline with a
+    /// precision of 6 digits, if the map was snapped to roads
+    route_polyline: Option<String>,
+    /// Road surface between each two points in a row, if the map was
+    /// snapped to roads and the surfaces are known
+    segments: Vec<SegmentResponse>,
+}
+
+/// Stretch of a route between two points in a row
+#[derive(Serialize, ToSchema)]
+pub struct SegmentResponse {
+    /// 0 leads from the start to the first checkpoint, the last one from the
+    /// last checkpoint to the finish
+    position: i32,
+    surface: Surface,
+}
+
+impl From<map_segment::Model> for SegmentResponse {
+    fn from(segment: map_segment::Model) -> Self {
+        Self {
+            position: segment.position,
+            surface: Surface::from_column(&segment.surface),
+        }
+    }
+}
+
+/// Everything needed to recreate a map, e.g. on another server
+///
+/// The map comes as it would be created, with its route along roads and the
+/// road surfaces of its legs if it was snapped to roads.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MapArchive {
+    /// "world-racers-map"
+    format: String,
+    /// Version of the archive format; servers read archives up to their own
+    /// version
+    version: u32,
+    /// Version of the server that exported the map
+    server_version: String,
+    exported_at: DateTime<Utc>,
+    map: CreateMapRequest,
+    /// Route along roads as an encoded polyline, if the map was snapped
+    route_polyline: Option<String>,
+    /// Road surface of each leg of a snapped map, from the start
+    #[serde(default)]
+    surfaces: Vec<Surface>,
+}
+
+/// A map as a GeoJSON FeatureCollection, for editing in GIS tools
+///
+/// The start, finish and checkpoints are points, and the route through them
+/// in order is a line. Coordinates are longitude first.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MapGeoJson {
+    /// "FeatureCollection"
+    #[serde(rename = "type")]
+    kind: String,
+    properties: MapGeoJsonProperties,
+    features: Vec<GeoJsonFeature>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MapGeoJsonProperties {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    weather: Weather,
+    #[serde(default)]
+    time_of_day: TimeOfDay,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GeoJsonFeature {
+    /// "Feature"
+    #[serde(rename = "type")]
+    kind: String,
+    geometry: GeoJsonGeometry,
+    properties: GeoJsonFeatureProperties,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type")]
+pub enum GeoJsonGeometry {
+    /// Longitude and latitude
+    Point {
+        coordinates: Vec<f64>,
+    },
+    LineString {
+        coordinates: Vec<Vec<f64>>,
+    },
+}
+
+/// What part of a map a feature is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GeoJsonRole {
+    Start,
+    Finish,
+    Checkpoint,
+    /// Ignored when importing, as it follows from the points
+    Route,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GeoJsonFeatureProperties {
+    role: GeoJsonRole,
+    /// Of checkpoints; their order in the collection unless given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<i32>,
+    /// Of checkpoints; 25 unless given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    radius_meters: Option<f32>,
+    /// Of checkpoints; mandatory unless given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<CheckpointKind>,
+}
+
+impl GeoJsonFeature {
+    fn new(geometry: GeoJsonGeometry, role: GeoJsonRole) -> Self {
+        Self {
+            kind: "Feature".to_string(),
+            geometry,
+            properties: GeoJsonFeatureProperties {
+                role,
+                position: None,
+                radius_meters: None,
+                kind: None,
+            },
+        }
+    }
 }
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/maps", get(list_maps))
         .route("/maps", post(create_map))
-        .route("/maps/{id}", get(get_map))
-        .route("/maps/{id}", delete(delete_map))
-        .route("/maps/{id}/checkpoints", get(get_checkpoints))
+        .route("/maps/search", get(search_maps))
+        .route("/maps/trending", get(trending_maps))
+        .route("/maps/nearby", get(nearby_maps))
+        .route("/maps/random", get(random_map))
+        .route("/maps/import", post(import_map))
+        .route("/maps/import/geojson", post(import_geojson))
+        .route(
+            "/maps/{id}",
+            get(get_map).put(update_map).delete(delete_map),
+        )
+        .route(
+            "/maps/{id}/checkpoints",
+            get(get_checkpoints).put(replace_checkpoints),
+        )
+        .route("/maps/{id}/thumbnail", post(upload_thumbnail))
+        .route("/maps/{id}/publish", post(publish_map))
+        .route("/maps/{id}/restore", post(restore_map))
         .route("/maps/{id}/details", get(get_map_with_checkpoints))
+        .route("/maps/{id}/stats", get(get_map_stats))
+        .route("/maps/{id}/geojson", get(export_geojson))
+        .route("/maps/{id}/export", get(export_map))
 }
 
-/// List all maps
+/// List maps
+///
+/// Lists published maps newest, most favorited, most played, easiest or
+/// hardest first, optionally filtered by author, tag and difficulty.
 #[utoipa::path(
     get,
     path = "/api/maps",
     tag = "maps",
+    params(PaginationParams, ListMapsParams),
     responses(
-        (status = 200, description = "List of maps retrieved successfully", body = Vec<MapResponse>),
+        (status = 200, description = "List of maps retrieved successfully", body = Paginated<MapResponse>),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 async fn list_maps(
     State(state): State<AppState>,
-) -> Result<Json<Vec<MapResponse>>, (StatusCode, String)> {
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(params): Query<ListMapsParams>,
+) -> Result<Json<Paginated<MapResponse>>, (StatusCode, String)> {
     let db = &state.conn;
 
-    let maps = Map::find()
-        .order_by_asc(map::Column::Id)
-        .all(db)
+    let mut query = Map::find().filter(map::Column::DeletedAt.is_null());
+
+    if let Some(author_id) = params.author_id {
+        query = query.filter(map::Column::AuthorId.eq(author_id));
+    }
+
+    // Users see all of their own maps
+    if params.author_id != Some(auth_user.0.sub) {
+        query = query
+            .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+            .filter(map::Column::HiddenAt.is_null());
+    }
+
+    if let Some(tag) = params.tag {
+        let tagged_ids: Vec<i32> = MapTag::find()
+            .select_only()
+            .column(map_tag::Column::MapId)
+            .inner_join(Tag)
+            .filter(tag::Column::Name.eq(tag.trim().to_lowercase()))
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        query = query.filter(map::Column::Id.is_in(tagged_ids));
+    }
+
+    if let Some(min_difficulty) = params.min_difficulty {
+        query = query.filter(map::Column::Difficulty.gte(min_difficulty));
+    }
+    if let Some(max_difficulty) = params.max_difficulty {
+        query = query.filter(map::Column::Difficulty.lte(max_difficulty));
+    }
+
+    // Maps not rated yet come last
+    query = match params.sort {
+        MapSort::Newest => query.order_by_desc(map::Column::CreatedAt),
+        MapSort::Popular => query.order_by_desc(map::Column::FavoriteCount),
+        MapSort::MostPlayed => query.order_by_desc(map::Column::PlayCount),
+        MapSort::Easiest => {
+            query.order_by_with_nulls(map::Column::Difficulty, Order::Asc, NullOrdering::Last)
+        }
+        MapSort::Hardest => {
+            query.order_by_with_nulls(map::Column::Difficulty, Order::Desc, NullOrdering::Last)
+        }
+    };
+
+    // Keep pages stable among maps that tie
+    let paginator = query
+        .order_by_desc(map::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: Vec<MapResponse> = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(MapResponse::from)
+        .collect();
+
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(maps.into_iter().map(MapResponse::from).collect()))
+    Ok(Json(Paginated::new(
+        maps,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
 }
 
-/// Get a map by ID
+/// Search maps by title and where they start
+///
+/// Finds published maps whose title contains `q` and whose start point lies
+/// in the bounding box, newest first. Any of the bounds may be left out.
 #[utoipa::path(
     get,
-    path = "/api/maps/{id}",
+    path = "/api/maps/search",
     tag = "maps",
-    params(
-        ("id" = i32, Path, description = "Map ID")
-    ),
+    params(MapSearchParams, PaginationParams),
     responses(
-        (status = 200, description = "Map found", body = MapResponse),
-        (status = 404, description = "Map not found", body = String),
+        (status = 200, description = "Maps retrieved successfully", body = Paginated<MapResponse>),
+        (status = 400, description = "Invalid bounding box", body = String),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-async fn get_map(
+async fn search_maps(
     State(state): State<AppState>,
-    Path(id): Path<i32>,
-) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    auth_user: AuthUser,
+    Query(search): Query<MapSearchParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<MapResponse>>, (StatusCode, String)> {
     let db = &state.conn;
 
-    let map = Map::find_by_id(id)
-        .one(db)
+    check_bounds("lat", search.min_lat, search.max_lat, 90.0)?;
+    check_bounds("lon", search.min_lon, search.max_lon, 180.0)?;
+
+    let mut query = Map::find()
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null());
+
+    if let Some(q) = search.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        // Match the search literally, not as a pattern
+        let escaped = q
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        query = query.filter(
+            Expr::expr(Func::lower(Expr::col(map::Column::Title)))
+                .like(LikeExpr::new(format!("%{}%", escaped)).escape('\\')),
+        );
+    }
+
+    if let Some(min_lat) = search.min_lat {
+        query = query.filter(map::Column::StartLatitude.gte(min_lat));
+    }
+    if let Some(max_lat) = search.max_lat {
+        query = query.filter(map::Column::StartLatitude.lte(max_lat));
+    }
+    if let Some(min_lon) = search.min_lon {
+        query = query.filter(map::Column::StartLongitude.gte(min_lon));
+    }
+    if let Some(max_lon) = search.max_lon {
+        query = query.filter(map::Column::StartLongitude.lte(max_lon));
+    }
+
+    let paginator = query
+        .order_by_desc(map::Column::CreatedAt)
+        .order_by_desc(map::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: Vec<MapResponse> = paginator
+        .fetch_page(pagination.page() - 1)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((
-            StatusCode::NOT_FOUND,
-            format!("Map with id {} not found", id),
-        ))?;
+        .into_iter()
+        .map(MapResponse::from)
+        .collect();
+
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(map.into()))
+    Ok(Json(Paginated::new(
+        maps,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
 }
 
-/// Get a map with all its checkpoints
+/// Find maps starting near a location
+///
+/// Lists published maps starting within the radius of the location, closest
+/// first, so players can race courses built around where they are.
 #[utoipa::path(
     get,
-    path = "/api/maps/{id}/details",
+    path = "/api/maps/nearby",
     tag = "maps",
-    params(
-        ("id" = i32, Path, description = "Map ID")
-    ),
+    params(NearbyMapsParams, PaginationParams),
     responses(
-        (status = 200, description = "Map with checkpoints found", body = MapWithCheckpointsResponse),
-        (status = 404, description = "Map not found", body = String),
+        (status = 200, description = "Maps retrieved successfully", body = Paginated<NearbyMapResponse>),
+        (status = 400, description = "Invalid location or radius", body = String),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-async fn get_map_with_checkpoints(
+async fn nearby_maps(
     State(state): State<AppState>,
-    Path(id): Path<i32>,
-) -> Result<Json<MapWithCheckpointsResponse>, (StatusCode, String)> {
-    let db: &DatabaseConnection = &state.conn;
+    auth_user: AuthUser,
+    Query(params): Query<NearbyMapsParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<NearbyMapResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
 
-    let map = Map::find_by_id(id)
-        .one(db)
+    if !(-90.0..=90.0).contains(&params.lat) || !(-180.0..=180.0).contains(&params.lon) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "lat must be between -90 and 90 and lon between -180 and 180".to_string(),
+        ));
+    }
+    let radius_km = params.radius_km.unwrap_or(DEFAULT_NEARBY_RADIUS_KM);
+    if !(radius_km > 0.0 && radius_km <= MAX_NEARBY_RADIUS_KM) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "radius_km must be above 0 and at most {}",
+                MAX_NEARBY_RADIUS_KM
+            ),
+        ));
+    }
+    let radius_meters = radius_km * 1000.0;
+
+    // The box around the location is looked up in the index, and the maps
+    // in its corners are left out by their distance
+    let start = r#"ll_to_earth("map"."start_latitude", "map"."start_longitude")"#;
+    let within_box = Expr::cust_with_values(
+        format!("earth_box(ll_to_earth(?, ?), ?) @> {}", start),
+        [params.lat, params.lon, radius_meters],
+    );
+    let distance = Expr::cust_with_values(
+        format!("earth_distance(ll_to_earth(?, ?), {})", start),
+        [params.lat, params.lon],
+    );
+
+    let paginator = Map::find()
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .filter(within_box)
+        .filter(Expr::expr(distance.clone()).lte(radius_meters))
+        .order_by(distance, Order::Asc)
+        .order_by_asc(map::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((
-            StatusCode::NOT_FOUND,
-            format!("Map with id {} not found", id),
-        ))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let nearby = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let checkpoints = Checkpoint::find()
-        .filter(checkpoint::Column::MapId.eq(id))
-        .order_by_asc(checkpoint::Column::Position)
-        .all(db)
+    let distances: Vec<f64> = nearby
+        .iter()
+        .map(|map| {
+            let meters = distance_meters(
+                (params.lat, params.lon),
+                (map.start_latitude, map.start_longitude),
+            );
+            (meters / 10.0).round() / 100.0
+        })
+        .collect();
+    let mut maps: Vec<MapResponse> = nearby.into_iter().map(MapResponse::from).collect();
+
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let response = MapWithCheckpointsResponse {
-        map: map.into(),
-        checkpoints: checkpoints
-            .into_iter()
-            .map(CheckpointResponse::from)
-            .collect(),
-    };
+    let maps = maps
+        .into_iter()
+        .zip(distances)
+        .map(|(map, distance_km)| NearbyMapResponse { map, distance_km })
+        .collect();
 
-    Ok(Json(response))
+    Ok(Json(Paginated::new(
+        maps,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
 }
 
-/// Create a new map
+// Helper function to check one side of a bounding box. Both bounds must be
+// real coordinates and the lower can't exceed the upper.
+fn check_bounds(
+    axis: &str,
+    min: Option<f64>,
+    max: Option<f64>,
+    limit: f64,
+) -> Result<(), (StatusCode, String)> {
+    for (name, bound) in [("min", min), ("max", max)] {
+        if let Some(bound) = bound
+            && !(-limit..=limit).contains(&bound)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("{}_{} must be between -{} and {}", name, axis, limit, limit),
+            ));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min, max)
+        && min > max
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("min_{} can't be greater than max_{}", axis, axis),
+        ));
+    }
+
+    Ok(())
+}
+
+/// List trending maps
+///
+/// Lists the 20 published maps raced most in the last 7 days, most played
+/// first.
 #[utoipa::path(
-    post,
-    path = "/api/maps",
+    get,
+    path = "/api/maps/trending",
     tag = "maps",
-    request_body = CreateMapRequest,
     responses(
-        (status = 200, description = "Map created successfully", body = MapWithCheckpointsResponse),
-        (status = 400, description = "Invalid request", body = String),
+        (status = 200, description = "Trending maps retrieved successfully", body = Vec<TrendingMapResponse>),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-async fn create_map(
+async fn trending_maps(
     State(state): State<AppState>,
-    Json(payload): Json<CreateMapRequest>,
-) -> Result<Json<MapWithCheckpointsResponse>, (StatusCode, String)> {
+    auth_user: AuthUser,
+) -> Result<Json<Vec<TrendingMapResponse>>, (StatusCode, String)> {
     let db = &state.conn;
+    let since = Utc::now() - Duration::days(TRENDING_DAYS);
 
-    // Verify author exists
-    let _author = User::find_by_id(payload.author_id)
+    let recent_plays: Vec<(i32, i64)> = MapPlay::find()
+        .select_only()
+        .column(map_play::Column::MapId)
+        .column_as(map_play::Column::Id.count(), "recent_plays")
+        .inner_join(Map)
+        .filter(map_play::Column::PlayedAt.gte(since))
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .group_by(map_play::Column::MapId)
+        .order_by_desc(map_play::Column::Id.count())
+        .order_by_desc(map_play::Column::MapId)
+        .limit(TRENDING_LIMIT)
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: HashMap<i32, map::Model> = Map::find()
+        .filter(map::Column::Id.is_in(recent_plays.iter().map(|(map_id, _)| *map_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect();
+
+    let (mut trending, plays): (Vec<MapResponse>, Vec<i64>) = recent_plays
+        .into_iter()
+        .filter_map(|(map_id, plays)| Some((MapResponse::from(maps.remove(&map_id)?), plays)))
+        .unzip();
+
+    mark_favorites(db, auth_user.0.sub, &mut trending)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut trending)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut trending)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        trending
+            .into_iter()
+            .zip(plays)
+            .map(|(map, recent_plays)| TrendingMapResponse { map, recent_plays })
+            .collect(),
+    ))
+}
+
+/// Get a random map
+///
+/// Picks one of the published maps matching the criteria, each as likely as
+/// the others.
+#[utoipa::path(
+    get,
+    path = "/api/maps/random",
+    tag = "maps",
+    params(RandomMapParams),
+    responses(
+        (status = 200, description = "Map picked successfully", body = MapResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "No map matches the criteria", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn random_map(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(params): Query<RandomMapParams>,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let map = pick_random_map(db, &params)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "No map matches the criteria".to_string(),
+        ))?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(map))
+}
+
+/// Pick one of the published maps matching the criteria at random, if any
+/// does. The matching maps are counted and one is read at a random offset,
+/// which spares sorting them all by `random()`.
+pub async fn pick_random_map<C: ConnectionTrait>(
+    db: &C,
+    params: &RandomMapParams,
+) -> Result<Option<map::Model>, DbErr> {
+    let mut query = Map::find()
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null());
+
+    if let Some(tag) = &params.tag {
+        let tagged_ids = SqlQuery::select()
+            .column(map_tag::Column::MapId)
+            .from(MapTag)
+            .inner_join(
+                Tag,
+                Expr::col((Tag, tag::Column::Id)).equals((MapTag, map_tag::Column::TagId)),
+            )
+            .and_where(tag::Column::Name.eq(tag.trim().to_lowercase()))
+            .to_owned();
+        query = query.filter(map::Column::Id.in_subquery(tagged_ids));
+    }
+
+    if let Some(min_difficulty) = params.min_difficulty {
+        query = query.filter(map::Column::Difficulty.gte(min_difficulty));
+    }
+    if let Some(max_difficulty) = params.max_difficulty {
+        query = query.filter(map::Column::Difficulty.lte(max_difficulty));
+    }
+    if let Some(min_length_km) = params.min_length_km {
+        query = query.filter(map::Column::LengthMeters.gte(min_length_km * 1000.0));
+    }
+    if let Some(max_length_km) = params.max_length_km {
+        query = query.filter(map::Column::LengthMeters.lte(max_length_km * 1000.0));
+    }
+
+    let count = query.clone().count(db).await?;
+    if count == 0 {
+        return Ok(None);
+    }
+
+    let offset = rand::rng().random_range(0..count);
+    query
+        .order_by_asc(map::Column::Id)
+        .offset(offset)
         .one(db)
         .await
+}
+
+/// Get a map by ID
+#[utoipa::path(
+    get,
+    path = "/api/maps/{id}",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map found", body = MapResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn get_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let map = find_visible_map(db, id, &auth_user).await?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(map))
+}
+
+/// Get a map with all its checkpoints
+#[utoipa::path(
+    get,
+    path = "/api/maps/{id}/details",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map with checkpoints found", body = MapWithCheckpointsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn get_map_with_checkpoints(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapWithCheckpointsResponse>, (StatusCode, String)> {
+    let db: &DatabaseConnection = &state.conn;
+
+    let map = find_visible_map(db, id, &auth_user).await?;
+    let route_polyline = map.route_polyline.clone();
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let segments = load_segments(db, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let response = MapWithCheckpointsResponse {
+        map,
+        checkpoints: checkpoints
+            .into_iter()
+            .map(CheckpointResponse::from)
+            .collect(),
+        route_polyline,
+        segments,
+    };
+
+    Ok(Json(response))
+}
+
+/// Get the statistics of a map
+///
+/// Added up from every race on the map. Statistics may be up to a minute
+/// old, except right after a race on the map ended.
+#[utoipa::path(
+    get,
+    path = "/api/maps/{id}/stats",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map statistics retrieved successfully", body = MapStats),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn get_map_stats(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapStats>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    find_visible_map(db, id, &auth_user).await?;
+
+    let stats = state
+        .map_stats
+        .get(db, id)
+        .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((
-            StatusCode::BAD_REQUEST,
-            format!("User with id {} not found", payload.author_id),
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
         ))?;
 
+    Ok(Json(stats))
+}
+
+/// Create a new map
+///
+/// The map is a draft, only visible to its author, until it is published.
+/// With road snapping, its points are moved onto the nearest drivable roads.
+/// Maps nearly identical to one the user can see are refused unless
+/// duplicates are allowed.
+#[utoipa::path(
+    post,
+    path = "/api/maps",
+    tag = "maps",
+    params(CreateMapParams),
+    request_body = CreateMapRequest,
+    responses(
+        (status = 200, description = "Map created successfully", body = MapWithCheckpointsResponse),
+        (status = 400, description = "Invalid request, or the points can't be snapped to roads", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 409, description = "A nearly identical map already exists", body = String),
+        (status = 422, description = "Invalid map title, tags or points", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String),
+        (status = 502, description = "The road snapping service failed", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn create_map(
+    State(state): State<AppState>,
+    Query(params): Query<CreateMapParams>,
+    auth_user: MapUploadUser,
+    Json(payload): Json<CreateMapRequest>,
+) -> Result<Json<MapWithCheckpointsResponse>, Response> {
+    insert_map(&state, &params, &auth_user, payload, None).await
+}
+
+// Helper function to create a map, with its route along roads and the road
+// surfaces of its legs if they are known already
+async fn insert_map(
+    state: &AppState,
+    params: &CreateMapParams,
+    auth_user: &MapUploadUser,
+    mut payload: CreateMapRequest,
+    known_route: Option<(String, Vec<Surface>)>,
+) -> Result<Json<MapWithCheckpointsResponse>, Response> {
+    let db = &state.conn;
+
+    let title = state
+        .validator
+        .text("title", &payload.title, MAX_TITLE_LENGTH)
+        .map_err(|e| validation::invalid(vec![e]))?;
+    let tags = check_tags(state, &payload.tags).map_err(|e| validation::invalid(vec![e]))?;
+    let errors = check_geometry(
+        (payload.start_latitude, payload.start_longitude),
+        (payload.end_latitude, payload.end_longitude),
+        &payload.checkpoints,
+    );
+    if !errors.is_empty() {
+        return Err(validation::invalid(errors));
+    }
+
+    let (route_polyline, surfaces) = match known_route {
+        Some((route_polyline, surfaces)) => (Some(route_polyline), surfaces),
+        None if params.snap => {
+            let (route_polyline, surfaces) = snap_to_roads(state, &mut payload).await?;
+            (Some(route_polyline), surfaces)
+        }
+        None => (None, Vec::new()),
+    };
+    let route = request_route(
+        (payload.start_latitude, payload.start_longitude),
+        (payload.end_latitude, payload.end_longitude),
+        &payload.checkpoints,
+    );
+    let difficulty = difficulty::rate(&route);
+    let length_meters = difficulty::length_meters(&route);
+    let fingerprint = fingerprint::of(&route);
+
+    if !params.allow_duplicate {
+        let duplicate = Map::find()
+            .filter(map::Column::Fingerprint.eq(fingerprint.as_str()))
+            .filter(map::Column::DeletedAt.is_null())
+            .order_by_asc(map::Column::Id)
+            .all(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+            .into_iter()
+            .find(|map| policy::can_view_map(&auth_user.0, map));
+        if let Some(duplicate) = duplicate {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Map {} is nearly identical; allow duplicates to create the map anyway",
+                    duplicate.id
+                ),
+            )
+                .into_response());
+        }
+    }
+
+    // The map is authored by the current user
+    let author_id = auth_user.0.sub;
+
+    // Verify author exists
+    let _author = User::find_by_id(author_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("User with id {} not found", author_id),
+            )
+                .into_response()
+        })?;
+
     // Start a transaction
     let txn = db
         .begin()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
     // Create the map
     let new_map = map::ActiveModel {
-        title: Set(payload.title),
+        title: Set(title),
         description: Set(payload.description),
-        author_id: Set(payload.author_id),
+        author_id: Set(author_id),
         start_latitude: Set(payload.start_latitude),
         start_longitude: Set(payload.start_longitude),
         end_latitude: Set(payload.end_latitude),
         end_longitude: Set(payload.end_longitude),
         checkpoint_count: Set(payload.checkpoints.len() as i32),
+        status: Set(MapStatus::Draft.as_str().to_string()),
+        route_polyline: Set(route_polyline),
+        difficulty: Set(Some(difficulty)),
+        length_meters: Set(Some(length_meters)),
+        fingerprint: Set(Some(fingerprint)),
+        weather: Set(payload.weather.as_str().to_string()),
+        time_of_day: Set(payload.time_of_day.as_str().to_string()),
         ..Default::default()
     };
 
     let map = new_map
         .insert(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
     // Create checkpoints
     let mut checkpoints = Vec::new();
@@ -268,121 +1482,1079 @@ async fn create_map(
             latitude: Set(checkpoint_data.latitude),
             longitude: Set(checkpoint_data.longitude),
             position: Set(checkpoint_data.position),
+            radius_meters: Set(checkpoint_data
+                .radius_meters
+                .unwrap_or(CHECKPOINT_RADIUS_METERS as f32)),
+            kind: Set(checkpoint_data.kind.as_str().to_string()),
             ..Default::default()
         };
 
         let checkpoint = new_checkpoint
             .insert(&txn)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
         checkpoints.push(checkpoint);
     }
 
+    let mut segments = Vec::with_capacity(surfaces.len());
+    for (position, surface) in surfaces.into_iter().enumerate() {
+        let segment = map_segment::ActiveModel {
+            map_id: Set(map.id),
+            position: Set(position as i32),
+            surface: Set(surface.as_str().to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+        segments.push(SegmentResponse::from(segment));
+    }
+
+    set_tags(&txn, map.id, &tags)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
     // Commit transaction
     txn.commit()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    if let Err(e) = progression::award(db, author_id, progression::MAP_CREATED_XP).await {
+        tracing::error!("Error awarding map creation XP: {}", e);
+    }
+
+    if let Err(e) = activity::record(
+        db,
+        author_id,
+        activity::Kind::MapCreated,
+        Some(map.id),
+        None,
+    )
+    .await
+    {
+        tracing::error!("Error recording map creation activity: {}", e);
+    }
 
-    // Create response
+    // Create response; nobody favorited the new map yet
+    let route_polyline = map.route_polyline.clone();
     let response = MapWithCheckpointsResponse {
-        map: map.into(),
+        map: MapResponse {
+            tags,
+            is_favorited: Some(false),
+            ..map.into()
+        },
         checkpoints: checkpoints
             .into_iter()
             .map(CheckpointResponse::from)
             .collect(),
+        route_polyline,
+        segments,
     };
 
     Ok(Json(response))
 }
 
-/// Delete a map and all its checkpoints
+// Helper function to move the points of a new map onto roads. Returns the
+// route through them as an encoded polyline, and the surface between each
+// two points in a row if it can be looked up.
+async fn snap_to_roads(
+    state: &AppState,
+    payload: &mut CreateMapRequest,
+) -> Result<(String, Vec<Surface>), Response> {
+    let Some(snapper) = state.config.road_snapper() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Road snapping is not available".to_string(),
+        )
+            .into_response());
+    };
+
+    // Cars drive from the start through the checkpoints to the finish
+    payload
+        .checkpoints
+        .sort_by_key(|checkpoint| checkpoint.position);
+    let points = request_route(
+        (payload.start_latitude, payload.start_longitude),
+        (payload.end_latitude, payload.end_longitude),
+        &payload.checkpoints,
+    );
+
+    let route = snapper.snap(&points).await.map_err(|e| {
+        let status = match e {
+            RoadSnapError::NoRoute(_) => StatusCode::BAD_REQUEST,
+            RoadSnapError::Request(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, e.to_string()).into_response()
+    })?;
+
+    let mut snapped = route.points.into_iter();
+    let (Some(start), Some(end)) = (snapped.next(), snapped.next_back()) else {
+        return Err((
+            StatusCode::BAD_GATEWAY,
+            "Road snapping lost points".to_string(),
+        )
+            .into_response());
+    };
+    (payload.start_latitude, payload.start_longitude) = start;
+    (payload.end_latitude, payload.end_longitude) = end;
+    for (checkpoint, (latitude, longitude)) in payload.checkpoints.iter_mut().zip(snapped) {
+        checkpoint.latitude = latitude;
+        checkpoint.longitude = longitude;
+    }
+
+    // Points close to each other may end up on the same spot of a road
+    let errors = check_geometry(
+        (payload.start_latitude, payload.start_longitude),
+        (payload.end_latitude, payload.end_longitude),
+        &payload.checkpoints,
+    );
+    if !errors.is_empty() {
+        return Err(validation::invalid(errors));
+    }
+
+    // Maps are fine without surfaces, so failing to look them up is no error
+    let mut surfaces = Vec::new();
+    if let Some(road_surfaces) = state.config.road_surfaces()
+        && !route.leg_nodes.is_empty()
+    {
+        match road_surfaces.surfaces(&route.leg_nodes).await {
+            Ok(found) => surfaces = found,
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+
+    Ok((route.polyline, surfaces))
+}
+
+/// Update a map (only by author or an admin)
+///
+/// Checkpoints given replace all checkpoints of the map, the way replacing
+/// only the checkpoints does. Maps that aren't drafts must still pass the
+/// checks of publishing.
 #[utoipa::path(
-    delete,
+    put,
     path = "/api/maps/{id}",
     tag = "maps",
     params(
         ("id" = i32, Path, description = "Map ID")
     ),
+    request_body = UpdateMapRequest,
     responses(
-        (status = 204, description = "Map deleted successfully"),
+        (status = 200, description = "Map updated successfully", body = MapWithCheckpointsResponse),
+        (status = 400, description = "The map can't be raced on anymore", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can edit the map", body = String),
         (status = 404, description = "Map not found", body = String),
+        (status = 422, description = "Invalid map title, tags or points", body = ValidationErrorResponse),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-async fn delete_map(
+async fn update_map(
     State(state): State<AppState>,
     Path(id): Path<i32>,
-) -> Result<StatusCode, (StatusCode, String)> {
+    auth_user: AuthUser,
+    Json(payload): Json<UpdateMapRequest>,
+) -> Result<Json<MapWithCheckpointsResponse>, Response> {
     let db = &state.conn;
 
-    // Check if map exists
-    let _map = Map::find_by_id(id)
-        .one(db)
+    let title = payload
+        .title
+        .map(|title| state.validator.text("title", &title, MAX_TITLE_LENGTH))
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?;
+    let tags = payload
+        .tags
+        .map(|tags| check_tags(&state, &tags))
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?;
+
+    let map = find_editable_map(db, id, &auth_user, "edit")
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((
-            StatusCode::NOT_FOUND,
-            format!("Map with id {} not found", id),
-        ))?;
+        .map_err(IntoResponse::into_response)?;
+
+    // Moved points are checked and rated along with the points that stay
+    let moves_points = payload.checkpoints.is_some()
+        || payload.start_latitude.is_some()
+        || payload.start_longitude.is_some()
+        || payload.end_latitude.is_some()
+        || payload.end_longitude.is_some();
+    let mut difficulty = None;
+    let mut length_meters = None;
+    let mut route_fingerprint = None;
+    if moves_points {
+        let stored_checkpoints;
+        let checkpoints = match &payload.checkpoints {
+            Some(checkpoints) => checkpoints.as_slice(),
+            None => {
+                stored_checkpoints = Checkpoint::find()
+                    .filter(checkpoint::Column::MapId.eq(id))
+                    .all(db)
+                    .await
+                    .map_err(|e| {
+                        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                    })?
+                    .into_iter()
+                    .map(CheckpointData::from)
+                    .collect::<Vec<_>>();
+                stored_checkpoints.as_slice()
+            }
+        };
+
+        let start = (
+            payload.start_latitude.unwrap_or(map.start_latitude),
+            payload.start_longitude.unwrap_or(map.start_longitude),
+        );
+        let end = (
+            payload.end_latitude.unwrap_or(map.end_latitude),
+            payload.end_longitude.unwrap_or(map.end_longitude),
+        );
+
+        let errors = check_geometry(start, end, checkpoints);
+        if !errors.is_empty() {
+            return Err(validation::invalid(errors));
+        }
+
+        let route = request_route(start, end, checkpoints);
+        difficulty = Some(difficulty::rate(&route));
+        length_meters = Some(difficulty::length_meters(&route));
+        route_fingerprint = Some(fingerprint::of(&route));
+    }
 
     // Start a transaction
     let txn = db
         .begin()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
-    // Delete all checkpoints first
-    Checkpoint::delete_many()
-        .filter(checkpoint::Column::MapId.eq(id))
-        .exec(&txn)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut map: map::ActiveModel = map.into();
 
-    // Then delete the map
-    Map::delete_by_id(id)
-        .exec(&txn)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    // The route along roads doesn't lead through moved points anymore
+    if moves_points {
+        map.route_polyline = Set(None);
+        map.difficulty = Set(difficulty);
+        map.length_meters = Set(length_meters);
+        map.fingerprint = Set(route_fingerprint);
+    }
+    if let Some(title) = title {
+        map.title = Set(title);
+    }
+    if let Some(description) = payload.description {
+        map.description = Set(description);
+    }
+    if let Some(weather) = payload.weather {
+        map.weather = Set(weather.as_str().to_string());
+    }
+    if let Some(time_of_day) = payload.time_of_day {
+        map.time_of_day = Set(time_of_day.as_str().to_string());
+    }
+    if let Some(start_latitude) = payload.start_latitude {
+        map.start_latitude = Set(start_latitude);
+    }
+    if let Some(start_longitude) = payload.start_longitude {
+        map.start_longitude = Set(start_longitude);
+    }
+    if let Some(end_latitude) = payload.end_latitude {
+        map.end_latitude = Set(end_latitude);
+    }
+    if let Some(end_longitude) = payload.end_longitude {
+        map.end_longitude = Set(end_longitude);
+    }
+
+    if moves_points {
+        delete_segments(&txn, id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    }
+
+    // Replace the checkpoints, if given
+    if let Some(checkpoints) = payload.checkpoints {
+        sync_checkpoints(&txn, id, &checkpoints)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+        map.checkpoint_count = Set(checkpoints.len() as i32);
+    }
+
+    let map = map
+        .update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    if let Some(tags) = tags {
+        set_tags(&txn, id, &tags)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    }
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let segments = load_segments(&txn, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // Maps others can race on must stay raceable
+    if MapStatus::from_column(&map.status) != MapStatus::Draft {
+        check_publishable(&map, &checkpoints).map_err(IntoResponse::into_response)?;
+    }
 
-    // Commit the transaction
+    // Commit transaction
     txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let route_polyline = map.route_polyline.clone();
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    Ok(Json(MapWithCheckpointsResponse {
+        map,
+        checkpoints: checkpoints
+            .into_iter()
+            .map(CheckpointResponse::from)
+            .collect(),
+        route_polyline,
+        segments,
+    }))
+}
+
+/// Delete a map (only by author or an admin)
+///
+/// The map disappears right away, but is kept for a few days in case its
+/// author wants it back. After that it is purged with all its checkpoints.
+#[utoipa::path(
+    delete,
+    path = "/api/maps/{id}",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 204, description = "Map deleted successfully"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can delete the map", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn delete_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let map = find_editable_map(db, id, &auth_user, "delete").await?;
+
+    let mut map: map::ActiveModel = map.into();
+    map.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    map.update(db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Get all checkpoints for a map
+/// Restore a deleted map (only by author or an admin)
+///
+/// Maps can be restored for a few days after they were deleted, until they
+/// are purged.
 #[utoipa::path(
-    get,
+    post,
+    path = "/api/maps/{id}/restore",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map restored successfully", body = MapResponse),
+        (status = 400, description = "The map isn't deleted", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can restore the map", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 410, description = "The map was deleted too long ago to be restored", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn restore_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let map = Map::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
+        ))?;
+
+    if !policy::can_edit_map(&auth_user.0, &map) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the map author or an admin can restore the map".to_string(),
+        ));
+    }
+
+    let Some(deleted_at) = map.deleted_at else {
+        return Err((StatusCode::BAD_REQUEST, "The map isn't deleted".to_string()));
+    };
+
+    // The map may not have been purged yet, but is due to be
+    let restorable_since = Utc::now() - Duration::days(state.config.map_purge_delay);
+    if deleted_at < restorable_since {
+        return Err((
+            StatusCode::GONE,
+            "The map was deleted too long ago to be restored".to_string(),
+        ));
+    }
+
+    let mut map: map::ActiveModel = map.into();
+    map.deleted_at = Set(None);
+    let map = map
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(map))
+}
+
+/// Replace all checkpoints of a map (only by author or an admin)
+///
+/// Takes the full list of checkpoints the map should have. Checkpoints at
+/// positions the map already has keep their ids and are only changed where
+/// they differ, so times recorded at them stay valid. Checkpoints at new
+/// positions are added, and those at positions left out are removed. Maps
+/// that aren't drafts must still pass the checks of publishing.
+#[utoipa::path(
+    put,
     path = "/api/maps/{map_id}/checkpoints",
     tag = "maps",
     params(
         ("map_id" = i32, Path, description = "Map ID")
     ),
+    request_body = Vec<CheckpointData>,
     responses(
-        (status = 200, description = "Checkpoints retrieved successfully", body = Vec<CheckpointResponse>),
+        (status = 200, description = "Checkpoints replaced successfully", body = Vec<CheckpointResponse>),
+        (status = 400, description = "The map can't be raced on anymore", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can edit the map", body = String),
         (status = 404, description = "Map not found", body = String),
+        (status = 422, description = "Invalid points", body = ValidationErrorResponse),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-async fn get_checkpoints(
+async fn replace_checkpoints(
     State(state): State<AppState>,
     Path(map_id): Path<i32>,
-) -> Result<Json<Vec<CheckpointResponse>>, (StatusCode, String)> {
+    auth_user: AuthUser,
+    Json(checkpoints): Json<Vec<CheckpointData>>,
+) -> Result<Json<Vec<CheckpointResponse>>, Response> {
     let db = &state.conn;
 
-    // First check if map exists
-    let _ = Map::find_by_id(map_id)
+    let map = find_editable_map(db, map_id, &auth_user, "edit")
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let start = (map.start_latitude, map.start_longitude);
+    let end = (map.end_latitude, map.end_longitude);
+    let errors = check_geometry(start, end, &checkpoints);
+    if !errors.is_empty() {
+        return Err(validation::invalid(errors));
+    }
+    let route = request_route(start, end, &checkpoints);
+    let difficulty = difficulty::rate(&route);
+    let length_meters = difficulty::length_meters(&route);
+    let fingerprint = fingerprint::of(&route);
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    sync_checkpoints(&txn, map_id, &checkpoints)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    delete_segments(&txn, map_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // The route along roads doesn't lead through moved points anymore
+    let mut map: map::ActiveModel = map.into();
+    map.checkpoint_count = Set(checkpoints.len() as i32);
+    map.route_polyline = Set(None);
+    map.difficulty = Set(Some(difficulty));
+    map.length_meters = Set(Some(length_meters));
+    map.fingerprint = Set(Some(fingerprint));
+    let map = map
+        .update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(map_id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // Maps others can race on must stay raceable
+    if MapStatus::from_column(&map.status) != MapStatus::Draft {
+        check_publishable(&map, &checkpoints).map_err(IntoResponse::into_response)?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    Ok(Json(
+        checkpoints
+            .into_iter()
+            .map(CheckpointResponse::from)
+            .collect(),
+    ))
+}
+
+// Helper function to load the segments of a map in order
+async fn load_segments<C: ConnectionTrait>(
+    db: &C,
+    map_id: i32,
+) -> Result<Vec<SegmentResponse>, DbErr> {
+    Ok(MapSegment::find()
+        .filter(map_segment::Column::MapId.eq(map_id))
+        .order_by_asc(map_segment::Column::Position)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(SegmentResponse::from)
+        .collect())
+}
+
+// Helper function to forget the surfaces of a map whose points moved, as
+// they were found along the route through the old points
+async fn delete_segments<C: ConnectionTrait>(db: &C, map_id: i32) -> Result<(), DbErr> {
+    MapSegment::delete_many()
+        .filter(map_segment::Column::MapId.eq(map_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+// Helper function to make the checkpoints of a map match the given ones.
+// Checkpoints are matched by position, which is unique once checked, so
+// those at positions that stay keep their ids.
+async fn sync_checkpoints<C: ConnectionTrait>(
+    db: &C,
+    map_id: i32,
+    checkpoints: &[CheckpointData],
+) -> Result<(), DbErr> {
+    let mut stored: HashMap<i32, checkpoint::Model> = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(map_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|checkpoint| (checkpoint.position, checkpoint))
+        .collect();
+
+    for checkpoint_data in checkpoints {
+        let radius_meters = checkpoint_data
+            .radius_meters
+            .unwrap_or(CHECKPOINT_RADIUS_METERS as f32);
+        let kind = checkpoint_data.kind.as_str();
+
+        match stored.remove(&checkpoint_data.position) {
+            Some(checkpoint) => {
+                let unchanged = checkpoint.latitude == checkpoint_data.latitude
+                    && checkpoint.longitude == checkpoint_data.longitude
+                    && checkpoint.radius_meters == radius_meters
+                    && checkpoint.kind == kind;
+                if unchanged {
+                    continue;
+                }
+
+                let mut checkpoint: checkpoint::ActiveModel = checkpoint.into();
+                checkpoint.latitude = Set(checkpoint_data.latitude);
+                checkpoint.longitude = Set(checkpoint_data.longitude);
+                checkpoint.radius_meters = Set(radius_meters);
+                checkpoint.kind = Set(kind.to_string());
+                checkpoint.update(db).await?;
+            }
+            None => {
+                checkpoint::ActiveModel {
+                    map_id: Set(map_id),
+                    latitude: Set(checkpoint_data.latitude),
+                    longitude: Set(checkpoint_data.longitude),
+                    position: Set(checkpoint_data.position),
+                    radius_meters: Set(radius_meters),
+                    kind: Set(kind.to_string()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+
+    // Positions left out are gone
+    if !stored.is_empty() {
+        Checkpoint::delete_many()
+            .filter(checkpoint::Column::Id.is_in(stored.values().map(|checkpoint| checkpoint.id)))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+// Helper function to find a map the user may see. Drafts of others are
+// answered like missing maps.
+async fn find_visible_map(
+    db: &DatabaseConnection,
+    id: i32,
+    auth_user: &AuthUser,
+) -> Result<map::Model, (StatusCode, String)> {
+    Map::find_by_id(id)
         .one(db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .filter(|map| policy::can_view_map(&auth_user.0, map))
         .ok_or((
             StatusCode::NOT_FOUND,
-            format!("Map with id {} not found", map_id),
+            format!("Map with id {} not found", id),
+        ))
+}
+
+// Helper function to check the points of a map. Coordinates must be on
+// Earth, checkpoints numbered from 1 without gaps with only the last one
+// being the finish, and points in a row far enough apart to tell them apart.
+fn check_geometry(
+    start: (f64, f64),
+    end: (f64, f64),
+    checkpoints: &[CheckpointData],
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut error = |field: String, code: ValidationCode, message: String| {
+        errors.push(FieldError {
+            field,
+            code,
+            message,
+        })
+    };
+
+    let mut check_coordinate = |prefix: &str, (latitude, longitude): (f64, f64)| {
+        let valid_latitude = (-90.0..=90.0).contains(&latitude);
+        let valid_longitude = (-180.0..=180.0).contains(&longitude);
+        if !valid_latitude {
+            error(
+                format!("{}latitude", prefix),
+                ValidationCode::Range,
+                "Must be between -90 and 90".to_string(),
+            );
+        }
+        if !valid_longitude {
+            error(
+                format!("{}longitude", prefix),
+                ValidationCode::Range,
+                "Must be between -180 and 180".to_string(),
+            );
+        }
+        valid_latitude && valid_longitude
+    };
+
+    let mut valid_points = check_coordinate("start_", start);
+    valid_points &= check_coordinate("end_", end);
+    for (index, checkpoint) in checkpoints.iter().enumerate() {
+        valid_points &= check_coordinate(
+            &format!("checkpoints[{}].", index),
+            (checkpoint.latitude, checkpoint.longitude),
+        );
+    }
+
+    let last_position = checkpoints
+        .iter()
+        .map(|checkpoint| checkpoint.position)
+        .max();
+    let mut positions = HashSet::new();
+
+    for (index, checkpoint) in checkpoints.iter().enumerate() {
+        if checkpoint.radius_meters.is_some_and(|radius| {
+            !(MIN_CHECKPOINT_RADIUS..=MAX_CHECKPOINT_RADIUS).contains(&radius)
+        }) {
+            error(
+                format!("checkpoints[{}].radius_meters", index),
+                ValidationCode::Range,
+                format!(
+                    "Must be between {} and {}",
+                    MIN_CHECKPOINT_RADIUS, MAX_CHECKPOINT_RADIUS
+                ),
+            );
+        }
+
+        if !(1..=checkpoints.len() as i32).contains(&checkpoint.position) {
+            error(
+                format!("checkpoints[{}].position", index),
+                ValidationCode::Order,
+                format!("Must be between 1 and {}", checkpoints.len()),
+            );
+        } else if !positions.insert(checkpoint.position) {
+            error(
+                format!("checkpoints[{}].position", index),
+                ValidationCode::Duplicate,
+                "Another checkpoint has the same position".to_string(),
+            );
+        }
+
+        if checkpoint.kind == CheckpointKind::Finish && Some(checkpoint.position) != last_position {
+            error(
+                format!("checkpoints[{}].kind", index),
+                ValidationCode::Order,
+                "Only the last checkpoint can be the finish".to_string(),
+            );
+        }
+    }
+
+    // Points are raced through from the start, by position, to the finish
+    if valid_points {
+        let mut route: Vec<(usize, &CheckpointData)> = checkpoints.iter().enumerate().collect();
+        route.sort_by_key(|(_, checkpoint)| checkpoint.position);
+
+        let points = std::iter::once(("start".to_string(), start))
+            .chain(route.into_iter().map(|(index, checkpoint)| {
+                (
+                    format!("checkpoints[{}]", index),
+                    (checkpoint.latitude, checkpoint.longitude),
+                )
+            }))
+            .chain(std::iter::once(("end".to_string(), end)))
+            .collect::<Vec<_>>();
+
+        for pair in points.windows(2) {
+            let [(_, previous), (field, point)] = pair else {
+                continue;
+            };
+            let distance = distance_meters(*previous, *point);
+            if distance < MIN_POINT_SPACING_METERS {
+                error(
+                    field.clone(),
+                    ValidationCode::Spacing,
+                    format!(
+                        "Must be at least {} meters from the point before",
+                        MIN_POINT_SPACING_METERS
+                    ),
+                );
+            }
+        }
+    }
+
+    errors
+}
+
+// Helper function to get the route of a request from the start through the
+// checkpoints by position to the finish
+fn request_route(start: (f64, f64), end: (f64, f64), checkpoints: &[CheckpointData]) -> Vec<Point> {
+    let mut ordered: Vec<&CheckpointData> = checkpoints.iter().collect();
+    ordered.sort_by_key(|checkpoint| checkpoint.position);
+
+    std::iter::once(start)
+        .chain(
+            ordered
+                .into_iter()
+                .map(|checkpoint| (checkpoint.latitude, checkpoint.longitude)),
+        )
+        .chain(std::iter::once(end))
+        .collect()
+}
+
+// Helper function to check that a map can be raced on before others get to
+// see it
+fn check_publishable(
+    map: &map::Model,
+    checkpoints: &[checkpoint::Model],
+) -> Result<(), (StatusCode, String)> {
+    let is_coordinate = |latitude: f64, longitude: f64| {
+        (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+    };
+
+    if !is_coordinate(map.start_latitude, map.start_longitude)
+        || !is_coordinate(map.end_latitude, map.end_longitude)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The start and end of the map must be valid coordinates".to_string(),
+        ));
+    }
+
+    if checkpoints
+        .iter()
+        .any(|checkpoint| !is_coordinate(checkpoint.latitude, checkpoint.longitude))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The checkpoints of the map must be valid coordinates".to_string(),
+        ));
+    }
+
+    // Checkpoints are passed in order of their position
+    let mut positions: Vec<i32> = checkpoints
+        .iter()
+        .map(|checkpoint| checkpoint.position)
+        .collect();
+    positions.sort_unstable();
+    if positions
+        .iter()
+        .zip(1..)
+        .any(|(position, expected)| *position != expected)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The checkpoints of the map must be numbered from 1 without gaps".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Helper function to find a map the user may change. Only its author or an
+// admin may; anyone else is answered 403.
+async fn find_editable_map(
+    db: &DatabaseConnection,
+    id: i32,
+    auth_user: &AuthUser,
+    action: &str,
+) -> Result<map::Model, (StatusCode, String)> {
+    // Deleted maps can only be restored
+    let map = Map::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .filter(|map| map.deleted_at.is_none())
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", id),
         ))?;
 
+    if !policy::can_edit_map(&auth_user.0, &map) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Only the map author or an admin can {} the map", action),
+        ));
+    }
+
+    Ok(map)
+}
+
+/// Upload a thumbnail of a map (only by author or an admin)
+///
+/// Returns a URL to upload the image to directly, which the map's thumbnail
+/// is pointed at right away. Uploading again replaces the thumbnail.
+#[utoipa::path(
+    post,
+    path = "/api/maps/{id}/thumbnail",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    request_body = ThumbnailUploadRequest,
+    responses(
+        (status = 200, description = "Upload URL created successfully", body = ThumbnailUploadResponse),
+        (status = 400, description = "Unsupported image type", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can edit the map", body = String),
+        (status = 404, description = "Map not found or uploads are not available", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn upload_thumbnail(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<ThumbnailUploadRequest>,
+) -> Result<Json<ThumbnailUploadResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let Some(storage) = state.config.object_storage() else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Uploads are not available".to_string(),
+        ));
+    };
+
+    let Some((content_type, extension)) = THUMBNAIL_TYPES
+        .iter()
+        .find(|(content_type, _)| content_type.eq_ignore_ascii_case(payload.content_type.trim()))
+    else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "content_type must be image/jpeg, image/png or image/webp".to_string(),
+        ));
+    };
+
+    let map = find_editable_map(db, id, &auth_user, "edit").await?;
+
+    // Every upload gets a new key, so cached old thumbnails are never served
+    let key = format!(
+        "maps/{}/thumbnail-{:016x}.{}",
+        map.id,
+        rand::random::<u64>(),
+        extension
+    );
+    let thumbnail_url = storage.public_url(&key);
+
+    let mut map: map::ActiveModel = map.into();
+    map.thumbnail_url = Set(Some(thumbnail_url.clone()));
+    map.update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(ThumbnailUploadResponse {
+        upload_url: storage.presign_put(&key, content_type, THUMBNAIL_UPLOAD_EXPIRY),
+        thumbnail_url,
+        expires_in: THUMBNAIL_UPLOAD_EXPIRY,
+    }))
+}
+
+/// Publish a map (only by author or an admin)
+///
+/// Checks that the map can be raced on: its start, end and checkpoints must
+/// be real coordinates and its checkpoints numbered from 1 without gaps.
+/// Published maps are listed for everyone, unlisted ones only reachable by
+/// ID. Publishing again switches between the two.
+#[utoipa::path(
+    post,
+    path = "/api/maps/{id}/publish",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    request_body = PublishMapRequest,
+    responses(
+        (status = 200, description = "Map published successfully", body = MapResponse),
+        (status = 400, description = "The map can't be raced on", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the map author or an admin can edit the map", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn publish_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    payload: Option<Json<PublishMapRequest>>,
+) -> Result<Json<MapResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let map = find_editable_map(db, id, &auth_user, "edit").await?;
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    check_publishable(&map, &checkpoints)?;
+
+    let status = match payload {
+        Some(Json(PublishMapRequest { unlisted: true })) => MapStatus::Unlisted,
+        _ => MapStatus::Published,
+    };
+
+    let mut map: map::ActiveModel = map.into();
+    map.status = Set(status.as_str().to_string());
+    let map = map
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut map = MapResponse::from(map);
+    mark_favorites(db, auth_user.0.sub, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(map))
+}
+
+/// Get all checkpoints for a map
+#[utoipa::path(
+    get,
+    path = "/api/maps/{map_id}/checkpoints",
+    tag = "maps",
+    params(
+        ("map_id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Checkpoints retrieved successfully", body = Vec<CheckpointResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn get_checkpoints(
+    State(state): State<AppState>,
+    Path(map_id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<CheckpointResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // First check if map exists
+    find_visible_map(db, map_id, &auth_user).await?;
+
     // Get all checkpoints for this map
     let checkpoints = Checkpoint::find()
         .filter(checkpoint::Column::MapId.eq(map_id))
@@ -398,3 +2570,511 @@ async fn get_checkpoints(
             .collect(),
     ))
 }
+
+/// Export a map as GeoJSON
+#[utoipa::path(
+    get,
+    path = "/api/maps/{id}/geojson",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map exported successfully", body = MapGeoJson, content_type = "application/geo+json"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn export_geojson(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let db = &state.conn;
+    let map = find_visible_map(db, id, &auth_user).await?;
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(map.id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let tags: Vec<String> = MapTag::find()
+        .select_only()
+        .column(tag::Column::Name)
+        .inner_join(Tag)
+        .filter(map_tag::Column::MapId.eq(map.id))
+        .order_by_asc(tag::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let point = |latitude: f64, longitude: f64| vec![longitude, latitude];
+
+    let mut route = vec![point(map.start_latitude, map.start_longitude)];
+    let mut features = vec![GeoJsonFeature::new(
+        GeoJsonGeometry::Point {
+            coordinates: point(map.start_latitude, map.start_longitude),
+        },
+        GeoJsonRole::Start,
+    )];
+
+    for checkpoint in checkpoints {
+        let coordinates = point(checkpoint.latitude, checkpoint.longitude);
+        route.push(coordinates.clone());
+
+        let mut feature = GeoJsonFeature::new(
+            GeoJsonGeometry::Point { coordinates },
+            GeoJsonRole::Checkpoint,
+        );
+        feature.properties.position = Some(checkpoint.position);
+        feature.properties.radius_meters = Some(checkpoint.radius_meters);
+        feature.properties.kind = Some(CheckpointKind::from_column(&checkpoint.kind));
+        features.push(feature);
+    }
+
+    route.push(point(map.end_latitude, map.end_longitude));
+    features.push(GeoJsonFeature::new(
+        GeoJsonGeometry::Point {
+            coordinates: point(map.end_latitude, map.end_longitude),
+        },
+        GeoJsonRole::Finish,
+    ));
+    features.push(GeoJsonFeature::new(
+        GeoJsonGeometry::LineString { coordinates: route },
+        GeoJsonRole::Route,
+    ));
+
+    let geojson = MapGeoJson {
+        kind: "FeatureCollection".to_string(),
+        properties: MapGeoJsonProperties {
+            title: map.title,
+            description: map.description,
+            tags,
+            weather: Weather::from_column(&map.weather),
+            time_of_day: TimeOfDay::from_column(&map.time_of_day),
+        },
+        features,
+    };
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/geo+json")],
+        Json(geojson),
+    ))
+}
+
+/// Create a map from GeoJSON
+///
+/// Takes a FeatureCollection like the export of a map, with one start and
+/// one finish point. Like any new map, it is a draft until it is published,
+/// and can be snapped to roads.
+#[utoipa::path(
+    post,
+    path = "/api/maps/import/geojson",
+    tag = "maps",
+    params(CreateMapParams),
+    request_body = MapGeoJson,
+    responses(
+        (status = 200, description = "Map imported successfully", body = MapWithCheckpointsResponse),
+        (status = 400, description = "Invalid GeoJSON, or the points can't be snapped to roads", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 409, description = "A nearly identical map already exists", body = String),
+        (status = 422, description = "Invalid map title, tags or points", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String),
+        (status = 502, description = "The road snapping service failed", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn import_geojson(
+    State(state): State<AppState>,
+    params: Query<CreateMapParams>,
+    auth_user: MapUploadUser,
+    Json(payload): Json<MapGeoJson>,
+) -> Result<Json<MapWithCheckpointsResponse>, Response> {
+    let request = map_from_geojson(payload).map_err(IntoResponse::into_response)?;
+
+    create_map(State(state), params, auth_user, Json(request)).await
+}
+
+// Helper function to read the map of a GeoJSON FeatureCollection
+fn map_from_geojson(geojson: MapGeoJson) -> Result<CreateMapRequest, (StatusCode, String)> {
+    let invalid = |message: &str| (StatusCode::BAD_REQUEST, message.to_string());
+
+    if geojson.kind != "FeatureCollection" {
+        return Err(invalid("Expected a GeoJSON FeatureCollection"));
+    }
+
+    let mut start = None;
+    let mut end = None;
+    let mut checkpoints: Vec<CheckpointData> = Vec::new();
+
+    for feature in geojson.features {
+        let properties = feature.properties;
+        if feature.kind != "Feature" {
+            return Err(invalid("Expected GeoJSON Features"));
+        }
+        if properties.role == GeoJsonRole::Route {
+            continue;
+        }
+
+        let GeoJsonGeometry::Point { coordinates } = feature.geometry else {
+            return Err(invalid("The start, finish and checkpoints must be points"));
+        };
+        let [longitude, latitude, ..] = coordinates[..] else {
+            return Err(invalid("Points must have a longitude and latitude"));
+        };
+
+        match properties.role {
+            GeoJsonRole::Start => {
+                if start.replace((latitude, longitude)).is_some() {
+                    return Err(invalid("A map has exactly one start"));
+                }
+            }
+            GeoJsonRole::Finish => {
+                if end.replace((latitude, longitude)).is_some() {
+                    return Err(invalid("A map has exactly one finish"));
+                }
+            }
+            GeoJsonRole::Checkpoint => checkpoints.push(CheckpointData {
+                latitude,
+                longitude,
+                position: properties.position.unwrap_or(checkpoints.len() as i32 + 1),
+                radius_meters: properties.radius_meters,
+                kind: properties.kind.unwrap_or_default(),
+            }),
+            GeoJsonRole::Route => {}
+        }
+    }
+
+    let (Some((start_latitude, start_longitude)), Some((end_latitude, end_longitude))) =
+        (start, end)
+    else {
+        return Err(invalid("A map has exactly one start and one finish"));
+    };
+
+    Ok(CreateMapRequest {
+        title: geojson.properties.title,
+        description: geojson.properties.description,
+        start_latitude,
+        start_longitude,
+        end_latitude,
+        end_longitude,
+        checkpoints,
+        tags: geojson.properties.tags,
+        weather: geojson.properties.weather,
+        time_of_day: geojson.properties.time_of_day,
+    })
+}
+
+/// Export a map as an archive
+///
+/// The archive holds everything needed to recreate the map on this or
+/// another server, like a backup.
+#[utoipa::path(
+    get,
+    path = "/api/maps/{id}/export",
+    tag = "maps",
+    params(
+        ("id" = i32, Path, description = "Map ID")
+    ),
+    responses(
+        (status = 200, description = "Map exported successfully", body = MapArchive),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Map not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn export_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<MapArchive>, (StatusCode, String)> {
+    let db = &state.conn;
+    let map = find_visible_map(db, id, &auth_user).await?;
+
+    let checkpoints = Checkpoint::find()
+        .filter(checkpoint::Column::MapId.eq(map.id))
+        .order_by_asc(checkpoint::Column::Position)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let tags: Vec<String> = MapTag::find()
+        .select_only()
+        .column(tag::Column::Name)
+        .inner_join(Tag)
+        .filter(map_tag::Column::MapId.eq(map.id))
+        .order_by_asc(tag::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let surfaces = load_segments(db, map.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|segment| segment.surface)
+        .collect();
+
+    Ok(Json(MapArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now(),
+        map: CreateMapRequest {
+            title: map.title,
+            description: map.description,
+            start_latitude: map.start_latitude,
+            start_longitude: map.start_longitude,
+            end_latitude: map.end_latitude,
+            end_longitude: map.end_longitude,
+            checkpoints: checkpoints.into_iter().map(CheckpointData::from).collect(),
+            tags,
+            weather: Weather::from_column(&map.weather),
+            time_of_day: TimeOfDay::from_column(&map.time_of_day),
+        },
+        route_polyline: map.route_polyline,
+        surfaces,
+    }))
+}
+
+/// Create a map from an archive
+///
+/// Takes an archive like the export of a map. Like any new map, it is a
+/// draft of the current user until it is published. Maps that weren't
+/// snapped to roads when they were exported can be snapped now.
+#[utoipa::path(
+    post,
+    path = "/api/maps/import",
+    tag = "maps",
+    params(CreateMapParams),
+    request_body = MapArchive,
+    responses(
+        (status = 200, description = "Map imported successfully", body = MapWithCheckpointsResponse),
+        (status = 400, description = "Not a map archive this server can read, or the points can't be snapped to roads", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 409, description = "A nearly identical map already exists", body = String),
+        (status = 422, description = "Invalid map title, tags or points", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String),
+        (status = 502, description = "The road snapping service failed", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn import_map(
+    State(state): State<AppState>,
+    Query(params): Query<CreateMapParams>,
+    auth_user: MapUploadUser,
+    Json(archive): Json<MapArchive>,
+) -> Result<Json<MapWithCheckpointsResponse>, Response> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Expected a map archive".to_string(),
+        )
+            .into_response());
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Map archives of version {} are newer than this server can read",
+                archive.version
+            ),
+        )
+            .into_response());
+    }
+
+    // A map has one leg more than it has checkpoints
+    let known_route = match archive.route_polyline {
+        Some(route_polyline) => {
+            if !archive.surfaces.is_empty()
+                && archive.surfaces.len() != archive.map.checkpoints.len() + 1
+            {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "The road surfaces don't match the legs of the map".to_string(),
+                )
+                    .into_response());
+            }
+            Some((route_polyline, archive.surfaces))
+        }
+        None => None,
+    };
+
+    insert_map(&state, &params, &auth_user, archive.map, known_route).await
+}
+
+/// Fill in which of the maps the user favorited
+pub async fn mark_favorites<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    maps: &mut [MapResponse],
+) -> Result<(), DbErr> {
+    let favorited: HashSet<i32> = MapFavorite::find()
+        .select_only()
+        .column(map_favorite::Column::MapId)
+        .filter(map_favorite::Column::UserId.eq(user_id))
+        .filter(map_favorite::Column::MapId.is_in(maps.iter().map(|map| map.id)))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    for map in maps {
+        map.is_favorited = Some(favorited.contains(&map.id));
+    }
+
+    Ok(())
+}
+
+/// Fill in the tags of maps
+pub async fn attach_tags<C: ConnectionTrait>(
+    db: &C,
+    maps: &mut [MapResponse],
+) -> Result<(), DbErr> {
+    let map_tags: Vec<(i32, String)> = MapTag::find()
+        .select_only()
+        .column(map_tag::Column::MapId)
+        .column(tag::Column::Name)
+        .inner_join(Tag)
+        .filter(map_tag::Column::MapId.is_in(maps.iter().map(|map| map.id)))
+        .order_by_asc(tag::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (map_id, name) in map_tags {
+        tags.entry(map_id).or_default().push(name);
+    }
+
+    for map in maps {
+        map.tags = tags.remove(&map.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Fill in the name and avatar of the authors of maps
+pub async fn attach_authors<C: ConnectionTrait>(
+    db: &C,
+    maps: &mut [MapResponse],
+) -> Result<(), DbErr> {
+    let authors: Vec<(i32, i32, String, Option<String>)> = Map::find()
+        .select_only()
+        .column(map::Column::Id)
+        .column_as(user::Column::Id, "author_id")
+        .column(user::Column::Name)
+        .column(user::Column::AvatarUrl)
+        .inner_join(User)
+        .filter(map::Column::Id.is_in(maps.iter().map(|map| map.id)))
+        .filter(user::Column::DeletedAt.is_null())
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut authors: HashMap<i32, MapAuthorResponse> = authors
+        .into_iter()
+        .map(|(map_id, id, name, avatar_url)| {
+            (
+                map_id,
+                MapAuthorResponse {
+                    id,
+                    name,
+                    avatar_url,
+                },
+            )
+        })
+        .collect();
+
+    for map in maps {
+        map.author = authors.remove(&map.id);
+    }
+
+    Ok(())
+}
+
+// Helper function to check the tags of a map and return them trimmed,
+// lowercased and without duplicates
+fn check_tags(state: &AppState, tags: &[String]) -> Result<Vec<String>, FieldError> {
+    let mut checked: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let tag = state
+            .validator
+            .text("tags", tag, MAX_TAG_LENGTH)?
+            .to_lowercase();
+
+        if !checked.contains(&tag) {
+            checked.push(tag);
+        }
+    }
+
+    if checked.len() > MAX_TAGS {
+        return Err(FieldError {
+            field: "tags".to_string(),
+            code: ValidationCode::Length,
+            message: format!("Must have at most {} tags", MAX_TAGS),
+        });
+    }
+
+    Ok(checked)
+}
+
+// Helper function to replace the tags of a map. Tags nobody used before are
+// created.
+async fn set_tags<C: ConnectionTrait>(db: &C, map_id: i32, tags: &[String]) -> Result<(), DbErr> {
+    MapTag::delete_many()
+        .filter(map_tag::Column::MapId.eq(map_id))
+        .exec(db)
+        .await?;
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    Tag::insert_many(tags.iter().map(|name| tag::ActiveModel {
+        name: Set(name.clone()),
+        ..Default::default()
+    }))
+    .on_conflict(
+        OnConflict::column(tag::Column::Name)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    let tag_ids: Vec<i32> = Tag::find()
+        .select_only()
+        .column(tag::Column::Id)
+        .filter(tag::Column::Name.is_in(tags.iter().cloned()))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    MapTag::insert_many(tag_ids.into_iter().map(|tag_id| map_tag::ActiveModel {
+        map_id: Set(map_id),
+        tag_id: Set(tag_id),
+        ..Default::default()
+    }))
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
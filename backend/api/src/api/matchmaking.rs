@@ -0,0 +1,175 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    routing::get,
+};
+use chrono::Utc;
+use entity::user::{self, Entity as User};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+use crate::matchmaking::{self, QueuedPlayer};
+use crate::region;
+
+#[derive(Serialize, ToSchema)]
+pub struct QueueStatusResponse {
+    /// Whether the user is waiting for a match
+    queued: bool,
+    /// Rating the user is matched by, which is their level
+    rating: Option<i32>,
+    /// Region the user is matched in
+    region: Option<String>,
+    queued_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Largest rating difference currently accepted, which widens while waiting
+    rating_spread: Option<i32>,
+    /// Players waiting in the user's region, including the user
+    players_waiting: usize,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/matchmaking/queue",
+        get(get_queue_status).post(join_queue).delete(leave_queue),
+    )
+}
+
+/// Queue for a match
+///
+/// The user is matched with players of their region, which is their
+/// preferred region if they set one, and a similar rating into a new party,
+/// and is notified over the websocket once a match is found. Queueing again
+/// keeps the user's place in the queue.
+#[utoipa::path(
+    post,
+    path = "/api/matchmaking/queue",
+    tag = "matchmaking",
+    responses(
+        (status = 200, description = "Queued for a match", body = QueueStatusResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn join_queue(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+) -> Result<Json<QueueStatusResponse>, (StatusCode, String)> {
+    let user_id = auth_user.0.sub;
+
+    let user = User::find_by_id(user_id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", user_id),
+        ))?;
+
+    let player_region = region::preferred_or_caller(&state, user_id, &headers)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let player = QueuedPlayer {
+        rating: user.level,
+        region: player_region,
+        queued_at: Utc::now(),
+    };
+
+    state
+        .matchmaking_queue
+        .lock()
+        .unwrap()
+        .entry(user_id)
+        .or_insert(player);
+
+    Ok(Json(queue_status(&state, user_id)))
+}
+
+/// Get the matchmaking status of the current user
+#[utoipa::path(
+    get,
+    path = "/api/matchmaking/queue",
+    tag = "matchmaking",
+    responses(
+        (status = 200, description = "Queue status retrieved successfully", body = QueueStatusResponse),
+        (status = 401, description = "Unauthorized", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_queue_status(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Json<QueueStatusResponse> {
+    Json(queue_status(&state, auth_user.0.sub))
+}
+
+/// Leave the matchmaking queue
+#[utoipa::path(
+    delete,
+    path = "/api/matchmaking/queue",
+    tag = "matchmaking",
+    responses(
+        (status = 204, description = "Left the queue"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Not queued", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn leave_queue(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .matchmaking_queue
+        .lock()
+        .unwrap()
+        .remove(&auth_user.0.sub)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "You are not queued for a match".to_string(),
+        ))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helper function to describe the place of a user in the queue
+fn queue_status(state: &AppState, user_id: i32) -> QueueStatusResponse {
+    let player = state
+        .matchmaking_queue
+        .lock()
+        .unwrap()
+        .get(&user_id)
+        .cloned();
+
+    match player {
+        Some(player) => QueueStatusResponse {
+            queued: true,
+            rating: Some(player.rating),
+            rating_spread: Some(player.rating_spread(Utc::now())),
+            players_waiting: matchmaking::waiting_in(state, player.region.as_deref()),
+            region: player.region,
+            queued_at: Some(player.queued_at),
+        },
+        None => QueueStatusResponse {
+            queued: false,
+            rating: None,
+            region: None,
+            queued_at: None,
+            rating_spread: None,
+            players_waiting: 0,
+        },
+    }
+}
@@ -1,27 +1,84 @@
+mod achievements;
+mod activity;
+mod admin;
+mod api_keys;
 mod auth;
+mod blocks;
+mod challenges;
+mod crews;
+mod export;
+mod favorites;
+mod follows;
 mod health;
-mod maps;
+mod invites;
+mod leaderboards;
+mod linked_accounts;
+mod loadouts;
+mod map_reports;
+pub mod maps;
+mod matchmaking;
 mod openapi;
-mod parties;
+mod pagination;
+pub mod parties;
+mod party_bans;
+mod party_events;
+mod party_settings;
+mod playlists;
+mod presence;
+mod races;
+mod recent_players;
+mod settings;
+mod teams;
 mod users;
-mod ws;
+mod voice;
+mod wallet;
+pub mod ws;
 
 use axum::body::{Body, Bytes};
 use axum::extract::Request;
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use axum::{Router, middleware};
 use http_body_util::BodyExt;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::{self, TraceLayer};
 use tracing::Level;
 
+use crate::client_version;
 use crate::db::AppState;
+use crate::metrics;
+
+// Fields of request and response bodies that hold passwords, tokens or codes
+// and are left out of the logs
+const REDACTED_FIELDS: &[&str] = &[
+    "password",
+    "access_token",
+    "refresh_token",
+    "token",
+    "secondary_token",
+    "captcha_token",
+    "ticket",
+    "code",
+    "key",
+];
 
 pub fn create_router(state: AppState) -> Router {
+    // Only allow the configured browser origins
+    let allow_origin = if state.config.allows_any_origin() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            state
+                .config
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(allow_origin)
         .allow_methods(Any)
         .allow_headers(Any);
 
@@ -32,17 +89,65 @@ pub fn create_router(state: AppState) -> Router {
         .on_request(trace::DefaultOnRequest::new().level(Level::INFO))
         .on_failure(trace::DefaultOnFailure::new().level(Level::ERROR));
 
+    // Reject REST calls from clients outside the supported version range
+    let client_version_gate =
+        middleware::from_fn_with_state(state.clone(), client_version::require_client_version);
+
+    // Make the claims of requests with credentials available to public routes
+    let optional_auth_layer = middleware::from_fn_with_state(
+        state.clone(),
+        ::auth::middleware::optional_auth::<AppState>,
+    );
+
     // Public routes that don't require authentication
     let public_routes = Router::new()
         .nest("/api", health::router())
-        .nest("/api", auth::router())
+        .nest(
+            "/api",
+            auth::router(&state)
+                .route_layer(optional_auth_layer)
+                .route_layer(client_version_gate.clone()),
+        )
         .merge(openapi::swagger_ui());
 
+    // Reject requests without a valid access token
+    let auth_layer =
+        middleware::from_fn_with_state(state.clone(), ::auth::middleware::require_auth);
+
     // Protected routes that require authentication
     let protected_routes = Router::new()
         .nest("/api", maps::router())
+        .nest("/api", favorites::router())
+        .nest("/api", map_reports::router())
         .nest("/api", parties::router())
+        .nest("/api", party_bans::router())
+        .nest("/api", party_events::router())
+        .nest("/api", party_settings::router())
+        .nest("/api", teams::router())
+        .nest("/api", voice::router())
+        .nest("/api", invites::router())
+        .nest("/api", matchmaking::router())
+        .nest("/api", playlists::router())
+        .nest("/api", challenges::router())
+        .nest("/api", races::router())
+        .nest("/api", crews::router())
         .nest("/api", users::router())
+        .nest("/api", export::router())
+        .nest("/api", settings::router())
+        .nest("/api", recent_players::router())
+        .nest("/api", activity::router())
+        .nest("/api", wallet::router())
+        .nest("/api", loadouts::router())
+        .nest("/api", presence::router())
+        .nest("/api", blocks::router())
+        .nest("/api", follows::router())
+        .nest("/api", achievements::router())
+        .nest("/api", api_keys::router())
+        .nest("/api", linked_accounts::router())
+        .nest("/api", admin::router())
+        .route_layer(auth_layer)
+        .route_layer(client_version_gate)
+        // The websocket handshake carries its client version and token as query parameters
         .nest("/api", ws::router());
 
     // Combine public and protected routes
@@ -50,6 +155,10 @@ pub fn create_router(state: AppState) -> Router {
         .merge(public_routes)
         .merge(protected_routes)
         .layer(middleware::from_fn(print_request_response))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics::record_request,
+        ))
         .layer(cors)
         .layer(trace_layer)
         .with_state(state)
@@ -87,9 +196,29 @@ where
         }
     };
 
-    if let Ok(body) = std::str::from_utf8(&bytes) {
+    if let Ok(mut body) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+        redact(&mut body);
+        tracing::debug!("{direction} body = {body}");
+    } else if let Ok(body) = std::str::from_utf8(&bytes) {
         tracing::debug!("{direction} body = {body:?}");
     }
 
     Ok(bytes)
 }
+
+// Replace the values of secret fields, at any depth
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if REDACTED_FIELDS.contains(&name.as_str()) {
+                    *field = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
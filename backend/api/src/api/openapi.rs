@@ -5,8 +5,14 @@ use utoipa::{
 };
 use utoipa_swagger_ui::SwaggerUi;
 
-use super::{auth, health, maps, parties, users};
+use super::{
+    achievements, activity, admin, api_keys, auth, blocks, challenges, crews, export, favorites,
+    follows, health, invites, leaderboards, linked_accounts, loadouts, map_reports, maps,
+    matchmaking, pagination, parties, party_bans, party_events, party_settings, playlists,
+    presence, races, recent_players, settings, teams, users, voice, wallet, ws,
+};
 use crate::db::AppState;
+use crate::validation;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -14,57 +20,393 @@ use crate::db::AppState;
         // Health endpoints
         health::check_health,
         // User endpoints
+        users::list_users,
         users::me,
+        users::update_me,
+        users::get_progression,
+        users::delete_me,
+        users::merge_me,
+        settings::get_settings,
+        settings::update_settings,
+        recent_players::list_recent_players,
+        activity::list_activity,
+        wallet::get_wallet,
+        export::export_me,
+        // Loadout endpoints
+        loadouts::list_loadouts,
+        loadouts::get_loadout,
+        loadouts::create_loadout,
+        loadouts::update_loadout,
+        loadouts::delete_loadout,
+        loadouts::select_loadout,
+        // Presence endpoints
+        presence::get_presence,
+        presence::query_presence,
+        // Block endpoints
+        blocks::list_blocks,
+        blocks::block_user,
+        blocks::unblock_user,
+        // Follow endpoints
+        follows::list_following,
+        follows::get_feed,
+        follows::follow_user,
+        follows::unfollow_user,
+        // Achievement endpoints
+        achievements::list_achievements,
+        achievements::list_user_achievements,
         // Maps endpoints
         maps::list_maps,
+        maps::search_maps,
+        maps::trending_maps,
+        maps::nearby_maps,
+        maps::random_map,
         maps::get_map,
         maps::create_map,
+        maps::update_map,
         maps::delete_map,
+        maps::upload_thumbnail,
+        maps::publish_map,
+        maps::restore_map,
         maps::get_checkpoints,
+        maps::replace_checkpoints,
         maps::get_map_with_checkpoints,
+        maps::get_map_stats,
+        maps::export_geojson,
+        maps::import_geojson,
+        maps::export_map,
+        maps::import_map,
+        favorites::list_favorites,
+        favorites::favorite_map,
+        favorites::unfavorite_map,
+        map_reports::report_map,
         // Parties endpoints
         parties::list_parties,
+        parties::list_public_parties,
         parties::get_party,
+        parties::list_my_parties,
+        parties::list_user_parties,
         parties::create_party,
         parties::join_party,
+        parties::quick_join,
         parties::get_party_members,
         parties::update_party,
         parties::leave_party,
+        parties::kick_member,
+        parties::transfer_ownership,
+        party_events::list_party_events,
+        party_bans::list_bans,
+        party_bans::ban_user,
+        party_bans::unban_user,
+        parties::set_ready,
         parties::disband_party,
+        parties::split_party,
+        parties::request_merge,
+        parties::accept_merge,
+        parties::set_party_map,
+        parties::set_party_playlist,
+        parties::get_playlist_standings,
+        parties::regenerate_code,
+        party_settings::get_party_settings,
+        party_settings::update_party_settings,
+        teams::get_teams,
+        teams::assign_team,
+        teams::balance_teams,
+        voice::create_voice_token,
+        // Invite endpoints
+        invites::invite_user,
+        invites::create_invite_link,
+        invites::join_by_link,
+        invites::list_invites,
+        invites::accept_invite,
+        invites::decline_invite,
+        // Matchmaking endpoints
+        matchmaking::join_queue,
+        matchmaking::get_queue_status,
+        matchmaking::leave_queue,
+        // Crew endpoints
+        crews::list_crews,
+        crews::crew_leaderboard,
+        challenges::get_today,
+        challenges::get_leaderboard,
+        races::submit_race_result,
+        races::get_race_results,
+        races::get_race_splits,
+        races::get_race_replay,
+        crews::get_crew,
+        crews::create_crew,
+        crews::update_crew,
+        crews::delete_crew,
+        crews::join_crew,
+        crews::leave_crew,
+        crews::remove_member,
+        crews::set_member_role,
+        // Playlist endpoints
+        playlists::list_playlists,
+        playlists::get_playlist,
+        playlists::create_playlist,
+        playlists::update_playlist,
+        playlists::delete_playlist,
         // Auth endpoints
         auth::register,
-        auth::refresh
+        auth::login,
+        auth::refresh,
+        auth::request_login_code,
+        auth::redeem_login_code,
+        auth::logout,
+        auth::scoped_token,
+        auth::list_sessions,
+        auth::revoke_session,
+        auth::request_email_verification,
+        auth::verify_email,
+        auth::forgot_password,
+        auth::reset_password,
+        auth::oauth_start,
+        auth::oauth_callback,
+        auth::platform_login,
+        // WebSocket endpoints
+        ws::create_ws_ticket,
+        // API key endpoints
+        api_keys::list_api_keys,
+        api_keys::create_api_key,
+        api_keys::revoke_api_key,
+        // Linked account endpoints
+        linked_accounts::list_linked_accounts,
+        linked_accounts::link_account,
+        linked_accounts::unlink_account,
+        // Admin endpoints
+        admin::live_state,
+        admin::broadcast_announcement,
+        admin::sign_out_user,
+        admin::ban_user,
+        admin::unban_user,
+        admin::grant_currency,
+        admin::list_reports,
+        admin::list_duplicate_maps,
+        admin::resolve_report,
+        admin::hide_map,
+        admin::unhide_map
     ),
     components(
         schemas(
             // Health schemas
             health::HealthResponse,
+            // Validation schemas
+            validation::ValidationErrorResponse,
+            ::auth::validation::FieldError,
+            ::auth::validation::ValidationCode,
             // User schemas
             users::UserResponse,
+            pagination::Paginated<users::UserResponse>,
+            users::CurrentUserResponse,
+            users::UpdateUserRequest,
+            users::ProgressionResponse,
+            users::MergeRequest,
+            settings::Units,
+            settings::SettingsRequest,
+            settings::SettingsResponse,
+            crate::settings::ActivityVisibility,
+            recent_players::RecentPlayerResponse,
+            recent_players::RecentPartyResponse,
+            recent_players::RecentMapResponse,
+            activity::ActivityResponse,
+            activity::ActivityMapResponse,
+            activity::ActivityPartyResponse,
+            pagination::Paginated<activity::ActivityResponse>,
+            wallet::WalletResponse,
+            wallet::TransactionResponse,
+            export::UserExport,
+            export::ExportedProfile,
+            export::ExportedMap,
+            export::ExportedPlaylist,
+            export::ExportedRaceResult,
+            export::ExportedReplay,
+            export::ExportedSession,
+            export::ExportedApiKey,
+            // Loadout schemas
+            loadouts::CreateLoadoutRequest,
+            loadouts::UpdateLoadoutRequest,
+            loadouts::LoadoutResponse,
+            // Presence schemas
+            presence::PresenceQueryRequest,
+            presence::PresenceResponse,
+            // Block schemas
+            blocks::BlockUserRequest,
+            blocks::BlockResponse,
+            // Follow schemas
+            follows::FollowResponse,
+            follows::FeedItemResponse,
+            pagination::Paginated<follows::FeedItemResponse>,
+            // Achievement schemas
+            achievements::AchievementResponse,
+            achievements::UnlockedAchievementResponse,
             // Map schemas
             maps::CreateMapRequest,
+            maps::UpdateMapRequest,
             maps::MapResponse,
+            maps::MapAuthorResponse,
+            maps::MapSort,
+            leaderboards::LeaderboardWindow,
+            maps::MapStatus,
+            maps::PublishMapRequest,
+            maps::TrendingMapResponse,
+            maps::NearbyMapResponse,
+            pagination::Paginated<maps::NearbyMapResponse>,
+            maps::ThumbnailUploadRequest,
+            maps::ThumbnailUploadResponse,
+            pagination::Paginated<maps::MapResponse>,
             maps::CheckpointData,
+            maps::CheckpointKind,
+            maps::Weather,
+            maps::TimeOfDay,
             maps::CheckpointResponse,
             maps::MapWithCheckpointsResponse,
+            maps::SegmentResponse,
+            crate::road_surfaces::Surface,
+            crate::map_stats::MapStats,
+            maps::MapArchive,
+            maps::MapGeoJson,
+            maps::MapGeoJsonProperties,
+            maps::GeoJsonFeature,
+            maps::GeoJsonFeatureProperties,
+            maps::GeoJsonGeometry,
+            maps::GeoJsonRole,
+            favorites::FavoriteResponse,
+            pagination::Paginated<favorites::FavoriteResponse>,
+            map_reports::ReportReason,
+            map_reports::ReportMapRequest,
+            map_reports::MapReportResponse,
+            pagination::Paginated<map_reports::MapReportResponse>,
             // Party schemas
             parties::CreatePartyRequest,
             parties::PartyResponse,
+            parties::PartyVisibility,
+            parties::PartyStatus,
+            parties::PartyRole,
+            parties::MembershipResponse,
+            pagination::Paginated<parties::PartyResponse>,
             parties::JoinPartyRequest,
             parties::UpdatePartyRequest,
+            parties::SplitPartyRequest,
+            parties::TransferOwnershipRequest,
+            parties::JoinPartyResponse,
+            parties::LobbyMemberResponse,
+            party_events::PartyEventResponse,
+            party_bans::BanRequest,
+            party_bans::PartyBanResponse,
+            pagination::Paginated<party_events::PartyEventResponse>,
+            crate::party_events::Kind,
+            parties::MergePartyRequest,
+            parties::ReadyRequest,
+            parties::ReadyStateResponse,
+            parties::SetPartyMapRequest,
+            parties::SetPartyPlaylistRequest,
+            parties::PlaylistStandingResponse,
+            parties::PlaylistStandingsResponse,
+            party_settings::PartySettingsResponse,
+            party_settings::UpdatePartySettingsRequest,
+            teams::TeamAssignment,
+            teams::AssignTeamRequest,
+            teams::BalanceTeamsRequest,
+            voice::VoiceTokenResponse,
+            // Invite schemas
+            invites::InviteRequest,
+            invites::InviteLinkResponse,
+            invites::JoinLinkRequest,
+            invites::InviteResponse,
+            // Matchmaking schemas
+            matchmaking::QueueStatusResponse,
+            // Crew schemas
+            crews::CrewResponse,
+            pagination::Paginated<crews::CrewResponse>,
+            crews::CrewMemberResponse,
+            crews::CrewWithMembersResponse,
+            crews::CrewStandingResponse,
+            pagination::Paginated<crews::CrewStandingResponse>,
+            challenges::DailyChallengeResponse,
+            challenges::ChallengeStandingResponse,
+            pagination::Paginated<challenges::ChallengeStandingResponse>,
+            races::SubmitRaceResultRequest,
+            races::RaceResultResponse,
+            races::RaceResultsResponse,
+            races::RaceAwardResponse,
+            crate::awards::AwardKind,
+            races::RacerSplitsResponse,
+            races::RaceSplitsResponse,
+            races::ReplayFrameResponse,
+            races::RacerReplayResponse,
+            races::RaceReplayResponse,
+            crews::CreateCrewRequest,
+            crews::UpdateCrewRequest,
+            crews::CrewRoleRequest,
+            crate::crews::CrewRole,
+            // Playlist schemas
+            playlists::CreatePlaylistRequest,
+            playlists::UpdatePlaylistRequest,
+            playlists::PlaylistResponse,
+            playlists::PlaylistWithMapsResponse,
             // Auth schemas
             auth::AuthResponse,
             auth::RegisterRequest,
-            auth::RefreshRequest
+            auth::LoginRequest,
+            auth::LoginLockedResponse,
+            auth::RefreshRequest,
+            auth::LogoutRequest,
+            auth::SessionResponse,
+            auth::LoginCodeRequest,
+            auth::LoginCodeResponse,
+            auth::RedeemLoginCodeRequest,
+            auth::ScopedTokenRequest,
+            auth::ScopedTokenResponse,
+            ::auth::Scope,
+            auth::VerifyEmailRequest,
+            auth::EmailVerifiedResponse,
+            auth::ForgotPasswordRequest,
+            auth::ResetPasswordRequest,
+            auth::PlatformLoginRequest,
+            // WebSocket schemas
+            ws::WsTicketResponse,
+            // API key schemas
+            api_keys::CreateApiKeyRequest,
+            api_keys::ApiKeyResponse,
+            api_keys::CreatedApiKeyResponse,
+            // Linked account schemas
+            linked_accounts::LinkAccountRequest,
+            linked_accounts::LinkedAccountResponse,
+            // Admin schemas
+            admin::LiveStateResponse,
+            admin::ActivePartyResponse,
+            admin::ErrorRateResponse,
+            admin::AnnouncementRequest,
+            admin::AnnouncementResponse,
+            admin::BanRequest,
+            admin::BanResponse,
+            admin::GrantRequest,
+            admin::DuplicateMapsResponse,
+            pagination::Paginated<admin::DuplicateMapsResponse>
         ),
     ),
     modifiers(&SecurityAddon),
     tags(
         (name = "health", description = "Health check endpoints"),
         (name = "users", description = "User management endpoints"),
+        (name = "loadouts", description = "Vehicle loadout endpoints"),
+        (name = "presence", description = "Online presence endpoints"),
+        (name = "blocks", description = "User blocking endpoints"),
+        (name = "follows", description = "Map author follow endpoints"),
+        (name = "achievements", description = "Achievement endpoints"),
         (name = "maps", description = "Map management endpoints"),
         (name = "parties", description = "Party management endpoints"),
-        (name = "auth", description = "Authentication endpoints")
+        (name = "invites", description = "Party invite endpoints"),
+        (name = "matchmaking", description = "Matchmaking queue endpoints"),
+        (name = "crews", description = "Crew endpoints"),
+        (name = "challenges", description = "Daily challenge endpoints"),
+        (name = "races", description = "Race result endpoints"),
+        (name = "playlists", description = "Playlist management endpoints"),
+        (name = "auth", description = "Authentication endpoints"),
+        (name = "ws", description = "WebSocket connection endpoints"),
+        (name = "api-keys", description = "API key management endpoints"),
+        (name = "linked-accounts", description = "Game platform account endpoints"),
+        (name = "admin", description = "Administration endpoints")
     ),
     info(
         title = "World Racers API",
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// Page size used when the client doesn't ask for one, and the largest allowed
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+
+// Query parameters of paginated listings
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaginationParams {
+    /// Page to return, starting at 1
+    page: Option<u64>,
+    /// Items per page, at most 100
+    per_page: Option<u64>,
+}
+
+impl PaginationParams {
+    pub fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+}
+
+// One page of a listing, with what clients need to page through the rest
+#[derive(Serialize, ToSchema)]
+pub struct Paginated<T> {
+    items: Vec<T>,
+    page: u64,
+    per_page: u64,
+    total: u64,
+    total_pages: u64,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, params: &PaginationParams, total: u64, total_pages: u64) -> Self {
+        Self {
+            items,
+            page: params.page(),
+            per_page: params.per_page(),
+            total,
+            total_pages,
+        }
+    }
+}
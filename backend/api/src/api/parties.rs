@@ -1,26 +1,100 @@
 use auth::middleware::AuthUser;
 use axum::{
     Router,
-    extract::{Json, Path, State},
-    http::StatusCode,
-    routing::{get, post},
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
 };
+use entity::map::{self, Entity as Map};
 use entity::party::{self, Entity as Party};
+use entity::race::{self, Entity as Race};
+use entity::race_participant::{self, Entity as RaceParticipant};
 use entity::user::{self, Entity as User};
 use entity::user_party::{self, Entity as UserParty};
+use rand::seq::IndexedRandom;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+    sea_query::Expr,
 };
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
-use utoipa::ToSchema;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::db::AppState;
+use super::maps::{MapResponse, MapStatus, attach_authors, attach_tags};
+use super::pagination::{Paginated, PaginationParams};
+use super::playlists;
+use super::users::UserResponse;
+use super::ws::{self, WsMessage};
+use crate::activity;
+use crate::blocking;
+use crate::db::{AppState, SocketCommand};
+use crate::membership;
+use crate::party_bans;
+use crate::party_events;
+use crate::policy;
+use crate::region;
+use crate::settings;
+use crate::validation::{self, ValidationErrorResponse};
+
+// Longest party name
+const MAX_PARTY_NAME_LENGTH: usize = 32;
+
+// Characters of party codes, leaving out those easily mistaken for others
+const PARTY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const PARTY_CODE_LENGTH: usize = 8;
+
+// How long a merge request stays valid for the other owner to accept
+const MERGE_REQUEST_TTL: Duration = Duration::from_secs(300);
+
+/// Whether a party is listed in the party browser
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PartyVisibility {
+    #[default]
+    Public,
+    /// Only joinable by code, and only listed for its members
+    Private,
+}
+
+impl PartyVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PartyVisibility::Public => "public",
+            PartyVisibility::Private => "private",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "private" => PartyVisibility::Private,
+            _ => PartyVisibility::Public,
+        }
+    }
+}
+
+/// Whether a party is racing right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PartyStatus {
+    Lobby,
+    Racing,
+}
 
 #[derive(Deserialize, ToSchema)]
 pub struct CreatePartyRequest {
     name: String,
     map_id: i32,
+    /// Public unless given
+    #[serde(default)]
+    visibility: PartyVisibility,
+    /// Most members the party may have; the server's maximum unless given
+    max_members: Option<i32>,
+    /// Region the party races in; the owner's preferred region, or else the
+    /// region of their connection, unless given
+    region: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -31,6 +105,28 @@ pub struct PartyResponse {
     owner_id: i32,
     created_at: chrono::DateTime<chrono::FixedOffset>,
     map_id: i32,
+    /// Playlist the party races through, if any
+    playlist_id: Option<i32>,
+    /// Index of the current map within the playlist
+    playlist_position: i32,
+    visibility: PartyVisibility,
+    /// Most members the party may have
+    max_members: i32,
+    /// Region the party races in, or of the owner's connection if it has none
+    region: Option<String>,
+    status: PartyStatus,
+}
+
+impl PartyResponse {
+    pub fn with_live_state(mut self, state: &AppState) -> Self {
+        if self.region.is_none() {
+            self.region = region::of_user(state, self.owner_id);
+        }
+        if state.active_races.lock().unwrap().contains_key(&self.id) {
+            self.status = PartyStatus::Racing;
+        }
+        self
+    }
 }
 
 impl From<party::Model> for PartyResponse {
@@ -42,57 +138,401 @@ impl From<party::Model> for PartyResponse {
             owner_id: party.owner_id,
             created_at: party.created_at,
             map_id: party.map_id,
+            playlist_id: party.playlist_id,
+            playlist_position: party.playlist_position,
+            visibility: PartyVisibility::from_column(&party.visibility),
+            max_members: party.max_members,
+            region: party.region,
+            status: PartyStatus::Lobby,
         }
     }
 }
 
+/// The part a user plays in a party
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PartyRole {
+    Owner,
+    Member,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MembershipResponse {
+    party: PartyResponse,
+    role: PartyRole,
+    joined_at: chrono::DateTime<chrono::FixedOffset>,
+    /// Whether the user is ready for the next race
+    ready: bool,
+    /// Team the user races for, if teams were assigned
+    team: Option<i32>,
+}
+
 #[derive(Deserialize, ToSchema)]
 pub struct JoinPartyRequest {
     code: String,
 }
 
+/// A member of a party as shown in its lobby
+#[derive(Serialize, ToSchema)]
+pub struct LobbyMemberResponse {
+    user: UserResponse,
+    role: PartyRole,
+    joined_at: chrono::DateTime<chrono::FixedOffset>,
+    /// Whether the member is ready for the next race
+    ready: bool,
+    /// Team the member races for, if teams were assigned
+    team: Option<i32>,
+}
+
+/// The party joined, with everything needed to enter its lobby
+#[derive(Serialize, ToSchema)]
+pub struct JoinPartyResponse {
+    #[serde(flatten)]
+    party: PartyResponse,
+    /// Whether the user was a member before joining
+    already_member: bool,
+    /// Map the party races on next
+    map: MapResponse,
+    /// Members of the party in the order they joined, without users the
+    /// caller blocked
+    members: Vec<LobbyMemberResponse>,
+}
+
 #[derive(Deserialize, ToSchema)]
 pub struct UpdatePartyRequest {
     name: Option<String>,
+    visibility: Option<PartyVisibility>,
+    /// Can't be lower than the current number of members
+    max_members: Option<i32>,
+    region: Option<String>,
+}
+
+// Filters of the party listing
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListPartiesParams {
+    /// Only parties owned by this user
+    owner_id: Option<i32>,
+    /// Only parties racing on this map
+    map_id: Option<i32>,
+    /// Only parties that are racing, or waiting in the lobby
+    status: Option<PartyStatus>,
+}
+
+// Filters of the public party browser
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PublicPartiesParams {
+    /// Only parties racing on this map
+    map_id: Option<i32>,
+    /// Only parties with at least this many members
+    min_members: Option<u64>,
+    /// Only parties with at most this many members
+    max_members: Option<u64>,
+    /// Only parties in this region
+    region: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SplitPartyRequest {
+    /// Name of the new party
+    name: String,
+    /// Members moved to the new party; the first one becomes its owner
+    member_ids: Vec<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MergePartyRequest {
+    /// Code of the party to merge into this one
+    code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReadyRequest {
+    /// Whether the member is ready for the next race
+    ready: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadyStateResponse {
+    user_id: i32,
+    ready: bool,
+    /// Whether every member of the party is ready now
+    all_ready: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TransferOwnershipRequest {
+    /// Member who becomes the owner of the party
+    user_id: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetPartyMapRequest {
+    /// Map to race on next
+    map_id: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetPartyPlaylistRequest {
+    /// Playlist to race through, or null to detach the current one
+    playlist_id: Option<i32>,
+}
+
+/// How a racer did over a party's run through its playlist
+#[derive(Serialize, ToSchema)]
+pub struct PlaylistStandingResponse {
+    user: UserResponse,
+    /// Points for places, added up over the races
+    points: i32,
+    races: i32,
+    wins: i32,
+    /// Races the racer finished
+    finishes: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PlaylistStandingsResponse {
+    party_id: i32,
+    playlist_id: i32,
+    /// When the party attached the playlist, if known
+    started_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Races that ended since
+    races: i32,
+    /// Racers by points, then wins
+    standings: Vec<PlaylistStandingResponse>,
 }
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/parties", get(list_parties))
         .route("/parties", post(create_party))
+        .route("/parties/public", get(list_public_parties))
         .route("/parties/{id}", get(get_party))
         .route("/parties/{id}", post(update_party))
         .route("/parties/{id}/members", get(get_party_members))
         .route("/parties/{id}/leave", post(leave_party))
+        .route("/parties/{id}/members/{user_id}", delete(kick_member))
+        .route("/parties/{id}/transfer", post(transfer_ownership))
+        .route("/parties/{id}/ready", post(set_ready))
         .route("/parties/{id}/disband", post(disband_party))
         .route("/parties/join", post(join_party))
+        .route("/parties/quick-join", post(quick_join))
+        .route("/parties/{id}/split", post(split_party))
+        .route("/parties/{id}/merge", post(request_merge))
+        .route("/parties/{id}/merge/accept", post(accept_merge))
+        .route("/parties/{id}/map", post(set_party_map))
+        .route("/parties/{id}/playlist", post(set_party_playlist))
+        .route(
+            "/parties/{id}/playlist/standings",
+            get(get_playlist_standings),
+        )
+        .route("/parties/{id}/code/regenerate", post(regenerate_code))
+        .route("/users/me/parties", get(list_my_parties))
+        .route("/users/{id}/parties", get(list_user_parties))
 }
 
-/// List all parties
+/// List parties
+///
+/// Lists public parties and the parties the current user is in, optionally
+/// filtered by owner, map and status. Parties of owners who hide from the
+/// party browser are left out, except for the current user's own.
 #[utoipa::path(
     get,
     path = "/api/parties",
     tag = "parties",
+    params(PaginationParams, ListPartiesParams),
     responses(
-        (status = 200, description = "List of parties retrieved successfully", body = Vec<PartyResponse>),
+        (status = 200, description = "List of parties retrieved successfully", body = Paginated<PartyResponse>),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 pub async fn list_parties(
     State(state): State<AppState>,
-) -> Result<Json<Vec<PartyResponse>>, (StatusCode, String)> {
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(filters): Query<ListPartiesParams>,
+) -> Result<Json<Paginated<PartyResponse>>, (StatusCode, String)> {
     let db = &state.conn;
 
-    let parties = Party::find()
-        .order_by_asc(party::Column::Id)
+    let mut hidden_owner_ids = settings::hidden_party_owner_ids(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    hidden_owner_ids.remove(&auth_user.0.sub);
+
+    // Private parties are only listed for their members
+    let joined_party_ids: Vec<i32> = UserParty::find()
+        .filter(user_party::Column::UserId.eq(auth_user.0.sub))
         .all(db)
         .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|membership| membership.party_id)
+        .collect();
+
+    let mut query = Party::find()
+        .filter(party::Column::OwnerId.is_not_in(hidden_owner_ids))
+        .filter(
+            Condition::any()
+                .add(party::Column::Visibility.eq(PartyVisibility::Public.as_str()))
+                .add(party::Column::OwnerId.eq(auth_user.0.sub))
+                .add(party::Column::Id.is_in(joined_party_ids)),
+        );
+
+    if let Some(owner_id) = filters.owner_id {
+        query = query.filter(party::Column::OwnerId.eq(owner_id));
+    }
+
+    if let Some(map_id) = filters.map_id {
+        query = query.filter(party::Column::MapId.eq(map_id));
+    }
+
+    // Races are only known while they run
+    if let Some(status) = filters.status {
+        let racing_ids: Vec<i32> = state.active_races.lock().unwrap().keys().copied().collect();
+        query = match status {
+            PartyStatus::Racing => query.filter(party::Column::Id.is_in(racing_ids)),
+            PartyStatus::Lobby => query.filter(party::Column::Id.is_not_in(racing_ids)),
+        };
+    }
+
+    let paginator = query
+        .order_by_asc(party::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let parties = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|party| PartyResponse::from(party).with_live_state(&state))
+        .collect();
+
+    Ok(Json(Paginated::new(
+        parties,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
+
+/// Browse public parties
+///
+/// Lists public parties, newest first, optionally filtered by map, member
+/// count and region. Parties of owners who hide from the party browser or
+/// blocked the current user are left out.
+#[utoipa::path(
+    get,
+    path = "/api/parties/public",
+    tag = "parties",
+    params(PaginationParams, PublicPartiesParams),
+    responses(
+        (status = 200, description = "Public parties retrieved successfully", body = Paginated<PartyResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_public_parties(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(filters): Query<PublicPartiesParams>,
+) -> Result<Json<Paginated<PartyResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let hidden_owner_ids = settings::hidden_party_owner_ids(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let blocker_ids = blocking::blocker_ids(db, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut query = Party::find()
+        .filter(party::Column::Visibility.eq(PartyVisibility::Public.as_str()))
+        .filter(party::Column::OwnerId.is_not_in(hidden_owner_ids))
+        .filter(party::Column::OwnerId.is_not_in(blocker_ids));
+
+    if let Some(map_id) = filters.map_id {
+        query = query.filter(party::Column::MapId.eq(map_id));
+    }
+
+    // Member counts are resolved up front, so the filters can be applied
+    // in the query and pages stay full
+    if filters.min_members.is_some() || filters.max_members.is_some() {
+        let member_counts = count_party_members(db).await?;
+
+        if let Some(min_members) = filters.min_members.filter(|min| *min > 0) {
+            let party_ids = member_counts
+                .iter()
+                .filter(|(_, members)| **members as u64 >= min_members)
+                .map(|(party_id, _)| *party_id);
+            query = query.filter(party::Column::Id.is_in(party_ids));
+        }
+
+        if let Some(max_members) = filters.max_members {
+            let party_ids = member_counts
+                .iter()
+                .filter(|(_, members)| **members as u64 > max_members)
+                .map(|(party_id, _)| *party_id);
+            query = query.filter(party::Column::Id.is_not_in(party_ids));
+        }
+    }
+
+    // Parties without a region are in the region of their owner's
+    // connection, which is only known for connected owners
+    if let Some(region) = filters.region {
+        let region = region.trim().to_lowercase();
+        let owner_ids = region::user_ids_in(&state, &region);
+        query = query.filter(
+            Condition::any().add(party::Column::Region.eq(region)).add(
+                Condition::all()
+                    .add(party::Column::Region.is_null())
+                    .add(party::Column::OwnerId.is_in(owner_ids)),
+            ),
+        );
+    }
+
+    let paginator = query
+        .order_by_desc(party::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(parties.into_iter().map(PartyResponse::from).collect()))
+    let parties = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|party| PartyResponse::from(party).with_live_state(&state))
+        .collect();
+
+    Ok(Json(Paginated::new(
+        parties,
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
 }
 
 /// Get a party by ID
+///
+/// Private parties are only found by their members.
 #[utoipa::path(
     get,
     path = "/api/parties/{id}",
@@ -102,13 +542,18 @@ pub async fn list_parties(
     ),
     responses(
         (status = 200, description = "Party found", body = PartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 404, description = "Party not found", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 pub async fn get_party(
     State(state): State<AppState>,
     Path(id): Path<i32>,
+    auth_user: AuthUser,
 ) -> Result<Json<PartyResponse>, (StatusCode, String)> {
     let db = &state.conn;
 
@@ -121,7 +566,165 @@ pub async fn get_party(
             format!("Party with id {} not found", id),
         ))?;
 
-    Ok(Json(party.into()))
+    // Don't give away the code of private parties to outsiders
+    if PartyVisibility::from_column(&party.visibility) == PartyVisibility::Private
+        && !policy::is_admin(&auth_user.0)
+    {
+        let is_member = UserParty::find()
+            .filter(user_party::Column::UserId.eq(auth_user.0.sub))
+            .filter(user_party::Column::PartyId.eq(party.id))
+            .one(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .is_some();
+
+        if !is_member {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Party with id {} not found", id),
+            ));
+        }
+    }
+
+    Ok(Json(PartyResponse::from(party).with_live_state(&state)))
+}
+
+/// List the parties the current user is in
+///
+/// Lets clients restore the session after a restart.
+#[utoipa::path(
+    get,
+    path = "/api/users/me/parties",
+    tag = "users",
+    responses(
+        (status = 200, description = "Memberships retrieved successfully", body = Vec<MembershipResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_my_parties(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<MembershipResponse>>, (StatusCode, String)> {
+    let memberships = memberships_of(&state, auth_user.0.sub, auth_user.0.sub).await?;
+
+    Ok(Json(memberships))
+}
+
+/// List the parties a user is in
+///
+/// Private parties are only listed if the current user is in them as well.
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/parties",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Memberships retrieved successfully", body = Vec<MembershipResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Blocked by the user", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_user_parties(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<MembershipResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let viewer_id = auth_user.0.sub;
+
+    let user = User::find_by_id(id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", id),
+        ))?;
+
+    if user.id != viewer_id
+        && blocking::has_blocked(db, user.id, viewer_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "The parties of this user are hidden".to_string(),
+        ));
+    }
+
+    let memberships = memberships_of(&state, user.id, viewer_id).await?;
+
+    Ok(Json(memberships))
+}
+
+// Helper function to list the memberships of a user that a viewer may see
+async fn memberships_of(
+    state: &AppState,
+    user_id: i32,
+    viewer_id: i32,
+) -> Result<Vec<MembershipResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let memberships = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .find_also_related(Party)
+        .order_by_asc(user_party::Column::JoinedAt)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Others only see private parties they are in themselves
+    let viewer_party_ids: HashSet<i32> = if viewer_id == user_id {
+        HashSet::new()
+    } else {
+        UserParty::find()
+            .filter(user_party::Column::UserId.eq(viewer_id))
+            .all(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .into_iter()
+            .map(|membership| membership.party_id)
+            .collect()
+    };
+
+    Ok(memberships
+        .into_iter()
+        .filter_map(|(membership, party)| {
+            let party = party?;
+            let visible = viewer_id == user_id
+                || PartyVisibility::from_column(&party.visibility) == PartyVisibility::Public
+                || viewer_party_ids.contains(&party.id);
+            if !visible {
+                return None;
+            }
+
+            let role = if party.owner_id == user_id {
+                PartyRole::Owner
+            } else {
+                PartyRole::Member
+            };
+
+            Some(MembershipResponse {
+                party: PartyResponse::from(party).with_live_state(state),
+                role,
+                joined_at: membership.joined_at,
+                ready: membership.ready,
+                team: membership.team,
+            })
+        })
+        .collect())
 }
 
 /// Get members of a party
@@ -133,15 +736,20 @@ pub async fn get_party(
         ("party_id" = i32, Path, description = "Party ID")
     ),
     responses(
-        (status = 200, description = "Party members retrieved successfully"),
+        (status = 200, description = "Party members retrieved successfully", body = Vec<UserResponse>),
+        (status = 401, description = "Unauthorized", body = String),
         (status = 404, description = "Party not found", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 pub async fn get_party_members(
     State(state): State<AppState>,
     Path(party_id): Path<i32>,
-) -> Result<Json<Vec<user::Model>>, (StatusCode, String)> {
+    auth_user: AuthUser,
+) -> Result<Json<Vec<UserResponse>>, (StatusCode, String)> {
     let db = &state.conn;
 
     // First verify party exists
@@ -154,6 +762,11 @@ pub async fn get_party_members(
             format!("Party with id {} not found", party_id),
         ))?;
 
+    // Users the caller blocked are left out
+    let blocked_ids = blocking::blocked_ids(db, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     // Get all users in this party via user_party relation
     let users = UserParty::find()
         .filter(user_party::Column::PartyId.eq(party_id))
@@ -162,21 +775,40 @@ pub async fn get_party_members(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .into_iter()
-        .map(|(_, users)| users[0].clone())
-        .collect::<Vec<user::Model>>();
+        .filter(|(membership, _)| !blocked_ids.contains(&membership.user_id))
+        .map(|(_, users)| UserResponse::from(users[0].clone()))
+        .collect::<Vec<UserResponse>>();
 
     Ok(Json(users))
 }
 
-fn generate_party_code() -> String {
-    // Use current timestamp and format to create a unique code
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_millis();
+// Helper function to check a requested member limit against the server's
+fn check_max_members(state: &AppState, max_members: i32) -> Result<i32, (StatusCode, String)> {
+    if !(1..=state.config.max_party_size).contains(&max_members) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "max_members must be between 1 and {}",
+                state.config.max_party_size
+            ),
+        ));
+    }
+
+    Ok(max_members)
+}
+
+// Helper function to check a region chosen for a party
+fn check_region(value: &str) -> Result<String, (StatusCode, String)> {
+    region::parse(value).ok_or((StatusCode::BAD_REQUEST, format!("Invalid region {}", value)))
+}
 
-    // Format into a 6 character uppercase code
-    format!("{:06X}", timestamp % 0xFFFFFF).to_uppercase()
+/// Generate a code to join a party by. Codes are random, so knowing one
+/// doesn't help guessing another.
+pub fn generate_party_code() -> String {
+    let mut rng = rand::rng();
+    (0..PARTY_CODE_LENGTH)
+        .map(|_| *PARTY_CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+        .collect()
 }
 
 /// Create a new party
@@ -189,6 +821,8 @@ fn generate_party_code() -> String {
     responses(
         (status = 200, description = "Party created successfully", body = PartyResponse),
         (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 422, description = "Invalid party name", body = ValidationErrorResponse),
         (status = 500, description = "Internal server error", body = String)
     ),
     security(
@@ -198,19 +832,48 @@ fn generate_party_code() -> String {
 pub async fn create_party(
     State(state): State<AppState>,
     auth_user: AuthUser,
+    headers: HeaderMap,
     Json(payload): Json<CreatePartyRequest>,
-) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+) -> Result<Json<PartyResponse>, Response> {
     let db = &state.conn;
 
+    let name = state
+        .validator
+        .text("name", &payload.name, MAX_PARTY_NAME_LENGTH)
+        .map_err(|e| validation::invalid(vec![e]))?;
+
+    let max_members = payload
+        .max_members
+        .map(|max_members| check_max_members(&state, max_members))
+        .transpose()
+        .map_err(IntoResponse::into_response)?
+        .unwrap_or(state.config.max_party_size);
+
+    let map = find_map(db, payload.map_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let party_region = match payload.region {
+        Some(party_region) => {
+            Some(check_region(&party_region).map_err(IntoResponse::into_response)?)
+        }
+        None => region::preferred_or_caller(&state, auth_user.0.sub, &headers)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?,
+    };
+
     // Verify owner exists
     let _owner = User::find_by_id(auth_user.0.sub)
         .one(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((
-            StatusCode::BAD_REQUEST,
-            format!("User with id {} not found", auth_user.0.sub),
-        ))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("User with id {} not found", auth_user.0.sub),
+            )
+                .into_response()
+        })?;
 
     // Generate a unique party code
     let code = generate_party_code();
@@ -219,21 +882,24 @@ pub async fn create_party(
     let txn = db
         .begin()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
     // Create party
     let new_party = party::ActiveModel {
-        name: Set(payload.name),
+        name: Set(name),
         code: Set(code),
         owner_id: Set(auth_user.0.sub),
-        map_id: Set(payload.map_id),
+        map_id: Set(map.id),
+        visibility: Set(payload.visibility.as_str().to_string()),
+        max_members: Set(max_members),
+        region: Set(party_region),
         ..Default::default()
     };
 
     let party = new_party
         .insert(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
     // Add owner as a party member
     let new_user_party = user_party::ActiveModel {
@@ -245,34 +911,55 @@ pub async fn create_party(
     let _ = new_user_party
         .insert(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    party_events::record(
+        &txn,
+        party.id,
+        party_events::Kind::Joined,
+        auth_user.0.sub,
+        None,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
 
     // Commit transaction
     txn.commit()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    membership::remember(&state, auth_user.0.sub, party.id);
 
     Ok(Json(party.into()))
 }
 
 /// Join an existing party
+///
+/// Joining a party the user is already a member of returns their membership
+/// again, with `already_member` set.
 #[utoipa::path(
     post,
     path = "/api/parties/join",
     tag = "parties",
     request_body = JoinPartyRequest,
     responses(
-        (status = 200, description = "Successfully joined party", body = PartyResponse),
-        (status = 400, description = "Invalid request or already a member", body = String),
+        (status = 200, description = "Successfully joined party", body = JoinPartyResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Blocked by the party owner, or banned from the party", body = String),
         (status = 404, description = "Party not found", body = String),
+        (status = 409, description = "Party is full", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 pub async fn join_party(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Json(payload): Json<JoinPartyRequest>,
-) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+) -> Result<Json<JoinPartyResponse>, (StatusCode, String)> {
     let db = &state.conn;
 
     // Verify user exists
@@ -293,34 +980,277 @@ pub async fn join_party(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((StatusCode::NOT_FOUND, "Invalid party code".to_string()))?;
 
-    // Check if user is already a member
-    let existing_membership = UserParty::find()
-        .filter(user_party::Column::UserId.eq(auth_user.0.sub))
-        .filter(user_party::Column::PartyId.eq(party.id))
-        .one(db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    join(&state, auth_user.0.sub, party).await.map(Json)
+}
+
+/// Add a user to a party they found, unless the owner blocked them, and
+/// describe its lobby. Users who are already members stay members.
+pub async fn join(
+    state: &AppState,
+    user_id: i32,
+    party: party::Model,
+) -> Result<JoinPartyResponse, (StatusCode, String)> {
+    let db = &state.conn;
 
-    if existing_membership.is_some() {
+    // Users blocked by the owner can't join
+    if blocking::has_blocked(db, party.owner_id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "User is already a member of this party".to_string(),
+            StatusCode::FORBIDDEN,
+            "You can't join this party".to_string(),
+        ));
+    }
+
+    // Neither can users banned from the party
+    if party_bans::is_banned(db, party.id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are banned from this party".to_string(),
         ));
     }
 
+    // Check if user is already a member
+    let existing_membership = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .filter(user_party::Column::PartyId.eq(party.id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let already_member = existing_membership.is_some();
+
+    if !already_member {
+        // Add user to party, if it has room
+        add_member(db, user_id, party.id).await?;
+
+        membership::remember(state, user_id, party.id);
+        record_join(db, user_id, &party).await;
+    }
+
+    let map = Map::find_by_id(party.map_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Map with id {} not found", party.map_id),
+        ))?;
+
+    let mut map = MapResponse::from(map);
+    attach_tags(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, std::slice::from_mut(&mut map))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let members = lobby_members(db, &party, user_id).await?;
+
+    Ok(JoinPartyResponse {
+        party: PartyResponse::from(party).with_live_state(state),
+        already_member,
+        map,
+        members,
+    })
+}
+
+// Helper function to list the members of a party for its lobby, leaving out
+// users the caller blocked
+async fn lobby_members(
+    db: &DatabaseConnection,
+    party: &party::Model,
+    caller_id: i32,
+) -> Result<Vec<LobbyMemberResponse>, (StatusCode, String)> {
+    let blocked_ids = blocking::blocked_ids(db, caller_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let members = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party.id))
+        .order_by_asc(user_party::Column::JoinedAt)
+        .find_also_related(User)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(members
+        .into_iter()
+        .filter(|(membership, _)| !blocked_ids.contains(&membership.user_id))
+        .filter_map(|(membership, user)| {
+            let role = if membership.user_id == party.owner_id {
+                PartyRole::Owner
+            } else {
+                PartyRole::Member
+            };
+
+            Some(LobbyMemberResponse {
+                user: UserResponse::from(user?),
+                role,
+                joined_at: membership.joined_at,
+                ready: membership.ready,
+                team: membership.team,
+            })
+        })
+        .collect())
+}
+
+/// Quick-join a party, preferring parties in the caller's region
+#[utoipa::path(
+    post,
+    path = "/api/parties/quick-join",
+    tag = "parties",
+    responses(
+        (status = 200, description = "Successfully joined party", body = PartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "No party available to join", body = String),
+        (status = 409, description = "Party filled up while joining", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn quick_join(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+    let caller_region = region::preferred_or_caller(&state, user_id, &headers)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Parties the user is already in are not candidates
+    let joined_party_ids: Vec<i32> = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|membership| membership.party_id)
+        .collect();
+
+    // Neither are parties of owners who blocked the user
+    let blocker_ids = blocking::blocker_ids(db, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Nor parties whose owners keep them out of the browser
+    let hidden_owner_ids = settings::hidden_party_owner_ids(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Nor parties the user is banned from
+    let banned_party_ids = party_bans::banned_party_ids(db, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Private parties can only be joined by code
+    let parties = Party::find()
+        .filter(party::Column::Visibility.eq(PartyVisibility::Public.as_str()))
+        .filter(party::Column::Id.is_not_in(joined_party_ids))
+        .filter(party::Column::Id.is_not_in(banned_party_ids))
+        .filter(party::Column::OwnerId.is_not_in(blocker_ids))
+        .filter(party::Column::OwnerId.is_not_in(hidden_owner_ids))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let member_counts = count_party_members(db).await?;
+
+    // Prefer same-region parties, then fuller lobbies, then the newest party
+    let party = parties
+        .into_iter()
+        .filter(|party| {
+            member_counts.get(&party.id).copied().unwrap_or(0) < party.max_members as i64
+        })
+        .max_by_key(|party| {
+            let party_region = party
+                .region
+                .clone()
+                .or_else(|| region::of_user(&state, party.owner_id));
+            let same_region = caller_region.is_some() && party_region == caller_region;
+            let members = member_counts.get(&party.id).copied().unwrap_or(0);
+            (same_region, members, party.id)
+        })
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "No party available to join".to_string(),
+        ))?;
+
     // Add user to party
-    let new_user_party = user_party::ActiveModel {
-        user_id: Set(auth_user.0.sub),
-        party_id: Set(party.id),
-        ..Default::default()
-    };
+    add_member(db, user_id, party.id).await?;
 
-    let _ = new_user_party
-        .insert(db)
+    membership::remember(&state, user_id, party.id);
+    record_join(db, user_id, &party).await;
+
+    Ok(Json(PartyResponse::from(party).with_live_state(&state)))
+}
+
+/// Add a user to a party in a transaction, so concurrent joins can't
+/// overshoot its member limit
+pub async fn add_member(
+    db: &DatabaseConnection,
+    user_id: i32,
+    party_id: i32,
+) -> Result<(), (StatusCode, String)> {
+    let txn = db
+        .begin()
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(party.into()))
+    let joined = membership::join(&txn, user_id, party_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !joined {
+        return Err((StatusCode::CONFLICT, "Party is full".to_string()));
+    }
+
+    party_events::record(&txn, party_id, party_events::Kind::Joined, user_id, None)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Add joining a party to the activity of a user
+pub async fn record_join(db: &DatabaseConnection, user_id: i32, party: &party::Model) {
+    if let Err(e) = activity::record(
+        db,
+        user_id,
+        activity::Kind::PartyJoined,
+        Some(party.map_id),
+        Some(party.id),
+    )
+    .await
+    {
+        tracing::error!("Error recording party join activity: {}", e);
+    }
+}
+
+// Helper function to count the members of every party
+async fn count_party_members(
+    db: &DatabaseConnection,
+) -> Result<HashMap<i32, i64>, (StatusCode, String)> {
+    let counts: Vec<(i32, i64)> = UserParty::find()
+        .select_only()
+        .column(user_party::Column::PartyId)
+        .column_as(user_party::Column::Id.count(), "member_count")
+        .group_by(user_party::Column::PartyId)
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(counts.into_iter().collect())
 }
 
 /// Update party information
@@ -334,40 +1264,198 @@ pub async fn join_party(
     request_body = UpdatePartyRequest,
     responses(
         (status = 200, description = "Party updated successfully", body = PartyResponse),
+        (status = 400, description = "Invalid member limit or region", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can update the party", body = String),
         (status = 404, description = "Party not found", body = String),
+        (status = 422, description = "Invalid party name", body = ValidationErrorResponse),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 pub async fn update_party(
     State(state): State<AppState>,
     Path(id): Path<i32>,
+    auth_user: AuthUser,
     Json(payload): Json<UpdatePartyRequest>,
-) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+) -> Result<Json<PartyResponse>, Response> {
     let db = &state.conn;
 
     // Get the party
     let party = Party::find_by_id(id)
         .one(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((
-            StatusCode::NOT_FOUND,
-            format!("Party with id {} not found", id),
-        ))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Party with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    // Verify the user may manage the party
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can update the party".to_string(),
+        )
+            .into_response());
+    }
+
+    let name = payload
+        .name
+        .map(|name| state.validator.text("name", &name, MAX_PARTY_NAME_LENGTH))
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?;
+
+    let max_members = payload
+        .max_members
+        .map(|max_members| check_max_members(&state, max_members))
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+
+    let party_region = payload
+        .region
+        .map(|party_region| check_region(&party_region))
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+
+    // The limit can't drop below the members already in the party
+    if let Some(max_members) = max_members {
+        let members = UserParty::find()
+            .filter(user_party::Column::PartyId.eq(party.id))
+            .count(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+        if members > max_members as u64 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("The party already has {} members", members),
+            )
+                .into_response());
+        }
+    }
 
     // Update party
     let mut party_model: party::ActiveModel = party.clone().into();
 
-    if let Some(name) = payload.name {
+    if let Some(name) = name {
         party_model.name = Set(name);
     }
 
+    if let Some(visibility) = payload.visibility {
+        party_model.visibility = Set(visibility.as_str().to_string());
+    }
+
+    if let Some(max_members) = max_members {
+        party_model.max_members = Set(max_members);
+    }
+
+    if let Some(party_region) = party_region {
+        party_model.region = Set(Some(party_region));
+    }
+
     let updated_party = party_model
         .update(db)
         .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    Ok(Json(
+        PartyResponse::from(updated_party).with_live_state(&state),
+    ))
+}
+
+/// Set whether the current user is ready for the next race
+///
+/// The party is told over the websocket, along with when everyone is ready.
+/// The owner can only start a race once every member is ready; starting it
+/// marks everyone as not ready again.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/ready",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = ReadyRequest,
+    responses(
+        (status = 200, description = "Ready state updated successfully", body = ReadyStateResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not a member of the party", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_ready(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<ReadyRequest>,
+) -> Result<Json<ReadyStateResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    // Verify the party exists
+    let _ = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    let updated = UserParty::update_many()
+        .col_expr(user_party::Column::Ready, Expr::value(payload.ready))
+        .filter(user_party::Column::PartyId.eq(id))
+        .filter(user_party::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if updated.rows_affected == 0 {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are not a member of this party".to_string(),
+        ));
+    }
+
+    let all_ready = membership::all_ready(db, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Let the party know
+    let channel = state.party_channels.lock().unwrap().get(&id).cloned();
+
+    if let Some(channel) = channel {
+        let ready_msg = serde_json::to_string(&WsMessage::ReadyStateChanged {
+            user_id,
+            ready: payload.ready,
+        })
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(updated_party.into()))
+        let _ = channel.send(ready_msg);
+
+        if all_ready {
+            let all_ready_msg = serde_json::to_string(&WsMessage::AllReady {})
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            let _ = channel.send(all_ready_msg);
+        }
+    }
+
+    Ok(Json(ReadyStateResponse {
+        user_id,
+        ready: payload.ready,
+        all_ready,
+    }))
 }
 
 /// Leave a party
@@ -397,7 +1485,7 @@ pub async fn leave_party(
 ) -> Result<StatusCode, (StatusCode, String)> {
     let db = &state.conn;
     // Get the user ID from the auth token
-    let user_id = auth_user.0.sub; // Assuming sub is already an i32-compatible value
+    let user_id = auth_user.0.sub;
 
     // Verify the party exists
     let party = Party::find_by_id(party_id)
@@ -429,36 +1517,61 @@ pub async fn leave_party(
         ));
     }
 
+    // Start a transaction
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     // Delete the user_party entry to leave - using delete_many instead of delete_by_id
     UserParty::delete_many()
         .filter(user_party::Column::UserId.eq(user_id))
         .filter(user_party::Column::PartyId.eq(party_id))
-        .exec(db)
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    party_events::record(&txn, party_id, party_events::Kind::Left, user_id, None)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Commit transaction
+    txn.commit()
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    membership::forget(&state, user_id, party_id);
+    ws::leave_race(&state, party_id, user_id);
+
     Ok(StatusCode::OK)
 }
 
-/// Disband a party (only by owner)
-#[axum::debug_handler]
+/// Remove a member from a party (only by owner)
+///
+/// The member's websocket connection to the party is closed.
 #[utoipa::path(
-    post,
-    path = "/api/parties/{id}/disband",
+    delete,
+    path = "/api/parties/{id}/members/{user_id}",
     tag = "parties",
     params(
-        ("id" = i32, Path, description = "Party ID")
+        ("id" = i32, Path, description = "Party ID"),
+        ("user_id" = i32, Path, description = "User ID of the member")
     ),
     responses(
-        (status = 204, description = "Party disbanded successfully"),
-        (status = 403, description = "Only the party owner can disband it", body = String),
-        (status = 404, description = "Party not found", body = String),
+        (status = 204, description = "Member removed successfully"),
+        (status = 400, description = "The owner can't be removed", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can remove members", body = String),
+        (status = 404, description = "Party or member not found", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-pub async fn disband_party(
+pub async fn kick_member(
     State(state): State<AppState>,
-    Path(id): Path<i32>,
+    Path((id, user_id)): Path<(i32, i32)>,
     auth_user: AuthUser,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let db = &state.conn;
@@ -474,10 +1587,17 @@ pub async fn disband_party(
         ))?;
 
     // Verify the user is the owner
-    if party.owner_id != auth_user.0.sub {
+    if !policy::can_manage_party(&auth_user.0, &party) {
         return Err((
             StatusCode::FORBIDDEN,
-            "Only the party owner can disband the party".to_string(),
+            "Only the party owner can remove members".to_string(),
+        ));
+    }
+
+    if user_id == party.owner_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The party owner can't be removed from their own party".to_string(),
         ));
     }
 
@@ -487,23 +1607,1085 @@ pub async fn disband_party(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    // Delete all user-party relationships
-    UserParty::delete_many()
-        .filter(user_party::Column::PartyId.eq(id))
-        .exec(&txn)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    // Delete the party
-    Party::delete_by_id(id)
-        .exec(&txn)
+    let removed = remove_member(&txn, id, user_id, auth_user.0.sub)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    if !removed {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not a member of this party", user_id),
+        ));
+    }
+
     // Commit transaction
     txn.commit()
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    disconnect_member(&state, user_id, id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a member from a party on behalf of its owner and record it in the
+/// party's history. Returns whether the user was a member.
+pub async fn remove_member<C: ConnectionTrait>(
+    db: &C,
+    party_id: i32,
+    user_id: i32,
+    actor_id: i32,
+) -> Result<bool, DbErr> {
+    let removed = UserParty::delete_many()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .exec(db)
+        .await?;
+
+    if removed.rows_affected == 0 {
+        return Ok(false);
+    }
+
+    party_events::record(
+        db,
+        party_id,
+        party_events::Kind::Kicked,
+        user_id,
+        Some(actor_id),
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Forget the membership of a removed member and close their connection to
+/// the party, if any
+pub fn disconnect_member(state: &AppState, user_id: i32, party_id: i32) {
+    membership::forget(state, user_id, party_id);
+
+    // Only a connection subscribed to this party is closed
+    if state.user_parties.lock().unwrap().get(&user_id) == Some(&party_id)
+        && let Some(socket) = state.user_sockets.lock().unwrap().get(&user_id)
+    {
+        let _ = socket.send(SocketCommand::Close);
+    }
+}
+
+/// Hand a party over to another member (only by owner)
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/transfer",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = TransferOwnershipRequest,
+    responses(
+        (status = 200, description = "Ownership transferred successfully", body = PartyResponse),
+        (status = 400, description = "The new owner is not a member", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can transfer it", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn transfer_ownership(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<TransferOwnershipRequest>,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user is the owner
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can transfer the party".to_string(),
+        ));
+    }
+
+    if payload.user_id == party.owner_id {
+        return Ok(Json(PartyResponse::from(party).with_live_state(&state)));
+    }
+
+    let is_member = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(id))
+        .filter(user_party::Column::UserId.eq(payload.user_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .is_some();
+
+    if !is_member {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "User with id {} is not a member of this party",
+                payload.user_id
+            ),
+        ));
+    }
+
+    let previous_owner_id = party.owner_id;
+    let party = hand_over(db, party, payload.user_id, Some(auth_user.0.sub))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    announce_owner(&state, &party, previous_owner_id);
+
+    Ok(Json(PartyResponse::from(party).with_live_state(&state)))
+}
+
+/// Make a member the owner of a party and record it in the party's history
+pub async fn hand_over(
+    db: &DatabaseConnection,
+    party: party::Model,
+    new_owner_id: i32,
+    actor_id: Option<i32>,
+) -> Result<party::Model, DbErr> {
+    let txn = db.begin().await?;
+
+    let mut party: party::ActiveModel = party.into();
+    party.owner_id = Set(new_owner_id);
+    let party = party.update(&txn).await?;
+
+    party_events::record(
+        &txn,
+        party.id,
+        party_events::Kind::OwnershipTransferred,
+        new_owner_id,
+        actor_id,
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(party)
+}
+
+/// Tell the connected members of a party who owns it now
+pub fn announce_owner(state: &AppState, party: &party::Model, previous_owner_id: i32) {
+    let channel = state.party_channels.lock().unwrap().get(&party.id).cloned();
+
+    if let Some(channel) = channel {
+        let owner_msg = serde_json::to_string(&WsMessage::OwnerChanged {
+            party_id: party.id,
+            owner_id: party.owner_id,
+            previous_owner_id,
+        })
+        .unwrap();
+
+        let _ = channel.send(owner_msg);
+    }
+}
+
+/// Disband a party (only by owner)
+#[axum::debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/disband",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 204, description = "Party disbanded successfully"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can disband it", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn disband_party(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user is the owner
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can disband the party".to_string(),
+        ));
+    }
+
+    disband(&state, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Delete a party with its memberships
+pub async fn disband(state: &AppState, party_id: i32) -> Result<(), DbErr> {
+    // Start a transaction
+    let txn = state.conn.begin().await?;
+
+    // Delete all user-party relationships
+    UserParty::delete_many()
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .exec(&txn)
+        .await?;
+
+    // Delete the party
+    Party::delete_by_id(party_id).exec(&txn).await?;
+
+    // Commit transaction
+    txn.commit().await?;
+
+    membership::forget_party(state, party_id);
+
+    Ok(())
+}
+
+/// Split members off into a new party (only by owner)
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/split",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = SplitPartyRequest,
+    responses(
+        (status = 200, description = "Party split successfully, returns the new party", body = PartyResponse),
+        (status = 400, description = "Invalid member selection", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can split the party", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 422, description = "Invalid party name", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn split_party(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<SplitPartyRequest>,
+) -> Result<Json<PartyResponse>, Response> {
+    let db = &state.conn;
+
+    let name = state
+        .validator
+        .text("name", &payload.name, MAX_PARTY_NAME_LENGTH)
+        .map_err(|e| validation::invalid(vec![e]))?;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Party with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    // Verify the user is the owner
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can split the party".to_string(),
+        )
+            .into_response());
+    }
+
+    // Drop duplicates while keeping the order, the first member becomes owner
+    let mut member_ids = payload.member_ids;
+    let mut seen = HashSet::new();
+    member_ids.retain(|member_id| seen.insert(*member_id));
+
+    let new_owner_id = *member_ids.first().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "At least one member must be moved".to_string(),
+        )
+            .into_response()
+    })?;
+
+    if member_ids.contains(&party.owner_id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The party owner cannot be moved out of their own party".to_string(),
+        )
+            .into_response());
+    }
+
+    // Every selected user must be a member of this party
+    let memberships = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(id))
+        .filter(user_party::Column::UserId.is_in(member_ids.clone()))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    if memberships.len() != member_ids.len() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("All moved users must be members of party {}", id),
+        )
+            .into_response());
+    }
+
+    // Start a transaction
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // Create the new party
+    let new_party = party::ActiveModel {
+        name: Set(name),
+        code: Set(generate_party_code()),
+        owner_id: Set(new_owner_id),
+        map_id: Set(party.map_id),
+        visibility: Set(party.visibility.clone()),
+        max_members: Set(party.max_members),
+        region: Set(party.region.clone()),
+        ..Default::default()
+    };
+
+    let new_party = new_party
+        .insert(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // Move the selected memberships
+    UserParty::update_many()
+        .col_expr(user_party::Column::PartyId, Expr::value(new_party.id))
+        .filter(user_party::Column::PartyId.eq(id))
+        .filter(user_party::Column::UserId.is_in(member_ids.clone()))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // The moved members leave this party and join the new one
+    let actor_id = Some(auth_user.0.sub);
+    party_events::record_all(&txn, id, party_events::Kind::Left, &member_ids, actor_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    party_events::record_all(
+        &txn,
+        new_party.id,
+        party_events::Kind::Joined,
+        &member_ids,
+        actor_id,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    // Commit transaction
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    for user_id in &member_ids {
+        membership::forget(&state, *user_id, id);
+    }
+    move_member_sockets(&state, id, &member_ids, new_party.id);
+
+    Ok(Json(PartyResponse::from(new_party).with_live_state(&state)))
+}
+
+/// Request to merge another party into this one (only by owner)
+///
+/// The merge happens once the owner of the other party accepts it.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/merge",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "ID of the party absorbing the other one")
+    ),
+    request_body = MergePartyRequest,
+    responses(
+        (status = 202, description = "Merge requested, waiting for the other owner"),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not the party owner, or blocked by the other owner", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn request_merge(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<MergePartyRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user is the owner
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can request a merge".to_string(),
+        ));
+    }
+
+    // Find the other party by code
+    let other_party = Party::find()
+        .filter(party::Column::Code.eq(payload.code))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Invalid party code".to_string()))?;
+
+    if other_party.id == party.id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A party cannot be merged into itself".to_string(),
+        ));
+    }
+
+    // Owners who blocked the requester don't get their requests
+    if blocking::has_blocked(db, other_party.owner_id, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You can't merge with this party".to_string(),
+        ));
+    }
+
+    state
+        .party_merge_requests
+        .lock()
+        .unwrap()
+        .insert(other_party.id, (party.id, Instant::now()));
+
+    // Let the other party know so its owner can accept
+    let channel = state
+        .party_channels
+        .lock()
+        .unwrap()
+        .get(&other_party.id)
+        .cloned();
+
+    if let Some(channel) = channel {
+        let merge_msg = serde_json::to_string(&WsMessage::MergeRequested {
+            party_id: party.id,
+            name: party.name,
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        let _ = channel.send(merge_msg);
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Accept a pending request to merge this party into another one (only by owner)
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/merge/accept",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "ID of the party being merged away")
+    ),
+    responses(
+        (status = 200, description = "Parties merged, returns the absorbing party", body = PartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can accept a merge", body = String),
+        (status = 404, description = "Party or merge request not found", body = String),
+        (status = 409, description = "The other party doesn't have room for everyone", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn accept_merge(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user is the owner
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can accept a merge".to_string(),
+        ));
+    }
+
+    let target_id = state
+        .party_merge_requests
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .filter(|(_, requested_at)| requested_at.elapsed() < MERGE_REQUEST_TTL)
+        .map(|(target_id, _)| target_id)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("No pending merge request for party {}", id),
+        ))?;
+
+    let target = Party::find_by_id(target_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", target_id),
+        ))?;
+
+    // Start a transaction
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Lock the target party, so concurrent joins can't take its room
+    let target = Party::find_by_id(target.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", target_id),
+        ))?;
+
+    let target_member_ids: Vec<i32> = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(target.id))
+        .all(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|membership| membership.user_id)
+        .collect();
+
+    let member_ids: Vec<i32> = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(id))
+        .all(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|membership| membership.user_id)
+        .collect();
+
+    // The target party must have room for everyone joining it
+    let joining: Vec<i32> = member_ids
+        .iter()
+        .filter(|member_id| !target_member_ids.contains(member_id))
+        .copied()
+        .collect();
+    if target_member_ids.len() + joining.len() > target.max_members.max(0) as usize {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Party {} doesn't have room for everyone", target.id),
+        ));
+    }
+
+    // Members banned from the target party can't be merged into it
+    let banned_ids = party_bans::banned_user_ids(&txn, target.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if joining
+        .iter()
+        .any(|member_id| banned_ids.contains(member_id))
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Some members are banned from party {}", target.id),
+        ));
+    }
+
+    // Drop memberships of users already in the target party
+    UserParty::delete_many()
+        .filter(user_party::Column::PartyId.eq(id))
+        .filter(user_party::Column::UserId.is_in(target_member_ids))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Move the remaining memberships
+    UserParty::update_many()
+        .col_expr(user_party::Column::PartyId, Expr::value(target.id))
+        .filter(user_party::Column::PartyId.eq(id))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    party_events::record_all(
+        &txn,
+        target.id,
+        party_events::Kind::Joined,
+        &joining,
+        Some(auth_user.0.sub),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Delete the merged party
+    Party::delete_by_id(id)
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Commit transaction
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    membership::forget_party(&state, id);
+    move_member_sockets(&state, id, &member_ids, target.id);
+
+    Ok(Json(PartyResponse::from(target).with_live_state(&state)))
+}
+
+/// Switch the map of a party (only by owner)
+///
+/// Picking a map by hand detaches the party's playlist, if any. Connected
+/// members are told about the new map over the websocket.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/map",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = SetPartyMapRequest,
+    responses(
+        (status = 200, description = "Party map updated successfully", body = PartyResponse),
+        (status = 400, description = "Map not found", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can change the map", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_party_map(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<SetPartyMapRequest>,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user may manage the party
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can change the map".to_string(),
+        ));
+    }
+
+    let map = find_map(db, payload.map_id).await?;
+
+    let mut party_model: party::ActiveModel = party.into();
+    party_model.map_id = Set(map.id);
+    party_model.playlist_id = Set(None);
+    party_model.playlist_position = Set(0);
+    party_model.playlist_started_at = Set(None);
+
+    let updated_party = party_model
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    announce_map(&state, updated_party.id, &map);
+
+    Ok(Json(
+        PartyResponse::from(updated_party).with_live_state(&state),
+    ))
+}
+
+// Helper function to find the map a party should race on. Drafts, deleted
+// maps and maps taken down can't be raced on, as the other members can't
+// see them.
+async fn find_map(
+    db: &DatabaseConnection,
+    map_id: i32,
+) -> Result<map::Model, (StatusCode, String)> {
+    Map::find_by_id(map_id)
+        .filter(map::Column::Status.ne(MapStatus::Draft.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Map with id {} not found", map_id),
+        ))
+}
+
+// Helper function to tell the connected members of a party about its new map
+fn announce_map(state: &AppState, party_id: i32, map: &map::Model) {
+    let Some(channel) = state.party_channels.lock().unwrap().get(&party_id).cloned() else {
+        return;
+    };
+
+    let map_msg = serde_json::to_string(&WsMessage::MapChanged {
+        party_id,
+        map_id: map.id,
+        title: map.title.clone(),
+    })
+    .unwrap();
+
+    let _ = channel.send(map_msg);
+}
+
+/// Attach a playlist to a party (only by owner)
+///
+/// The party switches to the first map of the playlist and advances to the
+/// next one every time a race starts, wrapping around at the end.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/playlist",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = SetPartyPlaylistRequest,
+    responses(
+        (status = 200, description = "Party playlist updated successfully", body = PartyResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can change the playlist", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_party_playlist(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<SetPartyPlaylistRequest>,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user may manage the party
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can change the playlist".to_string(),
+        ));
+    }
+
+    let mut party_model: party::ActiveModel = party.into();
+    party_model.playlist_id = Set(payload.playlist_id);
+    party_model.playlist_position = Set(0);
+    party_model.playlist_started_at = Set(payload
+        .playlist_id
+        .map(|_| chrono::Utc::now().fixed_offset()));
+
+    let mut new_map = None;
+    if let Some(playlist_id) = payload.playlist_id {
+        // Start on the first map of the playlist
+        let first_map = playlists::playlist_maps(playlist_id, db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                format!("Playlist with id {} not found or empty", playlist_id),
+            ))?;
+
+        party_model.map_id = Set(first_map.id);
+        new_map = Some(first_map);
+    }
+
+    let updated_party = party_model
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(map) = new_map {
+        announce_map(&state, updated_party.id, &map);
+    }
+
+    Ok(Json(
+        PartyResponse::from(updated_party).with_live_state(&state),
+    ))
+}
+
+/// Get the standings of a party's run through its playlist
+///
+/// Racers score points for their place in every race that ended since the
+/// playlist was attached, from 10 for a win down to 1 for eighth place.
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/playlist/standings",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Standings retrieved successfully", body = PlaylistStandingsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Party not found or without a playlist", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_playlist_standings(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<PlaylistStandingsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+    let Some(playlist_id) = party.playlist_id else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} has no playlist", id),
+        ));
+    };
+
+    // Races of the current run through the playlist
+    let mut races = Race::find()
+        .select_only()
+        .column(race::Column::Id)
+        .filter(race::Column::PartyId.eq(party.id))
+        .filter(race::Column::PlaylistId.eq(playlist_id))
+        .filter(race::Column::EndedAt.is_not_null());
+    if let Some(started_at) = party.playlist_started_at {
+        races = races.filter(race::Column::StartedAt.gte(started_at));
+    }
+    let race_ids: Vec<i32> = races
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let participants = RaceParticipant::find()
+        .filter(race_participant::Column::RaceId.is_in(race_ids.clone()))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Points, races, wins and finishes of each racer
+    let mut totals: HashMap<i32, (i32, i32, i32, i32)> = HashMap::new();
+    for participant in participants {
+        let total = totals.entry(participant.user_id).or_default();
+        total.0 += participant.position.map_or(0, playlists::points_for_place);
+        total.1 += 1;
+        total.2 += i32::from(participant.position == Some(1));
+        total.3 += i32::from(!participant.dnf);
+    }
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(totals.keys().copied()))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let mut totals: Vec<(i32, (i32, i32, i32, i32))> = totals.into_iter().collect();
+    totals.sort_by_key(|(user_id, (points, _, wins, _))| {
+        (
+            std::cmp::Reverse(*points),
+            std::cmp::Reverse(*wins),
+            *user_id,
+        )
+    });
+
+    let standings = totals
+        .into_iter()
+        .filter_map(|(user_id, (points, races, wins, finishes))| {
+            Some(PlaylistStandingResponse {
+                user: users.remove(&user_id)?.into(),
+                points,
+                races,
+                wins,
+                finishes,
+            })
+        })
+        .collect();
+
+    Ok(Json(PlaylistStandingsResponse {
+        party_id: party.id,
+        playlist_id,
+        started_at: party.playlist_started_at,
+        races: race_ids.len() as i32,
+        standings,
+    }))
+}
+
+/// Give a party a new code (only by owner)
+///
+/// The old code stops working right away, e.g. after it leaked on stream.
+/// Connected members are told the new code over the websocket.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/code/regenerate",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Party code regenerated successfully", body = PartyResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can regenerate the code", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn regenerate_code(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    // Verify the user may manage the party
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can regenerate the code".to_string(),
+        ));
+    }
+
+    // Swap the code in a single update
+    let mut party_model: party::ActiveModel = party.into();
+    party_model.code = Set(generate_party_code());
+    let party = party_model
+        .update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Let connected members know
+    let channel = state.party_channels.lock().unwrap().get(&party.id).cloned();
+
+    if let Some(channel) = channel {
+        let code_msg = serde_json::to_string(&WsMessage::PartyCodeChanged {
+            party_id: party.id,
+            code: party.code.clone(),
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        let _ = channel.send(code_msg);
+    }
+
+    Ok(Json(PartyResponse::from(party).with_live_state(&state)))
+}
+
+// Helper function to move the websocket subscriptions of members to another party
+fn move_member_sockets(state: &AppState, from_party_id: i32, user_ids: &[i32], to_party_id: i32) {
+    let user_parties = state.user_parties.lock().unwrap();
+    let user_sockets = state.user_sockets.lock().unwrap();
+
+    for user_id in user_ids {
+        // Only connections currently subscribed to the old party are moved
+        if user_parties.get(user_id) != Some(&from_party_id) {
+            continue;
+        }
+
+        if let Some(socket) = user_sockets.get(user_id) {
+            let _ = socket.send(SocketCommand::SwitchParty(to_party_id));
+        }
+    }
+}
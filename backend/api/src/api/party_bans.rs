@@ -0,0 +1,280 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use entity::invite::{self, Entity as Invite};
+use entity::party::{self, Entity as Party};
+use entity::party_ban::{self, Entity as PartyBan};
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, SqlErr,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::parties;
+use crate::db::AppState;
+use crate::policy;
+use crate::validation::{self, ValidationErrorResponse};
+
+// Longest reason given for a ban
+const MAX_BAN_REASON_LENGTH: usize = 200;
+
+#[derive(Deserialize, ToSchema)]
+pub struct BanRequest {
+    /// User to ban
+    user_id: i32,
+    reason: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PartyBanResponse {
+    user_id: i32,
+    /// User who issued the ban, if their account still exists
+    banned_by: Option<i32>,
+    reason: Option<String>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+impl From<party_ban::Model> for PartyBanResponse {
+    fn from(ban: party_ban::Model) -> Self {
+        Self {
+            user_id: ban.user_id,
+            banned_by: ban.banned_by,
+            reason: ban.reason,
+            created_at: ban.created_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/parties/{id}/bans", get(list_bans).post(ban_user))
+        .route("/parties/{id}/bans/{user_id}", delete(unban_user))
+}
+
+/// List the users banned from a party (only by owner)
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/bans",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Bans retrieved successfully", body = Vec<PartyBanResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can manage bans", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_bans(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<PartyBanResponse>>, (StatusCode, String)> {
+    let party = find_managed_party(&state, id, &auth_user).await?;
+
+    let bans = PartyBan::find()
+        .filter(party_ban::Column::PartyId.eq(party.id))
+        .order_by_desc(party_ban::Column::CreatedAt)
+        .all(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(bans.into_iter().map(PartyBanResponse::from).collect()))
+}
+
+/// Ban a user from a party (only by owner)
+///
+/// A banned member is removed from the party and their connection to it is
+/// closed. Banned users can't join the party by code, invite link or invite,
+/// and their pending invites to it are dropped.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/bans",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = BanRequest,
+    responses(
+        (status = 200, description = "User banned successfully", body = PartyBanResponse),
+        (status = 400, description = "The owner can't be banned", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can manage bans", body = String),
+        (status = 404, description = "Party or user not found", body = String),
+        (status = 409, description = "User is already banned", body = String),
+        (status = 422, description = "Invalid reason", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn ban_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<BanRequest>,
+) -> Result<Json<PartyBanResponse>, Response> {
+    let db = &state.conn;
+
+    let reason = payload
+        .reason
+        .map(|reason| {
+            state
+                .validator
+                .text("reason", &reason, MAX_BAN_REASON_LENGTH)
+        })
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?
+        .filter(|reason| !reason.is_empty());
+
+    let party = find_managed_party(&state, id, &auth_user)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    if payload.user_id == party.owner_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The party owner can't be banned from their own party".to_string(),
+        )
+            .into_response());
+    }
+
+    let user = User::find_by_id(payload.user_id)
+        .filter(user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("User with id {} not found", payload.user_id),
+            )
+                .into_response()
+        })?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let ban = party_ban::ActiveModel {
+        party_id: Set(party.id),
+        user_id: Set(user.id),
+        banned_by: Set(Some(auth_user.0.sub)),
+        reason: Set(reason),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => (
+            StatusCode::CONFLICT,
+            "User is already banned from this party".to_string(),
+        )
+            .into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    })?;
+
+    let removed = parties::remove_member(&txn, party.id, user.id, auth_user.0.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    Invite::delete_many()
+        .filter(invite::Column::PartyId.eq(party.id))
+        .filter(invite::Column::InviteeId.eq(user.id))
+        .exec(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    if removed {
+        parties::disconnect_member(&state, user.id, party.id);
+    }
+
+    Ok(Json(ban.into()))
+}
+
+/// Lift the ban of a user from a party (only by owner)
+#[utoipa::path(
+    delete,
+    path = "/api/parties/{id}/bans/{user_id}",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID"),
+        ("user_id" = i32, Path, description = "User ID of the banned user")
+    ),
+    responses(
+        (status = 204, description = "Ban lifted successfully"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can manage bans", body = String),
+        (status = 404, description = "Party or ban not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unban_user(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let party = find_managed_party(&state, id, &auth_user).await?;
+
+    let deleted = PartyBan::delete_many()
+        .filter(party_ban::Column::PartyId.eq(party.id))
+        .filter(party_ban::Column::UserId.eq(user_id))
+        .exec(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not banned from this party", user_id),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helper function to find a party whose bans the user may manage
+async fn find_managed_party(
+    state: &AppState,
+    id: i32,
+    auth_user: &AuthUser,
+) -> Result<party::Model, (StatusCode, String)> {
+    let party = Party::find_by_id(id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can manage bans".to_string(),
+        ));
+    }
+
+    Ok(party)
+}
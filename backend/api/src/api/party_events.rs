@@ -0,0 +1,111 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::party::Entity as Party;
+use entity::party_event::{self, Entity as PartyEvent};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::pagination::{Paginated, PaginationParams};
+use crate::db::AppState;
+use crate::party_events::Kind;
+use crate::policy;
+
+#[derive(Serialize, ToSchema)]
+pub struct PartyEventResponse {
+    id: i32,
+    kind: Kind,
+    /// User the event happened to
+    user_id: i32,
+    /// User who made it happen, if that was someone else
+    actor_id: Option<i32>,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+impl From<party_event::Model> for PartyEventResponse {
+    fn from(event: party_event::Model) -> Self {
+        Self {
+            id: event.id,
+            kind: Kind::from_column(&event.kind),
+            user_id: event.user_id,
+            actor_id: event.actor_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/parties/{id}/events", get(list_party_events))
+}
+
+/// Get the membership history of a party, newest first (only by owner)
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/events",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID"),
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Party events retrieved successfully", body = Paginated<PartyEventResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can see its history", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_party_events(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(pagination): Query<PaginationParams>,
+    auth_user: AuthUser,
+) -> Result<Json<Paginated<PartyEventResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can see its history".to_string(),
+        ));
+    }
+
+    let paginator = PartyEvent::find()
+        .filter(party_event::Column::PartyId.eq(id))
+        .order_by_desc(party_event::Column::CreatedAt)
+        .order_by_desc(party_event::Column::Id)
+        .paginate(db, pagination.per_page());
+
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let events = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(Paginated::new(
+        events.into_iter().map(PartyEventResponse::from).collect(),
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
+}
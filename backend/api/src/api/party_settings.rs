@@ -0,0 +1,288 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use entity::party::Entity as Party;
+use entity::party_settings::{self, Entity as PartySettings};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, Set, sea_query::OnConflict};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::ws::WsMessage;
+use crate::db::AppState;
+use crate::policy;
+use crate::validation::{self, ValidationErrorResponse};
+
+// Limits of the race settings
+const MAX_LAPS: i32 = 20;
+const MAX_TIME_LIMIT: i32 = 3600; // in seconds
+const MAX_CHECKPOINT_FORGIVENESS: i32 = 50; // in meters
+const MAX_VEHICLE_CLASS_LENGTH: usize = 32;
+
+/// Race rules of a party
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct PartySettingsResponse {
+    laps: i32,
+    /// Vehicle class racers must use, or "any"
+    vehicle_class: String,
+    /// Whether cars collide with each other
+    collisions: bool,
+    /// Seconds until a race ends, or 0 for no limit
+    time_limit: i32,
+    /// Meters checkpoints count from beyond their usual radius
+    checkpoint_forgiveness: i32,
+    /// When the settings were last changed, if ever
+    updated_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl From<party_settings::Model> for PartySettingsResponse {
+    fn from(settings: party_settings::Model) -> Self {
+        Self {
+            laps: settings.laps,
+            vehicle_class: settings.vehicle_class,
+            collisions: settings.collisions,
+            time_limit: settings.time_limit,
+            checkpoint_forgiveness: settings.checkpoint_forgiveness,
+            updated_at: Some(settings.updated_at),
+        }
+    }
+}
+
+impl Default for PartySettingsResponse {
+    fn default() -> Self {
+        Self {
+            laps: 1,
+            vehicle_class: "any".to_string(),
+            collisions: true,
+            time_limit: 0,
+            checkpoint_forgiveness: 0,
+            updated_at: None,
+        }
+    }
+}
+
+impl PartySettingsResponse {
+    /// Meters checkpoints count from beyond their usual radius
+    pub fn checkpoint_forgiveness(&self) -> i32 {
+        self.checkpoint_forgiveness
+    }
+}
+
+/// Changes to the race rules of a party; fields left out keep their value
+#[derive(Deserialize, ToSchema)]
+pub struct UpdatePartySettingsRequest {
+    /// Between 1 and 20
+    laps: Option<i32>,
+    vehicle_class: Option<String>,
+    collisions: Option<bool>,
+    /// At most 3600 seconds, or 0 for no limit
+    time_limit: Option<i32>,
+    /// At most 50 meters
+    checkpoint_forgiveness: Option<i32>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/parties/{id}/settings",
+        get(get_party_settings).patch(update_party_settings),
+    )
+}
+
+/// Get the race settings of a party
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/settings",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Party settings retrieved successfully", body = PartySettingsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_party_settings(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<PartySettingsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let _ = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    let settings = load(db, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(settings))
+}
+
+/// Change the race settings of a party (only by owner)
+///
+/// Connected members are sent the new settings over the websocket.
+#[utoipa::path(
+    patch,
+    path = "/api/parties/{id}/settings",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = UpdatePartySettingsRequest,
+    responses(
+        (status = 200, description = "Party settings updated successfully", body = PartySettingsResponse),
+        (status = 400, description = "Setting out of range", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can change the settings", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 422, description = "Invalid vehicle class", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_party_settings(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdatePartySettingsRequest>,
+) -> Result<Json<PartySettingsResponse>, Response> {
+    let db = &state.conn;
+
+    // Verify the party exists
+    let party = Party::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Party with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    // Verify the user may manage the party
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can change the settings".to_string(),
+        )
+            .into_response());
+    }
+
+    let laps =
+        check_range("laps", payload.laps, 1, MAX_LAPS).map_err(IntoResponse::into_response)?;
+    let time_limit = check_range("time_limit", payload.time_limit, 0, MAX_TIME_LIMIT)
+        .map_err(IntoResponse::into_response)?;
+    let checkpoint_forgiveness = check_range(
+        "checkpoint_forgiveness",
+        payload.checkpoint_forgiveness,
+        0,
+        MAX_CHECKPOINT_FORGIVENESS,
+    )
+    .map_err(IntoResponse::into_response)?;
+    let vehicle_class = payload
+        .vehicle_class
+        .map(|vehicle_class| {
+            state
+                .validator
+                .text("vehicle_class", &vehicle_class, MAX_VEHICLE_CLASS_LENGTH)
+        })
+        .transpose()
+        .map_err(|e| validation::invalid(vec![e]))?;
+
+    // The first update creates the settings with the defaults
+    let current = load(db, party.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let settings = party_settings::ActiveModel {
+        party_id: Set(party.id),
+        laps: Set(laps.unwrap_or(current.laps)),
+        vehicle_class: Set(vehicle_class.unwrap_or(current.vehicle_class)),
+        collisions: Set(payload.collisions.unwrap_or(current.collisions)),
+        time_limit: Set(time_limit.unwrap_or(current.time_limit)),
+        checkpoint_forgiveness: Set(
+            checkpoint_forgiveness.unwrap_or(current.checkpoint_forgiveness)
+        ),
+        updated_at: Set(chrono::Utc::now().fixed_offset()),
+    };
+
+    let settings: PartySettingsResponse = PartySettings::insert(settings)
+        .on_conflict(
+            OnConflict::column(party_settings::Column::PartyId)
+                .update_columns([
+                    party_settings::Column::Laps,
+                    party_settings::Column::VehicleClass,
+                    party_settings::Column::Collisions,
+                    party_settings::Column::TimeLimit,
+                    party_settings::Column::CheckpointForgiveness,
+                    party_settings::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_with_returning(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .into();
+
+    // Let connected members know
+    let channel = state.party_channels.lock().unwrap().get(&party.id).cloned();
+
+    if let Some(channel) = channel {
+        let settings_msg = serde_json::to_string(&WsMessage::PartySettingsChanged {
+            party_id: party.id,
+            settings: settings.clone(),
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+        let _ = channel.send(settings_msg);
+    }
+
+    Ok(Json(settings))
+}
+
+/// Get the race settings of a party, or the defaults if it has none
+pub async fn load<C: ConnectionTrait>(
+    db: &C,
+    party_id: i32,
+) -> Result<PartySettingsResponse, DbErr> {
+    let settings = PartySettings::find_by_id(party_id).one(db).await?;
+
+    Ok(settings
+        .map(PartySettingsResponse::from)
+        .unwrap_or_default())
+}
+
+// Helper function to check that a setting is within its limits
+fn check_range(
+    field: &str,
+    value: Option<i32>,
+    min: i32,
+    max: i32,
+) -> Result<Option<i32>, (StatusCode, String)> {
+    match value {
+        Some(value) if !(min..=max).contains(&value) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} must be between {} and {}", field, min, max),
+        )),
+        _ => Ok(value),
+    }
+}
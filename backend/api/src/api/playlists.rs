@@ -0,0 +1,465 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use chrono::DateTime;
+use entity::map::{self, Entity as Map};
+use entity::playlist::{self, Entity as Playlist};
+use entity::playlist_map::{self, Entity as PlaylistMap};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+use super::maps::{MapResponse, attach_authors, attach_tags, mark_favorites};
+use crate::db::AppState;
+use crate::policy;
+
+// Points for the places of a race in playlist standings, from the winner on
+const PLACE_POINTS: [i32; 8] = [10, 8, 6, 5, 4, 3, 2, 1];
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePlaylistRequest {
+    name: String,
+    /// Maps in the order they are raced
+    map_ids: Vec<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdatePlaylistRequest {
+    name: Option<String>,
+    /// Replaces the maps of the playlist, in the order they are raced
+    map_ids: Option<Vec<i32>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PlaylistResponse {
+    id: i32,
+    name: String,
+    author_id: i32,
+    created_at: DateTime<chrono::FixedOffset>,
+}
+
+impl From<playlist::Model> for PlaylistResponse {
+    fn from(playlist: playlist::Model) -> Self {
+        Self {
+            id: playlist.id,
+            name: playlist.name,
+            author_id: playlist.author_id,
+            created_at: playlist.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PlaylistWithMapsResponse {
+    playlist: PlaylistResponse,
+    /// Maps in the order they are raced
+    maps: Vec<MapResponse>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/playlists", get(list_playlists))
+        .route("/playlists", post(create_playlist))
+        .route("/playlists/{id}", get(get_playlist))
+        .route("/playlists/{id}", post(update_playlist))
+        .route("/playlists/{id}", delete(delete_playlist))
+}
+
+/// List all playlists
+#[utoipa::path(
+    get,
+    path = "/api/playlists",
+    tag = "playlists",
+    responses(
+        (status = 200, description = "List of playlists retrieved successfully", body = Vec<PlaylistResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_playlists(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PlaylistResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let playlists = Playlist::find()
+        .order_by_asc(playlist::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        playlists.into_iter().map(PlaylistResponse::from).collect(),
+    ))
+}
+
+/// Get a playlist with its maps
+#[utoipa::path(
+    get,
+    path = "/api/playlists/{id}",
+    tag = "playlists",
+    params(
+        ("id" = i32, Path, description = "Playlist ID")
+    ),
+    responses(
+        (status = 200, description = "Playlist found", body = PlaylistWithMapsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Playlist not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_playlist(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<PlaylistWithMapsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let playlist = Playlist::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Playlist with id {} not found", id),
+        ))?;
+
+    let maps = playlist_maps(id, db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: Vec<MapResponse> = maps.into_iter().map(MapResponse::from).collect();
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PlaylistWithMapsResponse {
+        playlist: playlist.into(),
+        maps,
+    }))
+}
+
+/// Create a new playlist
+#[utoipa::path(
+    post,
+    path = "/api/playlists",
+    tag = "playlists",
+    request_body = CreatePlaylistRequest,
+    responses(
+        (status = 200, description = "Playlist created successfully", body = PlaylistWithMapsResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_playlist(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<CreatePlaylistRequest>,
+) -> Result<Json<PlaylistWithMapsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    // Start a transaction
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Create the playlist
+    let new_playlist = playlist::ActiveModel {
+        name: Set(payload.name),
+        author_id: Set(auth_user.0.sub),
+        ..Default::default()
+    };
+
+    let playlist = new_playlist
+        .insert(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let maps = set_playlist_maps(playlist.id, &payload.map_ids, &txn).await?;
+
+    // Commit transaction
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: Vec<MapResponse> = maps.into_iter().map(MapResponse::from).collect();
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PlaylistWithMapsResponse {
+        playlist: playlist.into(),
+        maps,
+    }))
+}
+
+/// Update a playlist (only by its author)
+#[utoipa::path(
+    post,
+    path = "/api/playlists/{id}",
+    tag = "playlists",
+    params(
+        ("id" = i32, Path, description = "Playlist ID")
+    ),
+    request_body = UpdatePlaylistRequest,
+    responses(
+        (status = 200, description = "Playlist updated successfully", body = PlaylistWithMapsResponse),
+        (status = 400, description = "Invalid request", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the playlist author can update the playlist", body = String),
+        (status = 404, description = "Playlist not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_playlist(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdatePlaylistRequest>,
+) -> Result<Json<PlaylistWithMapsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let playlist = Playlist::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Playlist with id {} not found", id),
+        ))?;
+
+    // Verify the user may edit the playlist
+    if !policy::can_edit_playlist(&auth_user.0, &playlist) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the playlist author can update the playlist".to_string(),
+        ));
+    }
+
+    // Start a transaction
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut playlist_model: playlist::ActiveModel = playlist.into();
+
+    if let Some(name) = payload.name {
+        playlist_model.name = Set(name);
+    }
+
+    let playlist = playlist_model
+        .update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(map_ids) = payload.map_ids {
+        // Replace the existing maps
+        PlaylistMap::delete_many()
+            .filter(playlist_map::Column::PlaylistId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        set_playlist_maps(id, &map_ids, &txn).await?;
+    }
+
+    let maps = playlist_maps(id, &txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Commit transaction
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut maps: Vec<MapResponse> = maps.into_iter().map(MapResponse::from).collect();
+    mark_favorites(db, auth_user.0.sub, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_tags(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_authors(db, &mut maps)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PlaylistWithMapsResponse {
+        playlist: playlist.into(),
+        maps,
+    }))
+}
+
+/// Delete a playlist (only by its author)
+///
+/// Parties racing through the playlist keep their current map.
+#[utoipa::path(
+    delete,
+    path = "/api/playlists/{id}",
+    tag = "playlists",
+    params(
+        ("id" = i32, Path, description = "Playlist ID")
+    ),
+    responses(
+        (status = 204, description = "Playlist deleted successfully"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the playlist author can delete the playlist", body = String),
+        (status = 404, description = "Playlist not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_playlist(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let playlist = Playlist::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Playlist with id {} not found", id),
+        ))?;
+
+    // Verify the user may edit the playlist
+    if !policy::can_edit_playlist(&auth_user.0, &playlist) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the playlist author can delete the playlist".to_string(),
+        ));
+    }
+
+    // Playlist maps are removed and parties detached by the foreign keys
+    Playlist::delete_by_id(id)
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Points a place in a race is worth towards playlist standings
+pub fn points_for_place(place: i32) -> i32 {
+    usize::try_from(place - 1)
+        .ok()
+        .and_then(|index| PLACE_POINTS.get(index))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Get the maps of a playlist in the order they are raced
+pub async fn playlist_maps<C: ConnectionTrait>(
+    playlist_id: i32,
+    db: &C,
+) -> Result<Vec<map::Model>, DbErr> {
+    let entries = PlaylistMap::find()
+        .filter(playlist_map::Column::PlaylistId.eq(playlist_id))
+        .order_by_asc(playlist_map::Column::Position)
+        .all(db)
+        .await?;
+
+    let map_ids: Vec<i32> = entries.iter().map(|entry| entry.map_id).collect();
+    // Deleted maps drop out of the playlist until they are restored
+    let maps: HashMap<i32, map::Model> = Map::find()
+        .filter(map::Column::Id.is_in(map_ids))
+        .filter(map::Column::DeletedAt.is_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect();
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| maps.get(&entry.map_id).cloned())
+        .collect())
+}
+
+// Helper function to insert the maps of a playlist in order
+async fn set_playlist_maps<C: ConnectionTrait>(
+    playlist_id: i32,
+    map_ids: &[i32],
+    db: &C,
+) -> Result<Vec<map::Model>, (StatusCode, String)> {
+    if map_ids.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A playlist needs at least one map".to_string(),
+        ));
+    }
+
+    // Verify all maps exist
+    let unique_ids: HashSet<i32> = map_ids.iter().copied().collect();
+    let maps: HashMap<i32, map::Model> = Map::find()
+        .filter(map::Column::Id.is_in(unique_ids))
+        .filter(map::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect();
+
+    let mut ordered = Vec::with_capacity(map_ids.len());
+    for (position, map_id) in map_ids.iter().enumerate() {
+        let map = maps.get(map_id).cloned().ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Map with id {} not found", map_id),
+        ))?;
+
+        let entry = playlist_map::ActiveModel {
+            playlist_id: Set(playlist_id),
+            map_id: Set(*map_id),
+            position: Set(position as i32),
+            ..Default::default()
+        };
+
+        entry
+            .insert(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        ordered.push(map);
+    }
+
+    Ok(ordered)
+}
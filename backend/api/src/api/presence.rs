@@ -0,0 +1,137 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::blocking;
+use crate::db::{AppState, UserId};
+use crate::settings;
+
+// Most users whose presence can be queried at once
+const MAX_QUERY_USERS: usize = 100;
+
+#[derive(Deserialize, ToSchema)]
+pub struct PresenceQueryRequest {
+    user_ids: Vec<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PresenceResponse {
+    user_id: i32,
+    /// Whether the user is connected to the game
+    online: bool,
+    /// The party the user is connected to, if any
+    party_id: Option<i32>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/users/{id}/presence", get(get_presence))
+        .route("/presence/query", post(query_presence))
+}
+
+/// Get whether a user is online and in which party
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/presence",
+    tag = "presence",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Presence retrieved successfully", body = PresenceResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_presence(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<PresenceResponse>, (StatusCode, String)> {
+    let mut presences = presences(&state, auth_user.0.sub, vec![id]).await?;
+
+    Ok(Json(presences.remove(0)))
+}
+
+/// Get whether several users are online and in which party
+#[utoipa::path(
+    post,
+    path = "/api/presence/query",
+    tag = "presence",
+    request_body = PresenceQueryRequest,
+    responses(
+        (status = 200, description = "Presences retrieved successfully", body = Vec<PresenceResponse>),
+        (status = 400, description = "Too many users", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn query_presence(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<PresenceQueryRequest>,
+) -> Result<Json<Vec<PresenceResponse>>, (StatusCode, String)> {
+    let mut user_ids = payload.user_ids;
+    user_ids.sort_unstable();
+    user_ids.dedup();
+
+    if user_ids.len() > MAX_QUERY_USERS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("At most {} users can be queried at once", MAX_QUERY_USERS),
+        ));
+    }
+
+    Ok(Json(presences(&state, auth_user.0.sub, user_ids).await?))
+}
+
+// Helper function to look up the presence of users. Users who blocked the
+// current user, or who chose to appear offline, always appear offline to them.
+async fn presences(
+    state: &AppState,
+    current_user_id: UserId,
+    user_ids: Vec<UserId>,
+) -> Result<Vec<PresenceResponse>, (StatusCode, String)> {
+    let blocker_ids = blocking::blocker_ids(&state.conn, current_user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut hidden_ids = settings::appearing_offline_ids(&state.conn, &user_ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    hidden_ids.remove(&current_user_id);
+    hidden_ids.extend(blocker_ids);
+
+    let online = state
+        .presence
+        .query(&user_ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(user_ids
+        .into_iter()
+        .map(|user_id| {
+            let presence = online
+                .get(&user_id)
+                .filter(|_| !hidden_ids.contains(&user_id));
+
+            PresenceResponse {
+                user_id,
+                online: presence.is_some(),
+                party_id: presence.copied().flatten(),
+            }
+        })
+        .collect())
+}
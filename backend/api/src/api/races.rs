@@ -0,0 +1,725 @@
+use auth::middleware::AuthUser;
+use auth::validation::{FieldError, ValidationCode};
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use entity::map::Entity as Map;
+use entity::race::Entity as Race;
+use entity::race_award::{self, Entity as RaceAward};
+use entity::race_participant::{self, Entity as RaceParticipant};
+use entity::race_replay::{self, Entity as RaceReplay};
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    sea_query::{NullOrdering, OnConflict, Order},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::users::UserResponse;
+use crate::awards::AwardKind;
+use crate::db::AppState;
+use crate::race::RacerResult;
+use crate::replays;
+use crate::splits;
+use crate::validation::{self, ValidationErrorResponse};
+
+// How far a submitted finish time may be from the one the server measured,
+// as positions arrive a little late
+const FINISH_TIME_TOLERANCE_MS: i64 = 2_000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct SubmitRaceResultRequest {
+    /// When the racer crossed the finish line since the start, absent if
+    /// they didn't finish
+    finish_time_ms: Option<i64>,
+    /// When the racer passed each checkpoint since the start, in order,
+    /// absent for optional checkpoints left out
+    #[serde(default)]
+    splits_ms: Vec<Option<i64>>,
+}
+
+/// How a racer did in a race
+#[derive(Serialize, ToSchema)]
+pub struct RaceResultResponse {
+    user: UserResponse,
+    /// Place among the racers who finished
+    position: Option<i32>,
+    /// Finish time measured by the server
+    finish_time_ms: Option<i64>,
+    /// Gap to the winner's finish time
+    gap_ms: Option<i64>,
+    /// Whether the racer didn't finish
+    dnf: bool,
+    /// When the racer passed each checkpoint since the start, absent for
+    /// optional checkpoints left out
+    splits_ms: Vec<Option<i64>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RaceResultsResponse {
+    race_id: i32,
+    map_id: i32,
+    party_id: Option<i32>,
+    started_at: DateTime<FixedOffset>,
+    /// When the race ended, absent while it runs
+    ended_at: Option<DateTime<FixedOffset>>,
+    /// Racers who finished by place, then everyone else
+    results: Vec<RaceResultResponse>,
+    /// Awards handed out when the race ended
+    awards: Vec<RaceAwardResponse>,
+}
+
+/// An award a racer earned in a race
+#[derive(Serialize, ToSchema)]
+pub struct RaceAwardResponse {
+    kind: AwardKind,
+    user: UserResponse,
+    /// Number of legs, checkpoints or places, or the spread of the racer's
+    /// pace in thousandths for the most consistent award
+    value: i64,
+}
+
+/// Where a racer was at a point in a race
+#[derive(Serialize, ToSchema)]
+pub struct ReplayFrameResponse {
+    /// Time since the start of the race
+    time_ms: u32,
+    /// x, y and z, as sent in updates
+    position: [f32; 3],
+    /// Yaw, pitch and roll
+    rotation: [f32; 3],
+}
+
+impl From<replays::Frame> for ReplayFrameResponse {
+    fn from(frame: replays::Frame) -> Self {
+        Self {
+            time_ms: frame.time_ms,
+            position: frame.position,
+            rotation: frame.rotation,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RacerReplayResponse {
+    user: UserResponse,
+    /// Positions of the racer in order
+    frames: Vec<ReplayFrameResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RaceReplayResponse {
+    race_id: i32,
+    map_id: i32,
+    /// Least time between two frames of a racer
+    sample_interval_ms: i32,
+    racers: Vec<RacerReplayResponse>,
+}
+
+/// When a racer passed each checkpoint of a race
+#[derive(Serialize, ToSchema)]
+pub struct RacerSplitsResponse {
+    user: UserResponse,
+    /// Place among the racers who finished
+    position: Option<i32>,
+    finish_time_ms: Option<i64>,
+    /// Time since the start at each checkpoint, absent for checkpoints left
+    /// out or not reached yet
+    splits_ms: Vec<Option<i64>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RaceSplitsResponse {
+    race_id: i32,
+    map_id: i32,
+    checkpoint_count: i32,
+    /// Whether the race still runs, in which case the splits are live
+    live: bool,
+    /// Racers who finished by place, then everyone else
+    racers: Vec<RacerSplitsResponse>,
+    /// Earliest split of any racer at each checkpoint, to compare against
+    best_splits_ms: Vec<Option<i64>>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/races/{id}/results",
+            get(get_race_results).post(submit_race_result),
+        )
+        .route("/races/{id}/splits", get(get_race_splits))
+        .route("/races/{id}/replay", get(get_race_replay))
+}
+
+/// Submit the current user's result of a race
+///
+/// Racers submit once, while the race runs or after it ended. The finish
+/// time must match the one the server measured from the racer's positions,
+/// which is the one kept. So are the splits the server measured, and the
+/// submitted ones are only kept when the server has none.
+#[utoipa::path(
+    post,
+    path = "/api/races/{id}/results",
+    tag = "races",
+    params(
+        ("id" = i32, Path, description = "Race ID")
+    ),
+    request_body = SubmitRaceResultRequest,
+    responses(
+        (status = 200, description = "Result submitted successfully", body = RaceResultResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "The user didn't take part in the race", body = String),
+        (status = 404, description = "Race not found", body = String),
+        (status = 409, description = "The user already submitted their result", body = String),
+        (status = 422, description = "Splits or finish time don't match the race", body = ValidationErrorResponse),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn submit_race_result(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<SubmitRaceResultRequest>,
+) -> Result<Json<RaceResultResponse>, Response> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let race = Race::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Race with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    let participant = RaceParticipant::find()
+        .filter(race_participant::Column::RaceId.eq(race.id))
+        .filter(race_participant::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    if participant
+        .as_ref()
+        .is_some_and(|participant| participant.submitted_at.is_some())
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            "You already submitted your result".to_string(),
+        )
+            .into_response());
+    }
+
+    // What the server measured, from memory while the race runs
+    let active_result = race.party_id.and_then(|party_id| {
+        state
+            .active_races
+            .lock()
+            .unwrap()
+            .get(&party_id)
+            .filter(|active| active.race_id == race.id)
+            .map(|active| {
+                let winner_time_ms = active
+                    .standings()
+                    .first()
+                    .map(|standing| standing.finish_time_ms as i64);
+                (active.result_of(user_id), winner_time_ms)
+            })
+    });
+    let winner_time_ms = match active_result {
+        Some((_, winner_time_ms)) => winner_time_ms,
+        None => RaceParticipant::find()
+            .select_only()
+            .column_as(
+                race_participant::Column::FinishTimeMs.min(),
+                "winner_time_ms",
+            )
+            .filter(race_participant::Column::RaceId.eq(race.id))
+            .into_tuple::<Option<i64>>()
+            .one(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+            .flatten(),
+    };
+    let measured = match active_result {
+        Some((result, _)) => result,
+        None => participant.as_ref().map(|participant| RacerResult {
+            user_id,
+            place: participant.position.map(|position| position as usize),
+            finish_time_ms: participant.finish_time_ms.map(|time| time as u64),
+            splits_ms: participant
+                .splits
+                .as_deref()
+                .map(splits::decode)
+                .unwrap_or_default(),
+        }),
+    };
+    let Some(measured) = measured else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You didn't take part in this race".to_string(),
+        )
+            .into_response());
+    };
+
+    let map = Map::find_by_id(race.map_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Race with id {} not found", id),
+            )
+                .into_response()
+        })?;
+
+    let errors = check_result(&payload, &measured, map.checkpoint_count as usize);
+    if !errors.is_empty() {
+        return Err(validation::invalid(errors));
+    }
+
+    let dnf = payload.finish_time_ms.is_none();
+    let splits_ms: Vec<Option<i64>> = if measured.splits_ms.is_empty() {
+        payload.splits_ms
+    } else {
+        measured
+            .splits_ms
+            .iter()
+            .map(|split| split.map(|time| time as i64))
+            .collect()
+    };
+    let submitted = race_participant::ActiveModel {
+        race_id: Set(race.id),
+        user_id: Set(user_id),
+        finish_time_ms: Set(measured.finish_time_ms.map(|time| time as i64)),
+        position: Set(measured.place.map(|place| place as i32)),
+        dnf: Set(dnf),
+        splits: Set(Some(splits::encode(&to_u64_splits(&splits_ms)))),
+        submitted_at: Set(Some(Utc::now().fixed_offset())),
+        ..Default::default()
+    };
+    RaceParticipant::insert(submitted)
+        .on_conflict(
+            OnConflict::columns([
+                race_participant::Column::RaceId,
+                race_participant::Column::UserId,
+            ])
+            .update_columns([
+                race_participant::Column::Splits,
+                race_participant::Column::SubmittedAt,
+            ])
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let user = User::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found".to_string()).into_response())?;
+
+    Ok(Json(RaceResultResponse {
+        user: user.into(),
+        position: measured.place.map(|place| place as i32),
+        finish_time_ms: measured.finish_time_ms.map(|time| time as i64),
+        gap_ms: gap_ms(
+            measured.finish_time_ms.map(|time| time as i64),
+            winner_time_ms,
+        ),
+        dnf: participant.map_or(dnf, |participant| participant.dnf),
+        splits_ms,
+    }))
+}
+
+/// Get the results of a race
+#[utoipa::path(
+    get,
+    path = "/api/races/{id}/results",
+    tag = "races",
+    params(
+        ("id" = i32, Path, description = "Race ID")
+    ),
+    responses(
+        (status = 200, description = "Results retrieved successfully", body = RaceResultsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Race not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_race_results(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<RaceResultsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let race = Race::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Race with id {} not found", id),
+        ))?;
+
+    let participants = RaceParticipant::find()
+        .filter(race_participant::Column::RaceId.eq(race.id))
+        .find_also_related(User)
+        .order_by_with_nulls(
+            race_participant::Column::Position,
+            Order::Asc,
+            NullOrdering::Last,
+        )
+        .order_by_asc(race_participant::Column::UserId)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let winner_time_ms = participants
+        .iter()
+        .filter_map(|(participant, _)| participant.finish_time_ms)
+        .min();
+    let results = participants
+        .into_iter()
+        .filter_map(|(participant, user)| {
+            Some(RaceResultResponse {
+                user: user?.into(),
+                position: participant.position,
+                finish_time_ms: participant.finish_time_ms,
+                gap_ms: gap_ms(participant.finish_time_ms, winner_time_ms),
+                dnf: participant.dnf,
+                splits_ms: participant
+                    .splits
+                    .as_deref()
+                    .map(to_i64_splits)
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    let awards = RaceAward::find()
+        .filter(race_award::Column::RaceId.eq(race.id))
+        .find_also_related(User)
+        .order_by_asc(race_award::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .filter_map(|(award, user)| {
+            Some(RaceAwardResponse {
+                kind: AwardKind::from_column(&award.kind)?,
+                user: user?.into(),
+                value: award.value,
+            })
+        })
+        .collect();
+
+    Ok(Json(RaceResultsResponse {
+        race_id: race.id,
+        map_id: race.map_id,
+        party_id: race.party_id,
+        started_at: race.started_at,
+        ended_at: race.ended_at,
+        results,
+        awards,
+    }))
+}
+
+/// Get when each racer of a race passed its checkpoints
+///
+/// Splits are live while the race runs, so clients can show how far ahead or
+/// behind racers are at each checkpoint.
+#[utoipa::path(
+    get,
+    path = "/api/races/{id}/splits",
+    tag = "races",
+    params(
+        ("id" = i32, Path, description = "Race ID")
+    ),
+    responses(
+        (status = 200, description = "Splits retrieved successfully", body = RaceSplitsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Race not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_race_splits(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<RaceSplitsResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let (race, map) = Race::find_by_id(id)
+        .find_also_related(Map)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .and_then(|(race, map)| Some((race, map?)))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Race with id {} not found", id),
+        ))?;
+    let checkpoint_count = map.checkpoint_count.max(0) as usize;
+
+    // What the server measured so far, from memory while the race runs
+    let live_results = match (race.ended_at, race.party_id) {
+        (None, Some(party_id)) => state
+            .active_races
+            .lock()
+            .unwrap()
+            .get(&party_id)
+            .filter(|active| active.race_id == race.id)
+            .map(|active| active.results()),
+        _ => None,
+    };
+    let live = live_results.is_some();
+
+    let results = match live_results {
+        Some(results) => results,
+        None => RaceParticipant::find()
+            .filter(race_participant::Column::RaceId.eq(race.id))
+            .order_by_with_nulls(
+                race_participant::Column::Position,
+                Order::Asc,
+                NullOrdering::Last,
+            )
+            .order_by_asc(race_participant::Column::UserId)
+            .all(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .into_iter()
+            .map(|participant| RacerResult {
+                user_id: participant.user_id,
+                place: participant.position.map(|position| position as usize),
+                finish_time_ms: participant.finish_time_ms.map(|time| time as u64),
+                splits_ms: participant
+                    .splits
+                    .as_deref()
+                    .map(splits::decode)
+                    .unwrap_or_default(),
+            })
+            .collect(),
+    };
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(results.iter().map(|result| result.user_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let mut best_splits_ms: Vec<Option<i64>> = vec![None; checkpoint_count];
+    let racers = results
+        .into_iter()
+        .filter_map(|result| {
+            let mut splits_ms: Vec<Option<i64>> = result
+                .splits_ms
+                .iter()
+                .map(|split| split.map(|time| time as i64))
+                .collect();
+            splits_ms.resize(checkpoint_count, None);
+            for (best, split) in best_splits_ms.iter_mut().zip(&splits_ms) {
+                if let Some(split) = *split {
+                    *best = Some(best.map_or(split, |best| best.min(split)));
+                }
+            }
+
+            Some(RacerSplitsResponse {
+                user: users.remove(&result.user_id)?.into(),
+                position: result.place.map(|place| place as i32),
+                finish_time_ms: result.finish_time_ms.map(|time| time as i64),
+                splits_ms,
+            })
+        })
+        .collect();
+
+    Ok(Json(RaceSplitsResponse {
+        race_id: race.id,
+        map_id: race.map_id,
+        checkpoint_count: checkpoint_count as i32,
+        live,
+        racers,
+        best_splits_ms,
+    }))
+}
+
+/// Get the replay the server recorded of a race
+///
+/// Replays are recorded if the server is configured to, and saved when the
+/// race ends. Long races only keep the last positions of each racer.
+#[utoipa::path(
+    get,
+    path = "/api/races/{id}/replay",
+    tag = "races",
+    params(
+        ("id" = i32, Path, description = "Race ID")
+    ),
+    responses(
+        (status = 200, description = "Replay retrieved successfully", body = RaceReplayResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "Race or replay not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_race_replay(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<RaceReplayResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let race = Race::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Race with id {} not found", id),
+        ))?;
+
+    let replays = RaceReplay::find()
+        .filter(race_replay::Column::RaceId.eq(race.id))
+        .find_also_related(User)
+        .order_by_asc(race_replay::Column::UserId)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let Some(sample_interval_ms) = replays.first().map(|(replay, _)| replay.sample_interval_ms)
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No replay was recorded of race {}", id),
+        ));
+    };
+
+    let racers = replays
+        .into_iter()
+        .filter_map(|(replay, user)| {
+            Some(RacerReplayResponse {
+                user: user?.into(),
+                frames: replays::decode(&replay.frames)
+                    .into_iter()
+                    .map(ReplayFrameResponse::from)
+                    .collect(),
+            })
+        })
+        .collect();
+
+    Ok(Json(RaceReplayResponse {
+        race_id: race.id,
+        map_id: race.map_id,
+        sample_interval_ms,
+        racers,
+    }))
+}
+
+// Helper function to get how far a finish time is behind the winner's
+fn gap_ms(finish_time_ms: Option<i64>, winner_time_ms: Option<i64>) -> Option<i64> {
+    Some((finish_time_ms? - winner_time_ms?).max(0))
+}
+
+// Helper function to decode stored splits for responses
+fn to_i64_splits(bytes: &[u8]) -> Vec<Option<i64>> {
+    splits::decode(bytes)
+        .into_iter()
+        .map(|split| split.map(|time| time as i64))
+        .collect()
+}
+
+// Helper function to get checked splits ready to be encoded
+fn to_u64_splits(splits_ms: &[Option<i64>]) -> Vec<Option<u64>> {
+    splits_ms
+        .iter()
+        .map(|split| split.map(|time| time.max(0) as u64))
+        .collect()
+}
+
+// Helper function to check a submitted result against the course and what
+// the server measured. Splits count up from the start, one per checkpoint at
+// most, with gaps for optional checkpoints left out, and come before the
+// finish.
+fn check_result(
+    payload: &SubmitRaceResultRequest,
+    measured: &RacerResult,
+    checkpoint_count: usize,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let present: Vec<i64> = payload.splits_ms.iter().flatten().copied().collect();
+
+    if payload.splits_ms.len() > checkpoint_count {
+        errors.push(FieldError {
+            field: "splits_ms".to_string(),
+            code: ValidationCode::Length,
+            message: format!("Must have at most {} splits", checkpoint_count),
+        });
+    }
+    if present.first().is_some_and(|split| *split < 0)
+        || present.windows(2).any(|pair| pair[0] >= pair[1])
+    {
+        errors.push(FieldError {
+            field: "splits_ms".to_string(),
+            code: ValidationCode::Order,
+            message: "Must count up from the start".to_string(),
+        });
+    }
+
+    match (payload.finish_time_ms, measured.finish_time_ms) {
+        (Some(finish_time_ms), Some(measured_ms)) => {
+            if (finish_time_ms - measured_ms as i64).abs() > FINISH_TIME_TOLERANCE_MS {
+                errors.push(FieldError {
+                    field: "finish_time_ms".to_string(),
+                    code: ValidationCode::Range,
+                    message: format!(
+                        "Must be within {} ms of the time the server measured",
+                        FINISH_TIME_TOLERANCE_MS
+                    ),
+                });
+            }
+            if present.last().is_some_and(|split| *split >= finish_time_ms) {
+                errors.push(FieldError {
+                    field: "splits_ms".to_string(),
+                    code: ValidationCode::Order,
+                    message: "Must come before the finish".to_string(),
+                });
+            }
+        }
+        (Some(_), None) => errors.push(FieldError {
+            field: "finish_time_ms".to_string(),
+            code: ValidationCode::Range,
+            message: "The server didn't see you finish".to_string(),
+        }),
+        (None, Some(_)) => errors.push(FieldError {
+            field: "finish_time_ms".to_string(),
+            code: ValidationCode::Range,
+            message: "The server saw you finish".to_string(),
+        }),
+        (None, None) => {}
+    }
+
+    errors
+}
@@ -0,0 +1,184 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::{
+    map::{self, Entity as Map},
+    party::{self, Entity as Party},
+    recent_player::{self, Entity as RecentPlayer},
+    user::{self, Entity as User},
+    user_party::{self, Entity as UserParty},
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::users::UserResponse;
+use crate::blocking;
+use crate::db::AppState;
+
+// How many recent players are listed
+const MAX_RECENT_PLAYERS: u64 = 50;
+
+#[derive(Serialize, ToSchema)]
+pub struct RecentPlayerResponse {
+    user: UserResponse,
+    races_together: i32,
+    last_played_at: chrono::DateTime<chrono::FixedOffset>,
+    /// Party of the last shared race, unless it was disbanded since
+    last_party: Option<RecentPartyResponse>,
+    /// Map of the last shared race, unless it was deleted since
+    last_map: Option<RecentMapResponse>,
+    /// Parties both users are members of right now
+    mutual_party_ids: Vec<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecentPartyResponse {
+    id: i32,
+    name: String,
+    code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecentMapResponse {
+    id: i32,
+    title: String,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/users/me/recent-players", get(list_recent_players))
+}
+
+/// List the players the current user raced with most recently
+#[utoipa::path(
+    get,
+    path = "/api/users/me/recent-players",
+    tag = "users",
+    responses(
+        (status = 200, description = "Recent players retrieved successfully", body = Vec<RecentPlayerResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_recent_players(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<RecentPlayerResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    // Blocks in either direction hide the other player
+    let mut hidden_ids = blocking::blocked_ids(db, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    hidden_ids.extend(
+        blocking::blocker_ids(db, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+    );
+
+    let recent_players = RecentPlayer::find()
+        .filter(recent_player::Column::UserId.eq(user_id))
+        .filter(recent_player::Column::OtherUserId.is_not_in(hidden_ids))
+        .order_by_desc(recent_player::Column::LastPlayedAt)
+        .limit(MAX_RECENT_PLAYERS)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let other_ids: Vec<i32> = recent_players
+        .iter()
+        .map(|recent| recent.other_user_id)
+        .collect();
+
+    let mut users: HashMap<i32, user::Model> = User::find()
+        .filter(user::Column::Id.is_in(other_ids.clone()))
+        .filter(user::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let parties: HashMap<i32, party::Model> = Party::find()
+        .filter(party::Column::Id.is_in(recent_players.iter().filter_map(|r| r.last_party_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|party| (party.id, party))
+        .collect();
+
+    let maps: HashMap<i32, map::Model> = Map::find()
+        .filter(map::Column::Id.is_in(recent_players.iter().filter_map(|r| r.last_map_id)))
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect();
+
+    // Parties of the current user the other players are in as well
+    let own_party_ids: Vec<i32> = UserParty::find()
+        .select_only()
+        .column(user_party::Column::PartyId)
+        .filter(user_party::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut mutual_parties: HashMap<i32, Vec<i32>> = HashMap::new();
+    let shared_memberships = UserParty::find()
+        .filter(user_party::Column::UserId.is_in(other_ids))
+        .filter(user_party::Column::PartyId.is_in(own_party_ids))
+        .order_by_asc(user_party::Column::PartyId)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    for membership in shared_memberships {
+        mutual_parties
+            .entry(membership.user_id)
+            .or_default()
+            .push(membership.party_id);
+    }
+
+    let recent_players = recent_players
+        .into_iter()
+        .filter_map(|recent| {
+            Some(RecentPlayerResponse {
+                user: users.remove(&recent.other_user_id)?.into(),
+                races_together: recent.races_together,
+                last_played_at: recent.last_played_at,
+                last_party: recent
+                    .last_party_id
+                    .and_then(|id| parties.get(&id))
+                    .map(|party| RecentPartyResponse {
+                        id: party.id,
+                        name: party.name.clone(),
+                        code: party.code.clone(),
+                    }),
+                last_map: recent.last_map_id.and_then(|id| maps.get(&id)).map(|map| {
+                    RecentMapResponse {
+                        id: map.id,
+                        title: map.title.clone(),
+                    }
+                }),
+                mutual_party_ids: mutual_parties
+                    .remove(&recent.other_user_id)
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(Json(recent_players))
+}
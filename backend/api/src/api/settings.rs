@@ -0,0 +1,196 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::user_settings::{self, Entity as UserSettings};
+use sea_orm::{EntityTrait, Set, sea_query::OnConflict};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+use crate::region;
+use crate::settings::ActivityVisibility;
+
+/// Units distances and speeds are shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        }
+    }
+
+    fn from_column(value: &str) -> Self {
+        match value {
+            "imperial" => Units::Imperial,
+            _ => Units::Metric,
+        }
+    }
+}
+
+/// Settings of a user; fields left out of an update are reset to their
+/// defaults
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+#[serde(default)]
+pub struct SettingsRequest {
+    units: Units,
+    /// Leave the user's parties out of the party browser and quick join
+    hide_from_party_browser: bool,
+    /// Show the user as offline to everyone else
+    appear_offline: bool,
+    /// Ask clients not to send gameplay telemetry for the user
+    telemetry_opt_out: bool,
+    /// Who can see the user's activity
+    activity_visibility: ActivityVisibility,
+    /// Region new parties and matchmaking use instead of the one of the
+    /// user's connection
+    preferred_region: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SettingsResponse {
+    units: Units,
+    hide_from_party_browser: bool,
+    appear_offline: bool,
+    telemetry_opt_out: bool,
+    activity_visibility: ActivityVisibility,
+    preferred_region: Option<String>,
+    /// When the settings were last changed, if ever
+    updated_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl From<user_settings::Model> for SettingsResponse {
+    fn from(settings: user_settings::Model) -> Self {
+        Self {
+            units: Units::from_column(&settings.units),
+            hide_from_party_browser: settings.hide_from_party_browser,
+            appear_offline: settings.appear_offline,
+            telemetry_opt_out: settings.telemetry_opt_out,
+            activity_visibility: ActivityVisibility::from_column(&settings.activity_visibility),
+            preferred_region: settings.preferred_region,
+            updated_at: Some(settings.updated_at),
+        }
+    }
+}
+
+impl Default for SettingsResponse {
+    fn default() -> Self {
+        let defaults = SettingsRequest::default();
+        Self {
+            units: defaults.units,
+            hide_from_party_browser: defaults.hide_from_party_browser,
+            appear_offline: defaults.appear_offline,
+            telemetry_opt_out: defaults.telemetry_opt_out,
+            activity_visibility: defaults.activity_visibility,
+            preferred_region: defaults.preferred_region,
+            updated_at: None,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/users/me/settings", get(get_settings).put(update_settings))
+}
+
+/// Get the settings of the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/settings",
+    tag = "users",
+    responses(
+        (status = 200, description = "Settings retrieved successfully", body = SettingsResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<SettingsResponse>, (StatusCode, String)> {
+    let settings = UserSettings::find_by_id(auth_user.0.sub)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        settings.map(SettingsResponse::from).unwrap_or_default(),
+    ))
+}
+
+/// Replace the settings of the current user
+#[utoipa::path(
+    put,
+    path = "/api/users/me/settings",
+    tag = "users",
+    request_body = SettingsRequest,
+    responses(
+        (status = 200, description = "Settings updated successfully", body = SettingsResponse),
+        (status = 400, description = "Invalid preferred region", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<SettingsRequest>,
+) -> Result<Json<SettingsResponse>, (StatusCode, String)> {
+    let preferred_region = payload
+        .preferred_region
+        .map(|preferred_region| {
+            region::parse(&preferred_region).ok_or((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid region {}", preferred_region),
+            ))
+        })
+        .transpose()?;
+
+    let settings = user_settings::ActiveModel {
+        user_id: Set(auth_user.0.sub),
+        units: Set(payload.units.as_str().to_string()),
+        hide_from_party_browser: Set(payload.hide_from_party_browser),
+        appear_offline: Set(payload.appear_offline),
+        telemetry_opt_out: Set(payload.telemetry_opt_out),
+        activity_visibility: Set(payload.activity_visibility.as_str().to_string()),
+        preferred_region: Set(preferred_region),
+        updated_at: Set(chrono::Utc::now().fixed_offset()),
+    };
+
+    // The first update creates the settings
+    let settings = UserSettings::insert(settings)
+        .on_conflict(
+            OnConflict::column(user_settings::Column::UserId)
+                .update_columns([
+                    user_settings::Column::Units,
+                    user_settings::Column::HideFromPartyBrowser,
+                    user_settings::Column::AppearOffline,
+                    user_settings::Column::TelemetryOptOut,
+                    user_settings::Column::ActivityVisibility,
+                    user_settings::Column::PreferredRegion,
+                    user_settings::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_with_returning(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(settings.into()))
+}
@@ -0,0 +1,333 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::{get, post, put},
+};
+use entity::party::{self, Entity as Party};
+use entity::user::Entity as User;
+use entity::user_party::{self, Entity as UserParty};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, TransactionTrait,
+    sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::ws::WsMessage;
+use crate::db::AppState;
+use crate::membership;
+use crate::policy;
+
+// Most teams a party can be split into
+const MAX_TEAMS: i32 = 8;
+
+/// The team a member races for
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct TeamAssignment {
+    user_id: i32,
+    /// Between 1 and 8, or null if not on a team
+    team: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignTeamRequest {
+    /// Between 1 and 8, or null to take the member off their team
+    team: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BalanceTeamsRequest {
+    /// Number of teams to split the members into, between 2 and 8
+    teams: i32,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/parties/{id}/teams", get(get_teams))
+        .route("/parties/{id}/teams/balance", post(balance_teams))
+        .route("/parties/{id}/members/{user_id}/team", put(assign_team))
+}
+
+/// Get the teams of the members of a party
+#[utoipa::path(
+    get,
+    path = "/api/parties/{id}/teams",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Teams retrieved successfully", body = Vec<TeamAssignment>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not a member of the party", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_teams(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<TeamAssignment>>, (StatusCode, String)> {
+    let party = find_party(&state, id).await?;
+
+    if !membership::is_member(&state, auth_user.0.sub, party.id).await
+        && !policy::can_manage_party(&auth_user.0, &party)
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are not a member of this party".to_string(),
+        ));
+    }
+
+    let teams = load_teams(&state.conn, party.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(teams))
+}
+
+/// Put a member of a party on a team (only by owner)
+///
+/// Connected members are sent the new teams over the websocket. Teams can't
+/// change during a race.
+#[utoipa::path(
+    put,
+    path = "/api/parties/{id}/members/{user_id}/team",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID"),
+        ("user_id" = i32, Path, description = "User ID of the member")
+    ),
+    request_body = AssignTeamRequest,
+    responses(
+        (status = 200, description = "Team assigned successfully", body = Vec<TeamAssignment>),
+        (status = 400, description = "Invalid team", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can assign teams", body = String),
+        (status = 404, description = "Party or member not found", body = String),
+        (status = 409, description = "The party is racing", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn assign_team(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth_user: AuthUser,
+    Json(payload): Json<AssignTeamRequest>,
+) -> Result<Json<Vec<TeamAssignment>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let party = find_managed_party(&state, id, &auth_user).await?;
+
+    if let Some(team) = payload.team
+        && !(1..=MAX_TEAMS).contains(&team)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("team must be between 1 and {}", MAX_TEAMS),
+        ));
+    }
+
+    let updated = UserParty::update_many()
+        .col_expr(user_party::Column::Team, Expr::value(payload.team))
+        .filter(user_party::Column::PartyId.eq(party.id))
+        .filter(user_party::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if updated.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("User with id {} is not a member of this party", user_id),
+        ));
+    }
+
+    announce_teams(&state, party.id).await
+}
+
+/// Split the members of a party into teams of even size and skill (only by
+/// owner)
+///
+/// Members are dealt out to the teams by level, strongest first, reversing
+/// the order of the teams every round. Connected members are sent the new
+/// teams over the websocket. Teams can't change during a race.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/teams/balance",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    request_body = BalanceTeamsRequest,
+    responses(
+        (status = 200, description = "Teams balanced successfully", body = Vec<TeamAssignment>),
+        (status = 400, description = "Invalid number of teams", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Only the party owner can assign teams", body = String),
+        (status = 404, description = "Party not found", body = String),
+        (status = 409, description = "The party is racing", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn balance_teams(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    Json(payload): Json<BalanceTeamsRequest>,
+) -> Result<Json<Vec<TeamAssignment>>, (StatusCode, String)> {
+    let db = &state.conn;
+    let party = find_managed_party(&state, id, &auth_user).await?;
+
+    let members = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party.id))
+        .find_also_related(User)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Every team needs at least one member
+    let max_teams = MAX_TEAMS.min(members.len() as i32);
+    if max_teams < 2 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The party needs at least 2 members for teams".to_string(),
+        ));
+    }
+    if !(2..=max_teams).contains(&payload.teams) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("teams must be between 2 and {}", max_teams),
+        ));
+    }
+
+    let mut members: Vec<(i32, i32)> = members
+        .into_iter()
+        .map(|(membership, user)| {
+            let level = user.map(|user| user.level).unwrap_or_default();
+            (membership.user_id, level)
+        })
+        .collect();
+    members.sort_by_key(|(user_id, level)| (std::cmp::Reverse(*level), *user_id));
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    for (position, (user_id, _)) in members.iter().enumerate() {
+        let round = position as i32 / payload.teams;
+        let seat = position as i32 % payload.teams;
+        let team = if round % 2 == 0 {
+            seat + 1
+        } else {
+            payload.teams - seat
+        };
+
+        UserParty::update_many()
+            .col_expr(user_party::Column::Team, Expr::value(team))
+            .filter(user_party::Column::PartyId.eq(party.id))
+            .filter(user_party::Column::UserId.eq(*user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    announce_teams(&state, party.id).await
+}
+
+// Helper function to find a party by ID
+async fn find_party(state: &AppState, id: i32) -> Result<party::Model, (StatusCode, String)> {
+    Party::find_by_id(id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))
+}
+
+// Helper function to find a party whose teams the user may change
+async fn find_managed_party(
+    state: &AppState,
+    id: i32,
+    auth_user: &AuthUser,
+) -> Result<party::Model, (StatusCode, String)> {
+    let party = find_party(state, id).await?;
+
+    if !policy::can_manage_party(&auth_user.0, &party) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the party owner can assign teams".to_string(),
+        ));
+    }
+
+    if state.active_races.lock().unwrap().contains_key(&party.id) {
+        return Err((
+            StatusCode::CONFLICT,
+            "Teams can't change during a race".to_string(),
+        ));
+    }
+
+    Ok(party)
+}
+
+// Helper function to send the teams of a party to its connected members
+async fn announce_teams(
+    state: &AppState,
+    party_id: i32,
+) -> Result<Json<Vec<TeamAssignment>>, (StatusCode, String)> {
+    let teams = load_teams(&state.conn, party_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let channel = state.party_channels.lock().unwrap().get(&party_id).cloned();
+
+    if let Some(channel) = channel {
+        let teams_msg = serde_json::to_string(&WsMessage::TeamsChanged {
+            party_id,
+            teams: teams.clone(),
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        let _ = channel.send(teams_msg);
+    }
+
+    Ok(Json(teams))
+}
+
+/// Get the teams of the members of a party, in the order they joined
+pub async fn load_teams<C: ConnectionTrait>(
+    db: &C,
+    party_id: i32,
+) -> Result<Vec<TeamAssignment>, DbErr> {
+    let members = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .order_by_asc(user_party::Column::JoinedAt)
+        .all(db)
+        .await?;
+
+    Ok(members
+        .into_iter()
+        .map(|membership| TeamAssignment {
+            user_id: membership.user_id,
+            team: membership.team,
+        })
+        .collect())
+}
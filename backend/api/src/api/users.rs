@@ -1,36 +1,191 @@
-use auth::Auth;
+use auth::{AuthError, middleware::AuthUser};
 use axum::{
     Router,
-    extract::{Json, State},
-    http::{Request, StatusCode, header},
-    routing::get,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
 };
 use entity::user::{self, Entity as User};
-use sea_orm::EntityTrait;
-use serde::Serialize;
-use utoipa::ToSchema;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    sea_query::{Expr, Func, LikeExpr},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::pagination::{Paginated, PaginationParams};
+use crate::db::{AppState, SocketCommand};
+use crate::merge;
+use crate::progression;
+use crate::validation::{self, ValidationErrorResponse};
 
-use crate::db::AppState;
+// Limits of the profile fields
+const MAX_AVATAR_URL_LENGTH: usize = 512;
+const MAX_BIO_LENGTH: usize = 280;
+const MAX_FAVORITE_VEHICLE_LENGTH: usize = 32;
 
 #[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     id: i32,
     name: String,
+    avatar_url: Option<String>,
+    /// ISO 3166-1 alpha-2 country code
+    country: Option<String>,
+    bio: Option<String>,
+    favorite_vehicle: Option<String>,
+    xp: i64,
+    level: i32,
     created_at: chrono::DateTime<chrono::FixedOffset>,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct ProgressionResponse {
+    user_id: i32,
+    xp: i64,
+    level: i32,
+    /// Total XP at which the current level was reached
+    level_xp: i64,
+    /// Total XP needed for the next level
+    next_level_xp: i64,
+}
+
+impl From<user::Model> for ProgressionResponse {
+    fn from(user: user::Model) -> Self {
+        Self {
+            user_id: user.id,
+            xp: user.xp,
+            level: user.level,
+            level_xp: progression::xp_for_level(user.level),
+            next_level_xp: progression::xp_for_level(user.level + 1),
+        }
+    }
+}
+
+// The current user, including details only they may see
+#[derive(Serialize, ToSchema)]
+pub struct CurrentUserResponse {
+    #[serde(flatten)]
+    user: UserResponse,
+    email: Option<String>,
+    email_verified: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSearchParams {
+    /// Only users whose name contains this, ignoring case
+    q: Option<String>,
+}
+
+// Fields left out are kept; profile fields set to an empty string are cleared
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateUserRequest {
+    name: Option<String>,
+    /// http(s) URL of an image
+    avatar_url: Option<String>,
+    /// ISO 3166-1 alpha-2 country code, e.g. "US"
+    country: Option<String>,
+    bio: Option<String>,
+    favorite_vehicle: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MergeRequest {
+    /// Access token of the account to merge into the current one, proving
+    /// the caller owns it too
+    secondary_token: String,
+}
+
+impl From<user::Model> for CurrentUserResponse {
+    fn from(user: user::Model) -> Self {
+        Self {
+            email: user.email.clone(),
+            email_verified: user.email_verified_at.is_some(),
+            user: user.into(),
+        }
+    }
+}
+
 impl From<user::Model> for UserResponse {
     fn from(user: user::Model) -> Self {
         Self {
             id: user.id,
             name: user.name,
+            avatar_url: user.avatar_url,
+            country: user.country,
+            bio: user.bio,
+            favorite_vehicle: user.favorite_vehicle,
+            xp: user.xp,
+            level: user.level,
             created_at: user.created_at,
         }
     }
 }
 
 pub fn router() -> Router<AppState> {
-    Router::new().route("/users/me", get(me))
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/me", get(me).patch(update_me).delete(delete_me))
+        .route("/users/me/merge", post(merge_me))
+        .route("/users/{id}/progression", get(get_progression))
+}
+
+/// List users, optionally searching by name
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "users",
+    params(UserSearchParams, PaginationParams),
+    responses(
+        (status = 200, description = "Users retrieved successfully", body = Paginated<UserResponse>),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn list_users(
+    State(state): State<AppState>,
+    Query(search): Query<UserSearchParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<Paginated<UserResponse>>, (StatusCode, String)> {
+    let db = &state.conn;
+
+    let mut query = User::find()
+        .filter(user::Column::DeletedAt.is_null())
+        .order_by_asc(user::Column::Id);
+
+    if let Some(q) = search.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        // Match the search literally, not as a pattern
+        let escaped = q
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        query = query.filter(
+            Expr::expr(Func::lower(Expr::col(user::Column::Name)))
+                .like(LikeExpr::new(format!("%{}%", escaped)).escape('\\')),
+        );
+    }
+
+    let paginator = query.paginate(db, pagination.per_page());
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let users = paginator
+        .fetch_page(pagination.page() - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(Paginated::new(
+        users.into_iter().map(UserResponse::from).collect(),
+        &pagination,
+        totals.number_of_items,
+        totals.number_of_pages,
+    )))
 }
 
 /// Get current authenticated user info
@@ -39,47 +194,61 @@ pub fn router() -> Router<AppState> {
     path = "/api/users/me",
     tag = "users",
     responses(
-        (status = 200, description = "Current user info retrieved successfully", body = UserResponse),
+        (status = 200, description = "Current user info retrieved successfully", body = CurrentUserResponse),
         (status = 401, description = "Unauthorized", body = String),
         (status = 404, description = "User not found", body = String),
         (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
 async fn me(
     State(state): State<AppState>,
-    req: Request<axum::body::Body>,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
-    // Extract and validate the JWT token
-    let auth_header = req
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|header| header.to_str().ok())
-        .and_then(|value| value.strip_prefix("Bearer ").map(|s| s.to_owned()))
+    auth_user: AuthUser,
+) -> Result<Json<CurrentUserResponse>, (StatusCode, String)> {
+    // Get user from database
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    let user = User::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((
-            StatusCode::UNAUTHORIZED,
-            "No authorization token provided".to_string(),
+            StatusCode::NOT_FOUND,
+            format!("User with id {} not found", user_id),
         ))?;
 
-    // Create auth instance
-    let auth = Auth::new(
-        state.config.jwt_secret.clone(),
-        state.config.jwt_expiry,
-        state.config.refresh_expiry,
-    );
-
-    // Validate the token
-    let claims = auth.verify_token(&auth_header).map_err(|_| {
-        (
-            StatusCode::UNAUTHORIZED,
-            "Invalid authorization token".to_string(),
-        )
-    })?;
+    Ok(Json(user.into()))
+}
 
-    // Get user from database
+/// Get the XP and level of a user
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/progression",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Progression retrieved successfully", body = ProgressionResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn get_progression(
+    State(state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<ProgressionResponse>, (StatusCode, String)> {
     let db = &state.conn;
-    let user_id = claims.sub;
 
     let user = User::find_by_id(user_id)
+        .filter(user::Column::DeletedAt.is_null())
         .one(db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -90,3 +259,301 @@ async fn me(
 
     Ok(Json(user.into()))
 }
+
+/// Update the name and profile of the current user
+#[utoipa::path(
+    patch,
+    path = "/api/users/me",
+    tag = "users",
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "User updated successfully", body = CurrentUserResponse),
+        (status = 400, description = "Invalid profile field", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 409, description = "Name is already taken", body = String),
+        (status = 422, description = "Invalid name", body = ValidationErrorResponse),
+        (status = 429, description = "Name was changed too recently", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn update_me(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdateUserRequest>,
+) -> Result<Json<CurrentUserResponse>, Response> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    // Validate the profile before renaming, so a bad field changes nothing
+    let avatar_url = payload
+        .avatar_url
+        .map(validate_avatar_url)
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+    let country = payload
+        .country
+        .map(validate_country)
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+    let bio = payload
+        .bio
+        .map(|bio| validate_length("Bio", bio, MAX_BIO_LENGTH))
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+    let favorite_vehicle = payload
+        .favorite_vehicle
+        .map(|vehicle| validate_length("Favorite vehicle", vehicle, MAX_FAVORITE_VEHICLE_LENGTH))
+        .transpose()
+        .map_err(IntoResponse::into_response)?;
+
+    let mut user = User::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("User with id {} not found", user_id),
+            )
+                .into_response()
+        })?;
+
+    if let Some(name) = payload.name {
+        user = auth::user::rename(db, &state.validator, user_id, &name)
+            .await
+            .map_err(|e| match e {
+                AuthError::Validation(errors) => validation::invalid(errors),
+                AuthError::NameTaken => (StatusCode::CONFLICT, e.to_string()).into_response(),
+                AuthError::RenameCooldown(_) => {
+                    (StatusCode::TOO_MANY_REQUESTS, e.to_string()).into_response()
+                }
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            })?;
+    }
+
+    if avatar_url.is_some() || country.is_some() || bio.is_some() || favorite_vehicle.is_some() {
+        let mut user_model: user::ActiveModel = user.into();
+        if let Some(avatar_url) = avatar_url {
+            user_model.avatar_url = Set(avatar_url);
+        }
+        if let Some(country) = country {
+            user_model.country = Set(country);
+        }
+        if let Some(bio) = bio {
+            user_model.bio = Set(bio);
+        }
+        if let Some(favorite_vehicle) = favorite_vehicle {
+            user_model.favorite_vehicle = Set(favorite_vehicle);
+        }
+
+        user = user_model
+            .update(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    }
+
+    Ok(Json(user.into()))
+}
+
+/// Delete the account of the current user
+///
+/// The user is signed out everywhere right away. The account and everything
+/// it created are purged after a grace period.
+#[utoipa::path(
+    delete,
+    path = "/api/users/me",
+    tag = "users",
+    responses(
+        (status = 204, description = "Account deleted"),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn delete_me(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = auth_user.0.sub;
+
+    auth::user::delete(&state.conn, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .blocklist
+        .revoke_user(user_id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Close the game connection
+    if let Some(socket) = state.user_sockets.lock().unwrap().get(&user_id) {
+        let _ = socket.send(SocketCommand::Close);
+    }
+
+    tracing::info!("User {} deleted their account", user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Merge another account of the current user into this one
+///
+/// Maps, playlists, parties, memberships, achievements, favorites, follows,
+/// loadouts, activity, linked accounts, coins and progress of the other
+/// account move to the current one in a single transaction. The other
+/// account is then deleted and signed out everywhere.
+#[utoipa::path(
+    post,
+    path = "/api/users/me/merge",
+    tag = "users",
+    request_body = MergeRequest,
+    responses(
+        (status = 200, description = "Accounts merged", body = CurrentUserResponse),
+        (status = 400, description = "Invalid token of the other account, or the same account", body = String),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "The other account is banned", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn merge_me(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<MergeRequest>,
+) -> Result<Json<CurrentUserResponse>, (StatusCode, String)> {
+    let user_id = auth_user.0.sub;
+
+    // The other account proves itself like any request would
+    let invalid_token = || {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid token of the account to merge".to_string(),
+        )
+    };
+    let claims = state
+        .auth
+        .verify_token(&payload.secondary_token)
+        .map_err(|_| invalid_token())?;
+    let claims = state
+        .blocklist
+        .check(claims)
+        .await
+        .map_err(|_| invalid_token())?;
+    if claims.is_scoped() {
+        return Err(invalid_token());
+    }
+    let secondary_id = claims.sub;
+
+    if secondary_id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "An account can't be merged into itself".to_string(),
+        ));
+    }
+
+    // Banned accounts can't shed their ban by merging
+    auth::user::check_ban_by_id(&state.conn, secondary_id)
+        .await
+        .map_err(|e| match e {
+            AuthError::Banned { .. } => (
+                StatusCode::FORBIDDEN,
+                "Banned accounts can't be merged".to_string(),
+            ),
+            AuthError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            _ => invalid_token(),
+        })?;
+
+    let user = merge::merge_users(&state.conn, user_id, secondary_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    auth::user::revoke_sessions(&state.conn, secondary_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .blocklist
+        .revoke_user(secondary_id, state.config.jwt_expiry)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Close the game connection of the merged account
+    if let Some(socket) = state.user_sockets.lock().unwrap().get(&secondary_id) {
+        let _ = socket.send(SocketCommand::Close);
+    }
+
+    tracing::info!(
+        "User {} merged account {} into theirs",
+        user_id,
+        secondary_id
+    );
+
+    Ok(Json(user.into()))
+}
+
+// Helper function to validate an avatar URL; an empty one clears the avatar
+fn validate_avatar_url(url: String) -> Result<Option<String>, (StatusCode, String)> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(None);
+    }
+
+    let valid_scheme = url.starts_with("https://") || url.starts_with("http://");
+    if !valid_scheme || url.len() > MAX_AVATAR_URL_LENGTH || url.contains(char::is_whitespace) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Avatar URL must be an http(s) URL of at most {} characters",
+                MAX_AVATAR_URL_LENGTH
+            ),
+        ));
+    }
+
+    Ok(Some(url.to_string()))
+}
+
+// Helper function to validate a country code; an empty one clears the country
+fn validate_country(country: String) -> Result<Option<String>, (StatusCode, String)> {
+    let country = country.trim().to_ascii_uppercase();
+    if country.is_empty() {
+        return Ok(None);
+    }
+
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Country must be an ISO 3166-1 alpha-2 code".to_string(),
+        ));
+    }
+
+    Ok(Some(country))
+}
+
+// Helper function to validate a free text field; an empty one clears it
+fn validate_length(
+    field: &str,
+    value: String,
+    max_length: usize,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    if value.chars().count() > max_length {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} must be at most {} characters long", field, max_length),
+        ));
+    }
+
+    Ok(Some(value.to_string()))
+}
@@ -0,0 +1,105 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    routing::post,
+};
+use entity::party::Entity as Party;
+use sea_orm::EntityTrait;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+use crate::membership;
+
+#[derive(Serialize, ToSchema)]
+pub struct VoiceTokenResponse {
+    /// Voice service the token is for, e.g. livekit
+    provider: String,
+    /// Server to connect to
+    url: String,
+    /// Room of the party on the voice server
+    room: String,
+    token: String,
+    expires_in: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/parties/{id}/voice-token", post(create_voice_token))
+}
+
+/// Get a token to join the voice chat of a party
+///
+/// The token only lets the user into the room of this party, under their
+/// user id.
+#[utoipa::path(
+    post,
+    path = "/api/parties/{id}/voice-token",
+    tag = "parties",
+    params(
+        ("id" = i32, Path, description = "Party ID")
+    ),
+    responses(
+        (status = 200, description = "Voice token issued successfully", body = VoiceTokenResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Not a member of the party", body = String),
+        (status = 404, description = "Party not found, or voice chat is not available", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_voice_token(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<VoiceTokenResponse>, (StatusCode, String)> {
+    let (Some(issuer), Some(url)) = (state.config.voice_issuer(), &state.config.voice_url) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Voice chat is not available".to_string(),
+        ));
+    };
+
+    let party = Party::find_by_id(id)
+        .one(&state.conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Party with id {} not found", id),
+        ))?;
+
+    if !membership::is_member(&state, auth_user.0.sub, party.id).await {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You are not a member of this party".to_string(),
+        ));
+    }
+
+    let room = voice_room(party.id);
+    let expires_in = state.config.voice_token_expiry;
+    let token = issuer
+        .issue(
+            &room,
+            &auth_user.0.sub.to_string(),
+            &auth_user.0.name,
+            expires_in,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(VoiceTokenResponse {
+        provider: issuer.provider().to_string(),
+        url: url.clone(),
+        room,
+        token,
+        expires_in,
+    }))
+}
+
+// Helper function to name the voice room of a party
+fn voice_room(party_id: i32) -> String {
+    format!("party-{}", party_id)
+}
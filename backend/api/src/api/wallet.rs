@@ -0,0 +1,96 @@
+use auth::middleware::AuthUser;
+use axum::{
+    Router,
+    extract::{Json, State},
+    http::StatusCode,
+    routing::get,
+};
+use entity::wallet::Entity as Wallet;
+use entity::wallet_transaction::{self, Entity as WalletTransaction};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::db::AppState;
+
+// How many of the latest transactions are returned with the balance
+const RECENT_TRANSACTIONS: u64 = 20;
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionResponse {
+    id: i32,
+    /// Negative when taken off the balance
+    amount: i64,
+    balance_after: i64,
+    reason: String,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+impl From<wallet_transaction::Model> for TransactionResponse {
+    fn from(transaction: wallet_transaction::Model) -> Self {
+        Self {
+            id: transaction.id,
+            amount: transaction.amount,
+            balance_after: transaction.balance_after,
+            reason: transaction.reason,
+            created_at: transaction.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WalletResponse {
+    balance: i64,
+    /// Latest transactions, newest first
+    transactions: Vec<TransactionResponse>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/users/me/wallet", get(get_wallet))
+}
+
+/// Get the balance and latest transactions of the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/wallet",
+    tag = "users",
+    responses(
+        (status = 200, description = "Wallet retrieved successfully", body = WalletResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_wallet(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<WalletResponse>, (StatusCode, String)> {
+    let db = &state.conn;
+    let user_id = auth_user.0.sub;
+
+    // Users who never earned anything have no wallet yet
+    let balance = Wallet::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_or(0, |wallet| wallet.balance);
+
+    let transactions = WalletTransaction::find()
+        .filter(wallet_transaction::Column::UserId.eq(user_id))
+        .order_by_desc(wallet_transaction::Column::CreatedAt)
+        .order_by_desc(wallet_transaction::Column::Id)
+        .limit(RECENT_TRANSACTIONS)
+        .all(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(WalletResponse {
+        balance,
+        transactions: transactions
+            .into_iter()
+            .map(TransactionResponse::from)
+            .collect(),
+    }))
+}
@@ -1,23 +1,56 @@
 use axum::{
     Router,
     extract::{
-        Query, State,
+        Json, Query, State,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
-    http::StatusCode,
-    response::IntoResponse,
-    routing::get,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
 };
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc};
 use tokio::task::JoinHandle;
 
-use crate::db::AppState;
-use auth::Auth;
-use entity::user_party::Entity as UserParty;
-use entity::{party::Entity as Party, user::Entity as User};
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use super::maps::{MapStatus, TimeOfDay, Weather};
+use super::party_settings;
+use super::party_settings::PartySettingsResponse;
+use super::playlists;
+use super::teams::TeamAssignment;
+use crate::achievements;
+use crate::activity;
+use crate::awards::{self, Award};
+use crate::blocking;
+use crate::challenges;
+use crate::client_version;
+use crate::db::{AppState, SocketCommand, UserId};
+use crate::membership;
+use crate::party_bans;
+use crate::policy;
+use crate::presence::PRESENCE_REFRESH;
+use crate::progression;
+use crate::race::{FinishStanding, RaceProgress};
+use crate::races;
+use crate::recent_players;
+use crate::region;
+use crate::replays::{self, ReplayRecorder};
+use crate::succession;
+use crate::wallet;
+use auth::middleware::TokenUser;
+use auth::{Claims, Scope, WS_TICKET_EXPIRY};
+use entity::{
+    achievement,
+    checkpoint::Entity as Checkpoint,
+    map::Entity as Map,
+    party::{self, Entity as Party},
+    user::Entity as User,
+    vehicle_loadout::{self, Entity as VehicleLoadout},
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use utoipa::ToSchema;
 
 // Position and rotation data structure
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -41,25 +74,143 @@ pub struct Rotation {
     roll: f32,
 }
 
+// Selected vehicle loadout, so other clients render the right car
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Loadout {
+    model: String,
+    color: String,
+    decals: serde_json::Value,
+}
+
 // WebSocket message types
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(tag = "type")]
 pub enum WsMessage {
-    Connect { user_id: i32, party_id: i32 },
-    NewPartyMember { user_id: i32, name: String },
+    Connect {
+        user_id: i32,
+        party_id: i32,
+    },
+    NewPartyMember {
+        user_id: i32,
+        name: String,
+        avatar_url: Option<String>,
+        country: Option<String>,
+        bio: Option<String>,
+        favorite_vehicle: Option<String>,
+        loadout: Option<Loadout>,
+        /// Team the member races for, if teams were assigned
+        team: Option<i32>,
+    },
 
     StartRace {},
 
-    RaceStarted {},
-    Update { state: PlayerState },
-    Disconnect { user_id: i32 },
+    RaceStarted {
+        /// Race to submit results to, unless it couldn't be saved
+        #[serde(default)]
+        race_id: Option<i32>,
+        /// Conditions of the map, so every client renders the same race
+        #[serde(default)]
+        weather: Weather,
+        #[serde(default)]
+        time_of_day: TimeOfDay,
+    },
+    Update {
+        state: PlayerState,
+    },
+    Disconnect {
+        user_id: i32,
+    },
+
+    Announcement {
+        message: String,
+    },
+
+    ProgressUpdate {
+        user_id: i32,
+        next_checkpoint: usize,
+        completion_pct: f32,
+    },
+
+    FinishStandings {
+        standings: Vec<FinishStanding>,
+    },
+    Awards {
+        race_id: i32,
+        awards: Vec<Award>,
+    },
+
+    PartyMoved {
+        party_id: i32,
+    },
+    MergeRequested {
+        party_id: i32,
+        name: String,
+    },
+    AchievementUnlocked {
+        user_id: i32,
+        key: String,
+        name: String,
+        description: String,
+    },
+    ReadyStateChanged {
+        user_id: i32,
+        ready: bool,
+    },
+    AllReady {},
+    PartyCodeChanged {
+        party_id: i32,
+        code: String,
+    },
+    PartySettingsChanged {
+        party_id: i32,
+        settings: PartySettingsResponse,
+    },
+    MapChanged {
+        party_id: i32,
+        map_id: i32,
+        title: String,
+    },
+    PartyInvite {
+        invite_id: i32,
+        party_id: i32,
+        party_name: String,
+        inviter_id: i32,
+        inviter_name: String,
+        expires_at: chrono::DateTime<chrono::FixedOffset>,
+    },
+    TeamsChanged {
+        party_id: i32,
+        teams: Vec<TeamAssignment>,
+    },
+    MatchFound {
+        party_id: i32,
+        code: String,
+        map_id: i32,
+        player_ids: Vec<i32>,
+    },
+    OwnerChanged {
+        party_id: i32,
+        owner_id: i32,
+        previous_owner_id: i32,
+    },
+    PartyDisbanded {
+        party_id: i32,
+    },
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WsTicketResponse {
+    /// Pass as the 'ticket' query parameter when opening the connection
+    ticket: String,
+    expires_in: i64,
 }
 
 // Query parameters for the WebSocket connection
 #[derive(Deserialize)]
 struct WsQueryParams {
-    token: String,
+    ticket: String,
     party_id: Option<i32>,
+    client_version: Option<String>,
 }
 
 #[axum::debug_handler]
@@ -67,19 +218,49 @@ async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<WsQueryParams>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // 1. Validate the JWT token
-    let auth = Auth::new(
-        state.config.jwt_secret.clone(),
-        state.config.jwt_expiry,
-        state.config.refresh_expiry,
-    );
-
-    let claims = auth.verify_token(&params.token).map_err(|e| {
-        (
+    headers: HeaderMap,
+) -> Result<Response, Response> {
+    // 0. Reject clients outside the supported version range
+    client_version::check(&state.config, params.client_version.as_deref())
+        .map_err(|update_required| (*update_required).into_response())?;
+
+    // Browsers always send their origin; only allowed sites may open sockets
+    if let Some(origin) = headers.get(header::ORIGIN)
+        && !origin
+            .to_str()
+            .is_ok_and(|origin| state.config.is_origin_allowed(origin))
+    {
+        return Err((StatusCode::FORBIDDEN, "Origin not allowed".to_string()).into_response());
+    }
+
+    // 1. Validate the ticket and use it up
+    let claims = state.auth.verify_ws_ticket(&params.ticket).map_err(|e| {
+        (StatusCode::UNAUTHORIZED, format!("Invalid ticket: {}", e)).into_response()
+    })?;
+
+    let claims = state.blocklist.check(claims).await.map_err(|e| {
+        (StatusCode::UNAUTHORIZED, format!("Invalid ticket: {}", e)).into_response()
+    })?;
+
+    auth::user::check_ban_by_id(&state.conn, claims.sub)
+        .await
+        .map_err(|e| match e {
+            auth::AuthError::Banned { .. } => {
+                (StatusCode::FORBIDDEN, e.to_string()).into_response()
+            }
+            auth::AuthError::DatabaseError(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+            _ => (StatusCode::UNAUTHORIZED, format!("Invalid ticket: {}", e)).into_response(),
+        })?;
+
+    let claims = state.ws_tickets.redeem(claims).await.map_err(|e| match e {
+        auth::AuthError::TokenRevoked => (
             StatusCode::UNAUTHORIZED,
-            format!("Invalid authentication token: {}", e),
+            "Ticket was already used".to_string(),
         )
+            .into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     })?;
 
     // Get the authenticated user id from the token claims
@@ -87,40 +268,61 @@ async fn ws_handler(
 
     // 2. If party_id is provided, verify that the user is a member of the party
     if let Some(party_id) = params.party_id {
-        let is_member = verify_user_in_party(authenticated_user_id, party_id, &state.conn).await;
+        let is_member = membership::is_member(&state, authenticated_user_id, party_id).await;
         if !is_member {
             return Err((
                 StatusCode::FORBIDDEN,
                 "You are not a member of this party".to_string(),
-            ));
+            )
+                .into_response());
+        }
+
+        // Members the owner blocked or banned since they joined can't connect either
+        let blocked = blocking::is_blocked_from_party(&state.conn, authenticated_user_id, party_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+            || party_bans::is_banned(&state.conn, party_id, authenticated_user_id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+        if blocked {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "You can't connect to this party".to_string(),
+            )
+                .into_response());
+        }
+
+        // Connections are limited like members, also after the limit was lowered
+        let has_room = membership::has_connection_room(&state, authenticated_user_id, party_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+        if !has_room {
+            return Err((StatusCode::CONFLICT, "Party is full".to_string()).into_response());
         }
     }
-    // 3. Proceed with the WebSocket upgrade with the authenticated user's info
-    let conn = state.conn.clone();
-    let party_channels = state.party_channels.clone();
-    let user_parties = state.user_parties.clone();
+    // 3. Resolve the coarse region of the connection for party suggestions
+    let connection_region = region::from_headers(&state.config, &headers);
 
+    // 4. Proceed with the WebSocket upgrade with the authenticated user's info
     Ok(ws.on_upgrade(move |socket| async move {
-        handle_socket(
-            socket,
-            conn,
-            party_channels,
-            user_parties,
-            authenticated_user_id,
-        )
-        .await
+        state.ws_connections.fetch_add(1, Ordering::Relaxed);
+        handle_socket(socket, state.clone(), claims, connection_region).await;
+        state.ws_connections.fetch_sub(1, Ordering::Relaxed);
     }))
 }
 
+#[allow(clippy::collapsible_if, clippy::unnecessary_literal_unwrap)]
 async fn handle_socket(
     socket: WebSocket,
-    conn: sea_orm::DatabaseConnection,
-    party_channels: std::sync::Arc<
-        std::sync::Mutex<std::collections::HashMap<i32, tokio::sync::broadcast::Sender<String>>>,
-    >,
-    user_parties: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<i32, i32>>>,
-    authenticated_user_id: i32,
+    state: AppState,
+    claims: Claims,
+    connection_region: Option<String>,
 ) {
+    let authenticated_user_id = claims.sub;
+    let conn = &state.conn;
+    let user_parties = &state.user_parties;
+    let user_regions = &state.user_regions;
+
     // Split the socket
     let (mut sender, mut receiver) = socket.split();
 
@@ -136,14 +338,100 @@ async fn handle_socket(
         }
     });
 
+    // Register a command channel so the REST API can act on this connection
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel::<SocketCommand>();
+    state
+        .user_sockets
+        .lock()
+        .unwrap()
+        .insert(authenticated_user_id, command_tx.clone());
+
+    // Show the user as online until the connection closes
+    state.presence.set_online(authenticated_user_id, None).await;
+    let mut presence_refresh = tokio::time::interval(Duration::from_secs(PRESENCE_REFRESH));
+    presence_refresh.tick().await;
+
     // To track the current user's state
     let user_id = Some(authenticated_user_id);
     let mut party_id: Option<i32> = None;
     let mut party_tx: Option<broadcast::Sender<String>> = None;
     let mut party_rx_task: Option<JoinHandle<()>> = None;
 
-    // Process incoming messages
-    while let Some(Ok(message)) = receiver.next().await {
+    // Process incoming messages and commands
+    loop {
+        let message = tokio::select! {
+            message = receiver.next() => match message {
+                Some(Ok(message)) => message,
+                _ => break,
+            },
+            Some(command) = command_rx.recv() => {
+                match command {
+                    SocketCommand::SwitchParty(new_pid) => {
+                        // Leave the current party channel
+                        if let Some(task) = party_rx_task.take() {
+                            task.abort();
+                        }
+                        if let Some(channel) = party_tx.take() {
+                            let disconnect_msg = serde_json::to_string(&WsMessage::Disconnect {
+                                user_id: authenticated_user_id,
+                            })
+                            .unwrap();
+
+                            let _ = channel.send(disconnect_msg);
+                        }
+                        if let Some(old_pid) = party_id {
+                            leave_race(&state, old_pid, authenticated_user_id);
+                            remove_unused_party_channel(&state, old_pid);
+                        }
+
+                        // Join the new party channel
+                        party_id = Some(new_pid);
+                        user_parties
+                            .lock()
+                            .unwrap()
+                            .insert(authenticated_user_id, new_pid);
+                        state
+                            .presence
+                            .set_online(authenticated_user_id, Some(new_pid))
+                            .await;
+
+                        let channel = party_channel(&state, new_pid);
+                        announce_party_member(&channel, authenticated_user_id, new_pid, conn).await;
+                        party_rx_task = Some(forward_party_messages(&channel, tx.clone()));
+                        party_tx = Some(channel);
+
+                        let moved_msg =
+                            serde_json::to_string(&WsMessage::PartyMoved { party_id: new_pid })
+                                .unwrap();
+
+                        if tx.send(Message::Text(moved_msg.into())).await.is_err() {
+                            tracing::error!("Error sending party moved message");
+                        }
+
+                        tracing::info!(
+                            "User {} moved to party {}",
+                            authenticated_user_id,
+                            new_pid
+                        );
+                    }
+                    SocketCommand::Close => {
+                        let _ = tx.send(Message::Close(None)).await;
+                        break;
+                    }
+                    SocketCommand::Notify(msg) => {
+                        if tx.send(Message::Text(msg.into())).await.is_err() {
+                            tracing::error!("Error sending notification");
+                        }
+                    }
+                }
+                continue;
+            }
+            _ = presence_refresh.tick() => {
+                state.presence.set_online(authenticated_user_id, party_id).await;
+                continue;
+            }
+        };
+
         if let Message::Text(text) = message {
             tracing::debug!("Received message: {}", text);
 
@@ -157,6 +445,34 @@ async fn handle_socket(
                 Ok(WsMessage::NewPartyMember { .. }) => {
                     // Ignore
                 }
+                Ok(WsMessage::Announcement { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::ProgressUpdate { .. })
+                | Ok(WsMessage::FinishStandings { .. })
+                | Ok(WsMessage::Awards { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::PartyMoved { .. }) | Ok(WsMessage::MergeRequested { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::AchievementUnlocked { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::ReadyStateChanged { .. })
+                | Ok(WsMessage::AllReady { .. })
+                | Ok(WsMessage::PartyCodeChanged { .. })
+                | Ok(WsMessage::PartySettingsChanged { .. })
+                | Ok(WsMessage::MapChanged { .. })
+                | Ok(WsMessage::TeamsChanged { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::PartyInvite { .. }) | Ok(WsMessage::MatchFound { .. }) => {
+                    // Ignore
+                }
+                Ok(WsMessage::OwnerChanged { .. }) | Ok(WsMessage::PartyDisbanded { .. }) => {
+                    // Ignore
+                }
                 Ok(WsMessage::Connect {
                     user_id: uid,
                     party_id: pid,
@@ -181,56 +497,63 @@ async fn handle_socket(
 
                     party_id = Some(pid);
 
-                    // Verify that user is a member of the party
-                    if verify_user_in_party(uid, pid, &conn).await {
+                    // Verify that user is a member of the party and not blocked by its owner
+                    // or banned from it
+                    let blocked = blocking::is_blocked_from_party(conn, uid, pid)
+                        .await
+                        .unwrap_or_else(|e| {
+                            tracing::error!("Error checking party blocks: {}", e);
+                            true
+                        })
+                        || party_bans::is_banned(conn, pid, uid)
+                            .await
+                            .unwrap_or_else(|e| {
+                                tracing::error!("Error checking party bans: {}", e);
+                                true
+                            });
+                    let has_room = membership::has_connection_room(&state, uid, pid)
+                        .await
+                        .unwrap_or_else(|e| {
+                            tracing::error!("Error checking party size: {}", e);
+                            false
+                        });
+                    if !has_room {
+                        let error_msg = serde_json::to_string(&serde_json::json!({
+                            "error": "Party is full"
+                        }))
+                        .unwrap();
+
+                        if tx.send(Message::Text(error_msg.into())).await.is_err() {
+                            tracing::error!("Error sending error message");
+                        }
+                        break;
+                    }
+
+                    if !blocked && membership::is_member(&state, uid, pid).await {
                         // Register the user to the party
                         {
                             let mut user_parties_lock = user_parties.lock().unwrap();
                             user_parties_lock.insert(uid, pid);
                         }
+                        state.presence.set_online(uid, Some(pid)).await;
 
-                        // Get or create the broadcast channel for this party
-                        {
-                            let mut party_channels_lock = party_channels.lock().unwrap();
-                            party_channels_lock.entry(pid).or_insert_with(|| {
-                                let (new_tx, _) = broadcast::channel(100);
-                                new_tx
-                            });
-
-                            party_tx = Some(party_channels_lock.get(&pid).unwrap().clone());
+                        // Record the region of this connection
+                        if let Some(region) = &connection_region {
+                            let mut user_regions_lock = user_regions.lock().unwrap();
+                            user_regions_lock.insert(uid, region.clone());
                         }
 
-                        // Notify other party members of the new connection
-                        if let Some(channel) = &party_tx {
-                            // Get the User name
-                            let user = User::find_by_id(uid).one(&conn).await.unwrap();
-                            let name = user.unwrap().name;
-
-                            let connect_msg = serde_json::to_string(&WsMessage::NewPartyMember {
-                                user_id: uid,
-                                name,
-                            })
-                            .unwrap();
+                        // Get or create the broadcast channel for this party
+                        let channel = party_channel(&state, pid);
 
-                            let _ = channel.send(connect_msg);
-                        }
+                        // Notify other party members of the new connection
+                        announce_party_member(&channel, uid, pid, conn).await;
 
                         tracing::info!("User {} connected to party {}", uid, pid);
 
                         // Set up a receiver to listen for party updates
-                        if let Some(channel) = &party_tx {
-                            let mut party_rx = channel.subscribe();
-                            let tx_clone = tx.clone();
-
-                            // Spawn a task to listen for party broadcasts and forward to the client
-                            party_rx_task = Some(tokio::spawn(async move {
-                                while let Ok(msg) = party_rx.recv().await {
-                                    if tx_clone.send(Message::Text(msg.into())).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }));
-                        }
+                        party_rx_task = Some(forward_party_messages(&channel, tx.clone()));
+                        party_tx = Some(channel);
                     } else {
                         // Send error message
                         let error_msg = serde_json::to_string(&serde_json::json!({
@@ -251,10 +574,37 @@ async fn handle_socket(
                     }
 
                     // verifyt the usider_id is the owner of the party
+                    let mut map_id = None;
+                    let mut racing_party = None;
                     if let Some(pid) = party_id {
-                        let party = Party::find_by_id(pid).one(&conn).await.unwrap();
-                        let owner_id = party.unwrap().owner_id;
-                        if authenticated_user_id != owner_id {
+                        let party = match Party::find_by_id(pid).one(conn).await {
+                            Ok(Some(party)) => party,
+                            Ok(None) => {
+                                let error_msg = serde_json::to_string(&serde_json::json!({
+                                    "error": "This party no longer exists"
+                                }))
+                                .unwrap();
+
+                                if tx.send(Message::Text(error_msg.into())).await.is_err() {
+                                    tracing::error!("Error sending error message");
+                                }
+                                continue;
+                            }
+                            Err(e) => {
+                                tracing::error!("Error loading party {}: {}", pid, e);
+                                let error_msg = serde_json::to_string(&serde_json::json!({
+                                    "error": "Could not start the race"
+                                }))
+                                .unwrap();
+
+                                if tx.send(Message::Text(error_msg.into())).await.is_err() {
+                                    tracing::error!("Error sending error message");
+                                }
+                                continue;
+                            }
+                        };
+                        map_id = Some(party.map_id);
+                        if !policy::can_manage_party(&claims, &party) {
                             // Error message
                             let error_msg = serde_json::to_string(&serde_json::json!({
                                 "error": "You are not the owner of this party"
@@ -266,17 +616,87 @@ async fn handle_socket(
                             }
                             continue;
                         }
+
+                        // Everyone has to be ready first
+                        let all_ready =
+                            membership::all_ready(conn, pid).await.unwrap_or_else(|e| {
+                                tracing::error!("Error checking ready states: {}", e);
+                                false
+                            });
+                        if !all_ready {
+                            let error_msg = serde_json::to_string(&serde_json::json!({
+                                "error": "Not everyone in the party is ready"
+                            }))
+                            .unwrap();
+
+                            if tx.send(Message::Text(error_msg.into())).await.is_err() {
+                                tracing::error!("Error sending error message");
+                            }
+                            continue;
+                        }
+                        racing_party = Some(party);
                     }
 
                     // Broadcast race start to all members of the party
                     if let Some(channel) = &party_tx {
-                        let race_started_msg =
-                            serde_json::to_string(&WsMessage::RaceStarted {}).unwrap();
+                        let pid = party_id.unwrap();
+
+                        // Track checkpoint progress server-side for this race
+                        let race = match load_race(map_id.unwrap(), pid, &state).await {
+                            Ok(race) => Some(race),
+                            Err(sea_orm::DbErr::RecordNotFound(_)) => {
+                                let error_msg = serde_json::to_string(&serde_json::json!({
+                                    "error": "The party's map is no longer available"
+                                }))
+                                .unwrap();
+
+                                if tx.send(Message::Text(error_msg.into())).await.is_err() {
+                                    tracing::error!("Error sending error message");
+                                }
+                                continue;
+                            }
+                            Err(e) => {
+                                tracing::error!("Error loading race course: {}", e);
+                                None
+                            }
+                        };
+
+                        let (weather, time_of_day) = race_conditions(map_id.unwrap(), conn).await;
+                        let race_started_msg = serde_json::to_string(&WsMessage::RaceStarted {
+                            race_id: race.as_ref().map(|race| race.race_id),
+                            weather,
+                            time_of_day,
+                        })
+                        .unwrap();
 
                         if let Err(e) = channel.send(race_started_msg) {
                             tracing::error!("Error broadcasting race start message: {}", e);
                         } else {
-                            tracing::info!("Race started in party {}", party_id.unwrap());
+                            if let Some(race) = race {
+                                let race_id = race.race_id;
+                                // A race still running ends with the new one
+                                if let Some(mut previous) =
+                                    state.active_races.lock().unwrap().insert(pid, race)
+                                {
+                                    record_race(&state, pid, &mut previous);
+                                }
+                                end_race_after_timeout(&state, pid, race_id);
+                                record_map_play(&state, map_id.unwrap());
+                            }
+
+                            // Everyone readies up again for the next race
+                            if let Err(e) = membership::reset_ready(conn, pid).await {
+                                tracing::error!("Error resetting ready states: {}", e);
+                            }
+
+                            // Line up the next map of the party's playlist
+                            if let Some(party) = racing_party
+                                && let Err(e) = advance_playlist(party, conn).await
+                            {
+                                tracing::error!("Error advancing party playlist: {}", e);
+                            }
+
+                            tracing::info!("Race started in party {}", pid);
                         }
                     }
                 }
@@ -293,6 +713,83 @@ async fn handle_socket(
                         continue;
                     }
 
+                    // Advance the racer's checkpoint progress (x is longitude, z is latitude)
+                    let (progress, standings, winner, race_info) = {
+                        let mut active_races = state.active_races.lock().unwrap();
+                        match active_races.get_mut(&party_id.unwrap()) {
+                            Some(race) => {
+                                race.record_frame(
+                                    authenticated_user_id,
+                                    [
+                                        player_state.position.x,
+                                        player_state.position.y,
+                                        player_state.position.z,
+                                    ],
+                                    [
+                                        player_state.rotation.yaw,
+                                        player_state.rotation.pitch,
+                                        player_state.rotation.roll,
+                                    ],
+                                );
+                                let progress = race.update(
+                                    authenticated_user_id,
+                                    (
+                                        player_state.position.z as f64,
+                                        player_state.position.x as f64,
+                                    ),
+                                );
+                                // Re-rank the finishers whenever someone crosses the line
+                                let standings = progress
+                                    .filter(|progress| progress.just_finished)
+                                    .map(|_| race.standings());
+                                let winner = standings.as_ref().and_then(|_| race.take_winner());
+                                if race.is_complete() {
+                                    record_race(&state, party_id.unwrap(), race);
+                                }
+                                // Identifies the race in rewards, so they are paid once
+                                let race_key = format!(
+                                    "race:{}:{}",
+                                    party_id.unwrap(),
+                                    race.started_at.timestamp_millis()
+                                );
+                                (progress, standings, winner, Some((race_key, race.map_id)))
+                            }
+                            None => (None, None, None, None),
+                        }
+                    };
+
+                    // Count towards achievements
+                    if let Some(progress) = progress
+                        && progress.checkpoints_passed > 0
+                    {
+                        record_checkpoints(
+                            &state,
+                            authenticated_user_id,
+                            progress.checkpoints_passed,
+                        );
+                    }
+                    if let Some(winner) = winner
+                        && let Some((race_key, map_id)) = &race_info
+                    {
+                        record_win(&state, winner, race_key, party_id.unwrap(), *map_id);
+                    }
+                    if progress.is_some_and(|progress| progress.just_finished)
+                        && let Some((race_key, map_id)) = &race_info
+                    {
+                        let finish_time_ms = standings
+                            .iter()
+                            .flatten()
+                            .find(|standing| standing.user_id == authenticated_user_id)
+                            .map(|standing| standing.finish_time_ms);
+                        record_finish(
+                            &state,
+                            authenticated_user_id,
+                            race_key,
+                            *map_id,
+                            finish_time_ms,
+                        );
+                    }
+
                     // Broadcast the update to all members of the party
                     if let Some(channel) = &party_tx {
                         let message_str = serde_json::to_string(&WsMessage::Update {
@@ -303,6 +800,29 @@ async fn handle_socket(
                         if let Err(e) = channel.send(message_str) {
                             tracing::error!("Error broadcasting message: {}", e);
                         }
+
+                        if let Some(progress) = progress {
+                            let progress_msg = serde_json::to_string(&WsMessage::ProgressUpdate {
+                                user_id: authenticated_user_id,
+                                next_checkpoint: progress.next_checkpoint,
+                                completion_pct: progress.completion_pct,
+                            })
+                            .unwrap();
+
+                            if let Err(e) = channel.send(progress_msg) {
+                                tracing::error!("Error broadcasting progress: {}", e);
+                            }
+                        }
+
+                        if let Some(standings) = standings {
+                            let standings_msg =
+                                serde_json::to_string(&WsMessage::FinishStandings { standings })
+                                    .unwrap();
+
+                            if let Err(e) = channel.send(standings_msg) {
+                                tracing::error!("Error broadcasting standings: {}", e);
+                            }
+                        }
                     }
                 }
                 Ok(WsMessage::Disconnect { user_id: uid }) => {
@@ -333,6 +853,31 @@ async fn handle_socket(
             }
         }
 
+        {
+            if let Ok(mut user_regions_lock) = user_regions.try_lock() {
+                user_regions_lock.remove(&uid);
+            }
+        }
+
+        // Unregister our command channel unless a newer connection replaced it
+        let replaced = {
+            let mut user_sockets_lock = state.user_sockets.lock().unwrap();
+            if user_sockets_lock
+                .get(&uid)
+                .is_some_and(|sender| sender.same_channel(&command_tx))
+            {
+                user_sockets_lock.remove(&uid);
+                false
+            } else {
+                true
+            }
+        };
+
+        // The newer connection keeps the user online
+        if !replaced {
+            state.presence.set_offline(uid).await;
+        }
+
         if let Some(pid) = party_id {
             if let Some(channel) = &party_tx {
                 // Notify others of disconnection
@@ -341,17 +886,14 @@ async fn handle_socket(
 
                 let _ = channel.send(disconnect_msg);
 
+                // The race doesn't wait for a racer who left
+                leave_race(&state, pid, uid);
+
                 // Clean up empty party channels
-                {
-                    let party_channels_lock = party_channels.lock().unwrap();
-                    if let Some(ch) = party_channels_lock.get(&pid) {
-                        if ch.receiver_count() == 0 {
-                            drop(party_channels_lock);
-                            let mut party_channels_lock = party_channels.lock().unwrap();
-                            party_channels_lock.remove(&pid);
-                        }
-                    }
-                }
+                remove_unused_party_channel(&state, pid);
+
+                // An owner who doesn't come back hands the party over
+                succession::owner_disconnected(&state, uid, pid).await;
             }
         }
     }
@@ -367,45 +909,495 @@ async fn handle_socket(
     tracing::debug!("WebSocket connection closed");
 }
 
-// Helper function to verify a user is in a party
-async fn verify_user_in_party(
+// Helper function to get or create the broadcast channel of a party
+fn party_channel(state: &AppState, party_id: i32) -> broadcast::Sender<String> {
+    let mut party_channels_lock = state.party_channels.lock().unwrap();
+    party_channels_lock
+        .entry(party_id)
+        .or_insert_with(|| {
+            let (new_tx, _) = broadcast::channel(100);
+            new_tx
+        })
+        .clone()
+}
+
+// Helper function to drop a party channel nobody listens to anymore
+fn remove_unused_party_channel(state: &AppState, party_id: i32) {
+    let mut party_channels_lock = state.party_channels.lock().unwrap();
+    if let Some(ch) = party_channels_lock.get(&party_id)
+        && ch.receiver_count() == 0
+    {
+        party_channels_lock.remove(&party_id);
+        if let Some(mut race) = state.active_races.lock().unwrap().remove(&party_id) {
+            record_race(state, party_id, &mut race);
+        }
+    }
+}
+
+/// Stop waiting for a racer who left a party in the middle of its race, and
+/// save the race if nobody else is still out on the course
+pub fn leave_race(state: &AppState, party_id: i32, user_id: i32) {
+    let mut active_races = state.active_races.lock().unwrap();
+    if let Some(race) = active_races.get_mut(&party_id) {
+        race.drop_out(user_id);
+        if race.is_complete() {
+            record_race(state, party_id, race);
+        }
+    }
+}
+
+// Helper function to end a race that is still running once the race timeout
+// is over, so racers who never finish don't keep it from being saved
+fn end_race_after_timeout(state: &AppState, party_id: i32, race_id: i32) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(state.config.race_timeout)).await;
+
+        // The party may have started another race meanwhile
+        let race = {
+            let mut active_races = state.active_races.lock().unwrap();
+            if active_races
+                .get(&party_id)
+                .is_some_and(|race| race.race_id == race_id)
+            {
+                active_races.remove(&party_id)
+            } else {
+                None
+            }
+        };
+        if let Some(mut race) = race {
+            record_race(&state, party_id, &mut race);
+            tracing::info!("Race {} of party {} timed out", race_id, party_id);
+        }
+    });
+}
+
+// Helper function to tell a party that a member connected
+async fn announce_party_member(
+    channel: &broadcast::Sender<String>,
     user_id: i32,
     party_id: i32,
     conn: &sea_orm::DatabaseConnection,
-) -> bool {
-    // Check if party exists first
-    match Party::find_by_id(party_id).one(conn).await {
-        Ok(Some(_)) => {
-            // Now check if user is in the party
-            matches!(
-                UserParty::find()
-                    .filter(entity::user_party::Column::UserId.eq(user_id))
-                    .filter(entity::user_party::Column::PartyId.eq(party_id))
-                    .one(conn)
-                    .await,
-                Ok(Some(_))
+) {
+    // Get the User profile. Users merged into another account or deleted
+    // meanwhile have nobody to announce.
+    let user = match User::find_by_id(user_id).one(conn).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Error loading user {}: {}", user_id, e);
+            return;
+        }
+    };
+
+    // Get the selected loadout, if the user has one
+    let loadout = VehicleLoadout::find()
+        .filter(vehicle_loadout::Column::UserId.eq(user_id))
+        .filter(vehicle_loadout::Column::Selected.eq(true))
+        .one(conn)
+        .await
+        .unwrap_or_default()
+        .map(|loadout| Loadout {
+            model: loadout.model,
+            color: loadout.color,
+            decals: loadout.decals,
+        });
+
+    let team = membership::team_of(conn, user_id, party_id)
+        .await
+        .unwrap_or_default();
+
+    let connect_msg = serde_json::to_string(&WsMessage::NewPartyMember {
+        user_id,
+        name: user.name,
+        avatar_url: user.avatar_url,
+        country: user.country,
+        bio: user.bio,
+        favorite_vehicle: user.favorite_vehicle,
+        loadout,
+        team,
+    })
+    .unwrap();
+
+    let _ = channel.send(connect_msg);
+}
+
+// Helper function to save the results and replay of a race that ended and
+// remember who took part in it in the background
+fn record_race(state: &AppState, party_id: i32, race: &mut RaceProgress) {
+    let results = race.take_results();
+    let participants = race.take_participants();
+    let replay = race.take_replay();
+
+    let race_id = race.race_id;
+    let map_id = race.map_id;
+    let awards = results
+        .as_ref()
+        .map(|results| awards::compute(results, race.checkpoint_count()))
+        .unwrap_or_default();
+    announce_awards(state, party_id, race_id, &awards);
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Some(results) = results {
+            match races::finish(&state.conn, race_id, &results, &awards).await {
+                Ok(_) => state.map_stats.invalidate(map_id),
+                Err(e) => tracing::error!("Error saving race: {}", e),
+            }
+        }
+        if let Some(replay) = replay
+            && let Err(e) = replays::save(&state.conn, race_id, replay).await
+        {
+            tracing::error!("Error saving replay: {}", e);
+        }
+        if let Some(participants) = participants
+            && let Err(e) =
+                recent_players::record_race(&state.conn, party_id, map_id, &participants).await
+        {
+            tracing::error!("Error recording recent players: {}", e);
+        }
+    });
+}
+
+// Helper function to count checkpoints towards achievements in the background
+fn record_checkpoints(state: &AppState, user_id: i32, passed: usize) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        match achievements::record_checkpoints(&state.conn, user_id, passed as i64).await {
+            Ok(unlocked) => announce_achievements(&state, user_id, unlocked),
+            Err(e) => tracing::error!("Error recording checkpoints: {}", e),
+        }
+    });
+}
+
+// Helper function to count a race win towards achievements, pay the win bonus
+// and add the win to the winner's activity in the background
+fn record_win(state: &AppState, user_id: i32, race_key: &str, party_id: i32, map_id: i32) {
+    let state = state.clone();
+    let grant_key = format!("{}:win", race_key);
+    tokio::spawn(async move {
+        match achievements::record_win(&state.conn, user_id).await {
+            Ok(unlocked) => announce_achievements(&state, user_id, unlocked),
+            Err(e) => tracing::error!("Error recording race win: {}", e),
+        }
+        if let Err(e) = wallet::grant(
+            &state.conn,
+            user_id,
+            wallet::RACE_WIN_COINS,
+            wallet::RACE_WIN_REASON,
+            &grant_key,
+        )
+        .await
+        {
+            tracing::error!("Error paying race win bonus: {}", e);
+        }
+        if let Err(e) = activity::record(
+            &state.conn,
+            user_id,
+            activity::Kind::RaceWon,
+            Some(map_id),
+            Some(party_id),
+        )
+        .await
+        {
+            tracing::error!("Error recording race win activity: {}", e);
+        }
+    });
+}
+
+// Helper function to award the XP and coins for finishing a race, and count
+// the time towards the daily challenge, in the background
+fn record_finish(
+    state: &AppState,
+    user_id: i32,
+    race_key: &str,
+    map_id: i32,
+    finish_time_ms: Option<u64>,
+) {
+    let state = state.clone();
+    let grant_key = format!("{}:finish", race_key);
+    tokio::spawn(async move {
+        if let Some(finish_time_ms) = finish_time_ms
+            && let Err(e) = challenges::record_result(
+                &state.conn,
+                user_id,
+                map_id,
+                finish_time_ms as i64,
+                chrono::Utc::now(),
             )
+            .await
+        {
+            tracing::error!("Error recording daily challenge result: {}", e);
         }
-        _ => false,
+        if let Err(e) = progression::award(&state.conn, user_id, progression::RACE_FINISH_XP).await
+        {
+            tracing::error!("Error awarding race finish XP: {}", e);
+        }
+        if let Err(e) = wallet::grant(
+            &state.conn,
+            user_id,
+            wallet::RACE_FINISH_COINS,
+            wallet::RACE_FINISH_REASON,
+            &grant_key,
+        )
+        .await
+        {
+            tracing::error!("Error paying race finish reward: {}", e);
+        }
+    });
+}
+
+// Helper function to count a play of a map towards its author's achievements
+// in the background
+fn record_map_play(state: &AppState, map_id: i32) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        match achievements::record_map_play(&state.conn, map_id).await {
+            Ok(Some((author_id, unlocked))) => announce_achievements(&state, author_id, unlocked),
+            Ok(None) => {}
+            Err(e) => tracing::error!("Error recording map play: {}", e),
+        }
+    });
+}
+
+/// Send a message to the websocket connection of a user, if they are
+/// connected. Returns whether the message was handed to the connection.
+pub fn notify_user(state: &AppState, user_id: i32, message: &WsMessage) -> bool {
+    let Some(socket) = state.user_sockets.lock().unwrap().get(&user_id).cloned() else {
+        return false;
+    };
+
+    match serde_json::to_string(message) {
+        Ok(msg) => socket.send(SocketCommand::Notify(msg)).is_ok(),
+        Err(e) => {
+            tracing::error!("Error serializing notification: {}", e);
+            false
+        }
+    }
+}
+
+// Helper function to tell the party a user is connected to about the
+// achievements they unlocked
+fn announce_achievements(state: &AppState, user_id: i32, unlocked: Vec<achievement::Model>) {
+    if unlocked.is_empty() {
+        return;
+    }
+
+    let Some(party_id) = state.user_parties.lock().unwrap().get(&user_id).copied() else {
+        return;
+    };
+    let Some(channel) = state.party_channels.lock().unwrap().get(&party_id).cloned() else {
+        return;
+    };
+
+    for achievement in unlocked {
+        let unlocked_msg = serde_json::to_string(&WsMessage::AchievementUnlocked {
+            user_id,
+            key: achievement.key,
+            name: achievement.name,
+            description: achievement.description,
+        })
+        .unwrap();
+
+        let _ = channel.send(unlocked_msg);
     }
 }
 
+// Helper function to tell a party the awards handed out at the end of its race
+fn announce_awards(state: &AppState, party_id: i32, race_id: i32, awards: &[Award]) {
+    if awards.is_empty() {
+        return;
+    }
+
+    let Some(channel) = state.party_channels.lock().unwrap().get(&party_id).cloned() else {
+        return;
+    };
+
+    let awards_msg = serde_json::to_string(&WsMessage::Awards {
+        race_id,
+        awards: awards.to_vec(),
+    })
+    .unwrap();
+
+    let _ = channel.send(awards_msg);
+}
+
+// Helper function to forward party broadcasts to the client
+fn forward_party_messages(
+    channel: &broadcast::Sender<String>,
+    tx: mpsc::Sender<Message>,
+) -> JoinHandle<()> {
+    let mut party_rx = channel.subscribe();
+
+    // Spawn a task to listen for party broadcasts and forward to the client
+    tokio::spawn(async move {
+        while let Ok(msg) = party_rx.recv().await {
+            if tx.send(Message::Text(msg.into())).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+// Helper function to get the weather and time of day of a map, or the
+// defaults if it can't be loaded
+async fn race_conditions(map_id: i32, conn: &sea_orm::DatabaseConnection) -> (Weather, TimeOfDay) {
+    match Map::find_by_id(map_id).one(conn).await {
+        Ok(Some(map)) => (
+            Weather::from_column(&map.weather),
+            TimeOfDay::from_column(&map.time_of_day),
+        ),
+        Ok(None) => (Weather::default(), TimeOfDay::default()),
+        Err(e) => {
+            tracing::error!("Error loading race conditions: {}", e);
+            (Weather::default(), TimeOfDay::default())
+        }
+    }
+}
+
+// Helper function to load the course of a map for server-side race tracking
+async fn load_race(
+    map_id: i32,
+    party_id: i32,
+    state: &AppState,
+) -> Result<RaceProgress, sea_orm::DbErr> {
+    let conn = &state.conn;
+    // The map may have been deleted, hidden or unpublished since the party
+    // picked it
+    let map = Map::find_by_id(map_id)
+        .filter(entity::map::Column::Status.ne(MapStatus::Draft.as_str()))
+        .filter(entity::map::Column::HiddenAt.is_null())
+        .filter(entity::map::Column::DeletedAt.is_null())
+        .one(conn)
+        .await?
+        .ok_or(sea_orm::DbErr::RecordNotFound(format!(
+            "Map with id {} not found",
+            map_id
+        )))?;
+
+    let checkpoints = Checkpoint::find()
+        .filter(entity::checkpoint::Column::MapId.eq(map_id))
+        .all(conn)
+        .await?;
+
+    // Apply the party's race settings
+    let settings = party_settings::load(conn, party_id).await?;
+
+    let playlist_id = Party::find_by_id(party_id)
+        .one(conn)
+        .await?
+        .and_then(|party| party.playlist_id);
+    let race = races::start(conn, party_id, map_id, playlist_id).await?;
+
+    // Everyone connected to the party races, whether or not they move
+    let racers: Vec<UserId> = state
+        .user_parties
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, racer_party_id)| **racer_party_id == party_id)
+        .map(|(user_id, _)| *user_id)
+        .collect();
+
+    let race = RaceProgress::new(&race, &map, &checkpoints)
+        .with_forgiveness(settings.checkpoint_forgiveness() as f64)
+        .with_racers(racers);
+
+    // Record a replay if the server is configured to
+    Ok(match state.config.replay_sample_interval {
+        Some(interval) => race.with_replay(ReplayRecorder::new(
+            Duration::from_millis(interval),
+            state.config.replay_max_frames,
+        )),
+        None => race,
+    })
+}
+
+// Helper function to move a party on to the next map of its playlist
+async fn advance_playlist(
+    party: party::Model,
+    conn: &sea_orm::DatabaseConnection,
+) -> Result<(), sea_orm::DbErr> {
+    let Some(playlist_id) = party.playlist_id else {
+        return Ok(());
+    };
+
+    let maps = playlists::playlist_maps(playlist_id, conn).await?;
+    if maps.is_empty() {
+        return Ok(());
+    }
+
+    let next_position = (party.playlist_position as usize + 1) % maps.len();
+
+    let mut party_model: party::ActiveModel = party.into();
+    party_model.map_id = Set(maps[next_position].id);
+    party_model.playlist_position = Set(next_position as i32);
+    party_model.update(conn).await?;
+
+    Ok(())
+}
+
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/ws", get(ws_handler))
+        .route("/ws/ticket", post(create_ws_ticket))
         .route("/ws/docs", get(ws_documentation))
 }
 
+/// Get a single-use ticket to open a websocket connection
+#[utoipa::path(
+    post,
+    path = "/api/ws/ticket",
+    tag = "ws",
+    responses(
+        (status = 200, description = "Ticket created successfully", body = WsTicketResponse),
+        (status = 401, description = "Unauthorized", body = String),
+        (status = 403, description = "Token is not valid for websocket connections", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+async fn create_ws_ticket(
+    State(state): State<AppState>,
+    TokenUser(claims): TokenUser,
+) -> Result<Json<WsTicketResponse>, (StatusCode, String)> {
+    // Tokens scoped to something else can't open connections
+    if !claims.allows(Scope::Ws) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            auth::AuthError::InsufficientScope.to_string(),
+        ));
+    }
+
+    let ticket = state
+        .auth
+        .generate_ws_ticket(&claims)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(WsTicketResponse {
+        ticket,
+        expires_in: WS_TICKET_EXPIRY,
+    }))
+}
+
 #[axum::debug_handler]
+#[allow(clippy::let_and_return)]
 async fn ws_documentation() -> impl IntoResponse {
     let docs = r#"
     WebSocket Connection Documentation:
     
     To connect to the WebSocket, you need to provide:
-    1. A valid JWT token in the 'token' query parameter
+    1. A ticket in the 'ticket' query parameter. Get one with POST /api/ws/ticket
+       using a full access token or one scoped to 'ws'; tickets can be used
+       once and expire after 30 seconds
     2. Optionally, a party_id parameter if you want to pre-validate party membership
     
-    Example URL: ws://your-server.com/api/ws?token=your.jwt.token&party_id=123
+    3. Your client version in the 'client_version' parameter (required when the
+       server enforces a supported version range)
+    
+    Example URL: ws://your-server.com/api/ws?ticket=your.ws.ticket&party_id=123&client_version=1.2.0
     
     Message Format:
     All messages use JSON format with a "type" field determining the message type.
@@ -451,12 +1443,185 @@ async fn ws_documentation() -> impl IntoResponse {
     5. Race started notification (sent to all party members):
     {
         "type": "RaceStarted",
-        "countdown": 3
+        "race_id": 42,
+        "weather": "rain",
+        "time_of_day": "night"
+    }
+    
+    6. Announcement from the server operators (sent to all parties):
+    {
+        "type": "Announcement",
+        "message": "Servers restart in 5 minutes"
+    }
+    
+    7. Race progress derived from server-side checkpoint tracking (sent to all
+       party members about once per second per racer, and whenever a
+       checkpoint is passed). next_checkpoint equals the number of checkpoints
+       plus one once the racer has crossed the finish line:
+    {
+        "type": "ProgressUpdate",
+        "user_id": 42,
+        "next_checkpoint": 2,
+        "completion_pct": 37.5
+    }
+    
+    8. Finish standings (sent to all party members whenever a racer crosses
+       the finish line). Finish times are interpolated between the last two
+       position samples, so racers finishing within the same update are still
+       ranked fairly; gap_ms is the gap to the winner:
+    {
+        "type": "FinishStandings",
+        "standings": [
+            { "user_id": 42, "place": 1, "finish_time_ms": 83412, "gap_ms": 0 },
+            { "user_id": 7, "place": 2, "finish_time_ms": 83455, "gap_ms": 43 }
+        ]
+    }
+    
+    9. Your connection was moved to another party after a split or merge (sent
+       only to the moved member; updates now flow to and from the new party):
+    {
+        "type": "PartyMoved",
+        "party_id": 124
+    }
+    
+    10. Another party asked to merge your party into it (sent to your party;
+       the owner accepts with POST /api/parties/{id}/merge/accept):
+    {
+        "type": "MergeRequested",
+        "party_id": 124,
+        "name": "Sunday Racers"
+    }
+    
+    11. A member of your party unlocked an achievement (sent to the party the
+       member is connected to):
+    {
+        "type": "AchievementUnlocked",
+        "user_id": 42,
+        "key": "first_win",
+        "name": "First Win",
+        "description": "Win a race"
+    }
+    
+    12. Awards of a race, computed once the race is over (sent to all party
+       members, and only if anyone earned one). The value is the number of
+       legs, checkpoints or places for fastest_legs, first_to_checkpoints and
+       biggest_comeback, and the spread of the racer's pace in thousandths
+       for most_consistent (lower is steadier):
+    {
+        "type": "Awards",
+        "race_id": 42,
+        "awards": [
+            { "kind": "fastest_legs", "user_id": 42, "value": 3 },
+            { "kind": "most_consistent", "user_id": 7, "value": 41 },
+            { "kind": "first_to_checkpoints", "user_id": 42, "value": 4 },
+            { "kind": "biggest_comeback", "user_id": 7, "value": 2 }
+        ]
+    }
+    
+    13. A member of your party changed whether they are ready (sent to all
+       party members after PUT /api/parties/{id}/ready):
+    {
+        "type": "ReadyStateChanged",
+        "user_id": 42,
+        "ready": true
+    }
+    
+    14. Every member of your party is ready (sent to all party members right
+       after the ReadyStateChanged message that made everyone ready):
+    {
+        "type": "AllReady"
+    }
+    
+    15. You were invited to a party (sent only to the invitee, on every
+       connection they have open; accept or decline with
+       POST /api/invites/{id}/accept or /decline before it expires):
+    {
+        "type": "PartyInvite",
+        "invite_id": 17,
+        "party_id": 123,
+        "party_name": "Sunday Racers",
+        "inviter_id": 7,
+        "inviter_name": "alice",
+        "expires_at": "2025-06-01T12:15:00+00:00"
+    }
+    
+    16. The code of your party was regenerated (sent to all party members;
+       the previous code no longer works):
+    {
+        "type": "PartyCodeChanged",
+        "party_id": 123,
+        "code": "K7QM2XPA"
+    }
+    
+    17. The race settings of your party changed (sent to all party members
+       after PATCH /api/parties/{id}/settings, with every setting):
+    {
+        "type": "PartySettingsChanged",
+        "party_id": 123,
+        "settings": {
+            "laps": 3,
+            "vehicle_class": "any",
+            "collisions": true,
+            "time_limit": 0,
+            "checkpoint_forgiveness": 0,
+            "updated_at": "2025-06-01T12:00:00+00:00"
+        }
+    }
+    
+    18. Your party switched to another map (sent to all party members when the
+       owner picks a map or attaches a playlist):
+    {
+        "type": "MapChanged",
+        "party_id": 123,
+        "map_id": 9,
+        "title": "Harbor Loop"
+    }
+    
+    19. Matchmaking put you in a party (sent only to each matched player, who
+       is already a member of the new party; reconnect with its party_id):
+    {
+        "type": "MatchFound",
+        "party_id": 124,
+        "code": "K7QM2XPA",
+        "map_id": 9,
+        "player_ids": [42, 7, 13]
+    }
+    
+    20. The teams of your party changed (sent to all party members when a
+       member is assigned to a team or the teams are balanced, with every
+       member; team is null for members not on a team):
+    {
+        "type": "TeamsChanged",
+        "party_id": 123,
+        "teams": [
+            { "user_id": 42, "team": 1 },
+            { "user_id": 7, "team": 2 },
+            { "user_id": 13, "team": null }
+        ]
+    }
+    
+    21. Your party has a new owner (sent to all party members when the owner
+       hands the party over, or when the owner's connection dropped and they
+       didn't come back within the grace period):
+    {
+        "type": "OwnerChanged",
+        "party_id": 123,
+        "owner_id": 7,
+        "previous_owner_id": 42
+    }
+    
+    22. Your party was disbanded because its owner left and nobody else was
+       left to take it over (sent to all party members still connected):
+    {
+        "type": "PartyDisbanded",
+        "party_id": 123
     }
     
     Authentication:
-    - You must provide a valid JWT token as a query parameter
-    - Your user_id in messages must match the authenticated user ID from the token
+    - You must provide a valid ticket from POST /api/ws/ticket as the 'ticket'
+      query parameter; each ticket opens a single connection, so get a new one
+      before reconnecting
+    - Your user_id in messages must match the user the ticket was issued to
     - You must be a member of a party to send/receive updates within that party
     "#;
 
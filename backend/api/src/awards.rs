@@ -0,0 +1,279 @@
+//! Awards handed out when a race ends.
+//!
+//! Once a race is finalized, the times the server measured at each checkpoint
+//! are looked at for a few awards to show on the results screen. A leg runs
+//! from the start or a checkpoint to the next checkpoint or the finish line;
+//! legs a racer skipped or didn't get to don't count for them. Races of a
+//! single racer hand out no awards, and awards nobody earned are left out.
+//! Ties go to the better placed racer.
+
+use entity::race_award::{self, Entity as RaceAward};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, Set, sea_query::OnConflict};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::db::UserId;
+use crate::race::RacerResult;
+
+/// What an award is given for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AwardKind {
+    /// Fastest time on the most legs; the value is the number of legs
+    FastestLegs,
+    /// Steadiest pace compared to the field over every leg; the value is the
+    /// spread of the racer's pace in thousandths, lower being steadier
+    MostConsistent,
+    /// First to pass the most checkpoints; the value is the number of
+    /// checkpoints
+    FirstToCheckpoints,
+    /// Most places gained from the racer's worst running position to the
+    /// finish; the value is the number of places
+    BiggestComeback,
+}
+
+impl AwardKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AwardKind::FastestLegs => "fastest_legs",
+            AwardKind::MostConsistent => "most_consistent",
+            AwardKind::FirstToCheckpoints => "first_to_checkpoints",
+            AwardKind::BiggestComeback => "biggest_comeback",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Option<Self> {
+        match value {
+            "fastest_legs" => Some(AwardKind::FastestLegs),
+            "most_consistent" => Some(AwardKind::MostConsistent),
+            "first_to_checkpoints" => Some(AwardKind::FirstToCheckpoints),
+            "biggest_comeback" => Some(AwardKind::BiggestComeback),
+            _ => None,
+        }
+    }
+}
+
+/// An award given to a racer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct Award {
+    pub kind: AwardKind,
+    pub user_id: UserId,
+    pub value: i64,
+}
+
+/// Work out the awards of a race from how every racer did
+pub fn compute(results: &[RacerResult], checkpoint_count: usize) -> Vec<Award> {
+    if results.len() < 2 {
+        return Vec::new();
+    }
+
+    // When each racer passed each checkpoint and then the finish line
+    let passages: Vec<Vec<Option<u64>>> = results
+        .iter()
+        .map(|result| {
+            let mut passages = result.splits_ms.clone();
+            passages.resize(checkpoint_count, None);
+            passages.push(result.finish_time_ms);
+            passages
+        })
+        .collect();
+    let legs: Vec<Vec<Option<u64>>> = passages
+        .iter()
+        .map(|passages| leg_times(passages))
+        .collect();
+
+    [
+        fastest_legs(results, &legs),
+        most_consistent(results, &legs),
+        first_to_checkpoints(results, &passages, checkpoint_count),
+        biggest_comeback(results, &passages, checkpoint_count),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Save the awards of a race
+pub async fn save<C: ConnectionTrait>(db: &C, race_id: i32, awards: &[Award]) -> Result<(), DbErr> {
+    if awards.is_empty() {
+        return Ok(());
+    }
+
+    RaceAward::insert_many(awards.iter().map(|award| race_award::ActiveModel {
+        race_id: Set(race_id),
+        user_id: Set(award.user_id),
+        kind: Set(award.kind.as_str().to_string()),
+        value: Set(award.value),
+        ..Default::default()
+    }))
+    .on_conflict(
+        OnConflict::columns([race_award::Column::RaceId, race_award::Column::Kind])
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+// Helper function to get how long each leg took from the passages of a racer
+fn leg_times(passages: &[Option<u64>]) -> Vec<Option<u64>> {
+    let mut previous = Some(0);
+    passages
+        .iter()
+        .map(|passage| {
+            let time = Some(passage.as_ref()?.saturating_sub(previous?));
+            previous = *passage;
+            time
+        })
+        .collect()
+}
+
+// Helper function to find who was quickest at each index, e.g. on a leg
+fn quickest(times: &[Vec<Option<u64>>], index: usize) -> Option<usize> {
+    times
+        .iter()
+        .enumerate()
+        .filter_map(|(racer, times)| Some((times.get(index).copied()??, racer)))
+        .min()
+        .map(|(_, racer)| racer)
+}
+
+// Helper function to pick the racer with the lowest score, breaking ties by
+// placement
+fn best<K: Ord + Copy>(
+    results: &[RacerResult],
+    scores: impl Iterator<Item = (usize, K)>,
+) -> Option<usize> {
+    scores
+        .min_by_key(|(racer, score)| {
+            let result = &results[*racer];
+            (*score, result.place.unwrap_or(usize::MAX), result.user_id)
+        })
+        .map(|(racer, _)| racer)
+}
+
+// Helper function to give an award to whoever counts the most of something
+fn most(results: &[RacerResult], kind: AwardKind, counts: HashMap<usize, i64>) -> Option<Award> {
+    let racer = best(
+        results,
+        counts
+            .iter()
+            .map(|(racer, count)| (*racer, Reverse(*count))),
+    )?;
+
+    Some(Award {
+        kind,
+        user_id: results[racer].user_id,
+        value: counts[&racer],
+    })
+}
+
+fn fastest_legs(results: &[RacerResult], legs: &[Vec<Option<u64>>]) -> Option<Award> {
+    let leg_count = legs.iter().map(Vec::len).max().unwrap_or(0);
+    let mut counts: HashMap<usize, i64> = HashMap::new();
+    for leg in 0..leg_count {
+        if let Some(racer) = quickest(legs, leg) {
+            *counts.entry(racer).or_default() += 1;
+        }
+    }
+
+    most(results, AwardKind::FastestLegs, counts)
+}
+
+fn first_to_checkpoints(
+    results: &[RacerResult],
+    passages: &[Vec<Option<u64>>],
+    checkpoint_count: usize,
+) -> Option<Award> {
+    let mut counts: HashMap<usize, i64> = HashMap::new();
+    for checkpoint in 0..checkpoint_count {
+        if let Some(racer) = quickest(passages, checkpoint) {
+            *counts.entry(racer).or_default() += 1;
+        }
+    }
+
+    most(results, AwardKind::FirstToCheckpoints, counts)
+}
+
+// Pace on a leg is the racer's time over the median time of the field, so
+// short and long legs weigh the same. Only finishers with at least two legs
+// that others ran too are considered.
+fn most_consistent(results: &[RacerResult], legs: &[Vec<Option<u64>>]) -> Option<Award> {
+    let leg_count = legs.iter().map(Vec::len).max().unwrap_or(0);
+    let medians: Vec<Option<f64>> = (0..leg_count)
+        .map(|leg| {
+            let mut times: Vec<u64> = legs.iter().filter_map(|legs| legs[leg]).collect();
+            if times.len() < 2 {
+                return None;
+            }
+            times.sort_unstable();
+            let median = times[times.len() / 2] as f64;
+            (median > 0.0).then_some(median)
+        })
+        .collect();
+
+    let spreads = legs.iter().enumerate().filter_map(|(racer, legs)| {
+        results[racer].finish_time_ms?;
+        let paces: Vec<f64> = legs
+            .iter()
+            .zip(&medians)
+            .filter_map(|(time, median)| Some(time.as_ref().copied()? as f64 / (*median)?))
+            .collect();
+        if paces.len() < 2 {
+            return None;
+        }
+
+        let mean = paces.iter().sum::<f64>() / paces.len() as f64;
+        let variance =
+            paces.iter().map(|pace| (pace - mean).powi(2)).sum::<f64>() / paces.len() as f64;
+        Some((racer, (variance.sqrt() * 1000.0).round() as i64))
+    });
+    let spreads: HashMap<usize, i64> = spreads.collect();
+
+    let racer = best(
+        results,
+        spreads.iter().map(|(racer, spread)| (*racer, *spread)),
+    )?;
+    Some(Award {
+        kind: AwardKind::MostConsistent,
+        user_id: results[racer].user_id,
+        value: spreads[&racer],
+    })
+}
+
+// Running positions are taken at each checkpoint, among the racers who
+// passed it
+fn biggest_comeback(
+    results: &[RacerResult],
+    passages: &[Vec<Option<u64>>],
+    checkpoint_count: usize,
+) -> Option<Award> {
+    let mut gains: HashMap<usize, i64> = HashMap::new();
+    for (racer, result) in results.iter().enumerate() {
+        let Some(place) = result.place else {
+            continue;
+        };
+
+        let worst = (0..checkpoint_count)
+            .filter_map(|checkpoint| {
+                let time = passages[racer][checkpoint]?;
+                let ahead = passages
+                    .iter()
+                    .filter(|passages| passages[checkpoint].is_some_and(|other| other < time))
+                    .count();
+                Some(ahead + 1)
+            })
+            .max()
+            .unwrap_or(place);
+
+        if worst > place {
+            gains.insert(racer, (worst - place) as i64);
+        }
+    }
+
+    most(results, AwardKind::BiggestComeback, gains)
+}
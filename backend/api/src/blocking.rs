@@ -0,0 +1,76 @@
+//! Blocks between users, shared by the REST and websocket handlers.
+//!
+//! A block is one-sided: the blocked user can't join the blocker's parties
+//! and is hidden from the blocker's member lists.
+
+use entity::block::{self, Entity as Block};
+use entity::party::Entity as Party;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::collections::HashSet;
+
+use crate::db::{PartyId, UserId};
+
+/// Whether a user has blocked another one
+pub async fn has_blocked(
+    db: &DatabaseConnection,
+    blocker_id: UserId,
+    blocked_id: UserId,
+) -> Result<bool, DbErr> {
+    let block = Block::find()
+        .filter(block::Column::BlockerId.eq(blocker_id))
+        .filter(block::Column::BlockedId.eq(blocked_id))
+        .one(db)
+        .await?;
+
+    Ok(block.is_some())
+}
+
+/// The users a user has blocked
+pub async fn blocked_ids(
+    db: &DatabaseConnection,
+    blocker_id: UserId,
+) -> Result<HashSet<UserId>, DbErr> {
+    let blocks = Block::find()
+        .filter(block::Column::BlockerId.eq(blocker_id))
+        .all(db)
+        .await?;
+
+    Ok(blocks.into_iter().map(|block| block.blocked_id).collect())
+}
+
+/// The users who have blocked a user
+pub async fn blocker_ids(
+    db: &DatabaseConnection,
+    blocked_id: UserId,
+) -> Result<HashSet<UserId>, DbErr> {
+    let blocks = Block::find()
+        .filter(block::Column::BlockedId.eq(blocked_id))
+        .all(db)
+        .await?;
+
+    Ok(blocks.into_iter().map(|block| block.blocker_id).collect())
+}
+
+/// The users a user has blocked or was blocked by
+pub async fn blocked_either_way(
+    db: &DatabaseConnection,
+    user_id: UserId,
+) -> Result<HashSet<UserId>, DbErr> {
+    let mut ids = blocked_ids(db, user_id).await?;
+    ids.extend(blocker_ids(db, user_id).await?);
+    Ok(ids)
+}
+
+/// Whether the owner of a party has blocked a user, which keeps the user out
+/// of the party
+pub async fn is_blocked_from_party(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    party_id: PartyId,
+) -> Result<bool, DbErr> {
+    let Some(party) = Party::find_by_id(party_id).one(db).await? else {
+        return Ok(false);
+    };
+
+    has_blocked(db, party.owner_id, user_id).await
+}
@@ -0,0 +1,154 @@
+//! Daily challenges.
+//!
+//! Every day (in UTC) one published map is the challenge of the day. The map
+//! is picked at random, seeded by the day, so picking it again yields the
+//! same map. A background task picks the challenge as the day starts, and it
+//! is picked on demand should nobody have picked it yet. Every race finished
+//! on the map during the day counts, and each player keeps their best time
+//! on the leaderboard of the day.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use entity::challenge_result::{self, Entity as ChallengeResult};
+use entity::daily_challenge::{self, Entity as DailyChallenge};
+use entity::map::{self, Entity as Map};
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    sea_query::{Expr, OnConflict},
+};
+
+use crate::api::maps::MapStatus;
+use crate::db::{AppState, UserId};
+
+// How often the challenge of the day is looked for
+const CHALLENGE_INTERVAL: u64 = 300; // in seconds
+
+/// Pick the challenge of each day in the background for as long as the
+/// server runs
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(CHALLENGE_INTERVAL));
+        loop {
+            interval.tick().await;
+
+            let today = Utc::now().date_naive();
+            if let Err(e) = challenge_of(&state.conn, today).await {
+                tracing::error!("Error picking the daily challenge: {}", e);
+            }
+        }
+    });
+}
+
+/// The challenge of a day, picked now if it wasn't yet. Days without any
+/// published map have none.
+pub async fn challenge_of<C: ConnectionTrait>(
+    db: &C,
+    day: NaiveDate,
+) -> Result<Option<daily_challenge::Model>, DbErr> {
+    if let Some(challenge) = find_challenge(db, day).await? {
+        return Ok(Some(challenge));
+    }
+
+    let Some(map_id) = pick_map_id(db, day).await? else {
+        return Ok(None);
+    };
+
+    // Another server may pick the same challenge at the same time
+    DailyChallenge::insert(daily_challenge::ActiveModel {
+        day: Set(day),
+        map_id: Set(map_id),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(daily_challenge::Column::Day)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    find_challenge(db, day).await
+}
+
+/// The challenge of a day, if one was picked
+pub async fn find_challenge<C: ConnectionTrait>(
+    db: &C,
+    day: NaiveDate,
+) -> Result<Option<daily_challenge::Model>, DbErr> {
+    DailyChallenge::find()
+        .filter(daily_challenge::Column::Day.eq(day))
+        .one(db)
+        .await
+}
+
+// Helper function to pick the map of a day among the published ones
+async fn pick_map_id<C: ConnectionTrait>(db: &C, day: NaiveDate) -> Result<Option<i32>, DbErr> {
+    let map_ids: Vec<i32> = Map::find()
+        .select_only()
+        .column(map::Column::Id)
+        .filter(map::Column::Status.eq(MapStatus::Published.as_str()))
+        .filter(map::Column::HiddenAt.is_null())
+        .filter(map::Column::DeletedAt.is_null())
+        .order_by_asc(map::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut rng = StdRng::seed_from_u64(day.num_days_from_ce() as u64);
+    Ok(map_ids.choose(&mut rng).copied())
+}
+
+/// Count a finished race towards today's challenge, if it was raced on its
+/// map. Only the best time of a user is kept. Returns whether it counted.
+pub async fn record_result<C: ConnectionTrait>(
+    db: &C,
+    user_id: UserId,
+    map_id: i32,
+    finish_time_ms: i64,
+    finished_at: DateTime<Utc>,
+) -> Result<bool, DbErr> {
+    let Some(challenge) = find_challenge(db, finished_at.date_naive()).await? else {
+        return Ok(false);
+    };
+    if challenge.map_id != map_id {
+        return Ok(false);
+    }
+
+    let inserted = ChallengeResult::insert(challenge_result::ActiveModel {
+        challenge_id: Set(challenge.id),
+        user_id: Set(user_id),
+        finish_time_ms: Set(finish_time_ms),
+        finished_at: Set(finished_at.fixed_offset()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([
+            challenge_result::Column::ChallengeId,
+            challenge_result::Column::UserId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    if inserted == 0 {
+        ChallengeResult::update_many()
+            .col_expr(
+                challenge_result::Column::FinishTimeMs,
+                Expr::value(finish_time_ms),
+            )
+            .col_expr(
+                challenge_result::Column::FinishedAt,
+                Expr::value(finished_at.fixed_offset()),
+            )
+            .filter(challenge_result::Column::ChallengeId.eq(challenge.id))
+            .filter(challenge_result::Column::UserId.eq(user_id))
+            .filter(challenge_result::Column::FinishTimeMs.gt(finish_time_ms))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,101 @@
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// Address or range of addresses of a reverse proxy whose forwarding headers
+/// are trusted, e.g. `10.0.0.1` or `172.16.0.0/12`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u32,
+}
+
+impl TrustedProxy {
+    /// Whether an address belongs to the proxy
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for TrustedProxy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match value.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (value, None),
+        };
+
+        let network = address
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|e| format!("{}: {}", value, e))?
+            .to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_len)
+                .ok_or_else(|| format!("{}: invalid prefix length", value))?,
+            None => max_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// Resolve the IP address of the client.
+///
+/// Clients may send any forwarding headers, so they are only believed when
+/// the peer is a trusted proxy. Proxies append the address they received a
+/// request from to `X-Forwarded-For`, so the right-most entry that isn't a
+/// trusted proxy is the client. `X-Real-IP` is used if a trusted proxy sets
+/// that instead.
+pub fn from_request(trusted: &[TrustedProxy], headers: &HeaderMap, peer: SocketAddr) -> String {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|proxy| proxy.contains(ip));
+    if !is_trusted(peer.ip()) {
+        return peer.ip().to_canonical().to_string();
+    }
+
+    let forwarded_for = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+
+    // Past the last trusted proxy, the entries were written by the client.
+    // An entry that isn't an address can't be trusted to be a proxy either.
+    if let Some(client) = forwarded_for
+        .iter()
+        .rev()
+        .find(|value| value.parse::<IpAddr>().map_or(true, |ip| !is_trusted(ip)))
+    {
+        return client.to_string();
+    }
+
+    headers
+        .get("x-real-ip")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| forwarded_for.first().map(|value| value.to_string()))
+        .unwrap_or_else(|| peer.ip().to_canonical().to_string())
+}
@@ -0,0 +1,132 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::db::AppState;
+
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+
+/// A `major.minor.patch` client version. Missing components default to 0 and
+/// any pre-release or build suffix (e.g. `-beta.1`, `+abc`) is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClientVersion(u64, u64, u64);
+
+impl FromStr for ClientVersion {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let core = s.trim().trim_start_matches('v');
+        let core = core.split(['-', '+']).next().unwrap_or_default();
+
+        let mut parts = [0u64; 3];
+        for (index, part) in core.split('.').enumerate() {
+            if index == parts.len() {
+                return Err(());
+            }
+            parts[index] = part.parse().map_err(|_| ())?;
+        }
+
+        Ok(Self(parts[0], parts[1], parts[2]))
+    }
+}
+
+impl fmt::Display for ClientVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// Structured "update required" error returned to outdated clients
+#[derive(Debug, Serialize)]
+pub struct UpdateRequired {
+    error: &'static str,
+    message: String,
+    client_version: Option<String>,
+    min_version: Option<String>,
+    max_version: Option<String>,
+    download_url: Option<String>,
+}
+
+impl IntoResponse for UpdateRequired {
+    fn into_response(self) -> Response {
+        (StatusCode::UPGRADE_REQUIRED, Json(self)).into_response()
+    }
+}
+
+/// Check a client version against the supported range from the config.
+///
+/// The gate is only active when `MIN_CLIENT_VERSION` or `MAX_CLIENT_VERSION`
+/// is configured; in that case a missing or malformed version is rejected.
+pub fn check(config: &Config, client_version: Option<&str>) -> Result<(), Box<UpdateRequired>> {
+    let min = config.min_client_version;
+    let max = config.max_client_version;
+
+    if min.is_none() && max.is_none() {
+        return Ok(());
+    }
+
+    let reject = |message: String| {
+        Box::new(UpdateRequired {
+            error: "update_required",
+            message,
+            client_version: client_version.map(str::to_string),
+            min_version: min.map(|v| v.to_string()),
+            max_version: max.map(|v| v.to_string()),
+            download_url: config.client_download_url.clone(),
+        })
+    };
+
+    let version = match client_version {
+        Some(raw) => raw
+            .parse::<ClientVersion>()
+            .map_err(|_| reject(format!("Invalid client version '{}'", raw)))?,
+        None => return Err(reject("Client version is required".to_string())),
+    };
+
+    if let Some(min) = min
+        && version < min
+    {
+        return Err(reject(format!(
+            "Client version {} is no longer supported, please update to {} or newer",
+            version, min
+        )));
+    }
+
+    if let Some(max) = max
+        && version > max
+    {
+        return Err(reject(format!(
+            "Client version {} is not supported by this server (newest supported is {})",
+            version, max
+        )));
+    }
+
+    Ok(())
+}
+
+/// Middleware rejecting REST calls whose `X-Client-Version` header is outside
+/// the supported range
+pub async fn require_client_version(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let client_version = req
+        .headers()
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    if let Err(update_required) = check(&state.config, client_version) {
+        return (*update_required).into_response();
+    }
+
+    next.run(req).await
+}
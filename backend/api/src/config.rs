@@ -1,14 +1,87 @@
+use auth::JwtKey;
+use auth::captcha::{CaptchaProvider, CaptchaVerifier};
+use auth::platform::{Platform, PlatformVerifier};
+use auth::voice::{VoiceProvider, VoiceTokenIssuer};
 use std::env;
 use thiserror::Error;
 
+use crate::client_ip::TrustedProxy;
+use crate::client_version::ClientVersion;
+use crate::road_snapping::{RoadSnapProvider, RoadSnapper};
+use crate::road_surfaces::RoadSurfaces;
+use crate::storage::ObjectStorage;
+
+// Origins of the frontend served by Docker Compose and of the Vite dev server
+const DEFAULT_ALLOWED_ORIGINS: &str = "http://localhost,http://localhost:5173";
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub server_host: String,
     pub server_port: u16,
-    pub jwt_secret: String,
-    pub jwt_expiry: i64,     // in seconds
-    pub refresh_expiry: i64, // in seconds
+    pub jwt_keys: Vec<JwtKey>, // Newest first; the first one signs new tokens
+    pub jwt_expiry: i64,       // in seconds
+    pub refresh_expiry: i64,   // in seconds
+    pub jwt_issuer: Option<String>, // Issuer of tokens; deployments sharing a secret need different ones
+    pub jwt_audience: String,
+    pub jwt_leeway: u64, // Tolerated clock skew in seconds
+    pub default_region: Option<String>,
+    pub region_header: Option<String>,
+    pub min_client_version: Option<ClientVersion>,
+    pub max_client_version: Option<ClientVersion>,
+    pub client_download_url: Option<String>,
+    pub invite_link_base_url: Option<String>, // Client page that opens party invite links
+    pub instance_id: String,
+    pub redis_host: Option<String>,
+    pub redis_port: u16,
+    pub oauth_redirect_base_url: Option<String>, // Public URL of this API
+    pub google_client_id: Option<String>,
+    pub google_client_secret: Option<String>,
+    pub discord_client_id: Option<String>,
+    pub discord_client_secret: Option<String>,
+    pub steam_web_api_key: Option<String>, // Publisher key, needed to verify Steam tickets
+    pub steam_app_id: Option<String>,
+    pub epic_client_id: Option<String>, // Client the Epic tokens of the game are issued to
+    pub auth_rate_limit_per_ip: u32,    // Auth requests per window and client IP
+    pub auth_rate_limit_per_name: u32,  // Auth requests per window and user name
+    pub auth_rate_limit_window: u64,    // in seconds
+    pub login_max_failures: u32,        // Failed logins before an account is locked
+    pub login_max_failures_per_ip: u32, // Failed logins before a client IP is locked
+    pub login_lockout_duration: u64,    // in seconds
+    pub account_purge_delay: i64,       // Days until deleted accounts are purged
+    pub map_purge_delay: i64,           // Days deleted maps can be restored before they are purged
+    pub max_party_size: i32,            // Default and largest allowed member limit of parties
+    pub owner_grace_period: u64,        // Seconds a disconnected owner has to come back
+    pub race_timeout: u64, // Seconds after which a race ends even if racers are still out
+    pub public_base_url: String, // Public URL of this API, used in emailed links
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub mail_from: String,
+    pub allowed_origins: Vec<String>, // Browser origins allowed to call the API; `*` allows any
+    pub trusted_proxies: Vec<TrustedProxy>, // Proxies whose forwarding headers tell the client IP
+    pub captcha_provider: Option<CaptchaProvider>, // Captcha required on registration, if set
+    pub captcha_secret: Option<String>,
+    pub profanity_words: Vec<String>, // Words filtered from names and titles besides the built-in ones
+    pub voice_provider: Option<VoiceProvider>, // Voice chat of parties, if set
+    pub voice_url: Option<String>,    // Server clients connect to for voice chat
+    pub voice_api_key: Option<String>,
+    pub voice_api_secret: Option<String>,
+    pub voice_token_expiry: i64,          // in seconds
+    pub storage_endpoint: Option<String>, // S3-compatible storage of uploads, if set
+    pub storage_bucket: Option<String>,
+    pub storage_region: String,
+    pub storage_access_key: Option<String>,
+    pub storage_secret_key: Option<String>,
+    pub storage_public_url: Option<String>, // URL uploads are served from, if not the bucket's
+    pub road_snap_provider: Option<RoadSnapProvider>, // Snapping of map points to roads, if set
+    pub road_snap_url: Option<String>,
+    pub road_snap_access_token: Option<String>,
+    pub road_surface_url: Option<String>, // Overpass API labelling snapped roads, if set
+    pub replay_sample_interval: Option<u64>, // Milliseconds between recorded positions, if replays are recorded
+    pub replay_max_frames: usize, // Positions kept per racer of a race; the oldest make room
+    pub replay_retention: i64,    // Days recorded replays are kept
 }
 
 #[derive(Error, Debug)]
@@ -24,14 +97,85 @@ impl Config {
     pub fn from_env() -> Result<Self, ConfigError> {
         dotenv::dotenv().ok();
 
+        let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+        let server_port = env::var("SERVER_PORT")
+            .unwrap_or_else(|_| "8080".to_string())
+            .parse::<u16>()
+            .map_err(|e| ConfigError::ParseError("SERVER_PORT".to_string(), e.to_string()))?;
+
+        let captcha_provider = env::var("CAPTCHA_PROVIDER")
+            .ok()
+            .filter(|provider| !provider.is_empty())
+            .map(|provider| {
+                provider.parse::<CaptchaProvider>().map_err(|e| {
+                    ConfigError::ParseError("CAPTCHA_PROVIDER".to_string(), e.to_string())
+                })
+            })
+            .transpose()?;
+        // A configured provider can't be used without its secret
+        let captcha_secret = match captcha_provider {
+            Some(_) => Some(get_env_var("CAPTCHA_SECRET")?),
+            None => None,
+        };
+
+        let voice_provider = env::var("VOICE_PROVIDER")
+            .ok()
+            .filter(|provider| !provider.is_empty())
+            .map(|provider| {
+                provider.parse::<VoiceProvider>().map_err(|e| {
+                    ConfigError::ParseError("VOICE_PROVIDER".to_string(), e.to_string())
+                })
+            })
+            .transpose()?;
+        // Neither can the voice server be used without its URL and credentials
+        let (voice_url, voice_api_key, voice_api_secret) = match voice_provider {
+            Some(_) => (
+                Some(get_env_var("VOICE_URL")?),
+                Some(get_env_var("VOICE_API_KEY")?),
+                Some(get_env_var("VOICE_API_SECRET")?),
+            ),
+            None => (None, None, None),
+        };
+
+        let storage_endpoint = env::var("STORAGE_ENDPOINT")
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty());
+        // Nor can the storage be used without its bucket and credentials
+        let (storage_bucket, storage_access_key, storage_secret_key) = match storage_endpoint {
+            Some(_) => (
+                Some(get_env_var("STORAGE_BUCKET")?),
+                Some(get_env_var("STORAGE_ACCESS_KEY")?),
+                Some(get_env_var("STORAGE_SECRET_KEY")?),
+            ),
+            None => (None, None, None),
+        };
+
+        let road_snap_provider = env::var("ROAD_SNAP_PROVIDER")
+            .ok()
+            .filter(|provider| !provider.is_empty())
+            .map(|provider| {
+                provider
+                    .parse::<RoadSnapProvider>()
+                    .map_err(|e| ConfigError::ParseError("ROAD_SNAP_PROVIDER".to_string(), e))
+            })
+            .transpose()?;
+        // An OSRM server has to be given, while Mapbox needs an access token
+        let (road_snap_url, road_snap_access_token) = match road_snap_provider {
+            Some(RoadSnapProvider::Osrm) => (Some(get_env_var("ROAD_SNAP_URL")?), None),
+            Some(RoadSnapProvider::Mapbox) => (
+                env::var("ROAD_SNAP_URL").ok().filter(|url| !url.is_empty()),
+                Some(get_env_var("ROAD_SNAP_ACCESS_TOKEN")?),
+            ),
+            None => (None, None),
+        };
+
         Ok(Self {
             database_url: get_env_var("DATABASE_URL")?,
-            server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
-            server_port: env::var("SERVER_PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse::<u16>()
-                .map_err(|e| ConfigError::ParseError("SERVER_PORT".to_string(), e.to_string()))?,
-            jwt_secret: env::var("JWT_SECRET").unwrap(),
+            public_base_url: env::var("PUBLIC_BASE_URL")
+                .unwrap_or_else(|_| format!("http://{}:{}", server_host, server_port)),
+            server_host,
+            server_port,
+            jwt_keys: get_jwt_keys()?,
             jwt_expiry: env::var("JWT_EXPIRY")
                 .unwrap_or_else(|_| "3600".to_string()) // 1 hour default
                 .parse::<i64>()
@@ -42,19 +186,352 @@ impl Config {
                 .map_err(|e| {
                     ConfigError::ParseError("REFRESH_EXPIRY".to_string(), e.to_string())
                 })?,
+            jwt_issuer: env::var("JWT_ISSUER")
+                .ok()
+                .filter(|issuer| !issuer.is_empty()),
+            jwt_audience: env::var("JWT_AUDIENCE")
+                .ok()
+                .filter(|audience| !audience.is_empty())
+                .unwrap_or_else(|| auth::ACCESS_TOKEN_AUDIENCE.to_string()),
+            jwt_leeway: env::var("JWT_LEEWAY")
+                .unwrap_or_else(|_| auth::DEFAULT_LEEWAY.to_string())
+                .parse::<u64>()
+                .map_err(|e| ConfigError::ParseError("JWT_LEEWAY".to_string(), e.to_string()))?,
+            // Compared with the regions of headers, which are lowercased
+            default_region: env::var("DEFAULT_REGION")
+                .ok()
+                .map(|region| region.trim().to_lowercase())
+                .filter(|region| !region.is_empty()),
+            region_header: env::var("REGION_HEADER").ok(),
+            min_client_version: get_optional_client_version("MIN_CLIENT_VERSION")?,
+            max_client_version: get_optional_client_version("MAX_CLIENT_VERSION")?,
+            client_download_url: env::var("CLIENT_DOWNLOAD_URL").ok(),
+            invite_link_base_url: env::var("INVITE_LINK_BASE_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            instance_id: env::var("INSTANCE_ID")
+                .or_else(|_| env::var("HOSTNAME"))
+                .unwrap_or_else(|_| "local".to_string()),
+            redis_host: env::var("REDIS_HOST").ok(),
+            redis_port: env::var("REDIS_PORT")
+                .unwrap_or_else(|_| "6379".to_string())
+                .parse::<u16>()
+                .map_err(|e| ConfigError::ParseError("REDIS_PORT".to_string(), e.to_string()))?,
+            oauth_redirect_base_url: env::var("OAUTH_REDIRECT_BASE_URL").ok(),
+            google_client_id: env::var("GOOGLE_CLIENT_ID").ok(),
+            google_client_secret: env::var("GOOGLE_CLIENT_SECRET").ok(),
+            discord_client_id: env::var("DISCORD_CLIENT_ID").ok(),
+            discord_client_secret: env::var("DISCORD_CLIENT_SECRET").ok(),
+            steam_web_api_key: env::var("STEAM_WEB_API_KEY").ok(),
+            steam_app_id: env::var("STEAM_APP_ID").ok(),
+            epic_client_id: env::var("EPIC_CLIENT_ID").ok(),
+            auth_rate_limit_per_ip: env::var("AUTH_RATE_LIMIT_PER_IP")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse::<u32>()
+                .map_err(|e| {
+                    ConfigError::ParseError("AUTH_RATE_LIMIT_PER_IP".to_string(), e.to_string())
+                })?,
+            auth_rate_limit_per_name: env::var("AUTH_RATE_LIMIT_PER_NAME")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse::<u32>()
+                .map_err(|e| {
+                    ConfigError::ParseError("AUTH_RATE_LIMIT_PER_NAME".to_string(), e.to_string())
+                })?,
+            auth_rate_limit_window: env::var("AUTH_RATE_LIMIT_WINDOW")
+                .unwrap_or_else(|_| "60".to_string()) // 1 minute default
+                .parse::<u64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("AUTH_RATE_LIMIT_WINDOW".to_string(), e.to_string())
+                })?,
+            login_max_failures: env::var("LOGIN_MAX_FAILURES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse::<u32>()
+                .map_err(|e| {
+                    ConfigError::ParseError("LOGIN_MAX_FAILURES".to_string(), e.to_string())
+                })?,
+            login_max_failures_per_ip: env::var("LOGIN_MAX_FAILURES_PER_IP")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse::<u32>()
+                .map_err(|e| {
+                    ConfigError::ParseError("LOGIN_MAX_FAILURES_PER_IP".to_string(), e.to_string())
+                })?,
+            login_lockout_duration: env::var("LOGIN_LOCKOUT_DURATION")
+                .unwrap_or_else(|_| "900".to_string()) // 15 minutes default
+                .parse::<u64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("LOGIN_LOCKOUT_DURATION".to_string(), e.to_string())
+                })?,
+            account_purge_delay: env::var("ACCOUNT_PURGE_DELAY")
+                .unwrap_or_else(|_| "30".to_string()) // 30 days default
+                .parse::<i64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("ACCOUNT_PURGE_DELAY".to_string(), e.to_string())
+                })?,
+            map_purge_delay: env::var("MAP_PURGE_DELAY")
+                .unwrap_or_else(|_| "7".to_string()) // 7 days default
+                .parse::<i64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("MAP_PURGE_DELAY".to_string(), e.to_string())
+                })?,
+            max_party_size: env::var("MAX_PARTY_SIZE")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse::<i32>()
+                .map_err(|e| ConfigError::ParseError("MAX_PARTY_SIZE".to_string(), e.to_string()))?
+                .max(1),
+            owner_grace_period: env::var("OWNER_GRACE_PERIOD")
+                .unwrap_or_else(|_| "60".to_string()) // 1 minute default
+                .parse::<u64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("OWNER_GRACE_PERIOD".to_string(), e.to_string())
+                })?,
+            race_timeout: env::var("RACE_TIMEOUT")
+                .unwrap_or_else(|_| "1800".to_string()) // 30 minutes default
+                .parse::<u64>()
+                .map_err(|e| ConfigError::ParseError("RACE_TIMEOUT".to_string(), e.to_string()))?,
+            smtp_host: env::var("SMTP_HOST").ok(),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse::<u16>()
+                .map_err(|e| ConfigError::ParseError("SMTP_PORT".to_string(), e.to_string()))?,
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "World Racers <noreply@localhost>".to_string()),
+            allowed_origins: env::var("ALLOWED_ORIGINS")
+                .ok()
+                .filter(|origins| !origins.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_ALLOWED_ORIGINS.to_string())
+                .split(',')
+                .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .map(|proxies| {
+                    proxies
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|proxy| !proxy.is_empty())
+                        .map(|proxy| {
+                            proxy.parse::<TrustedProxy>().map_err(|e| {
+                                ConfigError::ParseError("TRUSTED_PROXIES".to_string(), e)
+                            })
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .unwrap_or_else(|_| Ok(Vec::new()))?,
+            captcha_provider,
+            captcha_secret,
+            profanity_words: env::var("PROFANITY_WORDS")
+                .map(|words| {
+                    words
+                        .split(',')
+                        .map(|word| word.trim().to_string())
+                        .filter(|word| !word.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            voice_provider,
+            voice_url,
+            voice_api_key,
+            voice_api_secret,
+            voice_token_expiry: env::var("VOICE_TOKEN_EXPIRY")
+                .unwrap_or_else(|_| "3600".to_string()) // 1 hour default
+                .parse::<i64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("VOICE_TOKEN_EXPIRY".to_string(), e.to_string())
+                })?,
+            storage_endpoint,
+            storage_bucket,
+            storage_region: env::var("STORAGE_REGION")
+                .ok()
+                .filter(|region| !region.is_empty())
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            storage_access_key,
+            storage_secret_key,
+            road_snap_provider,
+            road_snap_url,
+            road_snap_access_token,
+            road_surface_url: env::var("ROAD_SURFACE_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            storage_public_url: env::var("STORAGE_PUBLIC_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            replay_sample_interval: env::var("REPLAY_SAMPLE_INTERVAL")
+                .ok()
+                .filter(|interval| !interval.is_empty())
+                .map(|interval| {
+                    interval.parse::<u64>().map_err(|e| {
+                        ConfigError::ParseError("REPLAY_SAMPLE_INTERVAL".to_string(), e.to_string())
+                    })
+                })
+                .transpose()?,
+            replay_max_frames: env::var("REPLAY_MAX_FRAMES")
+                .unwrap_or_else(|_| "6000".to_string()) // 10 minutes at 10 positions per second
+                .parse::<usize>()
+                .map_err(|e| {
+                    ConfigError::ParseError("REPLAY_MAX_FRAMES".to_string(), e.to_string())
+                })?
+                .max(1),
+            replay_retention: env::var("REPLAY_RETENTION")
+                .unwrap_or_else(|_| "30".to_string()) // 30 days default
+                .parse::<i64>()
+                .map_err(|e| {
+                    ConfigError::ParseError("REPLAY_RETENTION".to_string(), e.to_string())
+                })?,
+        })
+    }
+}
+
+impl Config {
+    /// Connection URL of Redis, if configured
+    pub fn redis_url(&self) -> Option<String> {
+        self.redis_host
+            .as_ref()
+            .map(|host| format!("redis://{}:{}", host, self.redis_port))
+    }
+
+    /// Verifier for registration captchas, if enabled
+    pub fn captcha_verifier(&self) -> Option<CaptchaVerifier> {
+        match (self.captcha_provider, &self.captcha_secret) {
+            (Some(provider), Some(secret)) => Some(CaptchaVerifier::new(provider, secret.clone())),
+            _ => None,
+        }
+    }
+
+    /// Issuer of voice chat tokens, if voice chat is enabled
+    pub fn voice_issuer(&self) -> Option<VoiceTokenIssuer> {
+        match (
+            self.voice_provider,
+            &self.voice_api_key,
+            &self.voice_api_secret,
+        ) {
+            (Some(provider), Some(api_key), Some(api_secret)) => Some(VoiceTokenIssuer::new(
+                provider,
+                api_key.clone(),
+                api_secret.clone(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Storage of uploaded files, if configured
+    pub fn object_storage(&self) -> Option<ObjectStorage> {
+        match (
+            &self.storage_endpoint,
+            &self.storage_bucket,
+            &self.storage_access_key,
+            &self.storage_secret_key,
+        ) {
+            (Some(endpoint), Some(bucket), Some(access_key), Some(secret_key)) => {
+                Some(ObjectStorage::new(
+                    endpoint.clone(),
+                    bucket.clone(),
+                    self.storage_region.clone(),
+                    access_key.clone(),
+                    secret_key.clone(),
+                    self.storage_public_url.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Snapper of map points to roads, if enabled
+    pub fn road_snapper(&self) -> Option<RoadSnapper> {
+        self.road_snap_provider.map(|provider| {
+            RoadSnapper::new(
+                provider,
+                self.road_snap_url.clone(),
+                self.road_snap_access_token.clone(),
+            )
         })
     }
+
+    /// Lookup of the road surfaces along snapped routes, if configured
+    pub fn road_surfaces(&self) -> Option<RoadSurfaces> {
+        self.road_surface_url.clone().map(RoadSurfaces::new)
+    }
+
+    /// Verifier for the tickets of a game platform, if it is configured
+    pub fn platform_verifier(&self, platform: Platform) -> Option<PlatformVerifier> {
+        match platform {
+            Platform::Steam => match (&self.steam_web_api_key, &self.steam_app_id) {
+                (Some(web_api_key), Some(app_id)) => Some(PlatformVerifier::Steam {
+                    web_api_key: web_api_key.clone(),
+                    app_id: app_id.clone(),
+                }),
+                _ => None,
+            },
+            Platform::Epic => {
+                self.epic_client_id
+                    .as_ref()
+                    .map(|client_id| PlatformVerifier::Epic {
+                        client_id: client_id.clone(),
+                    })
+            }
+        }
+    }
+
+    /// Whether any browser origin may call the API
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == "*")
+    }
+
+    /// Whether a browser origin may call the API
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allows_any_origin()
+            || self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    }
 }
 
 fn get_env_var(name: &str) -> Result<String, ConfigError> {
     env::var(name).map_err(|_| ConfigError::EnvVarNotFound(name.to_string()))
 }
 
-fn parse_env_var<T: std::str::FromStr>(name: &str) -> Result<T, ConfigError>
-where
-    T::Err: std::fmt::Display,
-{
-    let var = get_env_var(name)?;
-    var.parse()
-        .map_err(|e| ConfigError::ParseError(name.to_string(), format!("{}", e)))
+// Keys to rotate through are given as JWT_SECRETS, a comma-separated list of
+// `id:secret` pairs with the newest first; a single JWT_SECRET works as well
+fn get_jwt_keys() -> Result<Vec<JwtKey>, ConfigError> {
+    let Some(secrets) = env::var("JWT_SECRETS")
+        .ok()
+        .filter(|secrets| !secrets.trim().is_empty())
+    else {
+        return Ok(vec![JwtKey {
+            id: "default".to_string(),
+            secret: get_env_var("JWT_SECRET")?,
+        }]);
+    };
+
+    secrets
+        .split(',')
+        .map(|key| {
+            key.trim()
+                .split_once(':')
+                .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+                .map(|(id, secret)| JwtKey {
+                    id: id.to_string(),
+                    secret: secret.to_string(),
+                })
+                .ok_or_else(|| {
+                    ConfigError::ParseError(
+                        "JWT_SECRETS".to_string(),
+                        "expected comma-separated id:secret pairs".to_string(),
+                    )
+                })
+        })
+        .collect()
+}
+
+fn get_optional_client_version(name: &str) -> Result<Option<ClientVersion>, ConfigError> {
+    env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .parse::<ClientVersion>()
+                .map_err(|_| ConfigError::ParseError(name.to_string(), value.clone()))
+        })
+        .transpose()
 }
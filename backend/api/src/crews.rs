@@ -0,0 +1,82 @@
+//! Crews, the lasting groups players belong to besides the parties they
+//! race in.
+//!
+//! A user is in one crew at most. The owner manages the crew and officers
+//! help keep it tidy. When the owner leaves, or their account is purged, the
+//! longest-standing officer takes over, or else the longest-standing member.
+//! A crew without members is deleted.
+
+use entity::crew::Entity as Crew;
+use entity::crew_member::{self, Entity as CrewMember};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::UserId;
+
+/// The part a user plays in a crew
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CrewRole {
+    Owner,
+    /// Can remove members
+    Officer,
+    Member,
+}
+
+impl CrewRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrewRole::Owner => "owner",
+            CrewRole::Officer => "officer",
+            CrewRole::Member => "member",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "owner" => CrewRole::Owner,
+            "officer" => CrewRole::Officer,
+            _ => CrewRole::Member,
+        }
+    }
+}
+
+/// Hand a crew without an owner over to the member next in line, or delete
+/// it if it has no members left. Returns the new owner, if any.
+pub async fn hand_over<C: ConnectionTrait>(db: &C, crew_id: i32) -> Result<Option<UserId>, DbErr> {
+    let members = CrewMember::find()
+        .filter(crew_member::Column::CrewId.eq(crew_id))
+        .order_by_asc(crew_member::Column::JoinedAt)
+        .order_by_asc(crew_member::Column::Id)
+        .all(db)
+        .await?;
+
+    if members
+        .iter()
+        .any(|member| CrewRole::from_column(&member.role) == CrewRole::Owner)
+    {
+        return Ok(None);
+    }
+
+    let successor = members
+        .iter()
+        .find(|member| CrewRole::from_column(&member.role) == CrewRole::Officer)
+        .or(members.first())
+        .cloned();
+
+    let Some(successor) = successor else {
+        Crew::delete_by_id(crew_id).exec(db).await?;
+        return Ok(None);
+    };
+
+    let user_id = successor.user_id;
+    let mut successor: crew_member::ActiveModel = successor.into();
+    successor.role = Set(CrewRole::Owner.as_str().to_string());
+    successor.update(db).await?;
+
+    Ok(Some(user_id))
+}
@@ -1,22 +1,76 @@
+use auth::Auth;
+use auth::blocklist::Blocklist;
+use auth::lockout::LoginLockout;
+use auth::validation::{Validator, WordListFilter};
+use auth::ws_ticket::WsTickets;
 use sea_orm::{Database, DatabaseConnection, DbErr};
 use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
-use tokio::sync::broadcast;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc};
 
 use crate::config::Config;
+use crate::mailer::Mailer;
+use crate::map_stats::MapStatsCache;
+use crate::matchmaking::QueuedPlayer;
+use crate::metrics::RequestMetrics;
+use crate::presence::Presence;
+use crate::race::RaceProgress;
+use crate::rate_limit::RateLimiter;
 
 // Define type aliases for WebSocket party tracking
 pub type PartyId = i32;
 pub type UserId = i32;
 pub type PartyChannels = Arc<Mutex<HashMap<PartyId, broadcast::Sender<String>>>>;
 pub type UserParties = Arc<Mutex<HashMap<UserId, PartyId>>>;
+pub type UserRegions = Arc<Mutex<HashMap<UserId, String>>>;
+pub type ActiveRaces = Arc<Mutex<HashMap<PartyId, RaceProgress>>>;
+pub type UserSockets = Arc<Mutex<HashMap<UserId, mpsc::UnboundedSender<SocketCommand>>>>;
+// Pending merges keyed by the party to be merged, with the party absorbing it
+pub type PartyMergeRequests = Arc<Mutex<HashMap<PartyId, (PartyId, Instant)>>>;
+// Confirmed party memberships and when they were confirmed
+pub type PartyMemberships = Arc<Mutex<HashMap<(UserId, PartyId), Instant>>>;
+// Players waiting for a match
+pub type MatchmakingQueue = Arc<Mutex<HashMap<UserId, QueuedPlayer>>>;
+// Party owners whose connection dropped, and when
+pub type OwnerDisconnects = Arc<Mutex<HashMap<(UserId, PartyId), Instant>>>;
+
+// Commands sent to a user's websocket connection from outside of it
+#[derive(Debug, Clone)]
+pub enum SocketCommand {
+    // Move the connection's subscription to another party channel
+    SwitchParty(PartyId),
+    // Close the connection, e.g. when the user was banned
+    Close,
+    // Send a message to this connection only, e.g. a party invite
+    Notify(String),
+}
 
 #[derive(Clone)]
 pub struct AppState {
     pub conn: DatabaseConnection,
     pub config: Config,
+    pub auth: Auth,
     pub party_channels: PartyChannels,
     pub user_parties: UserParties,
+    pub user_regions: UserRegions,
+    pub active_races: ActiveRaces,
+    pub user_sockets: UserSockets,
+    pub party_merge_requests: PartyMergeRequests,
+    pub party_memberships: PartyMemberships,
+    pub matchmaking_queue: MatchmakingQueue,
+    pub owner_disconnects: OwnerDisconnects,
+    pub ws_connections: Arc<AtomicUsize>,
+    pub request_metrics: Arc<RequestMetrics>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub map_stats: Arc<MapStatsCache>,
+    pub blocklist: Blocklist,
+    pub ws_tickets: WsTickets,
+    pub login_lockout: LoginLockout,
+    pub presence: Presence,
+    pub validator: Validator,
+    pub mailer: Mailer,
 }
 
 pub async fn init_database(config: &Config) -> Result<DatabaseConnection, DbErr> {
@@ -27,14 +81,73 @@ pub async fn init_database(config: &Config) -> Result<DatabaseConnection, DbErr>
 pub async fn init_state(config: &Config) -> anyhow::Result<AppState> {
     let conn = init_database(config).await?;
 
+    // Connect to Redis for revoked tokens, used websocket tickets, failed
+    // logins and presence, if configured
+    let redis_url = config.redis_url();
+    if redis_url.is_some() {
+        tracing::info!("Connecting to Redis...");
+    }
+    let blocklist = Blocklist::connect(redis_url.as_deref(), config.jwt_leeway).await?;
+    let ws_tickets = WsTickets::connect(redis_url.as_deref()).await?;
+    let login_lockout = LoginLockout::connect(
+        redis_url.as_deref(),
+        config.login_max_failures,
+        config.login_max_failures_per_ip,
+        config.login_lockout_duration,
+    )
+    .await?;
+    let presence = Presence::connect(redis_url.as_deref()).await?;
+
+    let mailer = Mailer::from_config(config)?;
+
+    // Filter the built-in and configured words from names and titles
+    let validator = Validator::new(Arc::new(WordListFilter::new(
+        config.profanity_words.clone(),
+    )));
+
+    // Derive the token keys once for every request
+    let auth = Auth::new(
+        config.jwt_keys.clone(),
+        config.jwt_expiry,
+        config.refresh_expiry,
+        config.jwt_issuer.clone(),
+        config.jwt_audience.clone(),
+        config.jwt_leeway,
+    )?;
+
     // Initialize WebSocket party tracking
     let party_channels: PartyChannels = Arc::new(Mutex::new(HashMap::new()));
     let user_parties: UserParties = Arc::new(Mutex::new(HashMap::new()));
+    let user_regions: UserRegions = Arc::new(Mutex::new(HashMap::new()));
+    let active_races: ActiveRaces = Arc::new(Mutex::new(HashMap::new()));
+    let user_sockets: UserSockets = Arc::new(Mutex::new(HashMap::new()));
+    let party_merge_requests: PartyMergeRequests = Arc::new(Mutex::new(HashMap::new()));
+    let party_memberships: PartyMemberships = Arc::new(Mutex::new(HashMap::new()));
+    let matchmaking_queue: MatchmakingQueue = Arc::new(Mutex::new(HashMap::new()));
+    let owner_disconnects: OwnerDisconnects = Arc::new(Mutex::new(HashMap::new()));
 
     Ok(AppState {
         conn,
         config: config.clone(),
+        auth,
         party_channels,
         user_parties,
+        user_regions,
+        active_races,
+        user_sockets,
+        party_merge_requests,
+        party_memberships,
+        matchmaking_queue,
+        owner_disconnects,
+        ws_connections: Arc::new(AtomicUsize::new(0)),
+        request_metrics: Arc::new(RequestMetrics::default()),
+        rate_limiter: Arc::new(RateLimiter::default()),
+        map_stats: Arc::new(MapStatsCache::default()),
+        blocklist,
+        ws_tickets,
+        login_lockout,
+        presence,
+        validator,
+        mailer,
     })
 }
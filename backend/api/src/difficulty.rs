@@ -0,0 +1,127 @@
+//! Difficulty of maps.
+//!
+//! Maps are rated from 1 (easy) to 10 (hard) by how long their route is,
+//! how sharply it turns at the checkpoints and how closely the checkpoints
+//! follow each other. The rating and the length of the route are stored on
+//! the map whenever its points change, so listings can filter and sort by
+//! them.
+
+use entity::checkpoint::{self, Entity as Checkpoint};
+use entity::map::{self, Entity as Map};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+
+use crate::race::{Point, distance_meters};
+
+/// Range of difficulty ratings
+pub const MIN_DIFFICULTY: f32 = 1.0;
+pub const MAX_DIFFICULTY: f32 = 10.0;
+
+// Route length, checkpoints per kilometer and average turn at which each
+// part of the rating is at its hardest
+const HARDEST_LENGTH_METERS: f64 = 20_000.0;
+const HARDEST_DENSITY: f64 = 5.0;
+const HARDEST_TURN_DEGREES: f64 = 120.0;
+
+// How much each part counts towards the rating
+const LENGTH_WEIGHT: f64 = 0.3;
+const DENSITY_WEIGHT: f64 = 0.3;
+const TURN_WEIGHT: f64 = 0.4;
+
+/// Rate a route going from its first point through the others in order,
+/// rounded to one decimal
+pub fn rate(route: &[Point]) -> f32 {
+    let length = length_meters(route);
+    if length <= 0.0 {
+        return MIN_DIFFICULTY;
+    }
+
+    let checkpoints = route.len().saturating_sub(2) as f64;
+    let density = checkpoints / (length / 1000.0);
+
+    // Turns are made at every point between the start and the finish
+    let turns: Vec<f64> = route
+        .windows(3)
+        .map(|legs| turn_degrees(legs[0], legs[1], legs[2]))
+        .collect();
+    let average_turn = if turns.is_empty() {
+        0.0
+    } else {
+        turns.iter().sum::<f64>() / turns.len() as f64
+    };
+
+    let hardness = LENGTH_WEIGHT * (length / HARDEST_LENGTH_METERS).min(1.0)
+        + DENSITY_WEIGHT * (density / HARDEST_DENSITY).min(1.0)
+        + TURN_WEIGHT * (average_turn / HARDEST_TURN_DEGREES).min(1.0);
+    let rating = MIN_DIFFICULTY as f64 + (MAX_DIFFICULTY - MIN_DIFFICULTY) as f64 * hardness;
+
+    ((rating * 10.0).round() / 10.0) as f32
+}
+
+/// Length of a route in meters, in straight lines between its points
+pub fn length_meters(route: &[Point]) -> f64 {
+    route
+        .windows(2)
+        .map(|leg| distance_meters(leg[0], leg[1]))
+        .sum()
+}
+
+// Helper function to get by how many degrees a route turns at a point
+fn turn_degrees(from: Point, at: Point, to: Point) -> f64 {
+    let turn = (bearing(at, to) - bearing(from, at)).abs() % 360.0;
+    if turn > 180.0 { 360.0 - turn } else { turn }
+}
+
+// Helper function to get the direction from one point to another in
+// degrees, close enough over the distances between checkpoints
+fn bearing(from: Point, to: Point) -> f64 {
+    let north = to.0 - from.0;
+    let east = (to.1 - from.1) * from.0.to_radians().cos();
+    east.atan2(north).to_degrees()
+}
+
+/// Route of a map from its start through its checkpoints to its finish
+pub fn route_of(map: &map::Model, checkpoints: &[checkpoint::Model]) -> Vec<Point> {
+    let mut ordered: Vec<&checkpoint::Model> = checkpoints.iter().collect();
+    ordered.sort_by_key(|checkpoint| checkpoint.position);
+
+    std::iter::once((map.start_latitude, map.start_longitude))
+        .chain(
+            ordered
+                .into_iter()
+                .map(|checkpoint| (checkpoint.latitude, checkpoint.longitude)),
+        )
+        .chain(std::iter::once((map.end_latitude, map.end_longitude)))
+        .collect()
+}
+
+/// Rate and measure the maps saved before maps were rated or measured.
+/// Returns how many were rated.
+pub async fn rate_unrated(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let maps = Map::find()
+        .filter(
+            Condition::any()
+                .add(map::Column::Difficulty.is_null())
+                .add(map::Column::LengthMeters.is_null()),
+        )
+        .all(db)
+        .await?;
+
+    for map in &maps {
+        let checkpoints = Checkpoint::find()
+            .filter(checkpoint::Column::MapId.eq(map.id))
+            .order_by_asc(checkpoint::Column::Position)
+            .all(db)
+            .await?;
+
+        let route = route_of(map, &checkpoints);
+        let mut rated: map::ActiveModel = map.clone().into();
+        rated.difficulty = Set(Some(rate(&route)));
+        rated.length_meters = Set(Some(length_meters(&route)));
+        rated.update(db).await?;
+    }
+
+    Ok(maps.len())
+}
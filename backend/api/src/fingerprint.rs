@@ -0,0 +1,92 @@
+//! Fingerprints of map routes.
+//!
+//! Every point of a route, from the start through the checkpoints to the
+//! finish, is put into its geohash cell of about 150 by 150 meters. Routes
+//! whose points fall into the same cells in the same order share a
+//! fingerprint, which is how nearly identical maps are told apart from maps
+//! that only share a few streets. The cells are hashed, so fingerprints have
+//! the same length however many checkpoints a map has.
+
+use entity::checkpoint::{self, Entity as Checkpoint};
+use entity::map::{self, Entity as Map};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use sha2::{Digest, Sha256};
+
+use crate::difficulty;
+use crate::race::Point;
+
+// Characters of a geohash, each one holding 5 bits
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+// Characters of the geohash of each point, about 150 meters wide
+const GEOHASH_PRECISION: usize = 7;
+
+/// Fingerprint of a route going from its first point through the others in
+/// order
+pub fn of(route: &[Point]) -> String {
+    let cells = route
+        .iter()
+        .map(|&point| geohash(point, GEOHASH_PRECISION))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    hex::encode(Sha256::digest(cells.as_bytes()))
+}
+
+// Helper function to get the geohash of a point, made by halving the range
+// of longitudes and latitudes in turn
+fn geohash((latitude, longitude): Point, precision: usize) -> String {
+    let mut latitudes = (-90.0, 90.0);
+    let mut longitudes = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even_bit = true;
+
+    while hash.len() < precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            let (range, value) = if even_bit {
+                (&mut longitudes, longitude)
+            } else {
+                (&mut latitudes, latitude)
+            };
+            let middle = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= middle {
+                index |= 1;
+                range.0 = middle;
+            } else {
+                range.1 = middle;
+            }
+            even_bit = !even_bit;
+        }
+        hash.push(GEOHASH_ALPHABET[index] as char);
+    }
+
+    hash
+}
+
+/// Fingerprint the maps saved before maps were fingerprinted. Returns how
+/// many were fingerprinted.
+pub async fn fingerprint_missing(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let maps = Map::find()
+        .filter(map::Column::Fingerprint.is_null())
+        .all(db)
+        .await?;
+
+    for map in &maps {
+        let checkpoints = Checkpoint::find()
+            .filter(checkpoint::Column::MapId.eq(map.id))
+            .order_by_asc(checkpoint::Column::Position)
+            .all(db)
+            .await?;
+
+        let mut fingerprinted: map::ActiveModel = map.clone().into();
+        fingerprinted.fingerprint = Set(Some(of(&difficulty::route_of(map, &checkpoints))));
+        fingerprinted.update(db).await?;
+    }
+
+    Ok(maps.len())
+}
@@ -0,0 +1,55 @@
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::Config;
+
+/// Sends transactional email, e.g. verification links, over SMTP.
+///
+/// Without `SMTP_HOST` configured, emails are logged instead of sent so the
+/// flows can be used during development.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let from = config.mail_from.parse()?;
+
+        let transport = match &config.smtp_host {
+            Some(host) => {
+                let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+                    .port(config.smtp_port);
+                if let (Some(username), Some(password)) =
+                    (&config.smtp_username, &config.smtp_password)
+                {
+                    builder =
+                        builder.credentials(Credentials::new(username.clone(), password.clone()));
+                }
+                Some(builder.build())
+            }
+            None => None,
+        };
+
+        Ok(Self { transport, from })
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, body: String) -> anyhow::Result<()> {
+        let Some(transport) = &self.transport else {
+            tracing::info!("SMTP is not configured, email to {to} not sent: {subject}\n{body}");
+            return Ok(());
+        };
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(subject)
+            .body(body)?;
+
+        transport.send(message).await?;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,42 @@
+mod achievements;
+mod activity;
 mod api;
+mod awards;
+mod blocking;
+mod challenges;
+mod client_ip;
+mod client_version;
 mod config;
+mod crews;
 mod db;
+mod difficulty;
+mod fingerprint;
+mod mailer;
+mod map_stats;
+mod matchmaking;
+mod membership;
+mod merge;
+mod metrics;
+mod party_bans;
+mod party_events;
+mod policy;
+mod presence;
+mod progression;
+mod purge;
+mod race;
+mod races;
+mod rate_limit;
+mod recent_players;
+mod region;
+mod replays;
+mod road_snapping;
+mod road_surfaces;
+mod settings;
+mod splits;
+mod storage;
+mod succession;
+mod validation;
+mod wallet;
 
 use anyhow::Result;
 use auth::impl_auth_from_ref;
@@ -28,6 +64,9 @@ async fn main() -> Result<()> {
 
     // Load configuration
     let config = config::Config::from_env()?;
+    if config.allows_any_origin() {
+        tracing::warn!("ALLOWED_ORIGINS allows any browser origin to call the API");
+    }
 
     // Initialize database connections
     let state = db::init_state(&config).await?;
@@ -35,6 +74,30 @@ async fn main() -> Result<()> {
     // Run migrations
     migration::Migrator::up(&state.conn, None).await?;
 
+    // Rate the difficulty and measure the length of maps saved before maps
+    // were rated and measured
+    match difficulty::rate_unrated(&state.conn).await {
+        Ok(0) => {}
+        Ok(rated) => tracing::info!("Rated the difficulty and length of {} maps", rated),
+        Err(e) => tracing::error!("Error rating map difficulty: {}", e),
+    }
+
+    // Fingerprint the routes of maps saved before maps were fingerprinted
+    match fingerprint::fingerprint_missing(&state.conn).await {
+        Ok(0) => {}
+        Ok(fingerprinted) => tracing::info!("Fingerprinted {} maps", fingerprinted),
+        Err(e) => tracing::error!("Error fingerprinting maps: {}", e),
+    }
+
+    // Purge deleted accounts and maps once their grace period is over
+    purge::spawn(state.clone());
+
+    // Match queued players into parties
+    matchmaking::spawn(state.clone());
+
+    // Pick the daily challenge as each day starts
+    challenges::spawn(state.clone());
+
     // Build application router
     let app = api::create_router(state);
 
@@ -53,7 +116,11 @@ async fn main() -> Result<()> {
     tracing::info!("Server listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
         while running.load(Ordering::SeqCst) {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
@@ -0,0 +1,134 @@
+//! Statistics of maps, added up from their saved races.
+//!
+//! Adding up every race of a popular map takes a while, so statistics are
+//! kept in memory for a minute. Saving a race on a map drops its statistics,
+//! so racers see their results right away.
+
+use entity::map::Entity as Map;
+use entity::race::{self, Entity as Race};
+use entity::race_participant::{self, Entity as RaceParticipant};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect,
+    sea_query::{Alias, Expr, Func, SimpleExpr},
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+// How long statistics are kept before they are added up again
+const STATS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// Number of maps kept before expired statistics are dropped
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Statistics of a map over all its races
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MapStats {
+    map_id: i32,
+    /// Races started on the map
+    total_plays: i64,
+    /// Users who raced on the map at least once
+    unique_racers: i64,
+    /// Share of racers who crossed the finish line, from 0 to 1. Absent
+    /// until the map was raced.
+    completion_rate: Option<f64>,
+    /// Average time of the racers who finished
+    average_time_ms: Option<i64>,
+    /// Fastest time on the map
+    record_time_ms: Option<i64>,
+}
+
+/// Statistics of maps that were asked for recently, kept in memory per API
+/// instance
+#[derive(Debug, Default)]
+pub struct MapStatsCache {
+    entries: Mutex<HashMap<i32, (Instant, MapStats)>>,
+}
+
+impl MapStatsCache {
+    /// Statistics of a map, added up again when they are missing or expired.
+    /// Maps that don't exist have none.
+    pub async fn get<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        map_id: i32,
+    ) -> Result<Option<MapStats>, DbErr> {
+        let now = Instant::now();
+        if let Some((computed_at, stats)) = self.entries.lock().unwrap().get(&map_id)
+            && now.duration_since(*computed_at) < STATS_CACHE_TTL
+        {
+            return Ok(Some(stats.clone()));
+        }
+
+        let Some(stats) = compute(db, map_id).await? else {
+            return Ok(None);
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() > PRUNE_THRESHOLD {
+            entries
+                .retain(|_, (computed_at, _)| now.duration_since(*computed_at) < STATS_CACHE_TTL);
+        }
+        entries.insert(map_id, (now, stats.clone()));
+
+        Ok(Some(stats))
+    }
+
+    /// Drop the statistics of a map, e.g. after one of its races was saved
+    pub fn invalidate(&self, map_id: i32) {
+        self.entries.lock().unwrap().remove(&map_id);
+    }
+}
+
+// Helper function to add up the statistics of a map with a single aggregate
+// query over its races that ended
+async fn compute<C: ConnectionTrait>(db: &C, map_id: i32) -> Result<Option<MapStats>, DbErr> {
+    let Some(map) = Map::find_by_id(map_id).one(db).await? else {
+        return Ok(None);
+    };
+
+    // Averages of bigints are numeric in Postgres, so they are cast back
+    let (participants, unique_racers, finishers, average_time_ms, record_time_ms): (
+        i64,
+        i64,
+        i64,
+        Option<i64>,
+        Option<i64>,
+    ) = RaceParticipant::find()
+        .select_only()
+        .column_as(race_participant::Column::Id.count(), "participants")
+        .column_as(
+            Expr::col(race_participant::Column::UserId).count_distinct(),
+            "unique_racers",
+        )
+        .column_as(race_participant::Column::FinishTimeMs.count(), "finishers")
+        .column_as(
+            SimpleExpr::from(Func::cast_as(
+                Func::avg(Expr::col(race_participant::Column::FinishTimeMs)),
+                Alias::new("bigint"),
+            )),
+            "average_time_ms",
+        )
+        .column_as(
+            race_participant::Column::FinishTimeMs.min(),
+            "record_time_ms",
+        )
+        .inner_join(Race)
+        .filter(race::Column::MapId.eq(map_id))
+        .filter(race::Column::EndedAt.is_not_null())
+        .into_tuple()
+        .one(db)
+        .await?
+        .unwrap_or_default();
+
+    Ok(Some(MapStats {
+        map_id,
+        total_plays: map.play_count,
+        unique_racers,
+        completion_rate: (participants > 0).then(|| finishers as f64 / participants as f64),
+        average_time_ms,
+        record_time_ms,
+    }))
+}
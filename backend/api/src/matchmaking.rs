@@ -0,0 +1,238 @@
+//! Matchmaking of queued players into parties.
+//!
+//! Players wait in an in-memory queue with their rating, which is their
+//! level, and their region. A background task regularly groups players of
+//! the same region whose ratings are close, creates a private party for each
+//! group on a random map and lets the players know over their websocket
+//! connection. The longer a player waits, the wider the range of ratings
+//! they are matched with.
+
+use chrono::{DateTime, Utc};
+use entity::{party, user_party};
+use sea_orm::{ActiveModelTrait, DbErr, Set, TransactionTrait};
+
+use crate::api::maps::{self, RandomMapParams};
+use crate::api::{parties, ws};
+use crate::blocking;
+use crate::db::{AppState, UserId};
+use crate::membership;
+use crate::party_events;
+
+// How often the queue is searched for matches
+const MATCH_INTERVAL: u64 = 5; // in seconds
+
+// Fewest players a match is made with
+const MIN_MATCH_SIZE: usize = 2;
+
+// Rating difference allowed right away, and how it widens while waiting
+const BASE_RATING_SPREAD: i32 = 2;
+const RATING_SPREAD_WIDEN_EVERY: i64 = 15; // in seconds
+const MAX_RATING_SPREAD: i32 = 20;
+
+// Name of the parties created for matches
+const MATCH_PARTY_NAME: &str = "Matchmaking";
+
+/// A player waiting for a match
+#[derive(Debug, Clone)]
+pub struct QueuedPlayer {
+    pub rating: i32,
+    pub region: Option<String>,
+    pub queued_at: DateTime<Utc>,
+}
+
+impl QueuedPlayer {
+    /// Largest rating difference to other players this player accepts
+    pub fn rating_spread(&self, now: DateTime<Utc>) -> i32 {
+        let waited = (now - self.queued_at).num_seconds().max(0);
+        let widened = (waited / RATING_SPREAD_WIDEN_EVERY).min(MAX_RATING_SPREAD as i64) as i32;
+
+        (BASE_RATING_SPREAD + widened).min(MAX_RATING_SPREAD)
+    }
+}
+
+/// Match queued players in the background for as long as the server runs
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(MATCH_INTERVAL));
+        loop {
+            interval.tick().await;
+
+            match run_matcher(&state).await {
+                Ok(0) => {}
+                Ok(matched) => tracing::info!("Matched {} parties", matched),
+                Err(e) => tracing::error!("Error matching queued players: {}", e),
+            }
+        }
+    });
+}
+
+// Helper function to match the queue once. Returns how many parties were
+// created.
+async fn run_matcher(state: &AppState) -> Result<usize, DbErr> {
+    let mut matches = take_matches(state, state.config.max_party_size.max(1) as usize)
+        .await?
+        .into_iter();
+    let mut created = 0;
+
+    while let Some(players) = matches.next() {
+        if let Err(e) = create_match(state, &players).await {
+            // Put the players of this and every later match back so they
+            // are matched again
+            let mut queue = state.matchmaking_queue.lock().unwrap();
+            for (user_id, player) in std::iter::once(players).chain(matches).flatten() {
+                queue.entry(user_id).or_insert(player);
+            }
+            return Err(e);
+        }
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+// Helper function to take groups of players to be matched off the queue.
+// The longest waiting player picks the closest rated players of its region
+// that fit its rating spread. Nobody in a group blocked another of its players
+// or got blocked by them.
+async fn take_matches(
+    state: &AppState,
+    match_size: usize,
+) -> Result<Vec<Vec<(UserId, QueuedPlayer)>>, DbErr> {
+    let now = Utc::now();
+    let mut waiting: Vec<(UserId, QueuedPlayer)> = state
+        .matchmaking_queue
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(user_id, player)| (*user_id, player.clone()))
+        .collect();
+    waiting.sort_by_key(|(user_id, player)| (player.queued_at, *user_id));
+
+    let mut matches = Vec::new();
+
+    while let Some((anchor_id, anchor)) = waiting.first().cloned() {
+        let spread = anchor.rating_spread(now);
+
+        let mut candidates: Vec<(UserId, QueuedPlayer)> = waiting[1..]
+            .iter()
+            .filter(|(_, player)| {
+                player.region == anchor.region
+                    && (player.rating - anchor.rating).abs()
+                        <= spread.min(player.rating_spread(now))
+            })
+            .cloned()
+            .collect();
+        candidates.sort_by_key(|(_, player)| (player.rating - anchor.rating).abs());
+
+        // Add the closest rated candidates that have no block with anyone
+        // picked so far
+        let mut excluded = blocking::blocked_either_way(&state.conn, anchor_id).await?;
+        let mut players = vec![(anchor_id, anchor)];
+        for (user_id, player) in candidates {
+            if players.len() >= match_size {
+                break;
+            }
+            if excluded.contains(&user_id) {
+                continue;
+            }
+            excluded.extend(blocking::blocked_either_way(&state.conn, user_id).await?);
+            players.push((user_id, player));
+        }
+
+        if players.len() < MIN_MATCH_SIZE {
+            waiting.remove(0);
+            continue;
+        }
+        waiting.retain(|(user_id, _)| !players.iter().any(|(id, _)| id == user_id));
+
+        // Players who left the queue meanwhile are not matched
+        let taken = {
+            let mut queue = state.matchmaking_queue.lock().unwrap();
+            let all_queued = players
+                .iter()
+                .all(|(user_id, _)| queue.contains_key(user_id));
+            if all_queued {
+                for (user_id, _) in &players {
+                    queue.remove(user_id);
+                }
+            }
+            all_queued
+        };
+        if taken {
+            matches.push(players);
+        }
+    }
+
+    Ok(matches)
+}
+
+// Helper function to create the party of a match and tell its players
+async fn create_match(state: &AppState, players: &[(UserId, QueuedPlayer)]) -> Result<(), DbErr> {
+    let db = &state.conn;
+    let Some(map) = maps::pick_random_map(db, &RandomMapParams::default()).await? else {
+        return Err(DbErr::RecordNotFound("No map to race on".to_string()));
+    };
+
+    // The longest waiting player owns the party
+    let owner_id = players[0].0;
+
+    let txn = db.begin().await?;
+
+    let party = party::ActiveModel {
+        name: Set(MATCH_PARTY_NAME.to_string()),
+        code: Set(parties::generate_party_code()),
+        owner_id: Set(owner_id),
+        map_id: Set(map.id),
+        visibility: Set(parties::PartyVisibility::Private.as_str().to_string()),
+        max_members: Set(state.config.max_party_size),
+        region: Set(players[0].1.region.clone()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    for (user_id, _) in players {
+        user_party::ActiveModel {
+            user_id: Set(*user_id),
+            party_id: Set(party.id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        party_events::record(&txn, party.id, party_events::Kind::Joined, *user_id, None).await?;
+    }
+
+    txn.commit().await?;
+
+    let player_ids: Vec<UserId> = players.iter().map(|(user_id, _)| *user_id).collect();
+
+    for user_id in &player_ids {
+        membership::remember(state, *user_id, party.id);
+        parties::record_join(db, *user_id, &party).await;
+
+        ws::notify_user(
+            state,
+            *user_id,
+            &ws::WsMessage::MatchFound {
+                party_id: party.id,
+                code: party.code.clone(),
+                map_id: party.map_id,
+                player_ids: player_ids.clone(),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Number of players waiting in a region
+pub fn waiting_in(state: &AppState, region: Option<&str>) -> usize {
+    state
+        .matchmaking_queue
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|player| player.region.as_deref() == region)
+        .count()
+}
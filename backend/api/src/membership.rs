@@ -0,0 +1,185 @@
+use entity::party::Entity as Party;
+use entity::user_party::{self, Entity as UserParty};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QuerySelect, Set, sea_query::Expr,
+};
+use std::time::{Duration, Instant};
+
+use crate::db::{AppState, PartyId, UserId};
+
+// How long a confirmed membership is trusted without asking the database.
+// Changes made through this instance invalidate entries right away; the TTL
+// bounds how stale an entry can get when another instance changed it.
+pub const MEMBERSHIP_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Check whether a user is a member of a party, using the cache when possible
+pub async fn is_member(state: &AppState, user_id: UserId, party_id: PartyId) -> bool {
+    let cached = state
+        .party_memberships
+        .lock()
+        .unwrap()
+        .get(&(user_id, party_id))
+        .is_some_and(|cached_at| cached_at.elapsed() < MEMBERSHIP_CACHE_TTL);
+    if cached {
+        return true;
+    }
+
+    match load_membership(state, user_id, party_id).await {
+        Ok(true) => {
+            remember(state, user_id, party_id);
+            true
+        }
+        Ok(false) => {
+            forget(state, user_id, party_id);
+            false
+        }
+        Err(e) => {
+            tracing::error!("Error checking party membership: {}", e);
+            false
+        }
+    }
+}
+
+/// Record a membership that was just created
+pub fn remember(state: &AppState, user_id: UserId, party_id: PartyId) {
+    state
+        .party_memberships
+        .lock()
+        .unwrap()
+        .insert((user_id, party_id), Instant::now());
+}
+
+/// Drop a membership that was just removed
+pub fn forget(state: &AppState, user_id: UserId, party_id: PartyId) {
+    state
+        .party_memberships
+        .lock()
+        .unwrap()
+        .remove(&(user_id, party_id));
+}
+
+/// Drop every cached membership of a party, e.g. after it was disbanded
+pub fn forget_party(state: &AppState, party_id: PartyId) {
+    state
+        .party_memberships
+        .lock()
+        .unwrap()
+        .retain(|(_, cached_party_id), _| *cached_party_id != party_id);
+}
+
+/// Add a user to a party unless it is full. Returns whether the user was
+/// added.
+///
+/// The party row is locked until the end of the transaction `db` belongs to,
+/// so concurrent joins can't overshoot the member limit.
+pub async fn join<C: ConnectionTrait>(
+    db: &C,
+    user_id: UserId,
+    party_id: PartyId,
+) -> Result<bool, DbErr> {
+    let Some(party) = Party::find_by_id(party_id).lock_exclusive().one(db).await? else {
+        return Ok(false);
+    };
+
+    let members = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .count(db)
+        .await?;
+    if members >= party.max_members.max(0) as u64 {
+        return Ok(false);
+    }
+
+    user_party::ActiveModel {
+        user_id: Set(user_id),
+        party_id: Set(party_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(true)
+}
+
+/// Check whether a party has room for another websocket connection of a user
+pub async fn has_connection_room(
+    state: &AppState,
+    user_id: UserId,
+    party_id: PartyId,
+) -> Result<bool, DbErr> {
+    let Some(party) = Party::find_by_id(party_id).one(&state.conn).await? else {
+        return Ok(false);
+    };
+
+    let connected = state
+        .user_parties
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(connected_id, connected_party_id)| {
+            **connected_party_id == party_id && **connected_id != user_id
+        })
+        .count();
+
+    Ok(connected < party.max_members.max(0) as usize)
+}
+
+/// Check whether every member of a party is ready for the next race
+pub async fn all_ready<C: ConnectionTrait>(db: &C, party_id: PartyId) -> Result<bool, DbErr> {
+    let not_ready = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .filter(user_party::Column::Ready.eq(false))
+        .count(db)
+        .await?;
+
+    Ok(not_ready == 0)
+}
+
+/// Mark every member of a party as not ready, e.g. once a race started
+pub async fn reset_ready<C: ConnectionTrait>(db: &C, party_id: PartyId) -> Result<(), DbErr> {
+    UserParty::update_many()
+        .col_expr(user_party::Column::Ready, Expr::value(false))
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Get the team a member of a party races for, if any
+pub async fn team_of<C: ConnectionTrait>(
+    db: &C,
+    user_id: UserId,
+    party_id: PartyId,
+) -> Result<Option<i32>, DbErr> {
+    let membership = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .one(db)
+        .await?;
+
+    Ok(membership.and_then(|membership| membership.team))
+}
+
+async fn load_membership(
+    state: &AppState,
+    user_id: UserId,
+    party_id: PartyId,
+) -> Result<bool, DbErr> {
+    // Check if party exists first
+    if Party::find_by_id(party_id)
+        .one(&state.conn)
+        .await?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    let membership = UserParty::find()
+        .filter(user_party::Column::UserId.eq(user_id))
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .one(&state.conn)
+        .await?;
+
+    Ok(membership.is_some())
+}
@@ -0,0 +1,326 @@
+//! Merging duplicate accounts.
+//!
+//! Players who ended up with two accounts can fold the secondary one into
+//! the one they keep. Everything the secondary account made or earned moves
+//! over in one transaction, rows the primary account already has an
+//! equivalent of are dropped, and the secondary account is deleted the same
+//! way users delete their own accounts. Sessions, API keys and settings of
+//! the secondary account are not moved.
+
+use chrono::Utc;
+use entity::{
+    activity, block, follow, linked_account,
+    map::{self, Entity as Map},
+    map_favorite::{self, Entity as MapFavorite},
+    party, playlist, race_award, race_participant, race_replay, recent_player,
+    user::{self, Entity as User},
+    user_achievement, user_identity, user_party, vehicle_loadout,
+    wallet::Entity as Wallet,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction, DbErr,
+    EntityName, EntityTrait, QueryFilter, QuerySelect, Set, TransactionTrait,
+    sea_query::{Expr, Query},
+};
+
+use crate::db::UserId;
+use crate::progression;
+use crate::wallet;
+
+// Reason recorded on the transactions moving the balance
+const MERGE_REASON: &str = "account_merge";
+
+/// Move everything of the secondary account to the primary one and delete
+/// the secondary account. Returns the updated primary account.
+pub async fn merge_users(
+    db: &DatabaseConnection,
+    primary_id: UserId,
+    secondary_id: UserId,
+) -> Result<user::Model, DbErr> {
+    let txn = db.begin().await?;
+
+    let primary = User::find_by_id(primary_id)
+        .one(&txn)
+        .await?
+        .ok_or(DbErr::RecordNotFound(format!("User {}", primary_id)))?;
+    let secondary = User::find_by_id(secondary_id)
+        .one(&txn)
+        .await?
+        .ok_or(DbErr::RecordNotFound(format!("User {}", secondary_id)))?;
+
+    // Content the secondary account created
+    Map::update_many()
+        .col_expr(map::Column::AuthorId, Expr::value(primary_id))
+        .filter(map::Column::AuthorId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+    playlist::Entity::update_many()
+        .col_expr(playlist::Column::AuthorId, Expr::value(primary_id))
+        .filter(playlist::Column::AuthorId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+    party::Entity::update_many()
+        .col_expr(party::Column::OwnerId, Expr::value(primary_id))
+        .filter(party::Column::OwnerId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+    activity::Entity::update_many()
+        .col_expr(activity::Column::UserId, Expr::value(primary_id))
+        .filter(activity::Column::UserId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+    user_identity::Entity::update_many()
+        .col_expr(user_identity::Column::UserId, Expr::value(primary_id))
+        .filter(user_identity::Column::UserId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+
+    // Loadouts move over, but the primary account keeps its selected one
+    vehicle_loadout::Entity::update_many()
+        .col_expr(vehicle_loadout::Column::UserId, Expr::value(primary_id))
+        .col_expr(vehicle_loadout::Column::Selected, Expr::value(false))
+        .filter(vehicle_loadout::Column::UserId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+
+    move_rows::<user_party::Entity>(
+        &txn,
+        user_party::Column::UserId,
+        user_party::Column::PartyId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<user_achievement::Entity>(
+        &txn,
+        user_achievement::Column::UserId,
+        user_achievement::Column::AchievementId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<linked_account::Entity>(
+        &txn,
+        linked_account::Column::UserId,
+        linked_account::Column::Platform,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+
+    // Results and replays of races both accounts took part in stay with the
+    // primary account
+    move_rows::<race_participant::Entity>(
+        &txn,
+        race_participant::Column::UserId,
+        race_participant::Column::RaceId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<race_replay::Entity>(
+        &txn,
+        race_replay::Column::UserId,
+        race_replay::Column::RaceId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    // Each award goes to a single racer, so awards never clash
+    race_award::Entity::update_many()
+        .col_expr(race_award::Column::UserId, Expr::value(primary_id))
+        .filter(race_award::Column::UserId.eq(secondary_id))
+        .exec(&txn)
+        .await?;
+
+    // Maps both accounts favorited lose the second favorite
+    let favorited_by_primary = Query::select()
+        .column(map_favorite::Column::MapId)
+        .from(MapFavorite.table_ref())
+        .and_where(Expr::col(map_favorite::Column::UserId).eq(primary_id))
+        .to_owned();
+    let favorited_by_both: Vec<i32> = MapFavorite::find()
+        .select_only()
+        .column(map_favorite::Column::MapId)
+        .filter(map_favorite::Column::UserId.eq(secondary_id))
+        .filter(map_favorite::Column::MapId.in_subquery(favorited_by_primary))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+    Map::update_many()
+        .col_expr(
+            map::Column::FavoriteCount,
+            Expr::col(map::Column::FavoriteCount).sub(1),
+        )
+        .filter(map::Column::Id.is_in(favorited_by_both))
+        .filter(map::Column::FavoriteCount.gt(0))
+        .exec(&txn)
+        .await?;
+    move_rows::<map_favorite::Entity>(
+        &txn,
+        map_favorite::Column::UserId,
+        map_favorite::Column::MapId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+
+    // Relations between the two accounts would point at the primary account
+    // itself, so they are dropped before moving the rest
+    follow::Entity::delete_many()
+        .filter(between(
+            follow::Column::FollowerId,
+            follow::Column::FollowedId,
+            primary_id,
+            secondary_id,
+        ))
+        .exec(&txn)
+        .await?;
+    move_rows::<follow::Entity>(
+        &txn,
+        follow::Column::FollowerId,
+        follow::Column::FollowedId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<follow::Entity>(
+        &txn,
+        follow::Column::FollowedId,
+        follow::Column::FollowerId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+
+    block::Entity::delete_many()
+        .filter(between(
+            block::Column::BlockerId,
+            block::Column::BlockedId,
+            primary_id,
+            secondary_id,
+        ))
+        .exec(&txn)
+        .await?;
+    move_rows::<block::Entity>(
+        &txn,
+        block::Column::BlockerId,
+        block::Column::BlockedId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<block::Entity>(
+        &txn,
+        block::Column::BlockedId,
+        block::Column::BlockerId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+
+    recent_player::Entity::delete_many()
+        .filter(between(
+            recent_player::Column::UserId,
+            recent_player::Column::OtherUserId,
+            primary_id,
+            secondary_id,
+        ))
+        .exec(&txn)
+        .await?;
+    move_rows::<recent_player::Entity>(
+        &txn,
+        recent_player::Column::UserId,
+        recent_player::Column::OtherUserId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+    move_rows::<recent_player::Entity>(
+        &txn,
+        recent_player::Column::OtherUserId,
+        recent_player::Column::UserId,
+        secondary_id,
+        primary_id,
+    )
+    .await?;
+
+    // The balance moves through the ledger, so both sides stay audited
+    let balance = Wallet::find_by_id(secondary_id)
+        .one(&txn)
+        .await?
+        .map_or(0, |wallet| wallet.balance);
+    if balance > 0 {
+        wallet::grant(
+            &txn,
+            secondary_id,
+            -balance,
+            MERGE_REASON,
+            &format!("merge:into:{}", primary_id),
+        )
+        .await?;
+        wallet::grant(
+            &txn,
+            primary_id,
+            balance,
+            MERGE_REASON,
+            &format!("merge:from:{}", secondary_id),
+        )
+        .await?;
+    }
+
+    // Progress adds up, and the level follows the combined XP
+    let xp = primary.xp + secondary.xp;
+    let mut primary_model: user::ActiveModel = primary.clone().into();
+    primary_model.xp = Set(xp);
+    primary_model.level = Set(progression::level_for_xp(xp));
+    primary_model.race_wins = Set(primary.race_wins + secondary.race_wins);
+    primary_model.checkpoints_passed =
+        Set(primary.checkpoints_passed + secondary.checkpoints_passed);
+    let primary = primary_model.update(&txn).await?;
+
+    let mut secondary_model: user::ActiveModel = secondary.into();
+    secondary_model.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    secondary_model.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(primary)
+}
+
+// Helper function to hand the rows of one user over to another, dropping
+// those the other user has a row with the same key for
+async fn move_rows<E: EntityTrait>(
+    txn: &DatabaseTransaction,
+    user_column: E::Column,
+    key_column: E::Column,
+    from: UserId,
+    to: UserId,
+) -> Result<(), DbErr> {
+    let existing_keys = Query::select()
+        .column(key_column)
+        .from(E::default().table_ref())
+        .and_where(Expr::col(user_column).eq(to))
+        .to_owned();
+
+    E::delete_many()
+        .filter(user_column.eq(from))
+        .filter(key_column.in_subquery(existing_keys))
+        .exec(txn)
+        .await?;
+
+    E::update_many()
+        .col_expr(user_column, Expr::value(to))
+        .filter(user_column.eq(from))
+        .exec(txn)
+        .await?;
+
+    Ok(())
+}
+
+// Helper function to match the rows relating two users, in either direction
+fn between<C: ColumnTrait>(a: C, b: C, first: UserId, second: UserId) -> Condition {
+    Condition::any()
+        .add(a.eq(first).and(b.eq(second)))
+        .add(a.eq(second).and(b.eq(first)))
+}
@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::db::AppState;
+
+// Number of one-minute buckets kept for recent error rates
+pub const METRICS_WINDOW_MINUTES: i64 = 15;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RequestCounts {
+    pub total: u64,
+    pub client_errors: u64,
+    pub server_errors: u64,
+}
+
+/// Rolling per-minute counts of handled requests and their error statuses
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    buckets: Mutex<VecDeque<(i64, RequestCounts)>>,
+}
+
+impl RequestMetrics {
+    pub fn record(&self, status: u16) {
+        let minute = Utc::now().timestamp() / 60;
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Drop buckets that fell out of the window
+        while let Some((bucket_minute, _)) = buckets.front() {
+            if minute - bucket_minute < METRICS_WINDOW_MINUTES {
+                break;
+            }
+            buckets.pop_front();
+        }
+
+        if buckets
+            .back()
+            .is_none_or(|(bucket_minute, _)| *bucket_minute != minute)
+        {
+            buckets.push_back((minute, RequestCounts::default()));
+        }
+
+        let (_, counts) = buckets.back_mut().unwrap();
+        counts.total += 1;
+        match status {
+            400..=499 => counts.client_errors += 1,
+            500..=599 => counts.server_errors += 1,
+            _ => {}
+        }
+    }
+
+    /// Sum the counts of the buckets inside the window
+    pub fn recent(&self) -> RequestCounts {
+        let minute = Utc::now().timestamp() / 60;
+        let buckets = self.buckets.lock().unwrap();
+
+        buckets
+            .iter()
+            .filter(|(bucket_minute, _)| minute - bucket_minute < METRICS_WINDOW_MINUTES)
+            .fold(RequestCounts::default(), |mut sum, (_, counts)| {
+                sum.total += counts.total;
+                sum.client_errors += counts.client_errors;
+                sum.server_errors += counts.server_errors;
+                sum
+            })
+    }
+}
+
+/// Middleware recording the status of every handled request
+pub async fn record_request(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let res = next.run(req).await;
+    state.request_metrics.record(res.status().as_u16());
+    res
+}
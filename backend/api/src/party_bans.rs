@@ -0,0 +1,52 @@
+//! Bans that keep users out of a party.
+//!
+//! Unlike a block, which keeps a user out of every party of the blocker, a
+//! ban only applies to the party it was issued for, and lasts as long as the
+//! party does.
+
+use entity::party_ban::{self, Entity as PartyBan};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use std::collections::HashSet;
+
+use crate::db::{PartyId, UserId};
+
+/// Whether a user is banned from a party
+pub async fn is_banned<C: ConnectionTrait>(
+    db: &C,
+    party_id: PartyId,
+    user_id: UserId,
+) -> Result<bool, DbErr> {
+    let ban = PartyBan::find()
+        .filter(party_ban::Column::PartyId.eq(party_id))
+        .filter(party_ban::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+
+    Ok(ban.is_some())
+}
+
+/// The parties a user is banned from
+pub async fn banned_party_ids<C: ConnectionTrait>(
+    db: &C,
+    user_id: UserId,
+) -> Result<HashSet<PartyId>, DbErr> {
+    let bans = PartyBan::find()
+        .filter(party_ban::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    Ok(bans.into_iter().map(|ban| ban.party_id).collect())
+}
+
+/// The users banned from a party
+pub async fn banned_user_ids<C: ConnectionTrait>(
+    db: &C,
+    party_id: PartyId,
+) -> Result<HashSet<UserId>, DbErr> {
+    let bans = PartyBan::find()
+        .filter(party_ban::Column::PartyId.eq(party_id))
+        .all(db)
+        .await?;
+
+    Ok(bans.into_iter().map(|ban| ban.user_id).collect())
+}
@@ -0,0 +1,80 @@
+//! Membership history of parties.
+//!
+//! Joins, leaves, kicks and ownership transfers are stored as events so the
+//! owner of a long-running lobby can look back at who came and went.
+
+use entity::party_event;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{PartyId, UserId};
+
+/// Things that happen to the members of a party
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Joined,
+    Left,
+    /// Removed from the party by the owner
+    Kicked,
+    /// Made the owner of the party
+    OwnershipTransferred,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Joined => "joined",
+            Kind::Left => "left",
+            Kind::Kicked => "kicked",
+            Kind::OwnershipTransferred => "ownership_transferred",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "left" => Kind::Left,
+            "kicked" => Kind::Kicked,
+            "ownership_transferred" => Kind::OwnershipTransferred,
+            _ => Kind::Joined,
+        }
+    }
+}
+
+/// Record an event in the history of a party, with the user it happened to
+/// and the user who made it happen if that was someone else
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    party_id: PartyId,
+    kind: Kind,
+    user_id: UserId,
+    actor_id: Option<UserId>,
+) -> Result<(), DbErr> {
+    party_event::ActiveModel {
+        party_id: Set(party_id),
+        kind: Set(kind.as_str().to_string()),
+        user_id: Set(user_id),
+        actor_id: Set(actor_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Record the same event for several users of a party
+pub async fn record_all<C: ConnectionTrait>(
+    db: &C,
+    party_id: PartyId,
+    kind: Kind,
+    user_ids: &[UserId],
+    actor_id: Option<UserId>,
+) -> Result<(), DbErr> {
+    for user_id in user_ids {
+        record(db, party_id, kind, *user_id, actor_id).await?;
+    }
+
+    Ok(())
+}
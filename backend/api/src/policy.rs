@@ -0,0 +1,179 @@
+//! Authorization rules shared by the REST and websocket handlers.
+//!
+//! Handlers ask these functions instead of comparing ids themselves, so every
+//! rule (including the admin override) lives in one place.
+
+use auth::Claims;
+use entity::{map, party, playlist};
+
+use crate::api::maps::MapStatus;
+
+/// Administrators may act on any resource
+pub fn is_admin(claims: &Claims) -> bool {
+    claims.admin
+}
+
+/// Only the author of a map (or an admin) may edit or delete it
+pub fn can_edit_map(claims: &Claims, map: &map::Model) -> bool {
+    is_admin(claims) || map.author_id == claims.sub
+}
+
+/// Drafts and maps taken down by moderators are only visible to those who
+/// may edit them. Deleted maps are visible to nobody.
+pub fn can_view_map(claims: &Claims, map: &map::Model) -> bool {
+    map.deleted_at.is_none()
+        && ((MapStatus::from_column(&map.status) != MapStatus::Draft && map.hidden_at.is_none())
+            || can_edit_map(claims, map))
+}
+
+/// Only the author of a playlist (or an admin) may edit or delete it
+pub fn can_edit_playlist(claims: &Claims, playlist: &playlist::Model) -> bool {
+    is_admin(claims) || playlist.author_id == claims.sub
+}
+
+/// Only the owner of a party (or an admin) may manage it
+pub fn can_manage_party(claims: &Claims, party: &party::Model) -> bool {
+    is_admin(claims) || party.owner_id == claims.sub
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    const AUTHOR: i32 = 1;
+    const OTHER: i32 = 2;
+
+    fn claims(sub: i32, admin: bool) -> Claims {
+        Claims {
+            sub,
+            exp: 0,
+            iat: 0,
+            name: format!("user{}", sub),
+            admin,
+            jti: String::new(),
+            sid: None,
+            iss: None,
+            aud: None,
+            scope: Vec::new(),
+        }
+    }
+
+    fn map(status: MapStatus) -> map::Model {
+        map::Model {
+            id: 1,
+            title: "Map".to_string(),
+            description: String::new(),
+            created_at: Utc::now().fixed_offset(),
+            author_id: AUTHOR,
+            start_latitude: 0.0,
+            start_longitude: 0.0,
+            end_latitude: 0.0,
+            end_longitude: 0.0,
+            checkpoint_count: 0,
+            play_count: 0,
+            favorite_count: 0,
+            thumbnail_url: None,
+            route_polyline: None,
+            difficulty: None,
+            length_meters: None,
+            hidden_at: None,
+            deleted_at: None,
+            fingerprint: None,
+            weather: "clear".to_string(),
+            time_of_day: "day".to_string(),
+            status: status.as_str().to_string(),
+        }
+    }
+
+    fn party() -> party::Model {
+        party::Model {
+            id: 1,
+            name: "Party".to_string(),
+            code: "ABCDEF".to_string(),
+            owner_id: AUTHOR,
+            created_at: Utc::now().fixed_offset(),
+            map_id: 1,
+            playlist_id: None,
+            playlist_position: 0,
+            playlist_started_at: None,
+            visibility: "public".to_string(),
+            max_members: 8,
+            region: None,
+        }
+    }
+
+    fn playlist() -> playlist::Model {
+        playlist::Model {
+            id: 1,
+            name: "Playlist".to_string(),
+            author_id: AUTHOR,
+            created_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn author_and_admin_can_edit_map() {
+        let map = map(MapStatus::Published);
+        assert!(can_edit_map(&claims(AUTHOR, false), &map));
+        assert!(!can_edit_map(&claims(OTHER, false), &map));
+        assert!(can_edit_map(&claims(OTHER, true), &map));
+    }
+
+    #[test]
+    fn owner_and_admin_can_manage_party() {
+        let party = party();
+        assert!(can_manage_party(&claims(AUTHOR, false), &party));
+        assert!(!can_manage_party(&claims(OTHER, false), &party));
+        assert!(can_manage_party(&claims(OTHER, true), &party));
+    }
+
+    #[test]
+    fn author_and_admin_can_edit_playlist() {
+        let playlist = playlist();
+        assert!(can_edit_playlist(&claims(AUTHOR, false), &playlist));
+        assert!(!can_edit_playlist(&claims(OTHER, false), &playlist));
+        assert!(can_edit_playlist(&claims(OTHER, true), &playlist));
+    }
+
+    #[test]
+    fn published_and_unlisted_maps_are_visible_to_everyone() {
+        for status in [MapStatus::Published, MapStatus::Unlisted] {
+            let map = map(status);
+            assert!(can_view_map(&claims(AUTHOR, false), &map));
+            assert!(can_view_map(&claims(OTHER, false), &map));
+            assert!(can_view_map(&claims(OTHER, true), &map));
+        }
+    }
+
+    #[test]
+    fn drafts_are_only_visible_to_editors() {
+        let map = map(MapStatus::Draft);
+        assert!(can_view_map(&claims(AUTHOR, false), &map));
+        assert!(!can_view_map(&claims(OTHER, false), &map));
+        assert!(can_view_map(&claims(OTHER, true), &map));
+    }
+
+    #[test]
+    fn hidden_maps_are_only_visible_to_editors() {
+        let map = map::Model {
+            hidden_at: Some(Utc::now().fixed_offset()),
+            ..map(MapStatus::Published)
+        };
+        assert!(can_view_map(&claims(AUTHOR, false), &map));
+        assert!(!can_view_map(&claims(OTHER, false), &map));
+        assert!(can_view_map(&claims(OTHER, true), &map));
+    }
+
+    #[test]
+    fn deleted_maps_are_visible_to_nobody() {
+        let map = map::Model {
+            deleted_at: Some(Utc::now().fixed_offset()),
+            ..map(MapStatus::Published)
+        };
+        assert!(!can_view_map(&claims(AUTHOR, false), &map));
+        assert!(!can_view_map(&claims(OTHER, false), &map));
+        assert!(!can_view_map(&claims(OTHER, true), &map));
+    }
+}
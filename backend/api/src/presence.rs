@@ -0,0 +1,124 @@
+//! Which users are connected over websocket, and to which party.
+//!
+//! With Redis configured every instance sees the connections of the others.
+//! Entries expire unless the connection refreshes them, so users of an
+//! instance that went down don't stay online forever. Without Redis the
+//! connections of this instance are tracked in memory.
+
+use redis::RedisResult;
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::db::{PartyId, UserId};
+
+// How long an entry lives without being refreshed
+pub const PRESENCE_TTL: u64 = 120; // in seconds
+
+// How often connections refresh their entry
+pub const PRESENCE_REFRESH: u64 = 60; // in seconds
+
+#[derive(Clone, Default)]
+pub struct Presence {
+    conn: Option<ConnectionManager>,
+    local: Arc<Mutex<HashMap<UserId, Option<PartyId>>>>, // User to their party, if any
+}
+
+impl Presence {
+    pub async fn connect(redis_url: Option<&str>) -> RedisResult<Self> {
+        let Some(redis_url) = redis_url else {
+            return Ok(Self::default());
+        };
+
+        let client = redis::Client::open(redis_url)?;
+        let conn = ConnectionManager::new(client).await?;
+
+        Ok(Self {
+            conn: Some(conn),
+            ..Default::default()
+        })
+    }
+
+    /// Mark a user as online, in a party or not
+    pub async fn set_online(&self, user_id: UserId, party_id: Option<PartyId>) {
+        match &self.conn {
+            Some(conn) => {
+                // An empty value stands for no party
+                let value = party_id.map(|id| id.to_string()).unwrap_or_default();
+                let result = redis::cmd("SET")
+                    .arg(presence_key(user_id))
+                    .arg(value)
+                    .arg("EX")
+                    .arg(PRESENCE_TTL)
+                    .query_async::<()>(&mut conn.clone())
+                    .await;
+
+                if let Err(e) = result {
+                    tracing::error!("Error updating presence: {}", e);
+                }
+            }
+            None => {
+                self.local.lock().unwrap().insert(user_id, party_id);
+            }
+        }
+    }
+
+    /// Mark a user as offline
+    pub async fn set_offline(&self, user_id: UserId) {
+        match &self.conn {
+            Some(conn) => {
+                let result = redis::cmd("DEL")
+                    .arg(presence_key(user_id))
+                    .query_async::<()>(&mut conn.clone())
+                    .await;
+
+                if let Err(e) = result {
+                    tracing::error!("Error updating presence: {}", e);
+                }
+            }
+            None => {
+                self.local.lock().unwrap().remove(&user_id);
+            }
+        }
+    }
+
+    /// The online users among the given ones, with their party if any
+    pub async fn query(
+        &self,
+        user_ids: &[UserId],
+    ) -> RedisResult<HashMap<UserId, Option<PartyId>>> {
+        if user_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        match &self.conn {
+            Some(conn) => {
+                let keys: Vec<String> = user_ids.iter().map(|id| presence_key(*id)).collect();
+                let values: Vec<Option<String>> = redis::cmd("MGET")
+                    .arg(keys)
+                    .query_async(&mut conn.clone())
+                    .await?;
+
+                Ok(user_ids
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(user_id, value)| {
+                        value.map(|value| (*user_id, value.parse().ok()))
+                    })
+                    .collect())
+            }
+            None => {
+                let local = self.local.lock().unwrap();
+
+                Ok(user_ids
+                    .iter()
+                    .filter_map(|user_id| local.get(user_id).map(|party_id| (*user_id, *party_id)))
+                    .collect())
+            }
+        }
+    }
+}
+
+fn presence_key(user_id: UserId) -> String {
+    format!("presence:{}", user_id)
+}
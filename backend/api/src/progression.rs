@@ -0,0 +1,99 @@
+//! Experience points and levels.
+//!
+//! Users earn XP for finishing races, creating maps and logging in once a
+//! day. The level is derived from the XP here and stored next to it, so
+//! clients never compute thresholds themselves.
+
+use chrono::Utc;
+use entity::user::{self, Entity as User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Set, sea_query::Expr,
+};
+
+use crate::db::UserId;
+
+// XP awarded per event
+pub const RACE_FINISH_XP: i64 = 100;
+pub const MAP_CREATED_XP: i64 = 250;
+pub const DAILY_LOGIN_XP: i64 = 50;
+
+/// Total XP needed to reach a level. Each level takes 100 XP more than the
+/// one before: level 2 at 100, level 3 at 300, level 4 at 600 and so on.
+pub fn xp_for_level(level: i32) -> i64 {
+    let steps = (level.max(1) - 1) as i64;
+    50 * steps * (steps + 1)
+}
+
+/// The level reached with an amount of XP
+pub fn level_for_xp(xp: i64) -> i32 {
+    let mut level = 1;
+    while xp >= xp_for_level(level + 1) {
+        level += 1;
+    }
+    level
+}
+
+/// Award XP to a user. Returns the updated user, or nothing if the user
+/// doesn't exist.
+pub async fn award(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    amount: i64,
+) -> Result<Option<user::Model>, DbErr> {
+    let user = User::update_many()
+        .col_expr(user::Column::Xp, Expr::col(user::Column::Xp).add(amount))
+        .filter(user::Column::Id.eq(user_id))
+        .exec_with_returning(db)
+        .await?
+        .pop();
+
+    match user {
+        Some(user) => update_level(db, user).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Award the XP for the first login of the day (in UTC). Returns the updated
+/// user if XP was awarded.
+pub async fn record_daily_login(
+    db: &DatabaseConnection,
+    user_id: UserId,
+) -> Result<Option<user::Model>, DbErr> {
+    let now = Utc::now();
+    let today = now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+    // Checking and updating in one statement awards concurrent logins once
+    let user = User::update_many()
+        .col_expr(
+            user::Column::Xp,
+            Expr::col(user::Column::Xp).add(DAILY_LOGIN_XP),
+        )
+        .col_expr(user::Column::DailyXpAt, Expr::value(now.fixed_offset()))
+        .filter(user::Column::Id.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(user::Column::DailyXpAt.is_null())
+                .add(user::Column::DailyXpAt.lt(today.fixed_offset())),
+        )
+        .exec_with_returning(db)
+        .await?
+        .pop();
+
+    match user {
+        Some(user) => update_level(db, user).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+// Store the level matching the XP of a user, if it changed
+async fn update_level(db: &DatabaseConnection, user: user::Model) -> Result<user::Model, DbErr> {
+    let level = level_for_xp(user.xp);
+    if level == user.level {
+        return Ok(user);
+    }
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.level = Set(level);
+    user_model.update(db).await
+}
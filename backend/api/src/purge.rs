@@ -0,0 +1,238 @@
+//! Purge of deleted accounts and maps, and of old replays.
+//!
+//! Deleting an account only marks it as deleted. Once the grace period has
+//! passed, the account is purged together with everything it created. Most
+//! tables cascade, but parties and their memberships don't, so they are
+//! removed here first. The favorites of the account cascade too, but are
+//! taken off the favorite counts of the maps first. Crews owned by the
+//! account are handed over to another member.
+//!
+//! Deleted maps are kept the same way until they can no longer be restored,
+//! and are then purged together with the parties racing on them.
+//!
+//! Replays the server recorded are kept for the retention period.
+
+use chrono::{DateTime, Duration, Utc};
+use entity::{
+    crew_member::{self, Entity as CrewMember},
+    map::{self, Entity as Map},
+    map_favorite::{self, Entity as MapFavorite},
+    party::{self, Entity as Party},
+    race_replay::{self, Entity as RaceReplay},
+    user::{self, Entity as User},
+    user_party::{self, Entity as UserParty},
+};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect,
+    TransactionTrait, sea_query::Expr,
+};
+use std::collections::HashMap;
+
+use crate::crews::{self, CrewRole};
+use crate::db::AppState;
+
+// How often deleted accounts are looked for
+const PURGE_INTERVAL: u64 = 3600; // in seconds
+
+/// Purge deleted accounts and maps, and old replays, in the background for
+/// as long as the server runs
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL));
+        loop {
+            interval.tick().await;
+
+            let before = Utc::now() - Duration::days(state.config.account_purge_delay);
+            match purge_deleted_users(&state.conn, before).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("Purged {} deleted accounts", purged),
+                Err(e) => tracing::error!("Error purging deleted accounts: {}", e),
+            }
+
+            let before = Utc::now() - Duration::days(state.config.map_purge_delay);
+            match purge_deleted_maps(&state.conn, before).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("Purged {} deleted maps", purged),
+                Err(e) => tracing::error!("Error purging deleted maps: {}", e),
+            }
+
+            let before = Utc::now() - Duration::days(state.config.replay_retention);
+            match purge_old_replays(&state.conn, before).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("Purged {} old replays", purged),
+                Err(e) => tracing::error!("Error purging old replays: {}", e),
+            }
+        }
+    });
+}
+
+/// Purge the accounts deleted before a point in time. Returns how many were
+/// purged.
+pub async fn purge_deleted_users(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+) -> Result<u64, DbErr> {
+    let user_ids: Vec<i32> = User::find()
+        .select_only()
+        .column(user::Column::Id)
+        .filter(user::Column::DeletedAt.lt(before.fixed_offset()))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    if user_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let txn = db.begin().await?;
+
+    // Parties go away with their owner, and with the maps of the owner
+    let map_ids: Vec<i32> = Map::find()
+        .select_only()
+        .column(map::Column::Id)
+        .filter(map::Column::AuthorId.is_in(user_ids.clone()))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    let party_ids: Vec<i32> = Party::find()
+        .select_only()
+        .column(party::Column::Id)
+        .filter(
+            Condition::any()
+                .add(party::Column::OwnerId.is_in(user_ids.clone()))
+                .add(party::Column::MapId.is_in(map_ids)),
+        )
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    UserParty::delete_many()
+        .filter(
+            Condition::any()
+                .add(user_party::Column::UserId.is_in(user_ids.clone()))
+                .add(user_party::Column::PartyId.is_in(party_ids.clone())),
+        )
+        .exec(&txn)
+        .await?;
+
+    Party::delete_many()
+        .filter(party::Column::Id.is_in(party_ids))
+        .exec(&txn)
+        .await?;
+
+    // Maps of other users lose the favorites of the purged accounts
+    let favorited_map_ids: Vec<i32> = MapFavorite::find()
+        .select_only()
+        .column(map_favorite::Column::MapId)
+        .filter(map_favorite::Column::UserId.is_in(user_ids.clone()))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    let mut favorite_counts: HashMap<i32, i32> = HashMap::new();
+    for map_id in favorited_map_ids {
+        *favorite_counts.entry(map_id).or_default() += 1;
+    }
+
+    for (map_id, count) in favorite_counts {
+        Map::update_many()
+            .col_expr(
+                map::Column::FavoriteCount,
+                Expr::col(map::Column::FavoriteCount).sub(count),
+            )
+            .filter(map::Column::Id.eq(map_id))
+            .exec(&txn)
+            .await?;
+    }
+
+    // Crews of the purged owners need a new one
+    let owned_crew_ids: Vec<i32> = CrewMember::find()
+        .select_only()
+        .column(crew_member::Column::CrewId)
+        .filter(crew_member::Column::UserId.is_in(user_ids.clone()))
+        .filter(crew_member::Column::Role.eq(CrewRole::Owner.as_str()))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    CrewMember::delete_many()
+        .filter(crew_member::Column::UserId.is_in(user_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    for crew_id in owned_crew_ids {
+        crews::hand_over(&txn, crew_id).await?;
+    }
+
+    let result = User::delete_many()
+        .filter(user::Column::Id.is_in(user_ids))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(result.rows_affected)
+}
+
+/// Purge the maps deleted before a point in time. Returns how many were
+/// purged.
+pub async fn purge_deleted_maps(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+) -> Result<u64, DbErr> {
+    let map_ids: Vec<i32> = Map::find()
+        .select_only()
+        .column(map::Column::Id)
+        .filter(map::Column::DeletedAt.lt(before.fixed_offset()))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    if map_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let txn = db.begin().await?;
+
+    let party_ids: Vec<i32> = Party::find()
+        .select_only()
+        .column(party::Column::Id)
+        .filter(party::Column::MapId.is_in(map_ids.clone()))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    UserParty::delete_many()
+        .filter(user_party::Column::PartyId.is_in(party_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    Party::delete_many()
+        .filter(party::Column::Id.is_in(party_ids))
+        .exec(&txn)
+        .await?;
+
+    let result = Map::delete_many()
+        .filter(map::Column::Id.is_in(map_ids))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(result.rows_affected)
+}
+
+/// Purge the replays recorded before a point in time. Returns how many were
+/// purged.
+pub async fn purge_old_replays(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+) -> Result<u64, DbErr> {
+    let result = RaceReplay::delete_many()
+        .filter(race_replay::Column::CreatedAt.lt(before.fixed_offset()))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
@@ -0,0 +1,466 @@
+use chrono::{DateTime, Utc};
+use entity::{checkpoint, map, race};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::api::maps::CheckpointKind;
+use crate::db::UserId;
+use crate::replays::{Frame, ReplayRecorder};
+
+// How close a car must pass to a checkpoint for it to count, unless the
+// checkpoint says otherwise, and to the finish line
+pub const CHECKPOINT_RADIUS_METERS: f64 = 25.0;
+
+// Minimum time between two progress broadcasts for the same racer
+pub const PROGRESS_BROADCAST_INTERVAL: Duration = Duration::from_secs(1);
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// A point on the course as (latitude, longitude)
+pub type Point = (f64, f64);
+
+/// Server-side state of a race in progress
+#[derive(Debug, Clone)]
+pub struct RaceProgress {
+    pub race_id: i32,
+    pub map_id: i32,
+    pub started_at: DateTime<Utc>,
+    clock: Instant,
+    start: Point,
+    // Checkpoints in order followed by the finish line, and how close a car
+    // must pass to each of them
+    waypoints: Vec<Point>,
+    radii: Vec<f64>,
+    // Whether a waypoint may be left out, like a shortcut gate
+    optional: Vec<bool>,
+    // Added to every radius
+    forgiveness: f64,
+    racers: HashMap<UserId, RacerProgress>,
+    // Positions of the racers, if the server records replays
+    replay: Option<ReplayRecorder>,
+    winner_taken: bool,
+    participants_taken: bool,
+    results_taken: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RacerProgress {
+    next_waypoint: usize,
+    last_broadcast: Option<Instant>,
+    // Last position sample and when it was received, relative to the race start
+    last_sample: Option<(Point, Duration)>,
+    // Interpolated time at which the racer passed each checkpoint, absent
+    // for optional ones left out
+    splits: Vec<Option<Duration>>,
+    // Interpolated time at which the racer crossed the finish line
+    finish_time: Option<Duration>,
+    // Whether the racer left the party before finishing
+    left: bool,
+}
+
+/// Progress of one racer, ready to be broadcast
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressSnapshot {
+    pub next_checkpoint: usize,
+    pub completion_pct: f32,
+    pub just_finished: bool,
+    // Checkpoints passed with this update, not counting the finish line
+    pub checkpoints_passed: usize,
+}
+
+/// How a racer did in a race, whether they finished or not
+#[derive(Debug, Clone)]
+pub struct RacerResult {
+    pub user_id: UserId,
+    // Place and time of racers who crossed the finish line
+    pub place: Option<usize>,
+    pub finish_time_ms: Option<u64>,
+    // When the racer passed each checkpoint passed so far since the start
+    pub splits_ms: Vec<Option<u64>>,
+}
+
+/// Placement of a racer who crossed the finish line
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FinishStanding {
+    pub user_id: UserId,
+    pub place: usize,
+    pub finish_time_ms: u64,
+    // Gap to the winner
+    pub gap_ms: u64,
+}
+
+impl RaceProgress {
+    pub fn new(race: &race::Model, map: &map::Model, checkpoints: &[checkpoint::Model]) -> Self {
+        let mut ordered: Vec<&checkpoint::Model> = checkpoints.iter().collect();
+        ordered.sort_by_key(|checkpoint| checkpoint.position);
+
+        let mut waypoints: Vec<Point> = ordered
+            .iter()
+            .map(|checkpoint| (checkpoint.latitude, checkpoint.longitude))
+            .collect();
+        waypoints.push((map.end_latitude, map.end_longitude));
+
+        let mut radii: Vec<f64> = ordered
+            .iter()
+            .map(|checkpoint| checkpoint.radius_meters as f64)
+            .collect();
+        radii.push(CHECKPOINT_RADIUS_METERS);
+
+        let mut optional: Vec<bool> = ordered
+            .iter()
+            .map(|checkpoint| {
+                CheckpointKind::from_column(&checkpoint.kind) == CheckpointKind::Optional
+            })
+            .collect();
+        optional.push(false);
+
+        Self {
+            race_id: race.id,
+            map_id: map.id,
+            started_at: race.started_at.with_timezone(&Utc),
+            clock: Instant::now(),
+            start: (map.start_latitude, map.start_longitude),
+            waypoints,
+            radii,
+            optional,
+            forgiveness: 0.0,
+            racers: HashMap::new(),
+            replay: None,
+            winner_taken: false,
+            participants_taken: false,
+            results_taken: false,
+        }
+    }
+
+    /// Let checkpoints count from further away, e.g. for parties with
+    /// forgiving race settings
+    pub fn with_forgiveness(mut self, extra_meters: f64) -> Self {
+        self.forgiveness = extra_meters.max(0.0);
+        self
+    }
+
+    /// Expect these racers to take part, so the race isn't complete before
+    /// each of them finished or left
+    pub fn with_racers(mut self, user_ids: impl IntoIterator<Item = UserId>) -> Self {
+        for user_id in user_ids {
+            self.racers.entry(user_id).or_default();
+        }
+        self
+    }
+
+    /// Record the positions of the racers into replays
+    pub fn with_replay(mut self, recorder: ReplayRecorder) -> Self {
+        self.replay = Some(recorder);
+        self
+    }
+
+    /// Record where a racer is and which way they face into the replay, if
+    /// one is recorded. Racers who finished aren't recorded any longer.
+    pub fn record_frame(&mut self, user_id: UserId, position: [f32; 3], rotation: [f32; 3]) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        if self
+            .racers
+            .get(&user_id)
+            .is_some_and(|racer| racer.finish_time.is_some())
+        {
+            return;
+        }
+
+        replay.record(
+            user_id,
+            Frame {
+                time_ms: self.clock.elapsed().as_millis() as u32,
+                position,
+                rotation,
+            },
+        );
+    }
+
+    /// The replay recorded of the race, the first time it is asked for
+    pub fn take_replay(&mut self) -> Option<ReplayRecorder> {
+        self.replay.take()
+    }
+
+    /// Record a new position for a racer.
+    ///
+    /// Returns a snapshot when a broadcast is due, either because the racer
+    /// passed a checkpoint or because the broadcast interval has elapsed.
+    pub fn update(&mut self, user_id: UserId, position: Point) -> Option<ProgressSnapshot> {
+        let elapsed = self.clock.elapsed();
+        let finish = *self.waypoints.last().unwrap();
+        let finish_radius = *self.radii.last().unwrap() + self.forgiveness;
+        let racer = self.racers.entry(user_id).or_default();
+        // A racer who left races on if they come back
+        racer.left = false;
+
+        // Nothing left to track once the racer has finished
+        if racer.finish_time.is_some() {
+            return None;
+        }
+
+        // Racers pass waypoints in order, but may leave out optional ones
+        let previous_waypoint = racer.next_waypoint;
+        let mut checkpoints_passed = 0;
+        'advance: loop {
+            for index in racer.next_waypoint..self.waypoints.len() {
+                let radius = self.radii[index] + self.forgiveness;
+                if distance_meters(position, self.waypoints[index]) <= radius {
+                    racer.next_waypoint = index + 1;
+                    if index < self.waypoints.len() - 1 {
+                        checkpoints_passed += 1;
+                        racer.splits.resize(index, None);
+                        racer.splits.push(Some(crossing_time(
+                            racer.last_sample,
+                            (position, elapsed),
+                            self.waypoints[index],
+                            radius,
+                        )));
+                    }
+                    continue 'advance;
+                }
+                if !self.optional[index] {
+                    break;
+                }
+            }
+            break;
+        }
+        let passed_checkpoint = racer.next_waypoint > previous_waypoint;
+
+        let just_finished = racer.next_waypoint == self.waypoints.len();
+        if just_finished {
+            racer.finish_time = Some(crossing_time(
+                racer.last_sample,
+                (position, elapsed),
+                finish,
+                finish_radius,
+            ));
+        }
+        racer.last_sample = Some((position, elapsed));
+
+        let now = Instant::now();
+        let interval_elapsed = racer
+            .last_broadcast
+            .is_none_or(|last| now.duration_since(last) >= PROGRESS_BROADCAST_INTERVAL);
+
+        // Passed checkpoints are always reported, so they are counted once
+        if !passed_checkpoint && !interval_elapsed {
+            return None;
+        }
+        racer.last_broadcast = Some(now);
+
+        let next_waypoint = racer.next_waypoint;
+        Some(ProgressSnapshot {
+            next_checkpoint: next_waypoint,
+            completion_pct: self.completion_pct(next_waypoint, position),
+            just_finished,
+            checkpoints_passed,
+        })
+    }
+
+    /// The winner of the race, the first time it is asked for after the
+    /// first racer finished
+    pub fn take_winner(&mut self) -> Option<UserId> {
+        if self.winner_taken {
+            return None;
+        }
+
+        let winner = self.standings().first().map(|standing| standing.user_id)?;
+        self.winner_taken = true;
+        Some(winner)
+    }
+
+    /// Stop waiting for a racer who left the party before finishing
+    pub fn drop_out(&mut self, user_id: UserId) {
+        if let Some(racer) = self.racers.get_mut(&user_id) {
+            racer.left = racer.finish_time.is_none();
+        }
+    }
+
+    /// Whether every racer who took part has crossed the finish line or left
+    pub fn is_complete(&self) -> bool {
+        !self.racers.is_empty()
+            && self
+                .racers
+                .values()
+                .all(|racer| racer.finish_time.is_some() || racer.left)
+    }
+
+    /// Everyone who took part in the race, the first time it is asked for.
+    /// Races of a single racer have nobody to remember.
+    pub fn take_participants(&mut self) -> Option<Vec<UserId>> {
+        if self.participants_taken || self.racers.len() < 2 {
+            return None;
+        }
+
+        self.participants_taken = true;
+        let mut participants: Vec<UserId> = self.racers.keys().copied().collect();
+        participants.sort_unstable();
+        Some(participants)
+    }
+
+    /// How every racer did, the first time it is asked for
+    pub fn take_results(&mut self) -> Option<Vec<RacerResult>> {
+        if self.results_taken {
+            return None;
+        }
+
+        self.results_taken = true;
+        Some(self.results())
+    }
+
+    /// Number of checkpoints on the course, not counting the finish line
+    pub fn checkpoint_count(&self) -> usize {
+        self.waypoints.len() - 1
+    }
+
+    /// How every racer is doing. Finishers come first in order of their
+    /// placement, then everyone who didn't finish.
+    pub fn results(&self) -> Vec<RacerResult> {
+        let mut results: Vec<RacerResult> = self
+            .standings()
+            .into_iter()
+            .map(|standing| RacerResult {
+                user_id: standing.user_id,
+                place: Some(standing.place),
+                finish_time_ms: Some(standing.finish_time_ms),
+                splits_ms: self.splits_ms(standing.user_id),
+            })
+            .collect();
+
+        let mut non_finishers: Vec<UserId> = self
+            .racers
+            .iter()
+            .filter(|(_, racer)| racer.finish_time.is_none())
+            .map(|(user_id, _)| *user_id)
+            .collect();
+        non_finishers.sort_unstable();
+        results.extend(non_finishers.into_iter().map(|user_id| RacerResult {
+            user_id,
+            place: None,
+            finish_time_ms: None,
+            splits_ms: self.splits_ms(user_id),
+        }));
+
+        results
+    }
+
+    /// How a racer is doing, if they take part in the race. Racers still on
+    /// the course have no place or time yet.
+    pub fn result_of(&self, user_id: UserId) -> Option<RacerResult> {
+        let racer = self.racers.get(&user_id)?;
+        if racer.finish_time.is_none() {
+            return Some(RacerResult {
+                user_id,
+                place: None,
+                finish_time_ms: None,
+                splits_ms: self.splits_ms(user_id),
+            });
+        }
+
+        self.standings()
+            .into_iter()
+            .find(|standing| standing.user_id == user_id)
+            .map(|standing| RacerResult {
+                user_id,
+                place: Some(standing.place),
+                finish_time_ms: Some(standing.finish_time_ms),
+                splits_ms: self.splits_ms(user_id),
+            })
+    }
+
+    /// Placements of every racer who finished, ordered by interpolated finish
+    /// time. Exact ties are broken by user id so the order is deterministic.
+    pub fn standings(&self) -> Vec<FinishStanding> {
+        let mut finishers: Vec<(UserId, Duration)> = self
+            .racers
+            .iter()
+            .filter_map(|(user_id, racer)| racer.finish_time.map(|time| (*user_id, time)))
+            .collect();
+        finishers.sort_by_key(|(user_id, time)| (*time, *user_id));
+
+        let winner_time = finishers.first().map(|(_, time)| *time).unwrap_or_default();
+
+        finishers
+            .into_iter()
+            .enumerate()
+            .map(|(index, (user_id, time))| FinishStanding {
+                user_id,
+                place: index + 1,
+                finish_time_ms: time.as_millis() as u64,
+                gap_ms: (time - winner_time).as_millis() as u64,
+            })
+            .collect()
+    }
+
+    // When a racer passed each checkpoint passed so far, in milliseconds
+    fn splits_ms(&self, user_id: UserId) -> Vec<Option<u64>> {
+        self.racers.get(&user_id).map_or_else(Vec::new, |racer| {
+            racer
+                .splits
+                .iter()
+                .map(|split| split.map(|time| time.as_millis() as u64))
+                .collect()
+        })
+    }
+
+    // Fraction of the course completed, interpolated along the current leg
+    fn completion_pct(&self, next_waypoint: usize, position: Point) -> f32 {
+        let total = self.waypoints.len();
+        if next_waypoint >= total {
+            return 100.0;
+        }
+
+        let leg_start = match next_waypoint {
+            0 => self.start,
+            n => self.waypoints[n - 1],
+        };
+        let leg_end = self.waypoints[next_waypoint];
+
+        let leg_length = distance_meters(leg_start, leg_end);
+        let leg_fraction = if leg_length > 0.0 {
+            (1.0 - distance_meters(position, leg_end) / leg_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        ((next_waypoint as f64 + leg_fraction) / total as f64 * 100.0) as f32
+    }
+}
+
+// Interpolate when a racer entered the radius of a waypoint between its
+// previous sample and the sample that reached the waypoint
+fn crossing_time(
+    previous: Option<(Point, Duration)>,
+    current: (Point, Duration),
+    waypoint: Point,
+    radius: f64,
+) -> Duration {
+    let (position, time) = current;
+    let Some((previous_position, previous_time)) = previous else {
+        return time;
+    };
+
+    let previous_distance = distance_meters(previous_position, waypoint);
+    let distance = distance_meters(position, waypoint);
+    if previous_distance <= distance {
+        return time;
+    }
+
+    let fraction = ((previous_distance - radius) / (previous_distance - distance)).clamp(0.0, 1.0);
+
+    previous_time + (time - previous_time).mul_f64(fraction)
+}
+
+/// Great-circle distance between two points in meters
+pub fn distance_meters(a: Point, b: Point) -> f64 {
+    let (lat_a, lon_a) = (a.0.to_radians(), a.1.to_radians());
+    let (lat_b, lon_b) = (b.0.to_radians(), b.1.to_radians());
+
+    let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
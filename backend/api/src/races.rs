@@ -0,0 +1,96 @@
+//! Races and how each racer did in them.
+//!
+//! A race is saved when it starts, so racers can submit their results to it
+//! while it runs. The server tracks the race in memory meanwhile. When the
+//! race ends, because every racer finished, the next race started or
+//! everyone left, the finish times, places and checkpoint splits the server
+//! measured are saved over whatever racers submitted. Racers who never
+//! crossed the finish line did not finish. The awards the racers earned are
+//! saved along with the results.
+
+use chrono::Utc;
+use entity::race;
+use entity::race_participant::{self, Entity as RaceParticipant};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Set,
+    TransactionTrait, sea_query::OnConflict,
+};
+
+use crate::awards::{self, Award};
+use crate::db::PartyId;
+use crate::race::RacerResult;
+use crate::splits;
+
+/// Save a race starting now in a party, as part of the playlist it races
+/// through, if any
+pub async fn start<C: ConnectionTrait>(
+    db: &C,
+    party_id: PartyId,
+    map_id: i32,
+    playlist_id: Option<i32>,
+) -> Result<race::Model, DbErr> {
+    race::ActiveModel {
+        map_id: Set(map_id),
+        party_id: Set(Some(party_id)),
+        playlist_id: Set(playlist_id),
+        started_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+/// Save that a race ended, with the results the server measured for its
+/// racers and the awards they earned
+pub async fn finish(
+    db: &DatabaseConnection,
+    race_id: i32,
+    results: &[RacerResult],
+    awards: &[Award],
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+
+    race::ActiveModel {
+        id: Set(race_id),
+        ended_at: Set(Some(Utc::now().fixed_offset())),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    let participants: Vec<race_participant::ActiveModel> = results
+        .iter()
+        .map(|result| race_participant::ActiveModel {
+            race_id: Set(race_id),
+            user_id: Set(result.user_id),
+            finish_time_ms: Set(result.finish_time_ms.map(|time| time as i64)),
+            position: Set(result.place.map(|place| place as i32)),
+            dnf: Set(result.finish_time_ms.is_none()),
+            splits: Set(Some(splits::encode(&result.splits_ms))),
+            ..Default::default()
+        })
+        .collect();
+
+    if !participants.is_empty() {
+        RaceParticipant::insert_many(participants)
+            .on_conflict(
+                OnConflict::columns([
+                    race_participant::Column::RaceId,
+                    race_participant::Column::UserId,
+                ])
+                .update_columns([
+                    race_participant::Column::FinishTimeMs,
+                    race_participant::Column::Position,
+                    race_participant::Column::Dnf,
+                    race_participant::Column::Splits,
+                ])
+                .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+    }
+
+    awards::save(&txn, race_id, awards).await?;
+
+    txn.commit().await
+}
@@ -0,0 +1,110 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client_ip;
+use crate::db::AppState;
+
+// Largest auth request body read to find the user name
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+// Number of counters kept before expired ones are dropped
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Fixed-window request counters, kept in memory per API instance
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Count a request for a key. Returns how long to wait when the key has
+    /// used up its `limit` for the current window.
+    pub fn hit(&self, key: String, limit: u32, window: Duration) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < window);
+        }
+
+        let (started, count) = windows.entry(key).or_insert((now, 0));
+        if now.duration_since(*started) >= window {
+            *started = now;
+            *count = 0;
+        }
+
+        if *count >= limit {
+            return Err(window - now.duration_since(*started));
+        }
+
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Middleware limiting auth requests per client IP and per user name, to
+/// prevent registration spam, password guessing and token-refresh abuse
+pub async fn limit_auth_requests(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let config = &state.config;
+    let window = Duration::from_secs(config.auth_rate_limit_window);
+    let path = req.uri().path().to_string();
+
+    // Limit by client IP
+    let ip = client_ip::from_request(&config.trusted_proxies, req.headers(), peer);
+    if let Err(retry_after) = state.rate_limiter.hit(
+        format!("ip:{}:{}", path, ip),
+        config.auth_rate_limit_per_ip,
+        window,
+    ) {
+        return too_many_requests(retry_after);
+    }
+
+    // Limit by the user name in the body, if any
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(e) => return (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response(),
+    };
+
+    let name = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|body| body.get("name")?.as_str().map(|name| name.to_lowercase()));
+
+    if let Some(name) = name
+        && let Err(retry_after) = state.rate_limiter.hit(
+            format!("name:{}:{}", path, name),
+            config.auth_rate_limit_per_name,
+            window,
+        )
+    {
+        return too_many_requests(retry_after);
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs().max(1);
+
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, seconds.to_string())],
+        format!("Too many requests, try again in {} seconds", seconds),
+    )
+        .into_response()
+}
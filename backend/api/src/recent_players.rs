@@ -0,0 +1,67 @@
+//! Who raced with whom, so players can find each other again.
+//!
+//! Every pair of racers in a race is remembered in both directions, with the
+//! party and map of the last race they shared.
+
+use chrono::Utc;
+use entity::recent_player::{self, Entity as RecentPlayer};
+use sea_orm::{
+    DatabaseConnection, DbErr, EntityTrait, Set,
+    sea_query::{Expr, OnConflict},
+};
+
+use crate::db::{PartyId, UserId};
+
+/// Remember that the participants of a race played with each other
+pub async fn record_race(
+    db: &DatabaseConnection,
+    party_id: PartyId,
+    map_id: i32,
+    participants: &[UserId],
+) -> Result<(), DbErr> {
+    let now = Utc::now().fixed_offset();
+
+    let rows: Vec<recent_player::ActiveModel> = participants
+        .iter()
+        .flat_map(|user_id| {
+            participants
+                .iter()
+                .filter(move |other_user_id| *other_user_id != user_id)
+                .map(move |other_user_id| recent_player::ActiveModel {
+                    user_id: Set(*user_id),
+                    other_user_id: Set(*other_user_id),
+                    last_party_id: Set(Some(party_id)),
+                    last_map_id: Set(Some(map_id)),
+                    last_played_at: Set(now),
+                    ..Default::default()
+                })
+        })
+        .collect();
+
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    // Pairs that raced before count one more race together
+    RecentPlayer::insert_many(rows)
+        .on_conflict(
+            OnConflict::columns([
+                recent_player::Column::UserId,
+                recent_player::Column::OtherUserId,
+            ])
+            .value(
+                recent_player::Column::RacesTogether,
+                Expr::col((RecentPlayer, recent_player::Column::RacesTogether)).add(1),
+            )
+            .update_columns([
+                recent_player::Column::LastPartyId,
+                recent_player::Column::LastMapId,
+                recent_player::Column::LastPlayedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use axum::http::HeaderMap;
+use sea_orm::DbErr;
+
+use crate::config::Config;
+use crate::db::{AppState, UserId};
+use crate::settings;
+
+// Longest region name users can choose
+const MAX_REGION_LENGTH: usize = 32;
+
+/// Resolve the coarse region of a request.
+///
+/// If a `REGION_HEADER` is configured (e.g. a GeoIP header set by the edge
+/// proxy such as `CF-IPCountry` or `Fly-Region`), its value is used.
+/// Otherwise the server's `DEFAULT_REGION` is used, if any.
+pub fn from_headers(config: &Config, headers: &HeaderMap) -> Option<String> {
+    config
+        .region_header
+        .as_deref()
+        .and_then(|name| headers.get(name))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .or_else(|| config.default_region.clone())
+}
+
+/// Get the region recorded for a connected user, if any
+pub fn of_user(state: &AppState, user_id: UserId) -> Option<String> {
+    state.user_regions.lock().unwrap().get(&user_id).cloned()
+}
+
+/// Get the region of the caller, preferring the one recorded for their
+/// websocket connection over the one derived from the request headers
+pub fn of_caller(state: &AppState, user_id: UserId, headers: &HeaderMap) -> Option<String> {
+    of_user(state, user_id).or_else(|| from_headers(&state.config, headers))
+}
+
+/// Get the region a user races in: the region they prefer, if they set one,
+/// or else the region of the caller
+pub async fn preferred_or_caller(
+    state: &AppState,
+    user_id: UserId,
+    headers: &HeaderMap,
+) -> Result<Option<String>, DbErr> {
+    let preferred = settings::preferred_region(&state.conn, user_id).await?;
+
+    Ok(preferred.or_else(|| of_caller(state, user_id, headers)))
+}
+
+/// Normalize a region chosen by a user, e.g. "EU-West" to "eu-west". Returns
+/// `None` if it isn't a valid region name.
+pub fn parse(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    let valid = (1..=MAX_REGION_LENGTH).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    valid.then_some(value)
+}
+
+/// Get the connected users recorded in a region
+pub fn user_ids_in(state: &AppState, region: &str) -> Vec<UserId> {
+    state
+        .user_regions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, user_region)| user_region.as_str() == region)
+        .map(|(user_id, _)| *user_id)
+        .collect()
+}
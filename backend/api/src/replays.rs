@@ -0,0 +1,137 @@
+//! Replays recorded by the server.
+//!
+//! While a race runs, the positions racers send to their party are kept in
+//! memory, one per sample interval at most. Each racer keeps a limited number
+//! of them, so the oldest make room for new ones rather than long races
+//! growing without bound, and positions after the finish line aren't kept.
+//! When the race ends, the positions of each racer are packed into frames
+//! and saved, so races have replays without clients uploading them. Replays
+//! are purged once they are older than the retention period.
+
+use entity::race_replay::{self, Entity as RaceReplay};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, Set, sea_query::OnConflict};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::db::UserId;
+
+// Bytes of a packed frame: the time, then the position and rotation, each
+// little-endian
+const FRAME_SIZE: usize = 28;
+
+/// Where a racer was at a point in a race
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    // Since the start of the race
+    pub time_ms: u32,
+    // x, y and z, as sent by the client
+    pub position: [f32; 3],
+    // Yaw, pitch and roll
+    pub rotation: [f32; 3],
+}
+
+/// Positions of the racers of a race in progress
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder {
+    sample_interval: Duration,
+    max_frames: usize,
+    racers: HashMap<UserId, VecDeque<Frame>>,
+}
+
+impl ReplayRecorder {
+    pub fn new(sample_interval: Duration, max_frames: usize) -> Self {
+        Self {
+            sample_interval,
+            max_frames: max_frames.max(1),
+            racers: HashMap::new(),
+        }
+    }
+
+    /// Record where a racer is, unless their last position was recorded less
+    /// than the sample interval ago
+    pub fn record(&mut self, user_id: UserId, frame: Frame) {
+        let frames = self.racers.entry(user_id).or_default();
+        if let Some(last) = frames.back()
+            && u128::from(frame.time_ms.saturating_sub(last.time_ms))
+                < self.sample_interval.as_millis()
+        {
+            return;
+        }
+
+        if frames.len() == self.max_frames {
+            frames.pop_front();
+        }
+        frames.push_back(frame);
+    }
+}
+
+/// Pack frames to be saved
+pub fn encode(frames: &[Frame]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(frames.len() * FRAME_SIZE);
+    for frame in frames {
+        bytes.extend_from_slice(&frame.time_ms.to_le_bytes());
+        for value in frame.position.iter().chain(&frame.rotation) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Unpack saved frames. A truncated last frame is left out.
+pub fn decode(bytes: &[u8]) -> Vec<Frame> {
+    bytes
+        .chunks_exact(FRAME_SIZE)
+        .map(|chunk| {
+            let value = |index: usize| {
+                let start = 4 + index * 4;
+                f32::from_le_bytes(chunk[start..start + 4].try_into().unwrap())
+            };
+            Frame {
+                time_ms: u32::from_le_bytes(chunk[..4].try_into().unwrap()),
+                position: [value(0), value(1), value(2)],
+                rotation: [value(3), value(4), value(5)],
+            }
+        })
+        .collect()
+}
+
+/// Save the replays of the racers of a race that ended. Racers who already
+/// have a replay of the race keep it.
+pub async fn save<C: ConnectionTrait>(
+    db: &C,
+    race_id: i32,
+    recorder: ReplayRecorder,
+) -> Result<(), DbErr> {
+    let sample_interval_ms = recorder.sample_interval.as_millis() as i32;
+    let replays: Vec<race_replay::ActiveModel> = recorder
+        .racers
+        .into_iter()
+        .filter(|(_, frames)| !frames.is_empty())
+        .map(|(user_id, frames)| {
+            let frames: Vec<Frame> = frames.into();
+            race_replay::ActiveModel {
+                race_id: Set(race_id),
+                user_id: Set(user_id),
+                sample_interval_ms: Set(sample_interval_ms),
+                frame_count: Set(frames.len() as i32),
+                frames: Set(encode(&frames)),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    if replays.is_empty() {
+        return Ok(());
+    }
+
+    RaceReplay::insert_many(replays)
+        .on_conflict(
+            OnConflict::columns([race_replay::Column::RaceId, race_replay::Column::UserId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(())
+}
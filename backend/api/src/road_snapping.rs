@@ -0,0 +1,202 @@
+//! Snapping the points of maps to roads cars can drive on.
+//!
+//! Points placed by hand often end up next to a road, in a park or on a
+//! roof. An OSRM server or the Mapbox Directions API routes a car through
+//! the points in order, which moves each point to the nearest drivable road
+//! and yields the route in between. Both answer in the same format.
+
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::race::Point;
+
+// Where Mapbox is reached unless configured otherwise
+const MAPBOX_URL: &str = "https://api.mapbox.com";
+
+/// Services that can snap points to roads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoadSnapProvider {
+    Osrm,
+    Mapbox,
+}
+
+impl RoadSnapProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoadSnapProvider::Osrm => "osrm",
+            RoadSnapProvider::Mapbox => "mapbox",
+        }
+    }
+}
+
+impl fmt::Display for RoadSnapProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RoadSnapProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "osrm" => Ok(RoadSnapProvider::Osrm),
+            "mapbox" => Ok(RoadSnapProvider::Mapbox),
+            _ => Err(format!("Unknown road snapping provider: {}", s)),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RoadSnapError {
+    /// The provider found no drivable route through the points
+    #[error("The points can't be snapped to roads: {0}")]
+    NoRoute(String),
+
+    #[error("Road snapping failed: {0}")]
+    Request(String),
+}
+
+/// Points moved onto roads, and the route through them
+#[derive(Debug, Clone)]
+pub struct SnappedRoute {
+    /// The given points in the same order, each on its nearest road
+    pub points: Vec<Point>,
+    /// The route as an encoded polyline with a precision of 6 digits
+    pub polyline: String,
+    /// OpenStreetMap nodes the route passes between each point and the next,
+    /// if the provider tells; only OSRM does
+    pub leg_nodes: Vec<Vec<i64>>,
+}
+
+#[derive(Deserialize)]
+struct RouteResponse {
+    code: String,
+    message: Option<String>,
+    #[serde(default)]
+    waypoints: Vec<Waypoint>,
+    #[serde(default)]
+    routes: Vec<Route>,
+}
+
+#[derive(Deserialize)]
+struct Waypoint {
+    /// Longitude and latitude
+    location: [f64; 2],
+}
+
+#[derive(Deserialize)]
+struct Route {
+    geometry: String,
+    #[serde(default)]
+    legs: Vec<Leg>,
+}
+
+#[derive(Deserialize)]
+struct Leg {
+    annotation: Option<Annotation>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    #[serde(default)]
+    nodes: Vec<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoadSnapper {
+    provider: RoadSnapProvider,
+    url: String,
+    access_token: Option<String>,
+}
+
+impl RoadSnapper {
+    /// Snapper using a provider at a URL. Mapbox needs an access token and
+    /// is reached at its public API without a URL.
+    pub fn new(
+        provider: RoadSnapProvider,
+        url: Option<String>,
+        access_token: Option<String>,
+    ) -> Self {
+        let url = url
+            .unwrap_or_else(|| MAPBOX_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        Self {
+            provider,
+            url,
+            access_token,
+        }
+    }
+
+    /// Snap points, given as latitude and longitude, to roads and route a
+    /// car through them in order
+    pub async fn snap(&self, points: &[Point]) -> Result<SnappedRoute, RoadSnapError> {
+        let coordinates = points
+            .iter()
+            .map(|(latitude, longitude)| format!("{:.6},{:.6}", longitude, latitude))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let endpoint = match self.provider {
+            RoadSnapProvider::Osrm => format!("{}/route/v1/driving/{}", self.url, coordinates),
+            RoadSnapProvider::Mapbox => {
+                format!("{}/directions/v5/mapbox/driving/{}", self.url, coordinates)
+            }
+        };
+
+        let mut query = vec![("overview", "full"), ("geometries", "polyline6")];
+        if self.provider == RoadSnapProvider::Osrm {
+            query.push(("annotations", "nodes"));
+        }
+        if let Some(access_token) = &self.access_token {
+            query.push(("access_token", access_token));
+        }
+
+        // Routes that can't be found are answered with an error status and
+        // a code saying why
+        let response: RouteResponse = reqwest::Client::new()
+            .get(endpoint)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| RoadSnapError::Request(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| RoadSnapError::Request(e.to_string()))?;
+
+        if response.code != "Ok" {
+            return Err(RoadSnapError::NoRoute(
+                response.message.unwrap_or(response.code),
+            ));
+        }
+
+        let Some(route) = response.routes.into_iter().next() else {
+            return Err(RoadSnapError::NoRoute("No route found".to_string()));
+        };
+        if response.waypoints.len() != points.len() {
+            return Err(RoadSnapError::Request(format!(
+                "Expected {} waypoints, got {}",
+                points.len(),
+                response.waypoints.len()
+            )));
+        }
+
+        Ok(SnappedRoute {
+            points: response
+                .waypoints
+                .into_iter()
+                .map(|waypoint| (waypoint.location[1], waypoint.location[0]))
+                .collect(),
+            polyline: route.geometry,
+            leg_nodes: route
+                .legs
+                .into_iter()
+                .map(|leg| leg.annotation.map(|a| a.nodes).unwrap_or_default())
+                .collect(),
+        })
+    }
+}
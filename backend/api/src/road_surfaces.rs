@@ -0,0 +1,154 @@
+//! Road surfaces along the routes of maps.
+//!
+//! When the points of a map are snapped to roads with OSRM, the route passes
+//! known OpenStreetMap nodes. An Overpass API server looks up the ways
+//! through those nodes, and their `surface` tags tell what cars drive on
+//! between each two points in a row. Clients tune their physics by it.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// What the road between two points of a map is made of
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Surface {
+    Asphalt,
+    Gravel,
+    Dirt,
+    /// The road wasn't found in OpenStreetMap
+    Unknown,
+}
+
+impl Surface {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Surface::Asphalt => "asphalt",
+            Surface::Gravel => "gravel",
+            Surface::Dirt => "dirt",
+            Surface::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "asphalt" => Surface::Asphalt,
+            "gravel" => Surface::Gravel,
+            "dirt" => Surface::Dirt,
+            _ => Surface::Unknown,
+        }
+    }
+
+    // Helper function to sort the tags of a way into surfaces. Roads without
+    // a surface are paved, unless they are tracks.
+    fn from_tags(tags: &HashMap<String, String>) -> Self {
+        match tags.get("surface").map(String::as_str) {
+            Some("gravel" | "fine_gravel" | "compacted" | "pebblestone" | "rock" | "shells") => {
+                Surface::Gravel
+            }
+            Some(
+                "dirt" | "earth" | "ground" | "mud" | "sand" | "grass" | "unpaved" | "woodchips",
+            ) => Surface::Dirt,
+            Some(_) => Surface::Asphalt,
+            None if tags
+                .get("highway")
+                .is_some_and(|highway| highway == "track") =>
+            {
+                Surface::Dirt
+            }
+            None => Surface::Asphalt,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Road surface lookup failed: {0}")]
+pub struct RoadSurfaceError(String);
+
+#[derive(Deserialize)]
+struct OverpassResponse {
+    elements: Vec<Way>,
+}
+
+#[derive(Deserialize)]
+struct Way {
+    #[serde(default)]
+    nodes: Vec<i64>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoadSurfaces {
+    url: String,
+}
+
+impl RoadSurfaces {
+    /// Lookup using the interpreter of an Overpass API server
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    /// Surface of each leg of a route, given the OpenStreetMap nodes it
+    /// passes. Legs are labelled with the surface most of their stretches
+    /// have, the better one on a tie.
+    pub async fn surfaces(&self, legs: &[Vec<i64>]) -> Result<Vec<Surface>, RoadSurfaceError> {
+        let mut node_ids: Vec<i64> = legs.iter().flatten().copied().collect();
+        node_ids.sort_unstable();
+        node_ids.dedup();
+        if node_ids.is_empty() {
+            return Ok(vec![Surface::Unknown; legs.len()]);
+        }
+
+        let node_ids = node_ids
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let query = format!(
+            "[out:json];node(id:{});way(bn)[highway];out body;",
+            node_ids
+        );
+
+        let response: OverpassResponse = reqwest::Client::new()
+            .post(&self.url)
+            .form(&[("data", query)])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| RoadSurfaceError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| RoadSurfaceError(e.to_string()))?;
+
+        // Surface of the road between each two nodes next to each other
+        let mut stretches: HashMap<(i64, i64), Surface> = HashMap::new();
+        for way in &response.elements {
+            let surface = Surface::from_tags(&way.tags);
+            for pair in way.nodes.windows(2) {
+                stretches.insert((pair[0], pair[1]), surface);
+                stretches.insert((pair[1], pair[0]), surface);
+            }
+        }
+
+        Ok(legs
+            .iter()
+            .map(|nodes| {
+                let mut counts: HashMap<Surface, usize> = HashMap::new();
+                for pair in nodes.windows(2) {
+                    if let Some(&surface) = stretches.get(&(pair[0], pair[1])) {
+                        *counts.entry(surface).or_default() += 1;
+                    }
+                }
+                counts
+                    .into_iter()
+                    .max_by_key(|&(surface, count)| (count, Reverse(surface)))
+                    .map_or(Surface::Unknown, |(surface, _)| surface)
+            })
+            .collect())
+    }
+}
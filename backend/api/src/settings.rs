@@ -0,0 +1,92 @@
+//! Preferences of users that other subsystems respect.
+//!
+//! Users without stored settings use the defaults, which hide nothing.
+
+use entity::user_settings::{self, Entity as UserSettings};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+use crate::db::UserId;
+
+/// Who can see the activity of a user, besides the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityVisibility {
+    #[default]
+    Public,
+    /// Only users following the user
+    Followers,
+    Private,
+}
+
+impl ActivityVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityVisibility::Public => "public",
+            ActivityVisibility::Followers => "followers",
+            ActivityVisibility::Private => "private",
+        }
+    }
+
+    pub fn from_column(value: &str) -> Self {
+        match value {
+            "followers" => ActivityVisibility::Followers,
+            "private" => ActivityVisibility::Private,
+            _ => ActivityVisibility::Public,
+        }
+    }
+}
+
+/// The users whose parties are left out of the party browser
+pub async fn hidden_party_owner_ids(db: &DatabaseConnection) -> Result<HashSet<UserId>, DbErr> {
+    let user_ids: Vec<UserId> = UserSettings::find()
+        .select_only()
+        .column(user_settings::Column::UserId)
+        .filter(user_settings::Column::HideFromPartyBrowser.eq(true))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(user_ids.into_iter().collect())
+}
+
+/// The users among the given ones who appear offline to others
+pub async fn appearing_offline_ids(
+    db: &DatabaseConnection,
+    user_ids: &[UserId],
+) -> Result<HashSet<UserId>, DbErr> {
+    let user_ids: Vec<UserId> = UserSettings::find()
+        .select_only()
+        .column(user_settings::Column::UserId)
+        .filter(user_settings::Column::UserId.is_in(user_ids.iter().copied()))
+        .filter(user_settings::Column::AppearOffline.eq(true))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(user_ids.into_iter().collect())
+}
+
+/// Who can see the activity of a user
+pub async fn activity_visibility(
+    db: &DatabaseConnection,
+    user_id: UserId,
+) -> Result<ActivityVisibility, DbErr> {
+    let settings = UserSettings::find_by_id(user_id).one(db).await?;
+
+    Ok(settings
+        .map(|settings| ActivityVisibility::from_column(&settings.activity_visibility))
+        .unwrap_or_default())
+}
+
+/// The region a user prefers to race in, if they chose one
+pub async fn preferred_region(
+    db: &DatabaseConnection,
+    user_id: UserId,
+) -> Result<Option<String>, DbErr> {
+    let settings = UserSettings::find_by_id(user_id).one(db).await?;
+
+    Ok(settings.and_then(|settings| settings.preferred_region))
+}
@@ -0,0 +1,67 @@
+//! Compact storage of checkpoint split times.
+//!
+//! A racer's splits are the times at which they passed each checkpoint of a
+//! map since the start, with gaps for optional checkpoints they left out.
+//! Each split is stored as the milliseconds since the split before it, plus
+//! one, in an unsigned LEB128 varint, and a 0 marks a checkpoint left out.
+//! Checkpoints a few seconds apart take two bytes each.
+
+/// Encode the splits of a racer
+pub fn encode(splits: &[Option<u64>]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(splits.len() * 2);
+    let mut previous = 0;
+
+    for split in splits {
+        let mut value = match split {
+            Some(split) => {
+                let delta = split.saturating_sub(previous);
+                previous = previous.max(*split);
+                delta + 1
+            }
+            None => 0,
+        };
+
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    bytes
+}
+
+/// Decode the splits of a racer. A truncated last split is left out.
+pub fn decode(bytes: &[u8]) -> Vec<Option<u64>> {
+    let mut splits = Vec::new();
+    let mut previous = 0;
+    let mut value: u64 = 0;
+    let mut shift = 0;
+
+    for byte in bytes {
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 != 0 {
+            shift += 7;
+            if shift >= u64::BITS {
+                break;
+            }
+            continue;
+        }
+
+        splits.push(match value {
+            0 => None,
+            value => {
+                previous += value - 1;
+                Some(previous)
+            }
+        });
+        value = 0;
+        shift = 0;
+    }
+
+    splits
+}
@@ -0,0 +1,133 @@
+//! Object storage for files players upload, like map thumbnails.
+//!
+//! Any S3-compatible service works, e.g. AWS S3, Cloudflare R2 or MinIO.
+//! Files don't pass through the API: clients are handed a presigned URL to
+//! upload to directly, signed with AWS Signature Version 4, and the storage
+//! serves the files from its public URL.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<sha2::Sha256>;
+
+// Payload hash of presigned requests, whose body isn't known when signing
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+#[derive(Debug, Clone)]
+pub struct ObjectStorage {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    public_url: String,
+}
+
+impl ObjectStorage {
+    /// Storage of a bucket at an S3-compatible endpoint. Objects are
+    /// addressed path-style, which all S3-compatible services understand.
+    /// Without a public URL, files are served from the bucket's URL.
+    pub fn new(
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+        public_url: Option<String>,
+    ) -> Self {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let public_url = public_url
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("{}/{}", endpoint, bucket));
+
+        Self {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            public_url,
+        }
+    }
+
+    /// URL an object is served from
+    pub fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    /// URL to upload an object to with a PUT request, valid for `expires_in`
+    /// seconds. The upload must be sent with the given content type.
+    pub fn presign_put(&self, key: &str, content_type: &str, expires_in: u64) -> String {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, rest)| rest);
+        let path = format!(
+            "/{}/{}",
+            uri_encode(&self.bucket, true),
+            uri_encode(key, false)
+        );
+
+        // Parameters must be sorted by name to be signed
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=content-type%3Bhost",
+            uri_encode(&format!("{}/{}", self.access_key, scope), true),
+            amz_date,
+            expires_in,
+        );
+
+        let canonical_request = format!(
+            "PUT\n{}\n{}\ncontent-type:{}\nhost:{}\n\ncontent-type;host\n{}",
+            path, query, content_type, host, UNSIGNED_PAYLOAD
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date),
+            |key, part| hmac(&key, part),
+        );
+        let signature = hex::encode(hmac(&signing_key, &string_to_sign));
+
+        format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.endpoint, path, query, signature
+        )
+    }
+}
+
+// Helper function to sign a message with a key
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Helper function to percent-encode like AWS expects, leaving only unreserved
+// characters and, in paths, slashes as they are
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
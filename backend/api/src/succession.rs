@@ -0,0 +1,117 @@
+//! Handing parties over when their owner goes away.
+//!
+//! When the websocket connection of a party owner drops, they get a grace
+//! period to come back. If they don't, the member who has been in the party
+//! the longest becomes its owner, or the party is disbanded if nobody else is
+//! in it. Connected members are told either way.
+
+use entity::party::Entity as Party;
+use entity::user_party::{self, Entity as UserParty};
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use std::time::{Duration, Instant};
+
+use crate::api::{parties, ws::WsMessage};
+use crate::db::{AppState, PartyId, UserId};
+
+/// Start the grace period of a user whose connection to a party dropped, if
+/// they own it
+pub async fn owner_disconnected(state: &AppState, user_id: UserId, party_id: PartyId) {
+    match Party::find_by_id(party_id).one(&state.conn).await {
+        Ok(Some(party)) if party.owner_id == user_id => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::error!("Error checking party owner: {}", e);
+            return;
+        }
+    }
+
+    let disconnected_at = Instant::now();
+    state
+        .owner_disconnects
+        .lock()
+        .unwrap()
+        .insert((user_id, party_id), disconnected_at);
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(state.config.owner_grace_period)).await;
+
+        // A later disconnect started a grace period of its own
+        {
+            let mut owner_disconnects = state.owner_disconnects.lock().unwrap();
+            if owner_disconnects.get(&(user_id, party_id)) != Some(&disconnected_at) {
+                return;
+            }
+            owner_disconnects.remove(&(user_id, party_id));
+        }
+
+        if let Err(e) = hand_over_if_gone(&state, user_id, party_id).await {
+            tracing::error!("Error handing over party {}: {}", party_id, e);
+        }
+    });
+}
+
+// Helper function to hand a party over once its owner's grace period is over,
+// unless they came back meanwhile
+async fn hand_over_if_gone(
+    state: &AppState,
+    owner_id: UserId,
+    party_id: PartyId,
+) -> Result<(), DbErr> {
+    let db = &state.conn;
+
+    // The owner may have reconnected to any instance
+    let online = state
+        .presence
+        .query(&[owner_id])
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+    if online.get(&owner_id) == Some(&Some(party_id)) {
+        return Ok(());
+    }
+
+    // The party may be gone or have changed hands meanwhile
+    let Some(party) = Party::find_by_id(party_id).one(db).await? else {
+        return Ok(());
+    };
+    if party.owner_id != owner_id {
+        return Ok(());
+    }
+
+    let successor = UserParty::find()
+        .filter(user_party::Column::PartyId.eq(party_id))
+        .filter(user_party::Column::UserId.ne(owner_id))
+        .order_by_asc(user_party::Column::JoinedAt)
+        .order_by_asc(user_party::Column::Id)
+        .one(db)
+        .await?;
+
+    match successor {
+        Some(successor) => {
+            let party = parties::hand_over(db, party, successor.user_id, None).await?;
+            parties::announce_owner(state, &party, owner_id);
+
+            tracing::info!(
+                "Party {} handed over from user {} to user {}",
+                party_id,
+                owner_id,
+                successor.user_id
+            );
+        }
+        None => {
+            parties::disband(state, party_id).await?;
+
+            let channel = state.party_channels.lock().unwrap().get(&party_id).cloned();
+            if let Some(channel) = channel {
+                let disbanded_msg =
+                    serde_json::to_string(&WsMessage::PartyDisbanded { party_id }).unwrap();
+
+                let _ = channel.send(disbanded_msg);
+            }
+
+            tracing::info!("Party {} disbanded after its owner left", party_id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+//! Responses for requests with fields that failed validation.
+//!
+//! Names and texts shown to other players are checked by the
+//! `auth::validation::Validator` in the app state. Failures are answered with
+//! 422 and the list of failed fields, so clients can point at them.
+
+use auth::validation::FieldError;
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct ValidationErrorResponse {
+    errors: Vec<FieldError>,
+}
+
+/// Answer a request whose fields failed validation
+pub fn invalid(errors: Vec<FieldError>) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ValidationErrorResponse { errors }),
+    )
+        .into_response()
+}
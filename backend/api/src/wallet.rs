@@ -0,0 +1,115 @@
+//! Virtual currency.
+//!
+//! Every change to a balance is written to a ledger of transactions together
+//! with the balance it left, so balances can always be audited. Each grant
+//! carries a key unique per user: applying a grant again returns the
+//! original transaction instead of paying twice, which makes retries safe.
+
+use chrono::Utc;
+use entity::wallet::{self, Entity as Wallet};
+use entity::wallet_transaction::{self, Entity as WalletTransaction};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+    sea_query::{Expr, OnConflict},
+};
+
+use crate::db::UserId;
+
+// Coins awarded per event
+pub const RACE_FINISH_COINS: i64 = 10;
+pub const RACE_WIN_COINS: i64 = 25;
+pub const ACHIEVEMENT_COINS: i64 = 50;
+
+// Reasons recorded on the transactions of rewards
+pub const RACE_FINISH_REASON: &str = "race_finish";
+pub const RACE_WIN_REASON: &str = "race_win";
+pub const ACHIEVEMENT_REASON: &str = "achievement";
+
+/// Add an amount to the balance of a user, or take it off if negative.
+/// Returns the transaction, which is the original one if the key was used
+/// before, or nothing if the balance is too low for the amount.
+pub async fn grant<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    user_id: UserId,
+    amount: i64,
+    reason: &str,
+    idempotency_key: &str,
+) -> Result<Option<wallet_transaction::Model>, DbErr> {
+    if let Some(transaction) = find_transaction(db, user_id, idempotency_key).await? {
+        return Ok(Some(transaction));
+    }
+
+    let txn = db.begin().await?;
+
+    Wallet::insert(wallet::ActiveModel {
+        user_id: Set(user_id),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(wallet::Column::UserId)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(&txn)
+    .await?;
+
+    // Updating in one statement locks the wallet and keeps it from going
+    // below zero
+    let Some(wallet) = Wallet::update_many()
+        .col_expr(
+            wallet::Column::Balance,
+            Expr::col(wallet::Column::Balance).add(amount),
+        )
+        .col_expr(
+            wallet::Column::UpdatedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .filter(wallet::Column::UserId.eq(user_id))
+        .filter(wallet::Column::Balance.gte(-amount))
+        .exec_with_returning(&txn)
+        .await?
+        .pop()
+    else {
+        return Ok(None);
+    };
+
+    let inserted = wallet_transaction::ActiveModel {
+        user_id: Set(user_id),
+        amount: Set(amount),
+        balance_after: Set(wallet.balance),
+        reason: Set(reason.to_string()),
+        idempotency_key: Set(idempotency_key.to_string()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await;
+
+    match inserted {
+        Ok(transaction) => {
+            txn.commit().await?;
+            Ok(Some(transaction))
+        }
+        Err(e) => {
+            // A concurrent grant with the same key won; drop ours
+            txn.rollback().await?;
+            match find_transaction(db, user_id, idempotency_key).await? {
+                Some(transaction) => Ok(Some(transaction)),
+                None => Err(e),
+            }
+        }
+    }
+}
+
+// Helper function to find the transaction of a grant applied before
+async fn find_transaction<C: ConnectionTrait>(
+    db: &C,
+    user_id: UserId,
+    idempotency_key: &str,
+) -> Result<Option<wallet_transaction::Model>, DbErr> {
+    WalletTransaction::find()
+        .filter(wallet_transaction::Column::UserId.eq(user_id))
+        .filter(wallet_transaction::Column::IdempotencyKey.eq(idempotency_key))
+        .one(db)
+        .await
+}
@@ -0,0 +1,110 @@
+use chrono::Utc;
+use entity::{api_key, user};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    sea_query::Expr,
+};
+
+use crate::{AuthError, Claims, generate_secret, hash_secret};
+
+/// Header server-to-server callers send their key in
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Every key starts with this so leaked keys are easy to recognize
+const KEY_PREFIX: &str = "wr_";
+
+// Characters of a key kept in plain text to tell keys apart
+const DISPLAY_PREFIX_LENGTH: usize = 11;
+
+/// Create an API key for a user. Returns the stored key and the key itself,
+/// which is only available now since just its hash is stored.
+pub async fn create(
+    db: &DatabaseConnection,
+    user_id: i32,
+    name: String,
+) -> Result<(api_key::Model, String), AuthError> {
+    let key = format!("{}{}", KEY_PREFIX, generate_secret());
+
+    let stored = api_key::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(name),
+        prefix: Set(key[..DISPLAY_PREFIX_LENGTH].to_string()),
+        key_hash: Set(hash_secret(&key)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok((stored, key))
+}
+
+/// List the API keys of a user that have not been revoked
+pub async fn list(db: &DatabaseConnection, user_id: i32) -> Result<Vec<api_key::Model>, AuthError> {
+    api_key::Entity::find()
+        .filter(api_key::Column::UserId.eq(user_id))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .order_by_asc(api_key::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+}
+
+/// Revoke an API key of a user. Returns whether an active key was found.
+pub async fn revoke(db: &DatabaseConnection, user_id: i32, key_id: i32) -> Result<bool, AuthError> {
+    let result = api_key::Entity::update_many()
+        .col_expr(
+            api_key::Column::RevokedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .filter(api_key::Column::Id.eq(key_id))
+        .filter(api_key::Column::UserId.eq(user_id))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(result.rows_affected > 0)
+}
+
+/// Authenticate a request made with an API key.
+///
+/// The key acts on behalf of the user who created it, but never with
+/// administrator rights.
+pub async fn authenticate(db: &DatabaseConnection, key: &str) -> Result<Claims, AuthError> {
+    let stored = api_key::Entity::find()
+        .filter(api_key::Column::KeyHash.eq(hash_secret(key)))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let user = user::Entity::find_by_id(stored.user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    // Record when the key was last used
+    let now = Utc::now();
+    let mut key_model: api_key::ActiveModel = stored.into();
+    key_model.last_used_at = Set(Some(now.fixed_offset()));
+    key_model
+        .update(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(Claims {
+        sub: user.id,
+        exp: now.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        name: user.name,
+        admin: false,
+        jti: String::new(),
+        sid: None,
+        iss: None,
+        aud: None,
+        scope: Vec::new(),
+    })
+}
@@ -0,0 +1,135 @@
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+
+use crate::{AuthError, Claims};
+
+/// Access tokens revoked before they expire, e.g. on logout or when an admin
+/// signs a user out.
+///
+/// Entries live in Redis only as long as the tokens they revoke would have
+/// been accepted, including the leeway for clock skew. Without Redis configured nothing is revoked early, and if Redis
+/// cannot be reached tokens are accepted so an outage doesn't lock everyone out.
+#[derive(Clone, Default)]
+pub struct Blocklist {
+    conn: Option<ConnectionManager>,
+    leeway: u64, // Tolerated clock skew in seconds
+}
+
+impl Blocklist {
+    pub async fn connect(redis_url: Option<&str>, leeway: u64) -> Result<Self, AuthError> {
+        let Some(redis_url) = redis_url else {
+            return Ok(Self::default());
+        };
+
+        let client =
+            redis::Client::open(redis_url).map_err(|e| AuthError::InternalError(e.to_string()))?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        Ok(Self {
+            conn: Some(conn),
+            leeway,
+        })
+    }
+
+    /// Revoke a single access token
+    pub async fn revoke_token(&self, claims: &Claims) -> Result<(), AuthError> {
+        let Some(conn) = &self.conn else {
+            return Ok(());
+        };
+
+        // Keep the entry until the token would no longer be accepted
+        let ttl =
+            (claims.exp + self.leeway as usize).saturating_sub(Utc::now().timestamp() as usize);
+        if ttl == 0 {
+            return Ok(());
+        }
+
+        redis::cmd("SET")
+            .arg(token_key(&claims.jti))
+            .arg(1)
+            .arg("EX")
+            .arg(ttl)
+            .query_async::<()>(&mut conn.clone())
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))
+    }
+
+    /// Revoke every access token issued to a user so far
+    pub async fn revoke_user(&self, user_id: i32, jwt_expiry: i64) -> Result<(), AuthError> {
+        let Some(conn) = &self.conn else {
+            return Ok(());
+        };
+
+        // Tokens issued up to now are rejected until the last of them expires
+        redis::cmd("SET")
+            .arg(user_key(user_id))
+            .arg(Utc::now().timestamp())
+            .arg("EX")
+            .arg(self.ttl(jwt_expiry))
+            .query_async::<()>(&mut conn.clone())
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))
+    }
+
+    /// Revoke every access token issued for a session
+    pub async fn revoke_session(&self, session_id: i32, jwt_expiry: i64) -> Result<(), AuthError> {
+        let Some(conn) = &self.conn else {
+            return Ok(());
+        };
+
+        redis::cmd("SET")
+            .arg(session_key(session_id))
+            .arg(1)
+            .arg("EX")
+            .arg(self.ttl(jwt_expiry))
+            .query_async::<()>(&mut conn.clone())
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))
+    }
+
+    /// Reject claims of a revoked access token
+    pub async fn check(&self, claims: Claims) -> Result<Claims, AuthError> {
+        let Some(conn) = &self.conn else {
+            return Ok(claims);
+        };
+
+        let result: Result<(bool, bool, Option<i64>), _> = redis::pipe()
+            .exists(token_key(&claims.jti))
+            .exists(session_key(claims.sid.unwrap_or_default()))
+            .get(user_key(claims.sub))
+            .query_async(&mut conn.clone())
+            .await;
+
+        let Ok((token_revoked, session_revoked, revoked_before)) = result else {
+            return Ok(claims);
+        };
+
+        if token_revoked
+            || session_revoked
+            || revoked_before.is_some_and(|before| claims.iat as i64 <= before)
+        {
+            return Err(AuthError::TokenRevoked);
+        }
+
+        Ok(claims)
+    }
+
+    // How long to keep an entry revoking tokens issued up to now
+    fn ttl(&self, jwt_expiry: i64) -> i64 {
+        jwt_expiry.max(1) + self.leeway as i64
+    }
+}
+
+fn token_key(jti: &str) -> String {
+    format!("blocklist:token:{}", jti)
+}
+
+fn session_key(session_id: i32) -> String {
+    format!("blocklist:session:{}", session_id)
+}
+
+fn user_key(user_id: i32) -> String {
+    format!("blocklist:user:{}", user_id)
+}
@@ -0,0 +1,97 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AuthError;
+
+// Captcha services that can protect registration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaProvider {
+    HCaptcha,
+    Turnstile,
+}
+
+impl CaptchaProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptchaProvider::HCaptcha => "hcaptcha",
+            CaptchaProvider::Turnstile => "turnstile",
+        }
+    }
+
+    fn verify_endpoint(&self) -> &'static str {
+        match self {
+            CaptchaProvider::HCaptcha => "https://api.hcaptcha.com/siteverify",
+            CaptchaProvider::Turnstile => {
+                "https://challenges.cloudflare.com/turnstile/v0/siteverify"
+            }
+        }
+    }
+}
+
+impl fmt::Display for CaptchaProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CaptchaProvider {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hcaptcha" => Ok(CaptchaProvider::HCaptcha),
+            "turnstile" => Ok(CaptchaProvider::Turnstile),
+            _ => Err(AuthError::InternalError(format!(
+                "Unknown captcha provider: {}",
+                s
+            ))),
+        }
+    }
+}
+
+// Both providers answer with the same shape
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptchaVerifier {
+    provider: CaptchaProvider,
+    secret: String,
+}
+
+impl CaptchaVerifier {
+    pub fn new(provider: CaptchaProvider, secret: String) -> Self {
+        Self { provider, secret }
+    }
+
+    /// Check a token solved by the client with the provider
+    pub async fn verify(&self, token: Option<&str>, remote_ip: &str) -> Result<(), AuthError> {
+        let Some(token) = token.filter(|token| !token.is_empty()) else {
+            return Err(AuthError::CaptchaFailed);
+        };
+
+        let response: VerifyResponse = reqwest::Client::new()
+            .post(self.provider.verify_endpoint())
+            .form(&[
+                ("secret", self.secret.as_str()),
+                ("response", token),
+                ("remoteip", remote_ip),
+            ])
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AuthError::InternalError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        if !response.success {
+            return Err(AuthError::CaptchaFailed);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{Duration, Utc};
+use entity::{email_verification, user};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+
+use crate::{AuthError, generate_secret, hash_secret};
+
+// How long a verification link stays valid
+const VERIFICATION_EXPIRY: i64 = 86400; // in seconds
+
+// Longest address allowed by RFC 5321
+const MAX_EMAIL_LENGTH: usize = 254;
+
+/// Normalize an email address, rejecting obviously invalid ones. Whether the
+/// address exists is only known once the user follows the verification link.
+pub fn normalize_email(email: &str) -> Result<String, AuthError> {
+    let email = email.trim().to_lowercase();
+
+    let valid = email.len() <= MAX_EMAIL_LENGTH
+        && !email.chars().any(char::is_whitespace)
+        && email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        });
+
+    if !valid {
+        return Err(AuthError::InvalidEmail);
+    }
+
+    Ok(email)
+}
+
+/// Set the email address of a user and create a token to verify it. Returns
+/// the token, which is only available now since just its hash is stored.
+pub async fn request_verification(
+    db: &DatabaseConnection,
+    user_id: i32,
+    email: &str,
+) -> Result<String, AuthError> {
+    let email = normalize_email(email)?;
+
+    // Make sure no one else uses the address
+    let taken = user::Entity::find()
+        .filter(user::Column::Email.eq(email.clone()))
+        .filter(user::Column::Id.ne(user_id))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .is_some();
+
+    if taken {
+        return Err(AuthError::EmailTaken);
+    }
+
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    // A new address has to be verified again
+    if user.email.as_deref() != Some(email.as_str()) {
+        let mut user_model: user::ActiveModel = user.into();
+        user_model.email = Set(Some(email.clone()));
+        user_model.email_verified_at = Set(None);
+        user_model
+            .update(&txn)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+    }
+
+    let token = generate_secret();
+    let expires_at = Utc::now() + Duration::seconds(VERIFICATION_EXPIRY);
+
+    email_verification::ActiveModel {
+        user_id: Set(user_id),
+        email: Set(email),
+        token_hash: Set(hash_secret(&token)),
+        expires_at: Set(expires_at.fixed_offset()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(token)
+}
+
+/// Confirm the email address a verification token was sent to
+pub async fn verify(db: &DatabaseConnection, token: &str) -> Result<user::Model, AuthError> {
+    let verification = email_verification::Entity::find()
+        .filter(email_verification::Column::TokenHash.eq(hash_secret(token)))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if verification.used_at.is_some() {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let now = Utc::now();
+    if verification.expires_at < now {
+        return Err(AuthError::TokenExpired);
+    }
+
+    let user = user::Entity::find_by_id(verification.user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    // The user may have switched to another address since the token was sent
+    if user.email.as_deref() != Some(verification.email.as_str()) {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let mut verification_model: email_verification::ActiveModel = verification.into();
+    verification_model.used_at = Set(Some(now.fixed_offset()));
+    verification_model
+        .update(&txn)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.email_verified_at = Set(Some(now.fixed_offset()));
+    let user = user_model
+        .update(&txn)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(user)
+}
@@ -1,10 +1,57 @@
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{
+    DecodingKey, EncodingKey, Header, TokenData, Validation, decode, decode_header, encode,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use thiserror::Error;
+use uuid::Uuid;
 
+pub mod api_key;
+pub mod blocklist;
+pub mod captcha;
+pub mod email;
+pub mod lockout;
 pub mod middleware;
+pub mod oauth;
+pub mod password;
+pub mod platform;
 pub mod user;
+pub mod validation;
+pub mod voice;
+pub mod ws_ticket;
+
+use oauth::OAuthProvider;
+use platform::Platform;
+use validation::FieldError;
+
+// How long a user has to complete the provider's consent page
+const OAUTH_STATE_EXPIRY: i64 = 600; // in seconds
+
+// How long a websocket ticket can be used to open a connection
+pub const WS_TICKET_EXPIRY: i64 = 30; // in seconds
+
+// How long a party invite link can be used to join
+pub const PARTY_INVITE_EXPIRY: i64 = 86400; // in seconds
+
+// Default audience of the tokens issued for this API
+pub const ACCESS_TOKEN_AUDIENCE: &str = "world-racers-api";
+
+// Default clock skew tolerated when checking expiry times
+pub const DEFAULT_LEEWAY: u64 = 60; // in seconds
+
+/// Restrictions of a scoped access token. Tokens without scopes have full
+/// access; scoped tokens may only be used for what their scopes allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub enum Scope {
+    /// Open websocket connections
+    #[serde(rename = "ws")]
+    Ws,
+    /// Upload maps
+    #[serde(rename = "maps:upload")]
+    MapUpload,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -12,14 +59,62 @@ pub struct Claims {
     pub exp: usize,   // Expiration time
     pub iat: usize,   // Issued at
     pub name: String, // User name
+    #[serde(default)]
+    pub admin: bool, // Whether the user is an administrator
+    #[serde(default)]
+    pub jti: String, // Token id, used to revoke the token
+    #[serde(default)]
+    pub sid: Option<i32>, // Session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>, // Deployment that issued the token
+    #[serde(default)]
+    pub aud: Option<String>, // Audience the token was issued for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scope: Vec<Scope>, // Restrictions of the token, empty for full access
+}
+
+impl Claims {
+    /// Whether the token is restricted to some scopes
+    pub fn is_scoped(&self) -> bool {
+        !self.scope.is_empty()
+    }
+
+    /// Whether the token may be used for a scope
+    pub fn allows(&self, scope: Scope) -> bool {
+        !self.is_scoped() || self.scope.contains(&scope)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefreshClaims {
     pub sub: i32,           // Subject (user id)
+    pub jti: String,        // Token id, used to revoke the token
     pub exp: usize,         // Expiration time
     pub iat: usize,         // Issued at
     pub token_type: String, // To distinguish refresh tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>, // Deployment that issued the token
+    #[serde(default)]
+    pub aud: Option<String>, // Audience the token was issued for
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OAuthStateClaims {
+    pub provider: OAuthProvider, // Provider the flow was started for
+    pub nonce_hash: String,      // Hash of the nonce kept by the browser that started the flow
+    pub jti: String,             // State id, so a state is used once
+    pub exp: usize,              // Expiration time
+    pub iat: usize,              // Issued at
+    pub token_type: String,      // To distinguish state tokens
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartyInviteClaims {
+    pub party_id: i32,      // Party the link joins
+    pub sub: i32,           // User who shared the link
+    pub exp: usize,         // Expiration time
+    pub iat: usize,         // Issued at
+    pub token_type: String, // To distinguish invite links
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,12 +130,18 @@ pub enum AuthError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Password must be at least {0} characters long")]
+    PasswordTooShort(usize),
+
     #[error("JWT error: {0}")]
     JwtError(#[from] jsonwebtoken::errors::Error),
 
     #[error("Token expired")]
     TokenExpired,
 
+    #[error("Token revoked")]
+    TokenRevoked,
+
     #[error("Invalid token")]
     InvalidToken,
 
@@ -52,25 +153,144 @@ pub enum AuthError {
 
     #[error("Internal error: {0}")]
     InternalError(String),
+
+    #[error("Unknown OAuth provider: {0}")]
+    UnknownOAuthProvider(String),
+
+    #[error("OAuth error: {0}")]
+    OAuthError(String),
+
+    #[error("Token is not valid for this request")]
+    InsufficientScope,
+
+    #[error(
+        "Invalid {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    Validation(Vec<FieldError>),
+
+    #[error("Name is already taken")]
+    NameTaken,
+
+    #[error("Name can be changed again in {0} days")]
+    RenameCooldown(i64),
+
+    #[error("Captcha verification failed")]
+    CaptchaFailed,
+
+    #[error(
+        "Account is banned until {until}{}",
+        reason.as_ref().map(|reason| format!(": {}", reason)).unwrap_or_default()
+    )]
+    Banned {
+        until: chrono::DateTime<Utc>,
+        reason: Option<String>,
+    },
+
+    #[error("Account is locked after too many failed logins, try again in {0} seconds")]
+    AccountLocked(u64),
+
+    #[error("Too many failed logins, try again in {0} seconds")]
+    TooManyFailedLogins(u64),
+
+    #[error("Invalid email address")]
+    InvalidEmail,
+
+    #[error("Email address is already in use")]
+    EmailTaken,
+
+    #[error("Unknown platform: {0}")]
+    UnknownPlatform(String),
+
+    #[error("Platform error: {0}")]
+    PlatformError(String),
+
+    #[error("Invalid platform ticket")]
+    InvalidPlatformTicket,
+
+    #[error("This {0} account is linked to another user")]
+    PlatformAccountTaken(Platform),
+
+    #[error("A {0} account is already linked")]
+    PlatformAlreadyLinked(Platform),
 }
 
+/// A secret tokens are signed with, named by the `kid` header of the tokens
 #[derive(Debug, Clone)]
+pub struct JwtKey {
+    pub id: String,
+    pub secret: String,
+}
+
+// Keys derived from the secrets, newest first
+struct Keys {
+    signing_id: String,
+    signing: EncodingKey,
+    verifying: Vec<(String, DecodingKey)>,
+}
+
+/// Issues and verifies tokens. The keys are derived from the secrets once, so
+/// build it at startup and share it; clones are cheap.
+///
+/// Tokens are signed with the newest key and verified with whichever key
+/// signed them, so a new secret can be rolled out while tokens signed with
+/// the previous ones stay valid until those are removed.
+#[derive(Clone)]
 pub struct Auth {
-    jwt_secret: String,
-    jwt_expiry: i64,     // in seconds
-    refresh_expiry: i64, // in seconds
+    keys: Arc<Keys>,
+    jwt_expiry: i64,        // in seconds
+    refresh_expiry: i64,    // in seconds
+    issuer: Option<String>, // Required issuer of tokens, if any
+    audience: String,       // Required audience of tokens
+    leeway: u64,            // Tolerated clock skew in seconds
 }
 
 impl Auth {
-    pub fn new(jwt_secret: String, jwt_expiry: i64, refresh_expiry: i64) -> Self {
-        Self {
-            jwt_secret,
+    /// Create an instance signing with the first of the keys
+    pub fn new(
+        jwt_keys: Vec<JwtKey>,
+        jwt_expiry: i64,
+        refresh_expiry: i64,
+        issuer: Option<String>,
+        audience: String,
+        leeway: u64,
+    ) -> Result<Self, AuthError> {
+        let newest = jwt_keys
+            .first()
+            .ok_or_else(|| AuthError::InternalError("No JWT key configured".to_string()))?;
+
+        let keys = Keys {
+            signing_id: newest.id.clone(),
+            signing: EncodingKey::from_secret(newest.secret.as_bytes()),
+            verifying: jwt_keys
+                .iter()
+                .map(|key| {
+                    (
+                        key.id.clone(),
+                        DecodingKey::from_secret(key.secret.as_bytes()),
+                    )
+                })
+                .collect(),
+        };
+
+        Ok(Self {
+            keys: Arc::new(keys),
             jwt_expiry,
             refresh_expiry,
-        }
+            issuer,
+            audience,
+            leeway,
+        })
     }
 
-    pub fn generate_tokens(&self, user_id: i32, name: String) -> Result<AuthResponse, AuthError> {
+    pub fn generate_tokens(
+        &self,
+        user_id: i32,
+        name: String,
+        admin: bool,
+        session_id: i32,
+        refresh_jti: String,
+    ) -> Result<AuthResponse, AuthError> {
         let now = Utc::now();
         let jwt_expiry = now + Duration::seconds(self.jwt_expiry);
         let refresh_expiry = now + Duration::seconds(self.refresh_expiry);
@@ -81,29 +301,30 @@ impl Auth {
             exp: jwt_expiry.timestamp() as usize,
             iat: now.timestamp() as usize,
             name,
+            admin,
+            jti: Uuid::new_v4().to_string(),
+            sid: Some(session_id),
+            iss: self.issuer.clone(),
+            aud: Some(self.audience.clone()),
+            scope: Vec::new(),
         };
 
         // Refresh token claims
         let refresh_claims = RefreshClaims {
             sub: user_id,
+            jti: refresh_jti,
             exp: refresh_expiry.timestamp() as usize,
             iat: now.timestamp() as usize,
             token_type: "refresh".to_string(),
+            iss: self.issuer.clone(),
+            aud: Some(self.audience.clone()),
         };
 
         // Generate access token
-        let access_token = encode(
-            &Header::default(),
-            &access_claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )?;
+        let access_token = self.sign(&access_claims)?;
 
         // Generate refresh token
-        let refresh_token = encode(
-            &Header::default(),
-            &refresh_claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )?;
+        let refresh_token = self.sign(&refresh_claims)?;
 
         Ok(AuthResponse {
             access_token,
@@ -113,24 +334,102 @@ impl Auth {
         })
     }
 
+    /// Generate an access token restricted to some scopes, e.g. a
+    /// websocket-only token. It lives at most as long as a regular access token.
+    pub fn generate_scoped_token(
+        &self,
+        user_id: i32,
+        name: String,
+        scope: Vec<Scope>,
+        expires_in: i64,
+    ) -> Result<String, AuthError> {
+        if scope.is_empty() {
+            return Err(AuthError::InsufficientScope);
+        }
+
+        let now = Utc::now();
+        let expiry = now + Duration::seconds(expires_in.clamp(1, self.jwt_expiry));
+
+        let claims = Claims {
+            sub: user_id,
+            exp: expiry.timestamp() as usize,
+            iat: now.timestamp() as usize,
+            name,
+            admin: false,
+            jti: Uuid::new_v4().to_string(),
+            sid: None,
+            iss: self.issuer.clone(),
+            aud: Some(self.audience.clone()),
+            scope,
+        };
+
+        self.sign(&claims)
+    }
+
+    /// Generate a single-use ticket to open a websocket connection. Tickets
+    /// end up in URLs, so unlike access tokens they expire within seconds and
+    /// can't be used for anything else.
+    pub fn generate_ws_ticket(&self, claims: &Claims) -> Result<String, AuthError> {
+        let now = Utc::now();
+        let expiry = now + Duration::seconds(WS_TICKET_EXPIRY);
+
+        let ticket_claims = Claims {
+            sub: claims.sub,
+            exp: expiry.timestamp() as usize,
+            iat: now.timestamp() as usize,
+            name: claims.name.clone(),
+            admin: claims.admin,
+            jti: Uuid::new_v4().to_string(),
+            sid: claims.sid,
+            iss: self.issuer.clone(),
+            aud: Some(self.ws_ticket_audience()),
+            scope: vec![Scope::Ws],
+        };
+
+        self.sign(&ticket_claims)
+    }
+
+    /// Verify a websocket ticket. Whether it was used before is up to the
+    /// caller; see `WsTickets`.
+    pub fn verify_ws_ticket(&self, ticket: &str) -> Result<Claims, AuthError> {
+        let mut validation = self.validation();
+        validation.set_audience(&[self.ws_ticket_audience()]);
+        let token_data = self.decode::<Claims>(ticket, &validation)?;
+
+        Ok(token_data.claims)
+    }
+
+    /// Lifetime of access tokens in seconds
+    pub fn jwt_expiry(&self) -> i64 {
+        self.jwt_expiry
+    }
+
+    /// Lifetime of refresh tokens in seconds
+    pub fn refresh_expiry(&self) -> i64 {
+        self.refresh_expiry
+    }
+
+    /// Verify an access token, scoped or not. Callers decide which scopes
+    /// they accept; see `verify_token_for`.
     pub fn verify_token(&self, token: &str) -> Result<Claims, AuthError> {
-        let validation = Validation::default();
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &validation,
-        )?;
+        let token_data = self.decode::<Claims>(token, &self.validation())?;
 
         Ok(token_data.claims)
     }
 
+    /// Verify an access token that must be allowed to be used for a scope
+    pub fn verify_token_for(&self, token: &str, scope: Scope) -> Result<Claims, AuthError> {
+        let claims = self.verify_token(token)?;
+
+        if !claims.allows(scope) {
+            return Err(AuthError::InsufficientScope);
+        }
+
+        Ok(claims)
+    }
+
     pub fn verify_refresh_token(&self, token: &str) -> Result<RefreshClaims, AuthError> {
-        let validation = Validation::default();
-        let token_data = decode::<RefreshClaims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &validation,
-        )?;
+        let token_data = self.decode::<RefreshClaims>(token, &self.validation())?;
 
         // Verify this is actually a refresh token
         if token_data.claims.token_type != "refresh" {
@@ -139,6 +438,155 @@ impl Auth {
 
         Ok(token_data.claims)
     }
+
+    /// Generate the signed `state` parameter of an OAuth flow, along with
+    /// the nonce the browser starting the flow keeps. Only that browser can
+    /// complete the flow.
+    pub fn generate_oauth_state(
+        &self,
+        provider: OAuthProvider,
+    ) -> Result<(String, String), AuthError> {
+        let now = Utc::now();
+        let nonce = generate_secret();
+        let claims = OAuthStateClaims {
+            provider,
+            nonce_hash: hash_secret(&nonce),
+            jti: Uuid::new_v4().to_string(),
+            exp: (now + Duration::seconds(OAUTH_STATE_EXPIRY)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            token_type: "oauth_state".to_string(),
+        };
+
+        Ok((self.sign(&claims)?, nonce))
+    }
+
+    /// Verify the `state` parameter returned by a provider against the nonce
+    /// of the browser completing the flow. States still have to be redeemed
+    /// to be used once; see `WsTickets::redeem_oauth_state`.
+    pub fn verify_oauth_state(
+        &self,
+        state: &str,
+        provider: OAuthProvider,
+        nonce: Option<&str>,
+    ) -> Result<OAuthStateClaims, AuthError> {
+        let mut validation = Validation::default();
+        validation.leeway = self.leeway;
+        let token_data = self.decode::<OAuthStateClaims>(state, &validation)?;
+
+        // Verify this is a state token issued for this provider
+        if token_data.claims.token_type != "oauth_state" || token_data.claims.provider != provider {
+            return Err(AuthError::InvalidToken);
+        }
+
+        // And to this browser
+        if nonce.is_none_or(|nonce| hash_secret(nonce) != token_data.claims.nonce_hash) {
+            return Err(AuthError::InvalidToken);
+        }
+
+        Ok(token_data.claims)
+    }
+
+    /// Generate the token of a link that lets anyone join a party for a
+    /// while, without knowing its code
+    pub fn generate_party_invite(
+        &self,
+        party_id: i32,
+        inviter_id: i32,
+    ) -> Result<String, AuthError> {
+        let now = Utc::now();
+        let claims = PartyInviteClaims {
+            party_id,
+            sub: inviter_id,
+            exp: (now + Duration::seconds(PARTY_INVITE_EXPIRY)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            token_type: "party_invite".to_string(),
+        };
+
+        self.sign(&claims)
+    }
+
+    /// Verify the token of a party invite link
+    pub fn verify_party_invite(&self, token: &str) -> Result<PartyInviteClaims, AuthError> {
+        let mut validation = Validation::default();
+        validation.leeway = self.leeway;
+        let token_data = self.decode::<PartyInviteClaims>(token, &validation)?;
+
+        // Verify this is an invite link token
+        if token_data.claims.token_type != "party_invite" {
+            return Err(AuthError::InvalidToken);
+        }
+
+        Ok(token_data.claims)
+    }
+
+    // Sign claims with the newest key
+    fn sign<T: Serialize>(&self, claims: &T) -> Result<String, AuthError> {
+        let header = Header {
+            kid: Some(self.keys.signing_id.clone()),
+            ..Default::default()
+        };
+
+        Ok(encode(&header, claims, &self.keys.signing)?)
+    }
+
+    // Decode a token with the key that signed it
+    fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<TokenData<T>, AuthError> {
+        let header = decode_header(token)?;
+
+        let Some(kid) = header.kid else {
+            // Tokens signed before keys had ids may match any of them
+            let mut result = Err(AuthError::InvalidToken);
+            for (_, key) in &self.keys.verifying {
+                result = decode::<T>(token, key, validation).map_err(AuthError::from);
+                if result.is_ok() {
+                    break;
+                }
+            }
+            return result;
+        };
+
+        let (_, key) = self
+            .keys
+            .verifying
+            .iter()
+            .find(|(id, _)| *id == kid)
+            .ok_or(AuthError::InvalidToken)?;
+
+        Ok(decode::<T>(token, key, validation)?)
+    }
+
+    // Tickets have their own audience, so they aren't accepted as access
+    // tokens and vice versa
+    fn ws_ticket_audience(&self) -> String {
+        format!("{}/ws-ticket", self.audience)
+    }
+
+    // Validation of the tokens issued for this deployment, so tokens of
+    // other deployments sharing the secret are rejected
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::default();
+        validation.leeway = self.leeway;
+        validation.set_audience(&[&self.audience]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        validation
+    }
+}
+
+// Random secret handed out once and stored only as a hash, e.g. API keys
+// and email verification tokens
+pub(crate) fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+// Secrets are random enough that a fast hash is safe, and it lets us look them up
+pub(crate) fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
 }
 
 // This will be implemented in the API crate where AppState is defined
@@ -147,11 +595,19 @@ macro_rules! impl_auth_from_ref {
     ($state:ty) => {
         impl axum::extract::FromRef<$state> for $crate::Auth {
             fn from_ref(state: &$state) -> Self {
-                Self::new(
-                    state.config.jwt_secret.clone(),
-                    state.config.jwt_expiry,
-                    state.config.refresh_expiry,
-                )
+                state.auth.clone()
+            }
+        }
+
+        impl axum::extract::FromRef<$state> for $crate::blocklist::Blocklist {
+            fn from_ref(state: &$state) -> Self {
+                state.blocklist.clone()
+            }
+        }
+
+        impl axum::extract::FromRef<$state> for sea_orm::DatabaseConnection {
+            fn from_ref(state: &$state) -> Self {
+                state.conn.clone()
             }
         }
     };
@@ -0,0 +1,163 @@
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::AuthError;
+
+/// Login attempts per account and per client IP.
+///
+/// Every attempt counts until it succeeds. After too many failures the
+/// account, or the IP, can't log in until the lockout expires. Counters live in Redis so every instance sees them;
+/// without Redis they are kept in memory per instance. If Redis cannot be
+/// reached logins are allowed, so an outage doesn't lock everyone out.
+#[derive(Clone)]
+pub struct LoginLockout {
+    conn: Option<ConnectionManager>,
+    local: Arc<Mutex<HashMap<String, (u32, Instant)>>>, // Key to attempts and expiry
+    max_failures_per_account: u32,
+    max_failures_per_ip: u32,
+    duration: u64, // in seconds
+}
+
+impl LoginLockout {
+    pub async fn connect(
+        redis_url: Option<&str>,
+        max_failures_per_account: u32,
+        max_failures_per_ip: u32,
+        duration: u64,
+    ) -> Result<Self, AuthError> {
+        let conn = match redis_url {
+            Some(redis_url) => {
+                let client = redis::Client::open(redis_url)
+                    .map_err(|e| AuthError::InternalError(e.to_string()))?;
+                let conn = ConnectionManager::new(client)
+                    .await
+                    .map_err(|e| AuthError::InternalError(e.to_string()))?;
+                Some(conn)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            conn,
+            local: Arc::default(),
+            max_failures_per_account,
+            max_failures_per_ip,
+            duration: duration.max(1),
+        })
+    }
+
+    /// Count a login attempt to an account from an IP, rejecting it if
+    /// either has used up its attempts. Attempts are counted before the
+    /// password is checked, so parallel guesses can't slip past the limit.
+    /// The lockout starts with the attempt that reaches the limit.
+    pub async fn attempt(&self, name: &str, ip: &str) -> Result<(), AuthError> {
+        let account = self
+            .increment(&account_key(name), self.max_failures_per_account)
+            .await;
+        let client = self.increment(&ip_key(ip), self.max_failures_per_ip).await;
+
+        if let Some(retry_after) = account {
+            return Err(AuthError::AccountLocked(retry_after));
+        }
+        if let Some(retry_after) = client {
+            return Err(AuthError::TooManyFailedLogins(retry_after));
+        }
+
+        Ok(())
+    }
+
+    /// Forget the failures of an account after a successful login, and
+    /// don't count the attempt against the IP
+    pub async fn clear(&self, name: &str, ip: &str) {
+        let account = account_key(name);
+        let client = ip_key(ip);
+
+        match &self.conn {
+            Some(conn) => {
+                let _ = redis::pipe()
+                    .del(&account)
+                    .ignore()
+                    .cmd("DECR")
+                    .arg(&client)
+                    .ignore()
+                    .query_async::<()>(&mut conn.clone())
+                    .await;
+            }
+            None => {
+                let mut local = self.local.lock().unwrap();
+                local.remove(&account);
+                if let Some((attempts, _)) = local.get_mut(&client) {
+                    *attempts = attempts.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    // Count an attempt against a key. Returns the seconds until the key is
+    // unlocked if the attempt is over its limit. A limit of zero disables
+    // the lockout.
+    async fn increment(&self, key: &str, limit: u32) -> Option<u64> {
+        if limit == 0 {
+            return None;
+        }
+
+        match &self.conn {
+            Some(conn) => {
+                let mut conn = conn.clone();
+
+                // Attempts are counted for the lockout duration after the first one
+                let (attempts, ttl): (u32, i64) = redis::pipe()
+                    .cmd("SET")
+                    .arg(key)
+                    .arg(0)
+                    .arg("EX")
+                    .arg(self.duration)
+                    .arg("NX")
+                    .ignore()
+                    .incr(key, 1)
+                    .ttl(key)
+                    .query_async(&mut conn)
+                    .await
+                    .ok()?;
+
+                // Lock for the full duration from the attempt reaching the limit
+                if attempts == limit {
+                    let _ = redis::cmd("EXPIRE")
+                        .arg(key)
+                        .arg(self.duration)
+                        .query_async::<()>(&mut conn)
+                        .await;
+                }
+
+                (attempts > limit).then(|| ttl.max(1) as u64)
+            }
+            None => {
+                let now = Instant::now();
+                let duration = Duration::from_secs(self.duration);
+                let mut local = self.local.lock().unwrap();
+
+                // Forget counters that expired anyway
+                local.retain(|_, (_, expires_at)| *expires_at > now);
+
+                let (attempts, expires_at) =
+                    local.entry(key.to_string()).or_insert((0, now + duration));
+                *attempts += 1;
+                if *attempts == limit {
+                    *expires_at = now + duration;
+                }
+
+                (*attempts > limit).then(|| expires_at.duration_since(now).as_secs().max(1))
+            }
+        }
+    }
+}
+
+fn account_key(name: &str) -> String {
+    format!("lockout:account:{}", name.trim().to_lowercase())
+}
+
+fn ip_key(ip: &str) -> String {
+    format!("lockout:ip:{}", ip)
+}
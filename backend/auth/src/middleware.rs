@@ -1,26 +1,69 @@
-use crate::{Auth, Claims};
+use crate::api_key::{self, API_KEY_HEADER};
+use crate::blocklist::Blocklist;
+use crate::{Auth, AuthError, Claims, Scope, user};
 use axum::{
     RequestPartsExt,
-    extract::{FromRef, FromRequestParts},
-    http::{StatusCode, request::Parts},
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{StatusCode, header, request::Parts},
+    middleware::Next,
+    response::Response,
 };
 use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
+use sea_orm::DatabaseConnection;
 
-// Extractor for authenticated requests
+// Extractor for requests with any valid token, including scoped tokens.
+// Handlers should use an extractor that checks the scope instead.
 #[derive(Debug, Clone)]
-pub struct AuthUser(pub Claims);
+pub struct TokenUser(pub Claims);
 
-impl<S> FromRequestParts<S> for AuthUser
+impl<S> FromRequestParts<S> for TokenUser
 where
     Auth: FromRef<S>,
+    Blocklist: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = StatusCode;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // Routes behind require_auth or optional_auth were checked already
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(TokenUser(claims.clone()));
+        }
+
+        // Server-to-server callers authenticate with an API key instead
+        let claims = if parts.headers.contains_key(API_KEY_HEADER) {
+            let ApiKeyUser(claims) = ApiKeyUser::from_request_parts(parts, state).await?;
+            claims
+        } else {
+            Self::verify_bearer(parts, state).await?
+        };
+
+        // Banned users are rejected even with tokens issued before the ban
+        let db = DatabaseConnection::from_ref(state);
+        user::check_ban_by_id(&db, claims.sub)
+            .await
+            .map_err(|e| match e {
+                AuthError::Banned { .. } => StatusCode::FORBIDDEN,
+                AuthError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::UNAUTHORIZED,
+            })?;
+
+        Ok(TokenUser(claims))
+    }
+}
+
+impl TokenUser {
+    // Verify the bearer token of a request
+    async fn verify_bearer<S>(parts: &mut Parts, state: &S) -> Result<Claims, StatusCode>
+    where
+        Auth: FromRef<S>,
+        Blocklist: FromRef<S>,
+        S: Send + Sync,
+    {
         // Get the claims from the request extensions
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
@@ -35,11 +78,154 @@ where
             Err(_) => return Err(StatusCode::UNAUTHORIZED),
         };
 
-        // Return the claims
+        // Reject tokens revoked before they expired
+        Blocklist::from_ref(state)
+            .check(claims)
+            .await
+            .map_err(|_| StatusCode::UNAUTHORIZED)
+    }
+}
+
+// Extractor for authenticated requests, made by a user or with an API key.
+// Scoped tokens are rejected since they don't grant full access.
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub Claims);
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    Auth: FromRef<S>,
+    Blocklist: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TokenUser(claims) = TokenUser::from_request_parts(parts, state).await?;
+
+        if claims.is_scoped() {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
         Ok(AuthUser(claims))
     }
 }
 
+// Extractor for requests uploading maps, which also accepts tokens scoped
+// to map uploads
+#[derive(Debug, Clone)]
+pub struct MapUploadUser(pub Claims);
+
+impl<S> FromRequestParts<S> for MapUploadUser
+where
+    Auth: FromRef<S>,
+    Blocklist: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TokenUser(claims) = TokenUser::from_request_parts(parts, state).await?;
+
+        if !claims.allows(Scope::MapUpload) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        Ok(MapUploadUser(claims))
+    }
+}
+
+// Extractor for requests made by an administrator
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub Claims);
+
+impl<S> FromRequestParts<S> for AdminUser
+where
+    Auth: FromRef<S>,
+    Blocklist: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthUser(claims) = AuthUser::from_request_parts(parts, state).await?;
+
+        // Only administrators may continue
+        if !claims.admin {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        Ok(AdminUser(claims))
+    }
+}
+
+// Extractor for requests authenticated with an API key
+#[derive(Debug, Clone)]
+pub struct ApiKeyUser(pub Claims);
+
+impl<S> FromRequestParts<S> for ApiKeyUser
+where
+    DatabaseConnection: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        let db = DatabaseConnection::from_ref(state);
+
+        // Look the key up by its hash
+        let claims = api_key::authenticate(&db, key).await.map_err(|e| match e {
+            AuthError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        })?;
+
+        Ok(ApiKeyUser(claims))
+    }
+}
+
+// Middleware for routers whose routes all require authentication. Rejects
+// requests without a valid access token with 401 and makes the claims
+// available to the handlers, whose extractors use them instead of checking
+// the credentials again. Scoped tokens pass, so handlers that need more than
+// read access must check the scope with their extractor.
+pub async fn require_auth(TokenUser(claims): TokenUser, mut req: Request, next: Next) -> Response {
+    req.extensions_mut().insert(claims);
+    next.run(req).await
+}
+
+// Middleware for routers whose routes work with and without authentication.
+// The claims of valid credentials are made available to the handlers through
+// OptionalAuthUser. Requests without valid credentials pass anonymously, so
+// e.g. an expired token doesn't stop a client from refreshing it.
+pub async fn optional_auth<S>(State(state): State<S>, req: Request, next: Next) -> Response
+where
+    Auth: FromRef<S>,
+    Blocklist: FromRef<S>,
+    DatabaseConnection: FromRef<S>,
+    S: Clone + Send + Sync + 'static,
+{
+    let has_credentials = req.headers().contains_key(header::AUTHORIZATION)
+        || req.headers().contains_key(API_KEY_HEADER);
+    if !has_credentials {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    if let Ok(TokenUser(claims)) = TokenUser::from_request_parts(&mut parts, &state).await {
+        parts.extensions.insert(claims);
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
 // Optional auth user extractor - doesn't fail if no token is present
 pub struct OptionalAuthUser(pub Option<Claims>);
 
@@ -0,0 +1,194 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AuthError;
+
+// Social login providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthProvider {
+    Google,
+    Discord,
+}
+
+impl OAuthProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "google",
+            OAuthProvider::Discord => "discord",
+        }
+    }
+
+    fn authorize_endpoint(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "https://accounts.google.com/o/oauth2/v2/auth",
+            OAuthProvider::Discord => "https://discord.com/oauth2/authorize",
+        }
+    }
+
+    fn token_endpoint(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "https://oauth2.googleapis.com/token",
+            OAuthProvider::Discord => "https://discord.com/api/oauth2/token",
+        }
+    }
+
+    fn userinfo_endpoint(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "https://openidconnect.googleapis.com/v1/userinfo",
+            OAuthProvider::Discord => "https://discord.com/api/users/@me",
+        }
+    }
+
+    fn scope(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "openid profile",
+            OAuthProvider::Discord => "identify",
+        }
+    }
+}
+
+impl fmt::Display for OAuthProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OAuthProvider {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "google" => Ok(OAuthProvider::Google),
+            "discord" => Ok(OAuthProvider::Discord),
+            _ => Err(AuthError::UnknownOAuthProvider(s.to_string())),
+        }
+    }
+}
+
+// The identity of a user at a provider
+#[derive(Debug, Clone)]
+pub struct OAuthProfile {
+    pub provider: OAuthProvider,
+    pub subject: String, // Stable user id at the provider
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiscordUserInfo {
+    id: String,
+    username: String,
+    global_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    provider: OAuthProvider,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+impl OAuthClient {
+    pub fn new(
+        provider: OAuthProvider,
+        client_id: String,
+        client_secret: String,
+        redirect_uri: String,
+    ) -> Self {
+        Self {
+            provider,
+            client_id,
+            client_secret,
+            redirect_uri,
+        }
+    }
+
+    /// URL of the provider's consent page
+    pub fn authorize_url(&self, state: &str) -> String {
+        Url::parse_with_params(
+            self.provider.authorize_endpoint(),
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("response_type", "code"),
+                ("scope", self.provider.scope()),
+                ("state", state),
+            ],
+        )
+        .expect("provider endpoints are valid URLs")
+        .to_string()
+    }
+
+    /// Exchange an authorization code for the user's profile at the provider
+    pub async fn exchange_code(&self, code: &str) -> Result<OAuthProfile, AuthError> {
+        let http = reqwest::Client::new();
+
+        // Exchange the code for an access token
+        let token: TokenResponse = http
+            .post(self.provider.token_endpoint())
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("grant_type", "authorization_code"),
+                ("code", code),
+            ])
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AuthError::OAuthError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| AuthError::OAuthError(e.to_string()))?;
+
+        // Fetch the user's profile
+        let userinfo = http
+            .get(self.provider.userinfo_endpoint())
+            .bearer_auth(token.access_token)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AuthError::OAuthError(e.to_string()))?;
+
+        let profile = match self.provider {
+            OAuthProvider::Google => {
+                let info: GoogleUserInfo = userinfo
+                    .json()
+                    .await
+                    .map_err(|e| AuthError::OAuthError(e.to_string()))?;
+                OAuthProfile {
+                    provider: self.provider,
+                    name: info.name.unwrap_or_else(|| "Racer".to_string()),
+                    subject: info.sub,
+                }
+            }
+            OAuthProvider::Discord => {
+                let info: DiscordUserInfo = userinfo
+                    .json()
+                    .await
+                    .map_err(|e| AuthError::OAuthError(e.to_string()))?;
+                OAuthProfile {
+                    provider: self.provider,
+                    name: info.global_name.unwrap_or(info.username),
+                    subject: info.id,
+                }
+            }
+        };
+
+        Ok(profile)
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{Duration, Utc};
+use entity::{password_reset, user};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait, sea_query::Expr,
+};
+
+use crate::email::normalize_email;
+use crate::user::{MIN_PASSWORD_LENGTH, revoke_sessions};
+use crate::{AuthError, generate_secret, hash_secret};
+
+// How long a password reset link stays valid
+pub const RESET_EXPIRY: i64 = 3600; // in seconds
+
+/// Create a token to reset the password of the user with a verified email
+/// address. Returns the user and the token, or nothing if no user has the
+/// address, so callers can answer the same either way.
+pub async fn request_reset(
+    db: &DatabaseConnection,
+    email: &str,
+) -> Result<Option<(user::Model, String)>, AuthError> {
+    let email = normalize_email(email)?;
+
+    // Only verified addresses are known to belong to the user
+    let Some(user) = user::Entity::find()
+        .filter(user::Column::Email.eq(email))
+        .filter(user::Column::EmailVerifiedAt.is_not_null())
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+
+    let token = generate_secret();
+    let expires_at = Utc::now() + Duration::seconds(RESET_EXPIRY);
+
+    password_reset::ActiveModel {
+        user_id: Set(user.id),
+        token_hash: Set(hash_secret(&token)),
+        expires_at: Set(expires_at.fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(Some((user, token)))
+}
+
+/// Set a new password with the token from a reset link. Every session of the
+/// user is signed out, since whoever knew the old password may be using it.
+pub async fn reset(
+    db: &DatabaseConnection,
+    token: &str,
+    new_password: &str,
+) -> Result<user::Model, AuthError> {
+    if new_password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AuthError::PasswordTooShort(MIN_PASSWORD_LENGTH));
+    }
+
+    let reset = password_reset::Entity::find()
+        .filter(password_reset::Column::TokenHash.eq(hash_secret(token)))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if reset.used_at.is_some() {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let now = Utc::now();
+    if reset.expires_at < now {
+        return Err(AuthError::TokenExpired);
+    }
+
+    let user = user::Entity::find_by_id(reset.user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    let password_hash = bcrypt::hash(new_password, bcrypt::DEFAULT_COST)
+        .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    // Claim the token, so it can't be used twice concurrently
+    let claimed = password_reset::Entity::update_many()
+        .col_expr(
+            password_reset::Column::UsedAt,
+            Expr::value(now.fixed_offset()),
+        )
+        .filter(password_reset::Column::Id.eq(reset.id))
+        .filter(password_reset::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if claimed.rows_affected == 0 {
+        return Err(AuthError::InvalidToken);
+    }
+
+    // Use up any other outstanding link of the user as well
+    password_reset::Entity::update_many()
+        .col_expr(
+            password_reset::Column::UsedAt,
+            Expr::value(now.fixed_offset()),
+        )
+        .filter(password_reset::Column::UserId.eq(user.id))
+        .filter(password_reset::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.password_hash = Set(Some(password_hash));
+    let user = user_model
+        .update(&txn)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    revoke_sessions(db, user.id).await?;
+
+    Ok(user)
+}
@@ -0,0 +1,228 @@
+use entity::linked_account;
+use reqwest::Url;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    SqlErr,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AuthError;
+
+// Game platforms whose accounts can be linked to users
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Steam,
+    Epic,
+}
+
+impl Platform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Steam => "steam",
+            Platform::Epic => "epic",
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "steam" => Ok(Platform::Steam),
+            "epic" => Ok(Platform::Epic),
+            _ => Err(AuthError::UnknownPlatform(s.to_string())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SteamResponse {
+    response: SteamAuthenticateResponse,
+}
+
+#[derive(Deserialize)]
+struct SteamAuthenticateResponse {
+    params: Option<SteamTicketParams>,
+}
+
+#[derive(Deserialize)]
+struct SteamTicketParams {
+    result: String,
+    steamid: String,
+}
+
+#[derive(Deserialize)]
+struct EpicTokenInfo {
+    active: bool,
+    client_id: Option<String>,
+    account_id: Option<String>,
+}
+
+/// Checks the tickets game clients get from a platform's SDK
+#[derive(Debug, Clone)]
+pub enum PlatformVerifier {
+    Steam { web_api_key: String, app_id: String },
+    Epic { client_id: String },
+}
+
+impl PlatformVerifier {
+    pub fn platform(&self) -> Platform {
+        match self {
+            PlatformVerifier::Steam { .. } => Platform::Steam,
+            PlatformVerifier::Epic { .. } => Platform::Epic,
+        }
+    }
+
+    /// Verify a ticket with the platform and return the id of the account
+    /// it was issued to.
+    ///
+    /// For Steam the ticket is the hex encoded session ticket from
+    /// `GetAuthTicketForWebApi`, for Epic the access token of the account.
+    pub async fn verify(&self, ticket: &str) -> Result<String, AuthError> {
+        let ticket = ticket.trim();
+        if ticket.is_empty() {
+            return Err(AuthError::InvalidPlatformTicket);
+        }
+
+        let http = reqwest::Client::new();
+
+        match self {
+            PlatformVerifier::Steam {
+                web_api_key,
+                app_id,
+            } => {
+                let url = Url::parse_with_params(
+                    "https://partner.steam-api.com/ISteamUserAuth/AuthenticateUserTicket/v1/",
+                    &[
+                        ("key", web_api_key.as_str()),
+                        ("appid", app_id.as_str()),
+                        ("ticket", ticket),
+                    ],
+                )
+                .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+                let response: SteamResponse = http
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|res| res.error_for_status())
+                    .map_err(|e| AuthError::PlatformError(e.to_string()))?
+                    .json()
+                    .await
+                    .map_err(|e| AuthError::PlatformError(e.to_string()))?;
+
+                // Steam answers with an error instead of params for bad tickets
+                match response.response.params {
+                    Some(params) if params.result == "OK" => Ok(params.steamid),
+                    _ => Err(AuthError::InvalidPlatformTicket),
+                }
+            }
+            PlatformVerifier::Epic { client_id } => {
+                let info: EpicTokenInfo = http
+                    .post("https://api.epicgames.dev/epic/oauth/v2/tokenInfo")
+                    .form(&[("token", ticket)])
+                    .send()
+                    .await
+                    .and_then(|res| res.error_for_status())
+                    .map_err(|e| AuthError::PlatformError(e.to_string()))?
+                    .json()
+                    .await
+                    .map_err(|e| AuthError::PlatformError(e.to_string()))?;
+
+                // Only accept tokens issued to our own client
+                match info {
+                    EpicTokenInfo {
+                        active: true,
+                        client_id: Some(token_client_id),
+                        account_id: Some(account_id),
+                    } if &token_client_id == client_id => Ok(account_id),
+                    _ => Err(AuthError::InvalidPlatformTicket),
+                }
+            }
+        }
+    }
+}
+
+/// Link a platform account to a user. Linking the same account again is a
+/// no-op.
+pub async fn link(
+    db: &DatabaseConnection,
+    user_id: i32,
+    platform: Platform,
+    platform_user_id: String,
+) -> Result<linked_account::Model, AuthError> {
+    let existing = linked_account::Entity::find()
+        .filter(linked_account::Column::Platform.eq(platform.as_str()))
+        .filter(linked_account::Column::PlatformUserId.eq(platform_user_id.clone()))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if let Some(existing) = existing {
+        if existing.user_id != user_id {
+            return Err(AuthError::PlatformAccountTaken(platform));
+        }
+        return Ok(existing);
+    }
+
+    // One account per platform, so progression doesn't split across them
+    let linked = linked_account::Entity::find()
+        .filter(linked_account::Column::UserId.eq(user_id))
+        .filter(linked_account::Column::Platform.eq(platform.as_str()))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if linked.is_some() {
+        return Err(AuthError::PlatformAlreadyLinked(platform));
+    }
+
+    linked_account::ActiveModel {
+        user_id: Set(user_id),
+        platform: Set(platform.as_str().to_string()),
+        platform_user_id: Set(platform_user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| match e.sql_err() {
+        // Someone linked the account concurrently
+        Some(SqlErr::UniqueConstraintViolation(_)) => AuthError::PlatformAccountTaken(platform),
+        _ => AuthError::DatabaseError(e.to_string()),
+    })
+}
+
+/// List the platform accounts linked to a user
+pub async fn list(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<linked_account::Model>, AuthError> {
+    linked_account::Entity::find()
+        .filter(linked_account::Column::UserId.eq(user_id))
+        .order_by_asc(linked_account::Column::Id)
+        .all(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+}
+
+/// Unlink a platform account from a user. Returns whether it was linked.
+pub async fn unlink(db: &DatabaseConnection, user_id: i32, id: i32) -> Result<bool, AuthError> {
+    let result = linked_account::Entity::delete_many()
+        .filter(linked_account::Column::Id.eq(id))
+        .filter(linked_account::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(result.rows_affected > 0)
+}
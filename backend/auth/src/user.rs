@@ -1,18 +1,42 @@
-use entity::user;
+use chrono::{DateTime, Duration, Utc};
+use entity::{linked_account, login_code, refresh_token, user, user_identity, user_name_history};
 use sea_orm::DatabaseConnection;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, SqlErr, TransactionTrait,
+    sea_query::{Expr, Func},
+};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::{Auth, AuthError, AuthResponse};
+use crate::oauth::OAuthProfile;
+use crate::platform::Platform;
+use crate::validation::{self, Validator};
+use crate::{Auth, AuthError, AuthResponse, hash_secret};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+pub const MAX_NAME_LENGTH: usize = 32;
+
+// How long users have to wait between renames
+pub const RENAME_COOLDOWN_DAYS: i64 = 30;
+
+// How long a login code can be redeemed
+pub const LOGIN_CODE_EXPIRY: i64 = 600; // in seconds
+
+// Characters of login codes, without ones that are easily confused (0/O, 1/I)
+const LOGIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const LOGIN_CODE_LENGTH: usize = 8;
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct RegisterRequest {
     pub name: String,
+    pub password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct LoginRequest {
     pub name: String,
+    pub password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -20,25 +44,56 @@ pub struct RefreshRequest {
     pub refresh_token: String,
 }
 
+// Where a login comes from, recorded with its session
+#[derive(Debug, Clone, Default)]
+pub struct SessionInfo {
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+    #[serde(default)]
+    pub all_sessions: bool, // Also revoke the user's other sessions
+}
+
 /// Register a new user
 pub async fn register(
     db: &DatabaseConnection,
     auth: &Auth,
+    validator: &Validator,
     req: RegisterRequest,
+    session: SessionInfo,
 ) -> Result<AuthResponse, AuthError> {
+    let name = validator
+        .user_name("name", &req.name)
+        .map_err(|e| AuthError::Validation(vec![e]))?;
+
+    if req.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AuthError::PasswordTooShort(MIN_PASSWORD_LENGTH));
+    }
+
+    // Names are unique regardless of case
+    if find_by_name(db, &name).await?.is_some() {
+        return Err(AuthError::NameTaken);
+    }
+
+    // Hash password
+    let password_hash = bcrypt::hash(&req.password, bcrypt::DEFAULT_COST)
+        .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
     // Create user
     let new_user = user::ActiveModel {
-        name: Set(req.name.clone()),
+        name: Set(name),
+        password_hash: Set(Some(password_hash)),
         ..Default::default()
     };
 
-    let user = new_user
-        .insert(db)
-        .await
-        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+    let user = new_user.insert(db).await.map_err(name_conflict)?;
 
     // Generate tokens
-    let tokens = auth.generate_tokens(user.id, user.name)?;
+    let tokens = issue_tokens(db, auth, user, session).await?;
 
     Ok(tokens)
 }
@@ -48,17 +103,264 @@ pub async fn login(
     db: &DatabaseConnection,
     auth: &Auth,
     req: LoginRequest,
+    session: SessionInfo,
 ) -> Result<AuthResponse, AuthError> {
     // Find user by name
-    let user = user::Entity::find()
-        .filter(user::Column::Name.eq(req.name))
+    let user = find_by_name(db, req.name.trim())
+        .await?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    // Verify password
+    let valid = user
+        .password_hash
+        .as_deref()
+        .is_some_and(|hash| bcrypt::verify(&req.password, hash).unwrap_or(false));
+
+    if !valid {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    // Generate tokens
+    let tokens = issue_tokens(db, auth, user, session).await?;
+
+    Ok(tokens)
+}
+
+/// Login a user through a social login provider, creating the user the
+/// first time the provider account is seen
+pub async fn login_with_oauth(
+    db: &DatabaseConnection,
+    auth: &Auth,
+    profile: OAuthProfile,
+    session: SessionInfo,
+) -> Result<AuthResponse, AuthError> {
+    // Find the user linked to the provider account
+    let identity = user_identity::Entity::find()
+        .filter(user_identity::Column::Provider.eq(profile.provider.as_str()))
+        .filter(user_identity::Column::Subject.eq(profile.subject.clone()))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let user = match identity {
+        Some(identity) => user::Entity::find_by_id(identity.user_id)
+            .one(db)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+            .ok_or(AuthError::InvalidCredentials)?,
+        None => {
+            let txn = db
+                .begin()
+                .await
+                .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+            // Create user without a password, under a free variant of the
+            // name at the provider
+            let name = available_name(&txn, &profile.name).await?;
+            let user = user::ActiveModel {
+                name: Set(name),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .map_err(name_conflict)?;
+
+            // Link the provider account
+            user_identity::ActiveModel {
+                user_id: Set(user.id),
+                provider: Set(profile.provider.as_str().to_string()),
+                subject: Set(profile.subject),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+            txn.commit()
+                .await
+                .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+            user
+        }
+    };
+
+    // Generate tokens
+    let tokens = issue_tokens(db, auth, user, session).await?;
+
+    Ok(tokens)
+}
+
+/// Log in with a platform account that was linked to a user before. Unlike
+/// social logins this never creates users, so a game client can't start a
+/// second progression next to the user's existing one.
+pub async fn login_with_platform(
+    db: &DatabaseConnection,
+    auth: &Auth,
+    platform: Platform,
+    platform_user_id: &str,
+    session: SessionInfo,
+) -> Result<AuthResponse, AuthError> {
+    let linked = linked_account::Entity::find()
+        .filter(linked_account::Column::Platform.eq(platform.as_str()))
+        .filter(linked_account::Column::PlatformUserId.eq(platform_user_id))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let user = user::Entity::find_by_id(linked.user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    issue_tokens(db, auth, user, session).await
+}
+
+/// Rename a user, keeping the old name in the history. Users may only
+/// rename themselves once per cooldown period.
+pub async fn rename(
+    db: &DatabaseConnection,
+    validator: &Validator,
+    user_id: i32,
+    new_name: &str,
+) -> Result<user::Model, AuthError> {
+    let new_name = validator
+        .user_name("name", new_name)
+        .map_err(|e| AuthError::Validation(vec![e]))?;
+
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    if user.name == new_name {
+        return Ok(user);
+    }
+
+    // Other users may not have the name, but changing its case is fine
+    if let Some(existing) = find_by_name(db, &new_name).await?
+        && existing.id != user_id
+    {
+        return Err(AuthError::NameTaken);
+    }
+
+    // Enforce the cooldown since the last rename
+    let last_rename = user_name_history::Entity::find()
+        .filter(user_name_history::Column::UserId.eq(user_id))
+        .order_by_desc(user_name_history::Column::ChangedAt)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if let Some(last_rename) = last_rename {
+        let next_rename = last_rename.changed_at + Duration::days(RENAME_COOLDOWN_DAYS);
+        let remaining = next_rename.signed_duration_since(Utc::now());
+        if remaining > Duration::zero() {
+            // Round up so "0 days" is never reported
+            let days = (remaining.num_hours() + 23) / 24;
+            return Err(AuthError::RenameCooldown(days.max(1)));
+        }
+    }
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    user_name_history::ActiveModel {
+        user_id: Set(user_id),
+        old_name: Set(user.name.clone()),
+        new_name: Set(new_name.clone()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.name = Set(new_name);
+    let user = user_model.update(&txn).await.map_err(name_conflict)?;
+
+    txn.commit()
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(user)
+}
+
+/// Create a one-time code that logs the user in on another device, e.g. a
+/// console client. Returns the code formatted as `XXXX-XXXX`.
+pub async fn create_login_code(db: &DatabaseConnection, user_id: i32) -> Result<String, AuthError> {
+    let code: String = Uuid::new_v4()
+        .as_bytes()
+        .iter()
+        .take(LOGIN_CODE_LENGTH)
+        .map(|byte| LOGIN_CODE_ALPHABET[*byte as usize % LOGIN_CODE_ALPHABET.len()] as char)
+        .collect();
+
+    let expires_at = Utc::now() + Duration::seconds(LOGIN_CODE_EXPIRY);
+
+    login_code::ActiveModel {
+        user_id: Set(user_id),
+        code_hash: Set(hash_secret(&code)),
+        expires_at: Set(expires_at.fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    let (first, second) = code.split_at(LOGIN_CODE_LENGTH / 2);
+    Ok(format!("{}-{}", first, second))
+}
+
+/// Login a user with a code created on another device
+pub async fn login_with_code(
+    db: &DatabaseConnection,
+    auth: &Auth,
+    code: &str,
+    session: SessionInfo,
+) -> Result<AuthResponse, AuthError> {
+    // Accept the code in any case, with or without separators
+    let code: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let now = Utc::now().fixed_offset();
+
+    // Mark the code as used, unless it already is or has expired
+    let result = login_code::Entity::update_many()
+        .col_expr(login_code::Column::UsedAt, Expr::value(now))
+        .filter(login_code::Column::CodeHash.eq(hash_secret(&code)))
+        .filter(login_code::Column::UsedAt.is_null())
+        .filter(login_code::Column::ExpiresAt.gt(now))
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    let stored = login_code::Entity::find()
+        .filter(login_code::Column::CodeHash.eq(hash_secret(&code)))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let user = user::Entity::find_by_id(stored.user_id)
         .one(db)
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?
         .ok_or(AuthError::InvalidCredentials)?;
 
     // Generate tokens
-    let tokens = auth.generate_tokens(user.id, user.name)?;
+    let tokens = issue_tokens(db, auth, user, session).await?;
 
     Ok(tokens)
 }
@@ -68,10 +370,23 @@ pub async fn refresh_token(
     db: &DatabaseConnection,
     auth: &Auth,
     req: RefreshRequest,
+    session: SessionInfo,
 ) -> Result<AuthResponse, AuthError> {
     // Validate refresh token
     let claims = auth.verify_refresh_token(&req.refresh_token)?;
 
+    // Make sure the token has not been revoked
+    let stored = refresh_token::Entity::find()
+        .filter(refresh_token::Column::Jti.eq(claims.jti.clone()))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if stored.revoked_at.is_some() {
+        return Err(AuthError::InvalidToken);
+    }
+
     // Get user
     let user = user::Entity::find_by_id(claims.sub)
         .one(db)
@@ -79,8 +394,292 @@ pub async fn refresh_token(
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?
         .ok_or(AuthError::InvalidToken)?;
 
+    check_ban(&user)?;
+
+    // Rotate the session's refresh token so the old one can't be used again
+    let jti = Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    // Claim the old token, so only one of concurrent refreshes with it wins
+    let mut rotation = refresh_token::Entity::update_many()
+        .col_expr(refresh_token::Column::Jti, Expr::value(jti.clone()))
+        .col_expr(
+            refresh_token::Column::ExpiresAt,
+            Expr::value((now + Duration::seconds(auth.refresh_expiry())).fixed_offset()),
+        )
+        .col_expr(
+            refresh_token::Column::LastSeenAt,
+            Expr::value(now.fixed_offset()),
+        );
+    if let Some(ip_address) = session.ip_address {
+        rotation = rotation.col_expr(refresh_token::Column::IpAddress, Expr::value(ip_address));
+    }
+
+    let rotated = rotation
+        .filter(refresh_token::Column::Jti.eq(claims.jti))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    if rotated.rows_affected == 0 {
+        return Err(AuthError::InvalidToken);
+    }
+
     // Generate new tokens
-    let tokens = auth.generate_tokens(user.id, user.name)?;
+    let tokens = auth.generate_tokens(user.id, user.name, user.is_admin, stored.id, jti)?;
 
     Ok(tokens)
 }
+
+/// Logout a user by revoking their refresh token, or all of their sessions.
+/// Returns the id of the user.
+pub async fn logout(
+    db: &DatabaseConnection,
+    auth: &Auth,
+    req: LogoutRequest,
+) -> Result<i32, AuthError> {
+    // Validate refresh token
+    let claims = auth.verify_refresh_token(&req.refresh_token)?;
+
+    let jti = if req.all_sessions {
+        None
+    } else {
+        Some(claims.jti)
+    };
+
+    revoke_refresh_tokens(db, claims.sub, jti).await?;
+
+    Ok(claims.sub)
+}
+
+/// Reject users who are banned at the moment. Deleted accounts are rejected
+/// as if they didn't exist.
+pub fn check_ban(user: &user::Model) -> Result<(), AuthError> {
+    if user.deleted_at.is_some() {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    match user.banned_until {
+        Some(until) if until > Utc::now() => Err(AuthError::Banned {
+            until: until.with_timezone(&Utc),
+            reason: user.ban_reason.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reject a user who is banned at the moment, looking them up by id
+pub async fn check_ban_by_id(db: &DatabaseConnection, user_id: i32) -> Result<(), AuthError> {
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    check_ban(&user)
+}
+
+/// Ban a user until a point in time and sign them out of every session
+pub async fn ban(
+    db: &DatabaseConnection,
+    user_id: i32,
+    until: DateTime<Utc>,
+    reason: Option<String>,
+) -> Result<user::Model, AuthError> {
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.banned_until = Set(Some(until.fixed_offset()));
+    user_model.ban_reason = Set(reason);
+    let user = user_model
+        .update(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    revoke_refresh_tokens(db, user_id, None).await?;
+
+    Ok(user)
+}
+
+/// Delete the account of a user and sign them out of every session. The
+/// account is only marked as deleted; it is purged later.
+pub async fn delete(db: &DatabaseConnection, user_id: i32) -> Result<user::Model, AuthError> {
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    let user = user_model
+        .update(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    revoke_refresh_tokens(db, user_id, None).await?;
+
+    Ok(user)
+}
+
+/// Lift the ban of a user
+pub async fn unban(db: &DatabaseConnection, user_id: i32) -> Result<user::Model, AuthError> {
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    let mut user_model: user::ActiveModel = user.into();
+    user_model.banned_until = Set(None);
+    user_model.ban_reason = Set(None);
+    user_model
+        .update(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+}
+
+/// Revoke every refresh token of a user
+pub async fn revoke_sessions(db: &DatabaseConnection, user_id: i32) -> Result<(), AuthError> {
+    revoke_refresh_tokens(db, user_id, None).await
+}
+
+/// List the active sessions of a user, most recently used first
+pub async fn list_sessions(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<refresh_token::Model>, AuthError> {
+    refresh_token::Entity::find()
+        .filter(refresh_token::Column::UserId.eq(user_id))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .filter(refresh_token::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+        .order_by_desc(refresh_token::Column::LastSeenAt)
+        .all(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+}
+
+/// Revoke one session of a user. Returns whether an active session was found.
+pub async fn revoke_session(
+    db: &DatabaseConnection,
+    user_id: i32,
+    session_id: i32,
+) -> Result<bool, AuthError> {
+    let result = refresh_token::Entity::update_many()
+        .col_expr(
+            refresh_token::Column::RevokedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .filter(refresh_token::Column::Id.eq(session_id))
+        .filter(refresh_token::Column::UserId.eq(user_id))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(result.rows_affected > 0)
+}
+
+// Find the user with a name, regardless of case
+async fn find_by_name<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+) -> Result<Option<user::Model>, AuthError> {
+    user::Entity::find()
+        .filter(Expr::expr(Func::lower(Expr::col(user::Column::Name))).eq(name.to_lowercase()))
+        .one(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+}
+
+// Find a free name based on the given one, e.g. "Racer", "Racer2", "Racer3"
+async fn available_name<C: ConnectionTrait>(db: &C, base: &str) -> Result<String, AuthError> {
+    let base = validation::check_name("name", base).unwrap_or_else(|_| "Racer".to_string());
+
+    for attempt in 1..=100 {
+        let name = if attempt == 1 {
+            base.clone()
+        } else {
+            // Keep room for the suffix
+            let suffix = attempt.to_string();
+            let prefix: String = base.chars().take(MAX_NAME_LENGTH - suffix.len()).collect();
+            format!("{}{}", prefix, suffix)
+        };
+
+        if find_by_name(db, &name).await?.is_none() {
+            return Ok(name);
+        }
+    }
+
+    // Fall back to a random suffix for very common names
+    let suffix = Uuid::new_v4().simple().to_string();
+    let prefix: String = base.chars().take(MAX_NAME_LENGTH - 9).collect();
+    Ok(format!("{}-{}", prefix, &suffix[..8]))
+}
+
+// A concurrent insert may still take the name after it was checked
+fn name_conflict(e: DbErr) -> AuthError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => AuthError::NameTaken,
+        _ => AuthError::DatabaseError(e.to_string()),
+    }
+}
+
+// Start a new session and generate its tokens
+async fn issue_tokens(
+    db: &DatabaseConnection,
+    auth: &Auth,
+    user: user::Model,
+    session: SessionInfo,
+) -> Result<AuthResponse, AuthError> {
+    check_ban(&user)?;
+
+    let jti = Uuid::new_v4().to_string();
+    let expires_at = Utc::now() + Duration::seconds(auth.refresh_expiry());
+
+    // Record the session first so its id ends up in the tokens
+    let stored = refresh_token::ActiveModel {
+        user_id: Set(user.id),
+        jti: Set(jti.clone()),
+        expires_at: Set(expires_at.fixed_offset()),
+        device_name: Set(session.device_name),
+        ip_address: Set(session.ip_address),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    auth.generate_tokens(user.id, user.name, user.is_admin, stored.id, jti)
+}
+
+// Revoke one refresh token of a user, or all of them when no id is given
+async fn revoke_refresh_tokens(
+    db: &DatabaseConnection,
+    user_id: i32,
+    jti: Option<String>,
+) -> Result<(), AuthError> {
+    let mut query = refresh_token::Entity::update_many()
+        .col_expr(
+            refresh_token::Column::RevokedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .filter(refresh_token::Column::UserId.eq(user_id))
+        .filter(refresh_token::Column::RevokedAt.is_null());
+
+    if let Some(jti) = jti {
+        query = query.filter(refresh_token::Column::Jti.eq(jti));
+    }
+
+    query
+        .exec(db)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
@@ -0,0 +1,256 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::user::MAX_NAME_LENGTH;
+
+pub const MIN_NAME_LENGTH: usize = 3;
+
+// Characters allowed in names besides letters and digits
+const NAME_SYMBOLS: &[char] = &[' ', '_', '-', '.'];
+
+// Names that could pass for staff or the game itself, compared without case
+// and symbols
+const RESERVED_NAMES: &[&str] = &[
+    "admin",
+    "administrator",
+    "anonymous",
+    "deleted",
+    "everyone",
+    "gamemaster",
+    "moderator",
+    "null",
+    "official",
+    "root",
+    "server",
+    "staff",
+    "support",
+    "system",
+    "undefined",
+    "worldracers",
+];
+
+// Words the built-in filter rejects; deployments can add more
+const PROFANITY: &[&str] = &[
+    "asshole", "bastard", "bitch", "cock", "cunt", "dick", "fag", "faggot", "fuck", "nazi",
+    "nigga", "nigger", "porn", "pussy", "rape", "retard", "shit", "slut", "twat", "wanker",
+    "whore",
+];
+
+/// Why a field failed validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    Length,
+    Charset,
+    Reserved,
+    Profanity,
+    Range,
+    Duplicate,
+    Order,
+    Spacing,
+}
+
+/// A field of a request that failed validation
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: ValidationCode,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, code: ValidationCode, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Decides whether text shown to other players is offensive. The built-in
+/// word list can be replaced, e.g. by a filter backed by a moderation service.
+pub trait ProfanityFilter: Send + Sync {
+    fn is_profane(&self, text: &str) -> bool;
+}
+
+/// Filter rejecting text that contains one of a list of words, also when
+/// spelled with look-alike digits and symbols or spaced out
+pub struct WordListFilter {
+    words: HashSet<String>,
+}
+
+impl WordListFilter {
+    /// The built-in words plus the given ones
+    pub fn new(extra_words: impl IntoIterator<Item = String>) -> Self {
+        let words = PROFANITY
+            .iter()
+            .map(|word| word.to_string())
+            .chain(extra_words.into_iter().map(|word| fold(&word)))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        Self { words }
+    }
+
+    fn matches(&self, token: &str) -> bool {
+        self.words.iter().any(|word| {
+            // Longer words also count as part of a word, like in "xXwordXx"
+            token == word
+                || (word.chars().count() >= 4 && (token.starts_with(word) || token.ends_with(word)))
+        })
+    }
+}
+
+impl Default for WordListFilter {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl ProfanityFilter for WordListFilter {
+    fn is_profane(&self, text: &str) -> bool {
+        let folded: String = text.chars().map(fold_char).collect();
+        let tokens: Vec<&str> = folded
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .collect();
+
+        // Check the words themselves, and all of them joined to catch
+        // spaced out spellings like "w o r d"
+        tokens.iter().any(|token| self.matches(token)) || self.matches(&tokens.concat())
+    }
+}
+
+/// Checks names and other text players choose, before it is shown to others
+#[derive(Clone)]
+pub struct Validator {
+    filter: Arc<dyn ProfanityFilter>,
+}
+
+impl Validator {
+    pub fn new(filter: Arc<dyn ProfanityFilter>) -> Self {
+        Self { filter }
+    }
+
+    /// Check a user name and return it trimmed
+    pub fn user_name(&self, field: &str, name: &str) -> Result<String, FieldError> {
+        let name = check_name(field, name)?;
+        self.check_profanity(field, &name)?;
+
+        Ok(name)
+    }
+
+    /// Check a text like a party name or map title and return it trimmed
+    pub fn text(&self, field: &str, text: &str, max_length: usize) -> Result<String, FieldError> {
+        let text = text.trim();
+        let length = text.chars().count();
+
+        if length == 0 || length > max_length {
+            return Err(FieldError::new(
+                field,
+                ValidationCode::Length,
+                format!("Must be between 1 and {} characters long", max_length),
+            ));
+        }
+
+        if text.chars().any(char::is_control) {
+            return Err(FieldError::new(
+                field,
+                ValidationCode::Charset,
+                "Must not contain control characters",
+            ));
+        }
+
+        self.check_profanity(field, text)?;
+
+        Ok(text.to_string())
+    }
+
+    fn check_profanity(&self, field: &str, text: &str) -> Result<(), FieldError> {
+        if self.filter.is_profane(text) {
+            return Err(FieldError::new(
+                field,
+                ValidationCode::Profanity,
+                "Contains inappropriate language",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new(Arc::new(WordListFilter::default()))
+    }
+}
+
+/// Check the length, characters and reservation of a name and return it
+/// trimmed. Doesn't filter profanity; see `Validator::user_name`.
+pub fn check_name(field: &str, name: &str) -> Result<String, FieldError> {
+    let name = name.trim();
+    let length = name.chars().count();
+
+    if !(MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&length) {
+        return Err(FieldError::new(
+            field,
+            ValidationCode::Length,
+            format!(
+                "Must be between {} and {} characters long",
+                MIN_NAME_LENGTH, MAX_NAME_LENGTH
+            ),
+        ));
+    }
+
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || NAME_SYMBOLS.contains(&c));
+    if !valid_chars || name.contains("  ") {
+        return Err(FieldError::new(
+            field,
+            ValidationCode::Charset,
+            "May only contain letters, digits, single spaces and _ - .",
+        ));
+    }
+
+    let bare: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if RESERVED_NAMES.contains(&bare.as_str()) {
+        return Err(FieldError::new(
+            field,
+            ValidationCode::Reserved,
+            "This name is reserved",
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+// Lowercase a word and undo look-alike spellings
+fn fold(word: &str) -> String {
+    word.trim().chars().map(fold_char).collect()
+}
+
+fn fold_char(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' | '|' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c.to_lowercase().next().unwrap_or(c),
+    }
+}
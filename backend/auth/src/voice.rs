@@ -0,0 +1,119 @@
+use chrono::{Duration, Utc};
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AuthError;
+
+// Voice servers party members can talk on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceProvider {
+    LiveKit,
+}
+
+impl VoiceProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VoiceProvider::LiveKit => "livekit",
+        }
+    }
+}
+
+impl fmt::Display for VoiceProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for VoiceProvider {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "livekit" => Ok(VoiceProvider::LiveKit),
+            _ => Err(AuthError::InternalError(format!(
+                "Unknown voice provider: {}",
+                s
+            ))),
+        }
+    }
+}
+
+// Access token claims as LiveKit expects them
+#[derive(Serialize)]
+struct LiveKitClaims<'a> {
+    iss: &'a str,  // API key the token is signed with
+    sub: &'a str,  // Identity of the participant
+    name: &'a str, // Display name of the participant
+    nbf: usize,    // Not valid before
+    exp: usize,    // Expiration time
+    video: LiveKitGrant<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveKitGrant<'a> {
+    room: &'a str,
+    room_join: bool,
+    can_publish: bool,
+    can_subscribe: bool,
+    can_publish_data: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoiceTokenIssuer {
+    provider: VoiceProvider,
+    api_key: String,
+    api_secret: String,
+}
+
+impl VoiceTokenIssuer {
+    pub fn new(provider: VoiceProvider, api_key: String, api_secret: String) -> Self {
+        Self {
+            provider,
+            api_key,
+            api_secret,
+        }
+    }
+
+    pub fn provider(&self) -> VoiceProvider {
+        self.provider
+    }
+
+    /// Issue a token that lets a participant talk and listen in one room only
+    pub fn issue(
+        &self,
+        room: &str,
+        identity: &str,
+        name: &str,
+        expiry: i64,
+    ) -> Result<String, AuthError> {
+        let now = Utc::now();
+
+        match self.provider {
+            VoiceProvider::LiveKit => {
+                let claims = LiveKitClaims {
+                    iss: &self.api_key,
+                    sub: identity,
+                    name,
+                    nbf: now.timestamp() as usize,
+                    exp: (now + Duration::seconds(expiry)).timestamp() as usize,
+                    video: LiveKitGrant {
+                        room,
+                        room_join: true,
+                        can_publish: true,
+                        can_subscribe: true,
+                        can_publish_data: false,
+                    },
+                };
+
+                Ok(encode(
+                    &Header::default(),
+                    &claims,
+                    &EncodingKey::from_secret(self.api_secret.as_bytes()),
+                )?)
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{AuthError, Claims, OAuthStateClaims};
+
+/// Single use of websocket tickets and OAuth states.
+///
+/// Tickets are short-lived signed tokens (see `Auth::generate_ws_ticket`), so
+/// they only need to be remembered until they expire. The same goes for the
+/// `state` of OAuth flows (see `Auth::generate_oauth_state`). With Redis configured
+/// every instance sees which tickets were used; otherwise they are tracked in
+/// memory, which is enough for a single instance.
+#[derive(Clone, Default)]
+pub struct WsTickets {
+    conn: Option<ConnectionManager>,
+    used: Arc<Mutex<HashMap<String, usize>>>, // Key of the ticket or state to expiration time
+}
+
+impl WsTickets {
+    pub async fn connect(redis_url: Option<&str>) -> Result<Self, AuthError> {
+        let Some(redis_url) = redis_url else {
+            return Ok(Self::default());
+        };
+
+        let client =
+            redis::Client::open(redis_url).map_err(|e| AuthError::InternalError(e.to_string()))?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        Ok(Self {
+            conn: Some(conn),
+            ..Default::default()
+        })
+    }
+
+    /// Use up the ticket the claims were taken from, rejecting it if it was
+    /// used before
+    pub async fn redeem(&self, claims: Claims) -> Result<Claims, AuthError> {
+        if !self.use_once(ticket_key(&claims.jti), claims.exp).await? {
+            return Err(AuthError::TokenRevoked);
+        }
+
+        Ok(claims)
+    }
+
+    /// Use up the state of an OAuth flow, rejecting it if it was used before
+    pub async fn redeem_oauth_state(
+        &self,
+        claims: OAuthStateClaims,
+    ) -> Result<OAuthStateClaims, AuthError> {
+        if !self
+            .use_once(oauth_state_key(&claims.jti), claims.exp)
+            .await?
+        {
+            return Err(AuthError::TokenRevoked);
+        }
+
+        Ok(claims)
+    }
+
+    // Remember a key until it expires. Returns whether it was new.
+    async fn use_once(&self, key: String, exp: usize) -> Result<bool, AuthError> {
+        let now = Utc::now().timestamp() as usize;
+        let ttl = exp.saturating_sub(now).max(1);
+
+        let first_use = match &self.conn {
+            Some(conn) => redis::cmd("SET")
+                .arg(&key)
+                .arg(1)
+                .arg("NX")
+                .arg("EX")
+                .arg(ttl)
+                .query_async::<Option<String>>(&mut conn.clone())
+                .await
+                .map_err(|e| AuthError::InternalError(e.to_string()))?
+                .is_some(),
+            None => {
+                let mut used = self.used.lock().unwrap();
+
+                // Forget tickets that expired anyway
+                used.retain(|_, exp| *exp >= now);
+                used.insert(key, exp).is_none()
+            }
+        };
+
+        Ok(first_use)
+    }
+}
+
+fn ticket_key(jti: &str) -> String {
+    format!("ws-ticket:{}", jti)
+}
+
+fn oauth_state_key(jti: &str) -> String {
+    format!("oauth-state:{}", jti)
+}
@@ -9,11 +9,14 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub map_id: i32,
-    #[sea_orm(column_type = "Float")]
-    pub latitude: f32,
-    #[sea_orm(column_type = "Float")]
-    pub longitude: f32,
+    #[sea_orm(column_type = "Double")]
+    pub latitude: f64,
+    #[sea_orm(column_type = "Double")]
+    pub longitude: f64,
     pub position: i32,
+    #[sea_orm(column_type = "Float")]
+    pub radius_meters: f32,
+    pub kind: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,8 +2,46 @@
 
 pub mod prelude;
 
+pub mod achievement;
+pub mod activity;
+pub mod api_key;
+pub mod block;
+pub mod challenge_result;
 pub mod checkpoint;
+pub mod crew;
+pub mod crew_member;
+pub mod daily_challenge;
+pub mod email_verification;
+pub mod follow;
+pub mod invite;
+pub mod linked_account;
+pub mod login_code;
 pub mod map;
+pub mod map_favorite;
+pub mod map_play;
+pub mod map_report;
+pub mod map_segment;
+pub mod map_tag;
 pub mod party;
+pub mod party_ban;
+pub mod party_event;
+pub mod party_settings;
+pub mod password_reset;
+pub mod playlist;
+pub mod playlist_map;
+pub mod race;
+pub mod race_award;
+pub mod race_participant;
+pub mod race_replay;
+pub mod recent_player;
+pub mod refresh_token;
+pub mod tag;
 pub mod user;
+pub mod user_achievement;
+pub mod user_identity;
+pub mod user_name_history;
 pub mod user_party;
+pub mod user_settings;
+pub mod vehicle_loadout;
+pub mod wallet;
+pub mod wallet_transaction;
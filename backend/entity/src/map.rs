@@ -12,23 +12,58 @@ pub struct Model {
     pub description: String,
     pub created_at: DateTimeWithTimeZone,
     pub author_id: i32,
-    #[sea_orm(column_type = "Float")]
-    pub start_latitude: f32,
-    #[sea_orm(column_type = "Float")]
-    pub start_longitude: f32,
-    #[sea_orm(column_type = "Float")]
-    pub end_latitude: f32,
-    #[sea_orm(column_type = "Float")]
-    pub end_longitude: f32,
+    #[sea_orm(column_type = "Double")]
+    pub start_latitude: f64,
+    #[sea_orm(column_type = "Double")]
+    pub start_longitude: f64,
+    #[sea_orm(column_type = "Double")]
+    pub end_latitude: f64,
+    #[sea_orm(column_type = "Double")]
+    pub end_longitude: f64,
     pub checkpoint_count: i32,
+    pub play_count: i64,
+    pub favorite_count: i32,
+    pub thumbnail_url: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub route_polyline: Option<String>,
+    #[sea_orm(column_type = "Float", nullable)]
+    pub difficulty: Option<f32>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub length_meters: Option<f64>,
+    pub hidden_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub fingerprint: Option<String>,
+    pub weather: String,
+    pub time_of_day: String,
+    pub status: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity::Entity")]
+    Activity,
     #[sea_orm(has_many = "super::checkpoint::Entity")]
     Checkpoint,
+    #[sea_orm(has_many = "super::daily_challenge::Entity")]
+    DailyChallenge,
+    #[sea_orm(has_many = "super::map_favorite::Entity")]
+    MapFavorite,
+    #[sea_orm(has_many = "super::map_play::Entity")]
+    MapPlay,
+    #[sea_orm(has_many = "super::map_report::Entity")]
+    MapReport,
+    #[sea_orm(has_many = "super::map_segment::Entity")]
+    MapSegment,
+    #[sea_orm(has_many = "super::map_tag::Entity")]
+    MapTag,
     #[sea_orm(has_many = "super::party::Entity")]
     Party,
+    #[sea_orm(has_many = "super::playlist_map::Entity")]
+    PlaylistMap,
+    #[sea_orm(has_many = "super::race::Entity")]
+    Race,
+    #[sea_orm(has_many = "super::recent_player::Entity")]
+    RecentPlayer,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::AuthorId",
@@ -39,18 +74,78 @@ pub enum Relation {
     User,
 }
 
+impl Related<super::activity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Activity.def()
+    }
+}
+
 impl Related<super::checkpoint::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Checkpoint.def()
     }
 }
 
+impl Related<super::daily_challenge::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DailyChallenge.def()
+    }
+}
+
+impl Related<super::map_favorite::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapFavorite.def()
+    }
+}
+
+impl Related<super::map_play::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapPlay.def()
+    }
+}
+
+impl Related<super::map_report::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapReport.def()
+    }
+}
+
+impl Related<super::map_segment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapSegment.def()
+    }
+}
+
+impl Related<super::map_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapTag.def()
+    }
+}
+
 impl Related<super::party::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Party.def()
     }
 }
 
+impl Related<super::playlist_map::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlaylistMap.def()
+    }
+}
+
+impl Related<super::race::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Race.def()
+    }
+}
+
+impl Related<super::recent_player::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecentPlayer.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
@@ -14,10 +14,20 @@ pub struct Model {
     pub owner_id: i32,
     pub created_at: DateTimeWithTimeZone,
     pub map_id: i32,
+    pub playlist_id: Option<i32>,
+    pub playlist_position: i32,
+    pub playlist_started_at: Option<DateTimeWithTimeZone>,
+    pub visibility: String,
+    pub max_members: i32,
+    pub region: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity::Entity")]
+    Activity,
+    #[sea_orm(has_many = "super::invite::Entity")]
+    Invite,
     #[sea_orm(
         belongs_to = "super::map::Entity",
         from = "Column::MapId",
@@ -26,6 +36,24 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Map,
+    #[sea_orm(has_many = "super::party_ban::Entity")]
+    PartyBan,
+    #[sea_orm(has_many = "super::party_event::Entity")]
+    PartyEvent,
+    #[sea_orm(has_one = "super::party_settings::Entity")]
+    PartySettings,
+    #[sea_orm(
+        belongs_to = "super::playlist::Entity",
+        from = "Column::PlaylistId",
+        to = "super::playlist::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Playlist,
+    #[sea_orm(has_many = "super::race::Entity")]
+    Race,
+    #[sea_orm(has_many = "super::recent_player::Entity")]
+    RecentPlayer,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::OwnerId",
@@ -38,12 +66,60 @@ pub enum Relation {
     UserParty,
 }
 
+impl Related<super::activity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Activity.def()
+    }
+}
+
+impl Related<super::invite::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Invite.def()
+    }
+}
+
 impl Related<super::map::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Map.def()
     }
 }
 
+impl Related<super::party_ban::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PartyBan.def()
+    }
+}
+
+impl Related<super::party_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PartyEvent.def()
+    }
+}
+
+impl Related<super::party_settings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PartySettings.def()
+    }
+}
+
+impl Related<super::playlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Playlist.def()
+    }
+}
+
+impl Related<super::race::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Race.def()
+    }
+}
+
+impl Related<super::recent_player::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecentPlayer.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
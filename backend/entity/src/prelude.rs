@@ -1,7 +1,45 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.8
 
+pub use super::achievement::Entity as Achievement;
+pub use super::activity::Entity as Activity;
+pub use super::api_key::Entity as ApiKey;
+pub use super::block::Entity as Block;
+pub use super::challenge_result::Entity as ChallengeResult;
 pub use super::checkpoint::Entity as Checkpoint;
+pub use super::crew::Entity as Crew;
+pub use super::crew_member::Entity as CrewMember;
+pub use super::daily_challenge::Entity as DailyChallenge;
+pub use super::email_verification::Entity as EmailVerification;
+pub use super::follow::Entity as Follow;
+pub use super::invite::Entity as Invite;
+pub use super::linked_account::Entity as LinkedAccount;
+pub use super::login_code::Entity as LoginCode;
 pub use super::map::Entity as Map;
+pub use super::map_favorite::Entity as MapFavorite;
+pub use super::map_play::Entity as MapPlay;
+pub use super::map_report::Entity as MapReport;
+pub use super::map_segment::Entity as MapSegment;
+pub use super::map_tag::Entity as MapTag;
 pub use super::party::Entity as Party;
+pub use super::party_ban::Entity as PartyBan;
+pub use super::party_event::Entity as PartyEvent;
+pub use super::party_settings::Entity as PartySettings;
+pub use super::password_reset::Entity as PasswordReset;
+pub use super::playlist::Entity as Playlist;
+pub use super::playlist_map::Entity as PlaylistMap;
+pub use super::race::Entity as Race;
+pub use super::race_award::Entity as RaceAward;
+pub use super::race_participant::Entity as RaceParticipant;
+pub use super::race_replay::Entity as RaceReplay;
+pub use super::recent_player::Entity as RecentPlayer;
+pub use super::refresh_token::Entity as RefreshToken;
+pub use super::tag::Entity as Tag;
 pub use super::user::Entity as User;
+pub use super::user_achievement::Entity as UserAchievement;
+pub use super::user_identity::Entity as UserIdentity;
+pub use super::user_name_history::Entity as UserNameHistory;
 pub use super::user_party::Entity as UserParty;
+pub use super::user_settings::Entity as UserSettings;
+pub use super::vehicle_loadout::Entity as VehicleLoadout;
+pub use super::wallet::Entity as Wallet;
+pub use super::wallet_transaction::Entity as WalletTransaction;
@@ -10,16 +10,120 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub created_at: DateTimeWithTimeZone,
+    pub is_admin: bool,
+    pub password_hash: Option<String>,
+    #[sea_orm(unique)]
+    pub email: Option<String>,
+    pub email_verified_at: Option<DateTimeWithTimeZone>,
+    pub banned_until: Option<DateTimeWithTimeZone>,
+    pub ban_reason: Option<String>,
+    pub avatar_url: Option<String>,
+    pub country: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub bio: Option<String>,
+    pub favorite_vehicle: Option<String>,
+    pub race_wins: i64,
+    pub checkpoints_passed: i64,
+    pub xp: i64,
+    pub level: i32,
+    pub daily_xp_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity::Entity")]
+    Activity,
+    #[sea_orm(has_many = "super::api_key::Entity")]
+    ApiKey,
+    #[sea_orm(has_many = "super::challenge_result::Entity")]
+    ChallengeResult,
+    #[sea_orm(has_one = "super::crew_member::Entity")]
+    CrewMember,
+    #[sea_orm(has_many = "super::email_verification::Entity")]
+    EmailVerification,
+    #[sea_orm(has_many = "super::linked_account::Entity")]
+    LinkedAccount,
+    #[sea_orm(has_many = "super::login_code::Entity")]
+    LoginCode,
     #[sea_orm(has_many = "super::map::Entity")]
     Map,
+    #[sea_orm(has_many = "super::map_favorite::Entity")]
+    MapFavorite,
+    #[sea_orm(has_many = "super::map_report::Entity")]
+    MapReport,
     #[sea_orm(has_many = "super::party::Entity")]
     Party,
+    #[sea_orm(has_many = "super::password_reset::Entity")]
+    PasswordReset,
+    #[sea_orm(has_many = "super::playlist::Entity")]
+    Playlist,
+    #[sea_orm(has_many = "super::race_award::Entity")]
+    RaceAward,
+    #[sea_orm(has_many = "super::race_participant::Entity")]
+    RaceParticipant,
+    #[sea_orm(has_many = "super::race_replay::Entity")]
+    RaceReplay,
+    #[sea_orm(has_many = "super::refresh_token::Entity")]
+    RefreshToken,
+    #[sea_orm(has_many = "super::user_achievement::Entity")]
+    UserAchievement,
+    #[sea_orm(has_many = "super::user_identity::Entity")]
+    UserIdentity,
+    #[sea_orm(has_many = "super::user_name_history::Entity")]
+    UserNameHistory,
     #[sea_orm(has_many = "super::user_party::Entity")]
     UserParty,
+    #[sea_orm(has_one = "super::user_settings::Entity")]
+    UserSettings,
+    #[sea_orm(has_many = "super::vehicle_loadout::Entity")]
+    VehicleLoadout,
+    #[sea_orm(has_one = "super::wallet::Entity")]
+    Wallet,
+    #[sea_orm(has_many = "super::wallet_transaction::Entity")]
+    WalletTransaction,
+}
+
+impl Related<super::activity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Activity.def()
+    }
+}
+
+impl Related<super::api_key::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiKey.def()
+    }
+}
+
+impl Related<super::challenge_result::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChallengeResult.def()
+    }
+}
+
+impl Related<super::crew_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CrewMember.def()
+    }
+}
+
+impl Related<super::email_verification::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EmailVerification.def()
+    }
+}
+
+impl Related<super::linked_account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LinkedAccount.def()
+    }
+}
+
+impl Related<super::login_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LoginCode.def()
+    }
 }
 
 impl Related<super::map::Entity> for Entity {
@@ -28,16 +132,106 @@ impl Related<super::map::Entity> for Entity {
     }
 }
 
+impl Related<super::map_favorite::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapFavorite.def()
+    }
+}
+
+impl Related<super::map_report::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MapReport.def()
+    }
+}
+
 impl Related<super::party::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Party.def()
     }
 }
 
+impl Related<super::password_reset::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordReset.def()
+    }
+}
+
+impl Related<super::playlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Playlist.def()
+    }
+}
+
+impl Related<super::race_award::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RaceAward.def()
+    }
+}
+
+impl Related<super::race_participant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RaceParticipant.def()
+    }
+}
+
+impl Related<super::race_replay::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RaceReplay.def()
+    }
+}
+
+impl Related<super::refresh_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RefreshToken.def()
+    }
+}
+
+impl Related<super::user_achievement::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserAchievement.def()
+    }
+}
+
+impl Related<super::user_identity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserIdentity.def()
+    }
+}
+
+impl Related<super::user_name_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserNameHistory.def()
+    }
+}
+
 impl Related<super::user_party::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::UserParty.def()
     }
 }
 
+impl Related<super::user_settings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserSettings.def()
+    }
+}
+
+impl Related<super::vehicle_loadout::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::VehicleLoadout.def()
+    }
+}
+
+impl Related<super::wallet::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Wallet.def()
+    }
+}
+
+impl Related<super::wallet_transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WalletTransaction.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
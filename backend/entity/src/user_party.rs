@@ -11,6 +11,8 @@ pub struct Model {
     pub user_id: i32,
     pub party_id: i32,
     pub joined_at: DateTimeWithTimeZone,
+    pub ready: bool,
+    pub team: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
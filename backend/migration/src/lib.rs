@@ -5,6 +5,68 @@ mod m20250412_022647_add_map_table;
 mod m20250412_035913_make_created_at_columns_default_to_now;
 mod m20250412_040907_make_joined_at_columns_default_to_now;
 mod m20250413_062158_add_map_id_to_party;
+mod m20250414_090000_add_is_admin_to_user;
+mod m20250415_090000_add_playlist_tables;
+mod m20250416_090000_add_password_hash_to_user;
+mod m20250417_090000_add_user_identity_table;
+mod m20250418_090000_add_refresh_token_table;
+mod m20250419_090000_add_session_columns_to_refresh_token;
+mod m20250420_090000_add_api_key_table;
+mod m20250421_090000_add_email_verification;
+mod m20250422_090000_add_login_code_table;
+mod m20250423_090000_make_user_name_unique;
+mod m20250424_090000_add_password_reset_table;
+mod m20250425_090000_add_ban_columns_to_user;
+mod m20250426_090000_add_linked_account_table;
+mod m20250427_090000_add_profile_columns_to_user;
+mod m20250428_090000_add_block_table;
+mod m20250429_090000_add_user_name_search_index;
+mod m20250430_090000_add_achievement_tables;
+mod m20250501_090000_add_xp_columns_to_user;
+mod m20250502_090000_add_vehicle_loadout_table;
+mod m20250503_090000_add_deleted_at_to_user;
+mod m20250504_090000_add_user_settings_table;
+mod m20250505_090000_add_recent_player_table;
+mod m20250506_090000_add_map_favorite_table;
+mod m20250507_090000_add_follow_table;
+mod m20250508_090000_add_wallet_tables;
+mod m20250509_090000_add_activity_table;
+mod m20250510_090000_add_visibility_to_party;
+mod m20250511_090000_add_max_members_to_party;
+mod m20250512_090000_add_ready_to_user_party;
+mod m20250513_090000_add_invite_table;
+mod m20250514_090000_add_party_settings_table;
+mod m20250515_090000_add_region_to_party;
+mod m20250516_090000_add_preferred_region_to_user_settings;
+mod m20250517_090000_add_team_to_user_party;
+mod m20250518_090000_add_crew_tables;
+mod m20250519_090000_add_party_event_table;
+mod m20250520_090000_add_party_ban_table;
+mod m20250521_090000_add_map_search_indexes;
+mod m20250522_090000_add_tag_tables;
+mod m20250523_090000_add_map_play_table;
+mod m20250524_090000_add_thumbnail_url_to_map;
+mod m20250525_090000_add_status_to_map;
+mod m20250526_090000_add_radius_to_checkpoint;
+mod m20250527_090000_add_kind_to_checkpoint;
+mod m20250528_090000_add_route_polyline_to_map;
+mod m20250529_090000_add_difficulty_to_map;
+mod m20250530_090000_add_challenge_tables;
+mod m20250531_090000_add_map_report_table;
+mod m20250601_090000_add_map_deleted_at;
+mod m20250602_090000_add_map_fingerprint;
+mod m20250603_090000_add_conditions_to_map;
+mod m20250604_090000_add_map_segment_table;
+mod m20250605_090000_widen_map_coordinates;
+mod m20250606_090000_add_map_start_earth_index;
+mod m20250607_090000_add_race_tables;
+mod m20250608_090000_add_length_to_map;
+mod m20250609_090000_add_results_to_race_participant;
+mod m20250610_090000_store_race_splits_compactly;
+mod m20250611_090000_create_race_replay_table;
+mod m20250612_090000_add_playlist_runs;
+mod m20250613_090000_add_leaderboard_indexes;
+mod m20250614_090000_create_race_award_table;
 
 pub struct Migrator;
 
@@ -17,6 +79,68 @@ impl MigratorTrait for Migrator {
             Box::new(m20250412_035913_make_created_at_columns_default_to_now::Migration),
             Box::new(m20250412_040907_make_joined_at_columns_default_to_now::Migration),
             Box::new(m20250413_062158_add_map_id_to_party::Migration),
+            Box::new(m20250414_090000_add_is_admin_to_user::Migration),
+            Box::new(m20250415_090000_add_playlist_tables::Migration),
+            Box::new(m20250416_090000_add_password_hash_to_user::Migration),
+            Box::new(m20250417_090000_add_user_identity_table::Migration),
+            Box::new(m20250418_090000_add_refresh_token_table::Migration),
+            Box::new(m20250419_090000_add_session_columns_to_refresh_token::Migration),
+            Box::new(m20250420_090000_add_api_key_table::Migration),
+            Box::new(m20250421_090000_add_email_verification::Migration),
+            Box::new(m20250422_090000_add_login_code_table::Migration),
+            Box::new(m20250423_090000_make_user_name_unique::Migration),
+            Box::new(m20250424_090000_add_password_reset_table::Migration),
+            Box::new(m20250425_090000_add_ban_columns_to_user::Migration),
+            Box::new(m20250426_090000_add_linked_account_table::Migration),
+            Box::new(m20250427_090000_add_profile_columns_to_user::Migration),
+            Box::new(m20250428_090000_add_block_table::Migration),
+            Box::new(m20250429_090000_add_user_name_search_index::Migration),
+            Box::new(m20250430_090000_add_achievement_tables::Migration),
+            Box::new(m20250501_090000_add_xp_columns_to_user::Migration),
+            Box::new(m20250502_090000_add_vehicle_loadout_table::Migration),
+            Box::new(m20250503_090000_add_deleted_at_to_user::Migration),
+            Box::new(m20250504_090000_add_user_settings_table::Migration),
+            Box::new(m20250505_090000_add_recent_player_table::Migration),
+            Box::new(m20250506_090000_add_map_favorite_table::Migration),
+            Box::new(m20250507_090000_add_follow_table::Migration),
+            Box::new(m20250508_090000_add_wallet_tables::Migration),
+            Box::new(m20250509_090000_add_activity_table::Migration),
+            Box::new(m20250510_090000_add_visibility_to_party::Migration),
+            Box::new(m20250511_090000_add_max_members_to_party::Migration),
+            Box::new(m20250512_090000_add_ready_to_user_party::Migration),
+            Box::new(m20250513_090000_add_invite_table::Migration),
+            Box::new(m20250514_090000_add_party_settings_table::Migration),
+            Box::new(m20250515_090000_add_region_to_party::Migration),
+            Box::new(m20250516_090000_add_preferred_region_to_user_settings::Migration),
+            Box::new(m20250517_090000_add_team_to_user_party::Migration),
+            Box::new(m20250518_090000_add_crew_tables::Migration),
+            Box::new(m20250519_090000_add_party_event_table::Migration),
+            Box::new(m20250520_090000_add_party_ban_table::Migration),
+            Box::new(m20250521_090000_add_map_search_indexes::Migration),
+            Box::new(m20250522_090000_add_tag_tables::Migration),
+            Box::new(m20250523_090000_add_map_play_table::Migration),
+            Box::new(m20250524_090000_add_thumbnail_url_to_map::Migration),
+            Box::new(m20250525_090000_add_status_to_map::Migration),
+            Box::new(m20250526_090000_add_radius_to_checkpoint::Migration),
+            Box::new(m20250527_090000_add_kind_to_checkpoint::Migration),
+            Box::new(m20250528_090000_add_route_polyline_to_map::Migration),
+            Box::new(m20250529_090000_add_difficulty_to_map::Migration),
+            Box::new(m20250530_090000_add_challenge_tables::Migration),
+            Box::new(m20250531_090000_add_map_report_table::Migration),
+            Box::new(m20250601_090000_add_map_deleted_at::Migration),
+            Box::new(m20250602_090000_add_map_fingerprint::Migration),
+            Box::new(m20250603_090000_add_conditions_to_map::Migration),
+            Box::new(m20250604_090000_add_map_segment_table::Migration),
+            Box::new(m20250605_090000_widen_map_coordinates::Migration),
+            Box::new(m20250606_090000_add_map_start_earth_index::Migration),
+            Box::new(m20250607_090000_add_race_tables::Migration),
+            Box::new(m20250608_090000_add_length_to_map::Migration),
+            Box::new(m20250609_090000_add_results_to_race_participant::Migration),
+            Box::new(m20250610_090000_store_race_splits_compactly::Migration),
+            Box::new(m20250611_090000_create_race_replay_table::Migration),
+            Box::new(m20250612_090000_add_playlist_runs::Migration),
+            Box::new(m20250613_090000_add_leaderboard_indexes::Migration),
+            Box::new(m20250614_090000_create_race_award_table::Migration),
         ]
     }
 }
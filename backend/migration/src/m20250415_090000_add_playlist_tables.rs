@@ -0,0 +1,171 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Playlist table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Playlist::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Playlist::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Playlist::Name).string().not_null())
+                    .col(ColumnDef::new(Playlist::AuthorId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Playlist::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Playlist::Table, Playlist::AuthorId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create PlaylistMap table holding the ordered maps of a playlist
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlaylistMap::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PlaylistMap::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PlaylistMap::PlaylistId).integer().not_null())
+                    .col(ColumnDef::new(PlaylistMap::MapId).integer().not_null())
+                    .col(ColumnDef::new(PlaylistMap::Position).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PlaylistMap::Table, PlaylistMap::PlaylistId)
+                            .to(Playlist::Table, Playlist::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PlaylistMap::Table, PlaylistMap::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create index for quick lookup of a playlist's maps in order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_playlist_map_playlist_position")
+                    .table(PlaylistMap::Table)
+                    .col(PlaylistMap::PlaylistId)
+                    .col(PlaylistMap::Position)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Let parties race through a playlist
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .add_column(ColumnDef::new(Party::PlaylistId).integer().null())
+                    .add_column(
+                        ColumnDef::new(Party::PlaylistPosition)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_party_playlist")
+                            .from_tbl(Party::Table)
+                            .from_col(Party::PlaylistId)
+                            .to_tbl(Playlist::Table)
+                            .to_col(Playlist::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .drop_foreign_key(Alias::new("fk_party_playlist"))
+                    .drop_column(Party::PlaylistId)
+                    .drop_column(Party::PlaylistPosition)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PlaylistMap::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Playlist::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Playlist {
+    Table,
+    Id,
+    Name,
+    AuthorId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum PlaylistMap {
+    Table,
+    Id,
+    PlaylistId,
+    MapId,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    PlaylistId,
+    PlaylistPosition,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create UserIdentity table linking users to social login accounts
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserIdentity::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserIdentity::UserId).integer().not_null())
+                    .col(ColumnDef::new(UserIdentity::Provider).string().not_null())
+                    .col(ColumnDef::new(UserIdentity::Subject).string().not_null())
+                    .col(
+                        ColumnDef::new(UserIdentity::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserIdentity::Table, UserIdentity::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // An account at a provider belongs to one user only
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identity_provider_subject")
+                    .table(UserIdentity::Table)
+                    .col(UserIdentity::Provider)
+                    .col(UserIdentity::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserIdentity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserIdentity {
+    Table,
+    Id,
+    UserId,
+    Provider,
+    Subject,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
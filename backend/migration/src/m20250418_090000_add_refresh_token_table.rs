@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create RefreshToken table tracking issued refresh tokens
+        manager
+            .create_table(
+                Table::create()
+                    .table(RefreshToken::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RefreshToken::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RefreshToken::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RefreshToken::Jti)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RefreshToken::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(RefreshToken::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RefreshToken::RevokedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RefreshToken::Table, RefreshToken::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create index for quick lookup of a user's sessions
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_refresh_token_user")
+                    .table(RefreshToken::Table)
+                    .col(RefreshToken::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RefreshToken::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RefreshToken {
+    Table,
+    Id,
+    UserId,
+    Jti,
+    CreatedAt,
+    ExpiresAt,
+    RevokedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
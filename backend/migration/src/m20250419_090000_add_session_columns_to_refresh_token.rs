@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Each refresh token row is a login session; record where it came from
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RefreshToken::Table)
+                    .add_column(ColumnDef::new(RefreshToken::DeviceName).string().null())
+                    .add_column(ColumnDef::new(RefreshToken::IpAddress).string().null())
+                    .add_column(
+                        ColumnDef::new(RefreshToken::LastSeenAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RefreshToken::Table)
+                    .drop_column(RefreshToken::DeviceName)
+                    .drop_column(RefreshToken::IpAddress)
+                    .drop_column(RefreshToken::LastSeenAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RefreshToken {
+    Table,
+    DeviceName,
+    IpAddress,
+    LastSeenAt,
+}
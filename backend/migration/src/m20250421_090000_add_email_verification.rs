@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add optional email address to user table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::Email).string().null().unique_key())
+                    .add_column(
+                        ColumnDef::new(User::EmailVerifiedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create EmailVerification table with the tokens sent out to confirm addresses
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailVerification::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmailVerification::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerification::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EmailVerification::Email).string().not_null())
+                    .col(
+                        ColumnDef::new(EmailVerification::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerification::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerification::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerification::UsedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(EmailVerification::Table, EmailVerification::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EmailVerification::Table).to_owned())
+            .await?;
+
+        // Remove email from user table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Email)
+                    .drop_column(User::EmailVerifiedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    Email,
+    EmailVerifiedAt,
+}
+
+#[derive(DeriveIden)]
+enum EmailVerification {
+    Table,
+    Id,
+    UserId,
+    Email,
+    TokenHash,
+    CreatedAt,
+    ExpiresAt,
+    UsedAt,
+}
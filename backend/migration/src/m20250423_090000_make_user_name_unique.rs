@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Names were not unique so far; the oldest user keeps a name and the
+        // others get their id appended
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE "user" SET name = name || '-' || id
+            WHERE id NOT IN (SELECT MIN(id) FROM "user" GROUP BY LOWER(name))"#,
+        )
+        .await?;
+
+        // Names are unique regardless of case
+        db.execute_unprepared(r#"CREATE UNIQUE INDEX idx_user_name_lower ON "user" (LOWER(name))"#)
+            .await?;
+
+        // Create UserNameHistory table with the previous names of users
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserNameHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserNameHistory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserNameHistory::UserId).integer().not_null())
+                    .col(ColumnDef::new(UserNameHistory::OldName).string().not_null())
+                    .col(ColumnDef::new(UserNameHistory::NewName).string().not_null())
+                    .col(
+                        ColumnDef::new(UserNameHistory::ChangedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserNameHistory::Table, UserNameHistory::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create index for quick lookup of a user's renames
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_name_history_user")
+                    .table(UserNameHistory::Table)
+                    .col(UserNameHistory::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserNameHistory::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_name_lower")
+                    .table(User::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum UserNameHistory {
+    Table,
+    Id,
+    UserId,
+    OldName,
+    NewName,
+    ChangedAt,
+}
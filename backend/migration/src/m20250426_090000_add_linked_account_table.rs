@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create LinkedAccount table with the game platform accounts of users
+        manager
+            .create_table(
+                Table::create()
+                    .table(LinkedAccount::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LinkedAccount::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LinkedAccount::UserId).integer().not_null())
+                    .col(ColumnDef::new(LinkedAccount::Platform).string().not_null())
+                    .col(
+                        ColumnDef::new(LinkedAccount::PlatformUserId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LinkedAccount::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(LinkedAccount::Table, LinkedAccount::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A platform account belongs to one user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_linked_account_platform_user")
+                    .table(LinkedAccount::Table)
+                    .col(LinkedAccount::Platform)
+                    .col(LinkedAccount::PlatformUserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user links one account per platform
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_linked_account_user_platform")
+                    .table(LinkedAccount::Table)
+                    .col(LinkedAccount::UserId)
+                    .col(LinkedAccount::Platform)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LinkedAccount::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LinkedAccount {
+    Table,
+    Id,
+    UserId,
+    Platform,
+    PlatformUserId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
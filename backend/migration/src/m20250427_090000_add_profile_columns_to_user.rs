@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add profile columns to user table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::AvatarUrl).string().null())
+                    .add_column(ColumnDef::new(User::Country).string_len(2).null())
+                    .add_column(ColumnDef::new(User::Bio).text().null())
+                    .add_column(ColumnDef::new(User::FavoriteVehicle).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Remove profile columns from user table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::AvatarUrl)
+                    .drop_column(User::Country)
+                    .drop_column(User::Bio)
+                    .drop_column(User::FavoriteVehicle)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    AvatarUrl,
+    Country,
+    Bio,
+    FavoriteVehicle,
+}
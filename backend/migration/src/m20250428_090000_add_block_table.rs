@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Block table with the users each user has blocked
+        manager
+            .create_table(
+                Table::create()
+                    .table(Block::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Block::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Block::BlockerId).integer().not_null())
+                    .col(ColumnDef::new(Block::BlockedId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Block::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Block::Table, Block::BlockerId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Block::Table, Block::BlockedId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user blocks another user once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_block_blocker_blocked")
+                    .table(Block::Table)
+                    .col(Block::BlockerId)
+                    .col(Block::BlockedId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Find who blocked a user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_block_blocked_id")
+                    .table(Block::Table)
+                    .col(Block::BlockedId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Block::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Block {
+    Table,
+    Id,
+    BlockerId,
+    BlockedId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
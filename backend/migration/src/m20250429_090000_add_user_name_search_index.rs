@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Searching names matches anywhere in the name regardless of case,
+        // which only a trigram index can serve
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+
+        db.execute_unprepared(
+            r#"CREATE INDEX idx_user_name_trgm ON "user" USING GIN (LOWER(name) gin_trgm_ops)"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The extension stays, other database objects may use it by now
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_name_trgm")
+                    .table(User::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+}
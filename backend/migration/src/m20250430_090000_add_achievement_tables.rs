@@ -0,0 +1,239 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Achievement table with the achievements that can be unlocked;
+        // each is unlocked once a counter of its trigger reaches the threshold
+        manager
+            .create_table(
+                Table::create()
+                    .table(Achievement::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Achievement::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Achievement::Key)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Achievement::Name).string().not_null())
+                    .col(ColumnDef::new(Achievement::Description).string().not_null())
+                    .col(ColumnDef::new(Achievement::Trigger).string().not_null())
+                    .col(
+                        ColumnDef::new(Achievement::Threshold)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Achievement::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create UserAchievement table with the achievements users unlocked
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserAchievement::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserAchievement::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserAchievement::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(UserAchievement::AchievementId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserAchievement::UnlockedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserAchievement::Table, UserAchievement::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserAchievement::Table, UserAchievement::AchievementId)
+                            .to(Achievement::Table, Achievement::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // An achievement is unlocked once per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_achievement_user_achievement")
+                    .table(UserAchievement::Table)
+                    .col(UserAchievement::UserId)
+                    .col(UserAchievement::AchievementId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Add the counters achievements are evaluated against
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::RaceWins)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(User::CheckpointsPassed)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(
+                        ColumnDef::new(Map::PlayCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the initial achievements
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Achievement::Table)
+                    .columns([
+                        Achievement::Key,
+                        Achievement::Name,
+                        Achievement::Description,
+                        Achievement::Trigger,
+                        Achievement::Threshold,
+                    ])
+                    .values_panic([
+                        "first_win".into(),
+                        "First Win".into(),
+                        "Win a race".into(),
+                        "race_wins".into(),
+                        1.into(),
+                    ])
+                    .values_panic([
+                        "checkpoints_100".into(),
+                        "Checkpoint Hunter".into(),
+                        "Pass 100 checkpoints".into(),
+                        "checkpoints_passed".into(),
+                        100.into(),
+                    ])
+                    .values_panic([
+                        "map_plays_1000".into(),
+                        "Crowd Favorite".into(),
+                        "Create a map that was raced 1,000 times".into(),
+                        "map_plays".into(),
+                        1000.into(),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::PlayCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::RaceWins)
+                    .drop_column(User::CheckpointsPassed)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserAchievement::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Achievement::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Achievement {
+    Table,
+    Id,
+    Key,
+    Name,
+    Description,
+    Trigger,
+    Threshold,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserAchievement {
+    Table,
+    Id,
+    UserId,
+    AchievementId,
+    UnlockedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    RaceWins,
+    CheckpointsPassed,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    PlayCount,
+}
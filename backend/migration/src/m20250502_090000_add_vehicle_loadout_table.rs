@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create VehicleLoadout table with the customized cars of users; the
+        // selected one is shown to other racers
+        manager
+            .create_table(
+                Table::create()
+                    .table(VehicleLoadout::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(VehicleLoadout::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(VehicleLoadout::UserId).integer().not_null())
+                    .col(ColumnDef::new(VehicleLoadout::Name).string().not_null())
+                    .col(ColumnDef::new(VehicleLoadout::Model).string().not_null())
+                    .col(ColumnDef::new(VehicleLoadout::Color).string().not_null())
+                    .col(
+                        ColumnDef::new(VehicleLoadout::Decals)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'[]'::jsonb")),
+                    )
+                    .col(
+                        ColumnDef::new(VehicleLoadout::Selected)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(VehicleLoadout::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(VehicleLoadout::Table, VehicleLoadout::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create index for quick lookup of a user's loadouts
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_vehicle_loadout_user")
+                    .table(VehicleLoadout::Table)
+                    .col(VehicleLoadout::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user has at most one selected loadout
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_vehicle_loadout_selected ON vehicle_loadout (user_id) WHERE selected",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VehicleLoadout::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum VehicleLoadout {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Model,
+    Color,
+    Decals,
+    Selected,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add deleted_at column to user table; deleted accounts are purged
+        // some time after it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::DeletedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create index for finding accounts to purge
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_deleted_at")
+                    .table(User::Table)
+                    .col(User::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index first
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_deleted_at")
+                    .table(User::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Remove deleted_at column from user table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    DeletedAt,
+}
@@ -0,0 +1,134 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create RecentPlayer table with who raced with whom, and in which
+        // party and on which map they last did
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecentPlayer::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecentPlayer::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecentPlayer::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RecentPlayer::OtherUserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecentPlayer::RacesTogether)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(ColumnDef::new(RecentPlayer::LastPartyId).integer().null())
+                    .col(ColumnDef::new(RecentPlayer::LastMapId).integer().null())
+                    .col(
+                        ColumnDef::new(RecentPlayer::LastPlayedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecentPlayer::Table, RecentPlayer::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecentPlayer::Table, RecentPlayer::OtherUserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecentPlayer::Table, RecentPlayer::LastPartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecentPlayer::Table, RecentPlayer::LastMapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One row per pair of users and direction
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_recent_player_user_other")
+                    .table(RecentPlayer::Table)
+                    .col(RecentPlayer::UserId)
+                    .col(RecentPlayer::OtherUserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the most recent players of a user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_recent_player_user_played_at")
+                    .table(RecentPlayer::Table)
+                    .col(RecentPlayer::UserId)
+                    .col(RecentPlayer::LastPlayedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecentPlayer::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecentPlayer {
+    Table,
+    Id,
+    UserId,
+    OtherUserId,
+    RacesTogether,
+    LastPartyId,
+    LastMapId,
+    LastPlayedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
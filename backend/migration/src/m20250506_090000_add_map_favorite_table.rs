@@ -0,0 +1,122 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create MapFavorite table with the maps each user has bookmarked
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapFavorite::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapFavorite::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapFavorite::UserId).integer().not_null())
+                    .col(ColumnDef::new(MapFavorite::MapId).integer().not_null())
+                    .col(
+                        ColumnDef::new(MapFavorite::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapFavorite::Table, MapFavorite::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapFavorite::Table, MapFavorite::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user favorites a map once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_favorite_user_map")
+                    .table(MapFavorite::Table)
+                    .col(MapFavorite::UserId)
+                    .col(MapFavorite::MapId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Find who favorited a map
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_favorite_map_id")
+                    .table(MapFavorite::Table)
+                    .col(MapFavorite::MapId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keep count of the favorites on the map, like its plays
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(
+                        ColumnDef::new(Map::FavoriteCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::FavoriteCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MapFavorite::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MapFavorite {
+    Table,
+    Id,
+    UserId,
+    MapId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+    FavoriteCount,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Follow table with the map authors each user follows
+        manager
+            .create_table(
+                Table::create()
+                    .table(Follow::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Follow::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Follow::FollowerId).integer().not_null())
+                    .col(ColumnDef::new(Follow::FollowedId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Follow::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Follow::Table, Follow::FollowerId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Follow::Table, Follow::FollowedId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user follows another user once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_follow_follower_followed")
+                    .table(Follow::Table)
+                    .col(Follow::FollowerId)
+                    .col(Follow::FollowedId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Find the followers of a user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_follow_followed_id")
+                    .table(Follow::Table)
+                    .col(Follow::FollowedId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Follow::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Follow {
+    Table,
+    Id,
+    FollowerId,
+    FollowedId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
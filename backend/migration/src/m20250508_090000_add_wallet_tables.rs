@@ -0,0 +1,161 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Wallet table with the currency balance of each user; users
+        // without a row have nothing
+        manager
+            .create_table(
+                Table::create()
+                    .table(Wallet::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Wallet::UserId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Wallet::Balance)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Wallet::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Wallet::Table, Wallet::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create WalletTransaction table with every change to a balance
+        manager
+            .create_table(
+                Table::create()
+                    .table(WalletTransaction::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WalletTransaction::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::BalanceAfter)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::Reason)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::IdempotencyKey)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTransaction::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(WalletTransaction::Table, WalletTransaction::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A grant is applied to a user once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wallet_transaction_user_key")
+                    .table(WalletTransaction::Table)
+                    .col(WalletTransaction::UserId)
+                    .col(WalletTransaction::IdempotencyKey)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the transactions of a user, newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wallet_transaction_user_created_at")
+                    .table(WalletTransaction::Table)
+                    .col(WalletTransaction::UserId)
+                    .col(WalletTransaction::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WalletTransaction::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Wallet::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Wallet {
+    Table,
+    UserId,
+    Balance,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WalletTransaction {
+    Table,
+    Id,
+    UserId,
+    Amount,
+    BalanceAfter,
+    Reason,
+    IdempotencyKey,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
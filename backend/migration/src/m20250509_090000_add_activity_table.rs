@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Activity table with what users did, like creating a map or
+        // winning a race
+        manager
+            .create_table(
+                Table::create()
+                    .table(Activity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Activity::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Activity::UserId).integer().not_null())
+                    .col(ColumnDef::new(Activity::Kind).string().not_null())
+                    .col(ColumnDef::new(Activity::MapId).integer().null())
+                    .col(ColumnDef::new(Activity::PartyId).integer().null())
+                    .col(
+                        ColumnDef::new(Activity::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Activity::Table, Activity::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Activity::Table, Activity::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Activity::Table, Activity::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the activity of a user, newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_activity_user_created_at")
+                    .table(Activity::Table)
+                    .col(Activity::UserId)
+                    .col(Activity::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Add who may see the activity of a user to the settings
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSettings::Table)
+                    .add_column(
+                        ColumnDef::new(UserSettings::ActivityVisibility)
+                            .string()
+                            .not_null()
+                            .default("public"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSettings::Table)
+                    .drop_column(UserSettings::ActivityVisibility)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Activity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Activity {
+    Table,
+    Id,
+    UserId,
+    Kind,
+    MapId,
+    PartyId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserSettings {
+    Table,
+    ActivityVisibility,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add whether a party is listed in the party browser, or can only be
+        // joined by code
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .add_column(
+                        ColumnDef::new(Party::Visibility)
+                            .string()
+                            .not_null()
+                            .default("public"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .drop_column(Party::Visibility)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Visibility,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add how many members a party may have
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .add_column(
+                        ColumnDef::new(Party::MaxMembers)
+                            .integer()
+                            .not_null()
+                            .default(8),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .drop_column(Party::MaxMembers)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    MaxMembers,
+}
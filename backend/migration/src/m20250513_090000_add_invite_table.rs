@@ -0,0 +1,113 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Invite table with pending invites of users to parties
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invite::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invite::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Invite::PartyId).integer().not_null())
+                    .col(ColumnDef::new(Invite::InviterId).integer().not_null())
+                    .col(ColumnDef::new(Invite::InviteeId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Invite::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Invite::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Invite::Table, Invite::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Invite::Table, Invite::InviterId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Invite::Table, Invite::InviteeId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user has one pending invite per party
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invite_party_invitee")
+                    .table(Invite::Table)
+                    .col(Invite::PartyId)
+                    .col(Invite::InviteeId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the invites of a user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invite_invitee_id")
+                    .table(Invite::Table)
+                    .col(Invite::InviteeId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Invite::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Invite {
+    Table,
+    Id,
+    PartyId,
+    InviterId,
+    InviteeId,
+    CreatedAt,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
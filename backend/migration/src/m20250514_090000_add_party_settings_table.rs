@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create PartySettings table with the race rules of parties; parties
+        // without a row use the defaults
+        manager
+            .create_table(
+                Table::create()
+                    .table(PartySettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PartySettings::PartyId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::Laps)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::VehicleClass)
+                            .string()
+                            .not_null()
+                            .default("any"),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::Collisions)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::TimeLimit)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::CheckpointForgiveness)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(PartySettings::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartySettings::Table, PartySettings::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PartySettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PartySettings {
+    Table,
+    PartyId,
+    Laps,
+    VehicleClass,
+    Collisions,
+    TimeLimit,
+    CheckpointForgiveness,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
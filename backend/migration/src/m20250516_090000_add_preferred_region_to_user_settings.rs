@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add the region a user prefers to race in
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSettings::Table)
+                    .add_column(
+                        ColumnDef::new(UserSettings::PreferredRegion)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSettings::Table)
+                    .drop_column(UserSettings::PreferredRegion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserSettings {
+    Table,
+    PreferredRegion,
+}
@@ -0,0 +1,144 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Crew table with lasting groups of players
+        manager
+            .create_table(
+                Table::create()
+                    .table(Crew::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Crew::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Crew::Name).string().not_null().unique_key())
+                    .col(ColumnDef::new(Crew::Tag).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(Crew::Description)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(Crew::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create CrewMember table with the members of crews and their roles
+        manager
+            .create_table(
+                Table::create()
+                    .table(CrewMember::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CrewMember::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CrewMember::CrewId).integer().not_null())
+                    .col(ColumnDef::new(CrewMember::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(CrewMember::Role)
+                            .string()
+                            .not_null()
+                            .default("member"),
+                    )
+                    .col(
+                        ColumnDef::new(CrewMember::JoinedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(CrewMember::Table, CrewMember::CrewId)
+                            .to(Crew::Table, Crew::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(CrewMember::Table, CrewMember::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user is in one crew at most
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_crew_member_user_id")
+                    .table(CrewMember::Table)
+                    .col(CrewMember::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the members of a crew
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_crew_member_crew_id")
+                    .table(CrewMember::Table)
+                    .col(CrewMember::CrewId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CrewMember::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Crew::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Crew {
+    Table,
+    Id,
+    Name,
+    Tag,
+    Description,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum CrewMember {
+    Table,
+    Id,
+    CrewId,
+    UserId,
+    Role,
+    JoinedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create PartyEvent table with the membership history of parties
+        manager
+            .create_table(
+                Table::create()
+                    .table(PartyEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PartyEvent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PartyEvent::PartyId).integer().not_null())
+                    .col(ColumnDef::new(PartyEvent::Kind).string().not_null())
+                    .col(ColumnDef::new(PartyEvent::UserId).integer().not_null())
+                    .col(ColumnDef::new(PartyEvent::ActorId).integer().null())
+                    .col(
+                        ColumnDef::new(PartyEvent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyEvent::Table, PartyEvent::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyEvent::Table, PartyEvent::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyEvent::Table, PartyEvent::ActorId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // List the events of a party
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_party_event_party_id")
+                    .table(PartyEvent::Table)
+                    .col(PartyEvent::PartyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PartyEvent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PartyEvent {
+    Table,
+    Id,
+    PartyId,
+    Kind,
+    UserId,
+    ActorId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
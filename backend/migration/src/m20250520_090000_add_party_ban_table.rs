@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create PartyBan table with the users kept out of parties
+        manager
+            .create_table(
+                Table::create()
+                    .table(PartyBan::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PartyBan::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PartyBan::PartyId).integer().not_null())
+                    .col(ColumnDef::new(PartyBan::UserId).integer().not_null())
+                    .col(ColumnDef::new(PartyBan::BannedBy).integer().null())
+                    .col(ColumnDef::new(PartyBan::Reason).string().null())
+                    .col(
+                        ColumnDef::new(PartyBan::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyBan::Table, PartyBan::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyBan::Table, PartyBan::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PartyBan::Table, PartyBan::BannedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user is banned from a party once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_party_ban_party_user")
+                    .table(PartyBan::Table)
+                    .col(PartyBan::PartyId)
+                    .col(PartyBan::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PartyBan::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PartyBan {
+    Table,
+    Id,
+    PartyId,
+    UserId,
+    BannedBy,
+    Reason,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Titles are searched like user names, anywhere regardless of case
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+
+        db.execute_unprepared(
+            r#"CREATE INDEX idx_map_title_trgm ON "map" USING GIN (LOWER(title) gin_trgm_ops)"#,
+        )
+        .await?;
+
+        // Maps are found within a bounding box by where they start
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_start")
+                    .table(Map::Table)
+                    .col(Map::StartLatitude)
+                    .col(Map::StartLongitude)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_map_start")
+                    .table(Map::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_map_title_trgm")
+                    .table(Map::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    StartLatitude,
+    StartLongitude,
+}
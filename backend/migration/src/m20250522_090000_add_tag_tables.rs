@@ -0,0 +1,120 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Tag table with the tags maps can be browsed by, like "city"
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tag::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tag::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tag::Name).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(Tag::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create MapTag table with the tags of each map
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapTag::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapTag::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapTag::MapId).integer().not_null())
+                    .col(ColumnDef::new(MapTag::TagId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapTag::Table, MapTag::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapTag::Table, MapTag::TagId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A map has a tag once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_tag_map_tag")
+                    .table(MapTag::Table)
+                    .col(MapTag::MapId)
+                    .col(MapTag::TagId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Find the maps with a tag
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_tag_tag_id")
+                    .table(MapTag::Table)
+                    .col(MapTag::TagId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MapTag::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tag {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MapTag {
+    Table,
+    Id,
+    MapId,
+    TagId,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
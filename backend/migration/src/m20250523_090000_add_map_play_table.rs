@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create MapPlay table with when races started on each map, so
+        // recent plays can be counted besides the all-time play count
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapPlay::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapPlay::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapPlay::MapId).integer().not_null())
+                    .col(
+                        ColumnDef::new(MapPlay::PlayedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapPlay::Table, MapPlay::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Count the plays since a point in time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_play_played_at")
+                    .table(MapPlay::Table)
+                    .col(MapPlay::PlayedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MapPlay::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MapPlay {
+    Table,
+    Id,
+    MapId,
+    PlayedAt,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep where the preview image of a map is served from
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(ColumnDef::new(Map::ThumbnailUrl).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::ThumbnailUrl)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    ThumbnailUrl,
+}
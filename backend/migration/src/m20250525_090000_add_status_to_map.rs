@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add whether a map is a draft, published or unlisted; maps made
+        // before drafts existed are published
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(
+                        ColumnDef::new(Map::Status)
+                            .string()
+                            .not_null()
+                            .default("published"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Status,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep how close a car must pass to each checkpoint; existing
+        // checkpoints keep the radius that applied to all of them so far
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoint::Table)
+                    .add_column(
+                        ColumnDef::new(Checkpoint::RadiusMeters)
+                            .float()
+                            .not_null()
+                            .default(25.0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoint::Table)
+                    .drop_column(Checkpoint::RadiusMeters)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Checkpoint {
+    Table,
+    RadiusMeters,
+}
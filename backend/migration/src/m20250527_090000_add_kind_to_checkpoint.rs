@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add what a checkpoint is for; existing checkpoints all had to be
+        // passed
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoint::Table)
+                    .add_column(
+                        ColumnDef::new(Checkpoint::Kind)
+                            .string()
+                            .not_null()
+                            .default("mandatory"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoint::Table)
+                    .drop_column(Checkpoint::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Checkpoint {
+    Table,
+    Kind,
+}
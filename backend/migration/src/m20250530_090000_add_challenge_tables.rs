@@ -0,0 +1,158 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create DailyChallenge table with the map picked for each day
+        manager
+            .create_table(
+                Table::create()
+                    .table(DailyChallenge::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DailyChallenge::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DailyChallenge::Day)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(DailyChallenge::MapId).integer().not_null())
+                    .col(
+                        ColumnDef::new(DailyChallenge::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(DailyChallenge::Table, DailyChallenge::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create ChallengeResult table with the best time of each user on a
+        // daily challenge
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChallengeResult::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChallengeResult::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeResult::ChallengeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ChallengeResult::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(ChallengeResult::FinishTimeMs)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeResult::FinishedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ChallengeResult::Table, ChallengeResult::ChallengeId)
+                            .to(DailyChallenge::Table, DailyChallenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ChallengeResult::Table, ChallengeResult::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user has one result per challenge
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_challenge_result_challenge_user")
+                    .table(ChallengeResult::Table)
+                    .col(ChallengeResult::ChallengeId)
+                    .col(ChallengeResult::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Rank the results of a challenge by time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_challenge_result_challenge_time")
+                    .table(ChallengeResult::Table)
+                    .col(ChallengeResult::ChallengeId)
+                    .col(ChallengeResult::FinishTimeMs)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ChallengeResult::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(DailyChallenge::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum DailyChallenge {
+    Table,
+    Id,
+    Day,
+    MapId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ChallengeResult {
+    Table,
+    Id,
+    ChallengeId,
+    UserId,
+    FinishTimeMs,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create MapReport table with the reports of maps awaiting moderation
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapReport::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapReport::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapReport::MapId).integer().not_null())
+                    .col(ColumnDef::new(MapReport::ReporterId).integer().not_null())
+                    .col(ColumnDef::new(MapReport::Reason).string().not_null())
+                    .col(ColumnDef::new(MapReport::Details).text().null())
+                    .col(
+                        ColumnDef::new(MapReport::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(MapReport::ResolvedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapReport::Table, MapReport::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapReport::Table, MapReport::ReporterId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Find the open reports of a map
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_report_map_id")
+                    .table(MapReport::Table)
+                    .col(MapReport::MapId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keep when a map was taken down pending review
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(
+                        ColumnDef::new(Map::HiddenAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::HiddenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MapReport::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MapReport {
+    Table,
+    Id,
+    MapId,
+    ReporterId,
+    Reason,
+    Details,
+    CreatedAt,
+    ResolvedAt,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+    HiddenAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add the weather and time of day maps are raced in; existing maps
+        // are raced on a clear day
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .add_column(
+                        ColumnDef::new(Map::Weather)
+                            .string()
+                            .not_null()
+                            .default("clear"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Map::TimeOfDay)
+                            .string()
+                            .not_null()
+                            .default("day"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Map::Table)
+                    .drop_column(Map::Weather)
+                    .drop_column(Map::TimeOfDay)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Weather,
+    TimeOfDay,
+}
@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create MapSegment table with the road surface between each two
+        // points of a map in a row
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapSegment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapSegment::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapSegment::MapId).integer().not_null())
+                    .col(ColumnDef::new(MapSegment::Position).integer().not_null())
+                    .col(ColumnDef::new(MapSegment::Surface).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MapSegment::Table, MapSegment::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A map has one segment per position
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_map_segment_map_position")
+                    .table(MapSegment::Table)
+                    .col(MapSegment::MapId)
+                    .col(MapSegment::Position)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MapSegment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MapSegment {
+    Table,
+    Id,
+    MapId,
+    Position,
+    Surface,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Columns holding coordinates, as table and column
+const COORDINATES: [(&str, &str); 6] = [
+    ("map", "start_latitude"),
+    ("map", "start_longitude"),
+    ("map", "end_latitude"),
+    ("map", "end_longitude"),
+    ("checkpoint", "latitude"),
+    ("checkpoint", "longitude"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Single precision is only accurate to a meter or two at real
+        // longitudes. Going through text keeps the shortest decimal of each
+        // stored value, e.g. 13.405 instead of 13.404999732971191.
+        let db = manager.get_connection();
+        for (table, column) in COORDINATES {
+            db.execute_unprepared(&format!(
+                r#"ALTER TABLE "{table}" ALTER COLUMN "{column}" TYPE DOUBLE PRECISION USING "{column}"::TEXT::DOUBLE PRECISION"#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for (table, column) in COORDINATES {
+            db.execute_unprepared(&format!(
+                r#"ALTER TABLE "{table}" ALTER COLUMN "{column}" TYPE REAL"#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Maps are found around a location by how far away they start,
+        // using the earthdistance extension that ships with Postgres
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS cube")
+            .await?;
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS earthdistance")
+            .await?;
+
+        db.execute_unprepared(
+            r#"CREATE INDEX idx_map_start_earth ON "map" USING GIST (ll_to_earth(start_latitude, start_longitude))"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The extensions may be used by others, so they stay
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_map_start_earth")
+                    .table(Map::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+}
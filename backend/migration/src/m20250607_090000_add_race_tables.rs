@@ -0,0 +1,162 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create Race table with every race that ended, on which map and in
+        // which party
+        manager
+            .create_table(
+                Table::create()
+                    .table(Race::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Race::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Race::MapId).integer().not_null())
+                    .col(ColumnDef::new(Race::PartyId).integer().null())
+                    .col(
+                        ColumnDef::new(Race::StartedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Race::EndedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Race::Table, Race::MapId)
+                            .to(Map::Table, Map::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Race::Table, Race::PartyId)
+                            .to(Party::Table, Party::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Statistics of a map add up its races
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_map_id")
+                    .table(Race::Table)
+                    .col(Race::MapId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create RaceParticipant table with how each racer did in a race.
+        // Racers who didn't finish have no finish time or position.
+        manager
+            .create_table(
+                Table::create()
+                    .table(RaceParticipant::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RaceParticipant::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RaceParticipant::RaceId).integer().not_null())
+                    .col(ColumnDef::new(RaceParticipant::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RaceParticipant::FinishTimeMs)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(RaceParticipant::Position).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceParticipant::Table, RaceParticipant::RaceId)
+                            .to(Race::Table, Race::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceParticipant::Table, RaceParticipant::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A user takes part in a race once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_participant_race_user")
+                    .table(RaceParticipant::Table)
+                    .col(RaceParticipant::RaceId)
+                    .col(RaceParticipant::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RaceParticipant::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Race::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Race {
+    Table,
+    Id,
+    MapId,
+    PartyId,
+    StartedAt,
+    EndedAt,
+}
+
+#[derive(DeriveIden)]
+enum RaceParticipant {
+    Table,
+    Id,
+    RaceId,
+    UserId,
+    FinishTimeMs,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum Map {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
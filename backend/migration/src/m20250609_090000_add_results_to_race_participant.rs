@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Races are saved when they start, so racers can submit their results
+        // to them, and end later
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE "race" ALTER COLUMN "ended_at" DROP NOT NULL, ALTER COLUMN "ended_at" DROP DEFAULT"#,
+            )
+            .await?;
+
+        // Keep whether a racer didn't finish, the times at which they passed
+        // the checkpoints and when they submitted them
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RaceParticipant::Table)
+                    .add_column(
+                        ColumnDef::new(RaceParticipant::Dnf)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(RaceParticipant::SplitsMs)
+                            .json_binary()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(RaceParticipant::SubmittedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Racers saved before were saved when their race ended
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "race_participant" SET "dnf" = TRUE WHERE "finish_time_ms" IS NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RaceParticipant::Table)
+                    .drop_column(RaceParticipant::Dnf)
+                    .drop_column(RaceParticipant::SplitsMs)
+                    .drop_column(RaceParticipant::SubmittedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "race" SET "ended_at" = "started_at" WHERE "ended_at" IS NULL"#,
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE "race" ALTER COLUMN "ended_at" SET DEFAULT CURRENT_TIMESTAMP, ALTER COLUMN "ended_at" SET NOT NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaceParticipant {
+    Table,
+    Dnf,
+    SplitsMs,
+    SubmittedAt,
+}
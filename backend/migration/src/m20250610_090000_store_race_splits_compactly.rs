@@ -0,0 +1,108 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, JsonValue, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Splits are kept as varints of the time since the split before,
+        // about two bytes per checkpoint
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RaceParticipant::Table)
+                    .add_column(ColumnDef::new(RaceParticipant::Splits).binary().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Move the splits racers submitted so far
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = db
+            .query_all(Statement::from_string(
+                backend,
+                r#"SELECT "id", "splits_ms" FROM "race_participant" WHERE "splits_ms" IS NOT NULL"#,
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let splits: JsonValue = row.try_get("", "splits_ms")?;
+            let splits: Vec<Option<u64>> = splits
+                .as_array()
+                .map(|splits| splits.iter().map(JsonValue::as_u64).collect())
+                .unwrap_or_default();
+
+            db.execute(Statement::from_sql_and_values(
+                backend,
+                r#"UPDATE "race_participant" SET "splits" = $1 WHERE "id" = $2"#,
+                [encode(&splits).into(), id.into()],
+            ))
+            .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RaceParticipant::Table)
+                    .drop_column(RaceParticipant::SplitsMs)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RaceParticipant::Table)
+                    .drop_column(RaceParticipant::Splits)
+                    .add_column(
+                        ColumnDef::new(RaceParticipant::SplitsMs)
+                            .json_binary()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+// Helper function to encode splits like the API does: the milliseconds since
+// the split before plus one as LEB128 varints, with 0 for checkpoints left out
+fn encode(splits: &[Option<u64>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut previous = 0;
+
+    for split in splits {
+        let mut value = match split {
+            Some(split) => {
+                let delta = split.saturating_sub(previous);
+                previous = previous.max(*split);
+                delta + 1
+            }
+            None => 0,
+        };
+
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    bytes
+}
+
+#[derive(DeriveIden)]
+enum RaceParticipant {
+    Table,
+    Splits,
+    SplitsMs,
+}
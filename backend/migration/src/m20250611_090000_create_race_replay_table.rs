@@ -0,0 +1,108 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create RaceReplay table with the positions of a racer the server
+        // recorded during a race, packed into frames
+        manager
+            .create_table(
+                Table::create()
+                    .table(RaceReplay::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RaceReplay::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RaceReplay::RaceId).integer().not_null())
+                    .col(ColumnDef::new(RaceReplay::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RaceReplay::SampleIntervalMs)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RaceReplay::FrameCount).integer().not_null())
+                    .col(ColumnDef::new(RaceReplay::Frames).binary().not_null())
+                    .col(
+                        ColumnDef::new(RaceReplay::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceReplay::Table, RaceReplay::RaceId)
+                            .to(Race::Table, Race::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceReplay::Table, RaceReplay::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A racer has one replay of a race
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_replay_race_user")
+                    .table(RaceReplay::Table)
+                    .col(RaceReplay::RaceId)
+                    .col(RaceReplay::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Old replays are purged by age
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_replay_created_at")
+                    .table(RaceReplay::Table)
+                    .col(RaceReplay::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RaceReplay::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaceReplay {
+    Table,
+    Id,
+    RaceId,
+    UserId,
+    SampleIntervalMs,
+    FrameCount,
+    Frames,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Race {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep when a party attached its playlist, so standings only add up
+        // the races of the current run through it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .add_column(
+                        ColumnDef::new(Party::PlaylistStartedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keep which playlist a race was part of
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Race::Table)
+                    .add_column(ColumnDef::new(Race::PlaylistId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_race_playlist")
+                            .from_tbl(Race::Table)
+                            .from_col(Race::PlaylistId)
+                            .to_tbl(Playlist::Table)
+                            .to_col(Playlist::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Standings add up the races of a party
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_party_id")
+                    .table(Race::Table)
+                    .col(Race::PartyId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_race_party_id")
+                    .table(Race::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Race::Table)
+                    .drop_foreign_key(Alias::new("fk_race_playlist"))
+                    .drop_column(Race::PlaylistId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Party::Table)
+                    .drop_column(Party::PlaylistStartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Party {
+    Table,
+    PlaylistStartedAt,
+}
+
+#[derive(DeriveIden)]
+enum Race {
+    Table,
+    PartyId,
+    PlaylistId,
+}
+
+#[derive(DeriveIden)]
+enum Playlist {
+    Table,
+    Id,
+}
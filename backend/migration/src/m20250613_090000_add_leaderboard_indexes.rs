@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Leaderboards of a window count the races won since it started
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_participant_user_position")
+                    .table(RaceParticipant::Table)
+                    .col(RaceParticipant::UserId)
+                    .col(RaceParticipant::Position)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_started_at")
+                    .table(Race::Table)
+                    .col(Race::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_race_started_at")
+                    .table(Race::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_race_participant_user_position")
+                    .table(RaceParticipant::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaceParticipant {
+    Table,
+    UserId,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum Race {
+    Table,
+    StartedAt,
+}
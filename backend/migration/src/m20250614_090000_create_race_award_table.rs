@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create RaceAward table with the awards handed out when a race ends
+        manager
+            .create_table(
+                Table::create()
+                    .table(RaceAward::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RaceAward::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RaceAward::RaceId).integer().not_null())
+                    .col(ColumnDef::new(RaceAward::UserId).integer().not_null())
+                    .col(ColumnDef::new(RaceAward::Kind).string().not_null())
+                    .col(ColumnDef::new(RaceAward::Value).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceAward::Table, RaceAward::RaceId)
+                            .to(Race::Table, Race::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RaceAward::Table, RaceAward::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A race hands out each award once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_race_award_race_kind")
+                    .table(RaceAward::Table)
+                    .col(RaceAward::RaceId)
+                    .col(RaceAward::Kind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RaceAward::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaceAward {
+    Table,
+    Id,
+    RaceId,
+    UserId,
+    Kind,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum Race {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
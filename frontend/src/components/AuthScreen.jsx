@@ -3,10 +3,12 @@ import { Canvas } from "@react-three/fiber";
 import { OrbitControls, Environment } from "@react-three/drei";
 import GlobeModel from "./GlobeModel";
 import logo from "../assets/logo.png";
-import { register, fetchUserData } from "../utils/auth";
+import { register, login } from "../utils/auth";
 
 export default function AuthScreen({ onAuthenticated }) {
   const [name, setName] = useState("");
+  const [password, setPassword] = useState("");
+  const [isRegistering, setIsRegistering] = useState(false);
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState("");
 
@@ -18,12 +20,19 @@ export default function AuthScreen({ onAuthenticated }) {
       return;
     }
 
+    if (!password) {
+      setError("Please enter your password");
+      return;
+    }
+
     try {
       setError("");
       setIsLoading(true);
 
-      // Register user
-      const authData = await register(name.trim());
+      // Register or log in user
+      const authData = isRegistering
+        ? await register(name.trim(), password)
+        : await login(name.trim(), password);
 
       // Notify parent component
       onAuthenticated(authData);
@@ -76,7 +85,7 @@ export default function AuthScreen({ onAuthenticated }) {
 
         <div className="w-[300px] mx-auto mt-6">
           <h2 className="text-white text-lg font-semibold mb-2">
-            Enter Your Name
+            {isRegistering ? "Create an Account" : "Sign In"}
           </h2>
 
           {error && (
@@ -92,7 +101,15 @@ export default function AuthScreen({ onAuthenticated }) {
                 placeholder="Your Name"
                 value={name}
                 onChange={(e) => setName(e.target.value)}
-                className="flex-grow px-3 py-2 rounded-lg bg-white text-black font-semibold focus:outline-none"
+                className="flex-grow min-w-0 px-3 py-2 rounded-lg bg-white text-black font-semibold focus:outline-none"
+                disabled={isLoading}
+              />
+              <input
+                type="password"
+                placeholder="Password"
+                value={password}
+                onChange={(e) => setPassword(e.target.value)}
+                className="flex-grow min-w-0 px-3 py-2 rounded-lg bg-white text-black font-semibold focus:outline-none"
                 disabled={isLoading}
               />
               <button
@@ -142,6 +159,20 @@ export default function AuthScreen({ onAuthenticated }) {
               </button>
             </div>
           </form>
+
+          <button
+            type="button"
+            onClick={() => {
+              setIsRegistering(!isRegistering);
+              setError("");
+            }}
+            className="text-white text-sm underline mt-3"
+            disabled={isLoading}
+          >
+            {isRegistering
+              ? "Already have an account? Sign in"
+              : "New here? Create an account"}
+          </button>
         </div>
       </div>
     </div>
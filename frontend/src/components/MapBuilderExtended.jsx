@@ -219,9 +219,6 @@ export default function MapBuilderExtended({ onRouteSubmit }) {
       setIsSaving(true);
       setError("");
 
-      // Get author ID from user data
-      const authorId = userData.id;
-
       // Format checkpoints for API
       const formattedCheckpoints = checkpoints.map((cp, index) => ({
         latitude: cp[1],
@@ -230,7 +227,6 @@ export default function MapBuilderExtended({ onRouteSubmit }) {
       }));
 
       const mapData = {
-        author_id: authorId,
         title: title.trim(),
         description:
           description.trim() ||
@@ -249,12 +245,28 @@ export default function MapBuilderExtended({ onRouteSubmit }) {
         body: JSON.stringify(mapData),
       });
 
+      if (response.status === 422) {
+        const { errors } = await response.json();
+        throw new Error(`${errors[0].field}: ${errors[0].message}`);
+      }
+
       if (!response.ok) {
         throw new Error("Failed to save map");
       }
 
       const savedMap = await response.json();
       console.log("Map saved successfully:", savedMap);
+
+      // New maps are drafts until published
+      const publishResponse = await fetchWithAuth(
+        `/maps/${savedMap.map.id}/publish`,
+        { method: "POST", body: JSON.stringify({}) }
+      );
+
+      if (!publishResponse.ok) {
+        throw new Error("Failed to publish map");
+      }
+
       return true;
     } catch (err) {
       setError(err.message || "Failed to save map. Please try again.");
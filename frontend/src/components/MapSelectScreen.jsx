@@ -22,14 +22,14 @@ export default function MapSelectScreen({ onCreateNew, onSelectMap }) {
       setIsLoading(true);
       setError("");
 
-      const response = await fetchWithAuth("/maps");
+      const response = await fetchWithAuth("/maps?per_page=100");
 
       if (!response.ok) {
         throw new Error("Failed to fetch maps");
       }
 
       const mapsData = await response.json();
-      setMaps(mapsData);
+      setMaps(mapsData.items);
       setIsFetched(true);
     } catch (err) {
       setError(err.message || "Failed to load maps. Please try again.");
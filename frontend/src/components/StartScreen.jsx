@@ -67,7 +67,6 @@ export default function StartScreen({
 
   // Washington DC demo map data
   const dcMapData = {
-    author_id: 16,
     title: "Washington DC 2",
     description: "A race in Washington",
     start_latitude: 38.892631834527975,
@@ -69,28 +69,34 @@ export const fetchWithAuth = async (endpoint, options = {}) => {
   return response;
 };
 
-export const register = async (name) => {
-  const response = await fetch(`${API_BASE_URL}/api/auth/register`, {
+const authenticate = async (endpoint, name, password) => {
+  const response = await fetch(`${API_BASE_URL}/api/auth/${endpoint}`, {
     method: "POST",
     headers: {
       "Content-Type": "application/json",
     },
-    body: JSON.stringify({ name }),
+    body: JSON.stringify({ name, password }),
   });
 
   if (!response.ok) {
-    throw new Error("Authentication failed");
+    const message = await response.text();
+    throw new Error(message || "Authentication failed");
   }
 
   const authData = await response.json();
   saveAuthData(authData);
 
-  // Get user data after successful registration
+  // Get user data after successful authentication
   await fetchUserData();
 
   return authData;
 };
 
+export const register = (name, password) =>
+  authenticate("register", name, password);
+
+export const login = (name, password) => authenticate("login", name, password);
+
 export const fetchUserData = async () => {
   try {
     const response = await fetchWithAuth("/users/me");
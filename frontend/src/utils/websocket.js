@@ -1,4 +1,4 @@
-import { getAuthToken } from "./auth";
+import { fetchWithAuth } from "./auth";
 
 class MultiplayerConnection {
   constructor() {
@@ -54,23 +54,37 @@ class MultiplayerConnection {
       `Setting up connection for user ${this.userId} to party ${this.partyId}`
     );
 
-    const token = getAuthToken();
-    if (!token) {
-      console.error("Authentication token required for WebSocket connection");
-      return;
-    }
+    // Get a single-use ticket, so the access token doesn't end up in URLs
+    this.fetchTicket()
+      .then((ticket) => {
+        console.log(
+          `Connecting to WebSocket at ${this.WS_URL} for party ${partyId}`
+        );
 
-    console.log(
-      `Connecting to WebSocket at ${this.WS_URL} for party ${partyId}`
-    );
+        // Connect to WebSocket with the ticket
+        this.ws = new WebSocket(
+          `${this.WS_URL}?ticket=${encodeURIComponent(ticket)}`
+        );
+
+        this.ws.onopen = this.handleOpen.bind(this);
+        this.ws.onmessage = this.handleMessage.bind(this);
+        this.ws.onerror = this.handleError.bind(this);
+        this.ws.onclose = this.handleClose.bind(this);
+      })
+      .catch((error) => {
+        console.error("Failed to get a WebSocket ticket:", error);
+      });
+  }
+
+  async fetchTicket() {
+    const response = await fetchWithAuth("/ws/ticket", { method: "POST" });
 
-    // Connect to WebSocket with authentication token
-    this.ws = new WebSocket(`${this.WS_URL}?token=${token}`);
+    if (!response.ok) {
+      throw new Error(await response.text());
+    }
 
-    this.ws.onopen = this.handleOpen.bind(this);
-    this.ws.onmessage = this.handleMessage.bind(this);
-    this.ws.onerror = this.handleError.bind(this);
-    this.ws.onclose = this.handleClose.bind(this);
+    const { ticket } = await response.json();
+    return ticket;
   }
 
   handleOpen() {
@@ -181,7 +195,11 @@ class MultiplayerConnection {
             // Use setTimeout to ensure this runs after current execution context
             setTimeout(() => {
               try {
-                this.onRaceStart();
+                this.onRaceStart({
+                  raceId: message.race_id,
+                  weather: message.weather,
+                  timeOfDay: message.time_of_day,
+                });
                 console.log("onRaceStart handler executed successfully");
               } catch (error) {
                 console.error("Error in onRaceStart handler:", error);